@@ -0,0 +1,73 @@
+// Benchmarks for table loading, lookup and typing
+// 效能測試：載入字表、查碼與模擬打字（cargo bench）
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rustarray30::dict::{Dictionary, TableKind};
+use rustarray30::InputEngine;
+use std::hint::black_box;
+
+const CHAR_FILE: &str = "table/cin2/ar30-regular-v2023-1.0-20251012.cin2";
+const PHRASE_FILE: &str = "table/array30-phrase-20210725.txt";
+/// 模擬打字的按鍵數
+const KEYSTROKES: usize = 10_000;
+
+fn load_dictionary() -> Dictionary {
+    let mut dict = Dictionary::new();
+    dict.load_cin2_file(CHAR_FILE).expect("找不到字表");
+    dict.load_phrase_file(PHRASE_FILE).expect("找不到詞庫");
+    dict
+}
+
+/// 依字表順序輪流輸入各字的碼，每個字以空白上屏，直到按鍵數足夠
+fn typing_keys(dict: &Dictionary) -> String {
+    let codes: Vec<&str> = dict.word_pairs(TableKind::Chars).step_by(7).map(|(code, _)| code).collect();
+    let mut keys = String::new();
+    for code in codes.iter().cycle() {
+        if keys.len() >= KEYSTROKES {
+            break;
+        }
+        keys.push_str(code);
+        keys.push(' ');
+    }
+    keys.truncate(KEYSTROKES);
+    keys
+}
+
+fn bench_load(c: &mut Criterion) {
+    c.bench_function("load_dictionary", |b| b.iter(load_dictionary));
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let dict = load_dictionary();
+    c.bench_function("lookup_chars", |b| b.iter(|| dict.lookup_chars(black_box("ab"))));
+    c.bench_function("ordered_words", |b| b.iter(|| dict.ordered_words(TableKind::Chars, black_box("ab"))));
+    c.bench_function("lookup_char_prefix", |b| b.iter(|| dict.lookup_char_prefix(black_box("a"))));
+    // 候選預覽只取一頁
+    c.bench_function("char_prefix_iter_page", |b| {
+        b.iter(|| dict.char_prefix_iter(black_box("a"), 4).take(10).count())
+    });
+}
+
+fn bench_typing(c: &mut Criterion) {
+    let dict = load_dictionary();
+    let keys = typing_keys(&dict);
+    c.bench_function("typing_10k_keystrokes", |b| {
+        b.iter_batched(
+            || InputEngine::new(dict.clone()),
+            |mut engine| {
+                for key in keys.chars() {
+                    engine.handle_key(key);
+                }
+                engine.take_output()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_load, bench_lookup, bench_typing
+}
+criterion_main!(benches);
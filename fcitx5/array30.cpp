@@ -0,0 +1,192 @@
+// Fcitx5 addon forwarding to the rustarray30 engine
+// Fcitx5 外掛：將輸入情境轉接到行列 30 引擎
+
+#include "rustarray30_fcitx5.h"
+
+#include <fcitx-utils/event.h>
+#include <fcitx/addonfactory.h>
+#include <fcitx/addonmanager.h>
+#include <fcitx/candidatelist.h>
+#include <fcitx/inputcontext.h>
+#include <fcitx/inputmethodengine.h>
+#include <fcitx/inputpanel.h>
+#include <fcitx/instance.h>
+#include <fcitx/text.h>
+
+#include <memory>
+#include <string>
+#include <vector>
+
+namespace {
+
+class Array30Engine;
+
+/// 單一候選，選取時交給 Rust 引擎
+class Array30Candidate : public fcitx::CandidateWord {
+public:
+    Array30Candidate(Array30Engine *engine, uint32_t index, std::string text)
+        : fcitx::CandidateWord(fcitx::Text(std::move(text))), engine_(engine), index_(index) {}
+
+    void select(fcitx::InputContext *ic) const override;
+
+private:
+    Array30Engine *engine_;
+    uint32_t index_;
+};
+
+/// 目前頁面的候選；翻頁由 Rust 引擎負責
+class Array30CandidateList : public fcitx::CandidateList, public fcitx::PageableCandidateList {
+public:
+    Array30CandidateList(Array30Engine *engine, fcitx::InputContext *ic, Fcitx5Engine *rust);
+
+    const fcitx::Text &label(int idx) const override { return labels_.at(idx); }
+    const fcitx::CandidateWord &candidate(int idx) const override { return *words_.at(idx); }
+    int size() const override { return static_cast<int>(words_.size()); }
+    int cursorIndex() const override { return -1; }
+    fcitx::CandidateLayoutHint layoutHint() const override { return fcitx::CandidateLayoutHint::Horizontal; }
+
+    bool hasPrev() const override { return hasPrev_; }
+    bool hasNext() const override { return hasNext_; }
+    void prev() override;
+    void next() override;
+    bool usedNextBefore() const override { return true; }
+
+private:
+    Array30Engine *engine_;
+    fcitx::InputContext *ic_;
+    std::vector<std::unique_ptr<Array30Candidate>> words_;
+    std::vector<fcitx::Text> labels_;
+    bool hasPrev_ = false;
+    bool hasNext_ = false;
+};
+
+class Array30Engine : public fcitx::InputMethodEngineV2 {
+public:
+    explicit Array30Engine(fcitx::Instance *instance)
+        : instance_(instance), rust_(rustarray30_fcitx5_new(nullptr)) {}
+
+    ~Array30Engine() override { rustarray30_fcitx5_free(rust_); }
+
+    void keyEvent(const fcitx::InputMethodEntry &, fcitx::KeyEvent &event) override {
+        if (!rust_) {
+            return;
+        }
+        if (rustarray30_fcitx5_tick(rust_)) {
+            update(event.inputContext());
+        }
+        const fcitx::Key &key = event.rawKey();
+        if (rustarray30_fcitx5_key_event(rust_, key.sym(), static_cast<uint32_t>(key.states()), event.isRelease())) {
+            event.filterAndAccept();
+            update(event.inputContext());
+        }
+    }
+
+    void reset(const fcitx::InputMethodEntry &, fcitx::InputContextEvent &event) override {
+        rustarray30_fcitx5_reset(rust_);
+        update(event.inputContext());
+    }
+
+    void deactivate(const fcitx::InputMethodEntry &entry, fcitx::InputContextEvent &event) override {
+        reset(entry, event);
+    }
+
+    void select(fcitx::InputContext *ic, uint32_t index) {
+        rustarray30_fcitx5_select(rust_, index);
+        update(ic);
+    }
+
+    void page(fcitx::InputContext *ic, bool next) {
+        if (rustarray30_fcitx5_page(rust_, next)) {
+            update(ic);
+        }
+    }
+
+    /// 上屏並重新整理預編輯文字與候選表
+    void update(fcitx::InputContext *ic) {
+        std::string commit = rustarray30_fcitx5_take_commit(rust_);
+        if (!commit.empty()) {
+            ic->commitString(commit);
+        }
+
+        auto &panel = ic->inputPanel();
+        panel.reset();
+
+        std::string code = rustarray30_fcitx5_preedit(rust_);
+        if (!code.empty()) {
+            fcitx::Text preedit(code, fcitx::TextFormatFlag::Underline);
+            preedit.setCursor(static_cast<int>(code.size()));
+            if (ic->capabilityFlags().test(fcitx::CapabilityFlag::Preedit)) {
+                panel.setClientPreedit(preedit);
+            } else {
+                panel.setPreedit(preedit);
+            }
+            if (rustarray30_fcitx5_phrase_mode(rust_)) {
+                panel.setAuxUp(fcitx::Text("詞"));
+            }
+        }
+
+        if (rustarray30_fcitx5_candidate_count(rust_) > 0) {
+            panel.setCandidateList(std::make_unique<Array30CandidateList>(this, ic, rust_));
+        }
+
+        ic->updatePreedit();
+        ic->updateUserInterface(fcitx::UserInterfaceComponent::InputPanel);
+        scheduleTimeout(ic);
+    }
+
+private:
+    /// 依組字逾時設定排定計時器，到期時清除或上屏
+    void scheduleTimeout(fcitx::InputContext *ic) {
+        timeout_.reset();
+        int64_t ms = rustarray30_fcitx5_timeout_ms(rust_);
+        if (ms < 0) {
+            return;
+        }
+        auto ref = ic->watch();
+        timeout_ = instance_->eventLoop().addTimeEvent(
+            CLOCK_MONOTONIC, fcitx::now(CLOCK_MONOTONIC) + static_cast<uint64_t>(ms) * 1000, 0,
+            [this, ref](fcitx::EventSourceTime *, uint64_t) {
+                fcitx::InputContext *ic = ref.get();
+                if (ic && rustarray30_fcitx5_tick(rust_)) {
+                    update(ic);
+                }
+                return true;
+            });
+    }
+
+    fcitx::Instance *instance_;
+    Fcitx5Engine *rust_;
+    std::unique_ptr<fcitx::EventSourceTime> timeout_;
+};
+
+void Array30Candidate::select(fcitx::InputContext *ic) const { engine_->select(ic, index_); }
+
+Array30CandidateList::Array30CandidateList(Array30Engine *engine, fcitx::InputContext *ic, Fcitx5Engine *rust)
+    : engine_(engine), ic_(ic) {
+    setPageable(this);
+
+    std::string keys = rustarray30_fcitx5_selection_keys(rust);
+    uint32_t count = rustarray30_fcitx5_candidate_count(rust);
+    for (uint32_t i = 0; i < count; i++) {
+        // 每次呼叫都會覆寫回傳的字串，需立即複製
+        std::string text = rustarray30_fcitx5_candidate(rust, i);
+        words_.push_back(std::make_unique<Array30Candidate>(engine, i, std::move(text)));
+        std::string label = i < keys.size() ? std::string(1, keys[i]) : std::string();
+        labels_.emplace_back(label.empty() ? label : label + ".");
+    }
+    rustarray30_fcitx5_page_state(rust, &hasPrev_, &hasNext_);
+}
+
+void Array30CandidateList::prev() { engine_->page(ic_, false); }
+
+void Array30CandidateList::next() { engine_->page(ic_, true); }
+
+class Array30EngineFactory : public fcitx::AddonFactory {
+    fcitx::AddonInstance *create(fcitx::AddonManager *manager) override {
+        return new Array30Engine(manager->instance());
+    }
+};
+
+} // namespace
+
+FCITX_ADDON_FACTORY(Array30EngineFactory);
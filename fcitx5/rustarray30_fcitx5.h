@@ -0,0 +1,42 @@
+/* C ABI of the rustarray30 Fcitx5 shim (src/fcitx5.rs)
+ * 行列 30 Fcitx5 外掛的 C 介面
+ *
+ * Returned strings are owned by the engine and stay valid until the next
+ * call on the same engine.
+ */
+#ifndef RUSTARRAY30_FCITX5_H
+#define RUSTARRAY30_FCITX5_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct Fcitx5Engine Fcitx5Engine;
+
+Fcitx5Engine *rustarray30_fcitx5_new(const char *table_dir);
+void rustarray30_fcitx5_free(Fcitx5Engine *engine);
+
+bool rustarray30_fcitx5_key_event(Fcitx5Engine *engine, uint32_t keysym, uint32_t states, bool is_release);
+void rustarray30_fcitx5_reset(Fcitx5Engine *engine);
+int64_t rustarray30_fcitx5_timeout_ms(Fcitx5Engine *engine);
+bool rustarray30_fcitx5_tick(Fcitx5Engine *engine);
+
+const char *rustarray30_fcitx5_take_commit(Fcitx5Engine *engine);
+const char *rustarray30_fcitx5_preedit(Fcitx5Engine *engine);
+bool rustarray30_fcitx5_phrase_mode(Fcitx5Engine *engine);
+
+uint32_t rustarray30_fcitx5_candidate_count(Fcitx5Engine *engine);
+const char *rustarray30_fcitx5_candidate(Fcitx5Engine *engine, uint32_t index);
+const char *rustarray30_fcitx5_selection_keys(Fcitx5Engine *engine);
+bool rustarray30_fcitx5_select(Fcitx5Engine *engine, uint32_t index);
+bool rustarray30_fcitx5_page(Fcitx5Engine *engine, bool next);
+void rustarray30_fcitx5_page_state(Fcitx5Engine *engine, bool *has_prev, bool *has_next);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUSTARRAY30_FCITX5_H */
@@ -0,0 +1,105 @@
+/* rustarray30 C API - 由 cbindgen 產生，請勿手動修改 */
+
+#ifndef RUSTARRAY30_H
+#define RUSTARRAY30_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * 按鍵處理結果
+ */
+typedef enum Array30KeyResult {
+  /**
+   * 無變化
+   */
+  Array30KeyResult_NoChange = 0,
+  /**
+   * 需要更新介面顯示
+   */
+  Array30KeyResult_NeedUpdate = 1,
+  /**
+   * 已確認輸出，可呼叫 array30_take_commit 取出
+   */
+  Array30KeyResult_Committed = 2,
+  /**
+   * 按鍵被拒絕（錯碼、超過碼長或無候選可確認）
+   */
+  Array30KeyResult_Rejected = 3,
+} Array30KeyResult;
+
+/**
+ * 引擎把手（對 C 而言為不透明型別）
+ */
+typedef struct Array30Engine Array30Engine;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * 載入字表建立引擎
+ * `phrase_file` 可為 null（不載入詞庫）；載入失敗回傳 null
+ *
+ * # Safety
+ * `char_file` 必須為有效的 NUL 結尾 UTF-8 字串，`phrase_file` 為 null 或有效字串
+ */
+struct Array30Engine *array30_engine_new(const char *char_file, const char *phrase_file);
+
+/**
+ * 釋放引擎
+ *
+ * # Safety
+ * `engine` 必須為 `array30_engine_new` 回傳的指標或 null，且之後不再使用
+ */
+void array30_engine_free(struct Array30Engine *engine);
+
+/**
+ * 處理按鍵（Unicode 字元；退格為 0x08、Enter 為 0x0A、Esc 為 0x1B）
+ *
+ * # Safety
+ * `engine` 必須為有效的引擎指標或 null
+ */
+enum Array30KeyResult array30_handle_key(struct Array30Engine *engine, uint32_t key);
+
+/**
+ * 取得目前頁面的候選字詞
+ * 回傳字串指標陣列，數量寫入 `count`
+ *
+ * # Safety
+ * `engine` 必須為有效的引擎指標或 null；`count` 必須為有效指標或 null
+ */
+const char *const *array30_get_candidates(struct Array30Engine *engine, size_t *count);
+
+/**
+ * 選擇目前頁面第 `index` 個候選
+ *
+ * # Safety
+ * `engine` 必須為有效的引擎指標或 null
+ */
+bool array30_select_candidate(struct Array30Engine *engine, size_t index);
+
+/**
+ * 取得目前輸入的碼
+ *
+ * # Safety
+ * `engine` 必須為有效的引擎指標或 null
+ */
+const char *array30_get_code(struct Array30Engine *engine);
+
+/**
+ * 取出已上屏的文字（沒有時為空字串）
+ *
+ * # Safety
+ * `engine` 必須為有效的引擎指標或 null
+ */
+const char *array30_take_commit(struct Array30Engine *engine);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUSTARRAY30_H */
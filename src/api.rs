@@ -0,0 +1,246 @@
+// Stable facade for embedders
+// 嵌入用的穩定介面：輸入法框架（IBus、TSF、PIME 等）的整合只需使用本模組
+//
+// 本模組的型別不公開內部欄位，依語意化版本維護：次版本只會新增項目，不移除或改變既有的函式與列舉值。
+// 其他模組屬於內部實作，可能隨重構改變。
+
+use crate::input_engine::{self, InputEngine, KeyResult, PunctuationMode, TimeoutAction};
+use crate::state;
+use std::time::{Duration, Instant};
+
+pub use crate::dict::Dictionary;
+pub use crate::keymap::ArrayVariant;
+
+/// 引擎選項
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EngineOptions {
+    inner: input_engine::EngineOptions,
+}
+
+impl EngineOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 輸入法變體（決定組碼鍵、選字鍵與碼長）
+    pub fn variant(mut self, variant: ArrayVariant) -> Self {
+        self.inner.variant = variant;
+        self
+    }
+
+    /// 每頁候選數（超出範圍時取最接近的有效值）
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.inner.page_size = page_size.clamp(1, input_engine::MAX_PAGE_SIZE);
+        self
+    }
+
+    /// 達到碼長上限且只有一個候選時自動上屏
+    pub fn auto_commit(mut self, enabled: bool) -> Self {
+        self.inner.auto_commit = enabled;
+        self
+    }
+
+    /// 標點符號輸出為全形
+    pub fn full_width_punctuation(mut self, enabled: bool) -> Self {
+        self.inner.punctuation = if enabled { PunctuationMode::Full } else { PunctuationMode::Half };
+        self
+    }
+
+    /// 組字閒置多久後自動處理未完成的碼（None 為不處理），需定期呼叫 Engine::tick
+    pub fn composition_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.inner.composition_timeout = timeout;
+        self
+    }
+
+    /// 組字逾時時上屏第一候選（預設為捨棄未完成的碼）
+    pub fn commit_on_timeout(mut self, enabled: bool) -> Self {
+        self.inner.timeout_action = if enabled { TimeoutAction::Commit } else { TimeoutAction::Clear };
+        self
+    }
+}
+
+/// 按鍵處理結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeyEventResult {
+    /// 引擎沒有處理此按鍵（框架可交給應用程式）
+    NoChange,
+    /// 組字或候選有變化，需要更新介面
+    Updated,
+    /// 有文字上屏，以 Engine::take_output 取出
+    Committed,
+    /// 按鍵被拒絕（碼不存在、碼長已滿或沒有候選）
+    Rejected,
+}
+
+impl From<KeyResult> for KeyEventResult {
+    fn from(result: KeyResult) -> Self {
+        match result {
+            KeyResult::NoChange => Self::NoChange,
+            KeyResult::NeedUpdate => Self::Updated,
+            KeyResult::Committed => Self::Committed,
+            KeyResult::Rejected(_) => Self::Rejected,
+        }
+    }
+}
+
+/// 候選字詞
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    text: String,
+    code: String,
+    is_phrase: bool,
+}
+
+impl Candidate {
+    /// 顯示與上屏的文字
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// 對應的行列碼
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// 是否為詞彙
+    pub fn is_phrase(&self) -> bool {
+        self.is_phrase
+    }
+}
+
+impl From<&state::Candidate> for Candidate {
+    fn from(candidate: &state::Candidate) -> Self {
+        Self {
+            text: candidate.text.clone(),
+            code: candidate.code.clone(),
+            is_phrase: candidate.is_phrase,
+        }
+    }
+}
+
+/// 行列輸入引擎
+pub struct Engine {
+    inner: InputEngine,
+}
+
+impl Engine {
+    pub fn new(dict: Dictionary) -> Self {
+        Self::with_options(dict, EngineOptions::default())
+    }
+
+    pub fn with_options(dict: Dictionary, options: EngineOptions) -> Self {
+        Self {
+            inner: InputEngine::with_options(dict, options.inner),
+        }
+    }
+
+    pub fn set_options(&mut self, options: EngineOptions) {
+        self.inner.set_options(options.inner);
+    }
+
+    /// 替換字典
+    pub fn load_dict(&mut self, dict: Dictionary) {
+        self.inner.load_dict(dict);
+    }
+
+    pub fn dict(&self) -> &Dictionary {
+        self.inner.dict()
+    }
+
+    /// 處理一個按鍵：組碼鍵、選字鍵、空白、Enter（\n）、Backspace（\x08）、Esc（\x1b）
+    pub fn handle_key(&mut self, key: char) -> KeyEventResult {
+        self.inner.handle_key(key).into()
+    }
+
+    /// 檢查組字是否閒置逾時（框架的計時器定期呼叫），逾時時依選項清除或上屏
+    pub fn tick(&mut self, now: Instant) -> KeyEventResult {
+        self.inner.tick(now).into()
+    }
+
+    /// 下次需要呼叫 tick 的時間點，沒有組字或未設定逾時時為 None
+    pub fn composition_deadline(&self) -> Option<Instant> {
+        self.inner.composition_deadline()
+    }
+
+    /// 捨棄組字中的碼與候選
+    pub fn reset(&mut self) {
+        if !self.inner.state().current_code.is_empty() {
+            self.inner.handle_key('\x1b');
+        }
+    }
+
+    /// 組字中的碼
+    pub fn code(&self) -> &str {
+        &self.inner.state().current_code
+    }
+
+    /// 目前頁面的候選
+    pub fn candidates(&self) -> Vec<Candidate> {
+        self.inner.current_page_candidates().iter().map(Candidate::from).collect()
+    }
+
+    /// 目前頁面的選字鍵（依序對應 candidates）
+    pub fn selection_keys(&self) -> &'static str {
+        self.inner.options().variant.selection_keys()
+    }
+
+    /// 選擇目前頁面第 `index` 個候選（從 0 開始）並上屏
+    pub fn select_candidate(&mut self, index: usize) -> bool {
+        self.inner.select_candidate(index)
+    }
+
+    /// 目前頁碼與總頁數（從 1 開始），沒有候選時為 None
+    pub fn page(&self) -> Option<(usize, usize)> {
+        self.inner.page_indicator()
+    }
+
+    pub fn next_page(&mut self) -> bool {
+        self.inner.next_page()
+    }
+
+    pub fn prev_page(&mut self) -> bool {
+        self.inner.prev_page()
+    }
+
+    /// 取出已上屏的文字
+    pub fn take_output(&mut self) -> String {
+        self.inner.take_output()
+    }
+
+    pub fn is_english_mode(&self) -> bool {
+        self.inner.is_english_mode()
+    }
+
+    pub fn toggle_english_mode(&mut self) {
+        self.inner.toggle_english_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("ab", "甲");
+        dict.insert_char("ab", "乙");
+        dict.insert_phrase("abcd", "甲乙");
+        let mut engine = Engine::with_options(dict, EngineOptions::new().page_size(1));
+
+        assert_eq!(engine.handle_key('a'), KeyEventResult::Updated);
+        assert_eq!(engine.handle_key('b'), KeyEventResult::Updated);
+        assert_eq!(engine.code(), "ab");
+        assert_eq!(engine.candidates().iter().map(Candidate::text).collect::<Vec<_>>(), ["甲"]);
+        assert_eq!(engine.page(), Some((1, 2)));
+        assert!(engine.next_page());
+        assert!(engine.select_candidate(0));
+        assert_eq!(engine.take_output(), "乙");
+        assert_eq!(engine.page(), None);
+
+        engine.handle_key('a');
+        engine.reset();
+        assert!(engine.code().is_empty() && engine.candidates().is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+// Automatic backups of user data
+// 自動備份：恢復預設、匯入等會覆寫資料的操作之前，將設定與使用者資料打包存到設定目錄的 backups/，之後可從備份還原
+
+use crate::bundle::{self, Bundle, BundleRoot};
+use crate::config::Config;
+use crate::error::{Array30Error, Result};
+use crate::stats;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 備份目錄名稱（位於設定目錄，打包設定時略過）
+pub const BACKUP_DIR: &str = "backups";
+/// 保留的備份數量，超過時刪除最舊的
+const MAX_BACKUPS: usize = 20;
+
+/// 備份的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupReason {
+    ResetSettings,
+    ImportSettings,
+    ImportPhrases,
+    Sync,
+    ClearStats,
+    Restore,
+}
+
+impl BackupReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ResetSettings => "reset",
+            Self::ImportSettings => "import-settings",
+            Self::ImportPhrases => "import-phrases",
+            Self::Sync => "sync",
+            Self::ClearStats => "clear-stats",
+            Self::Restore => "restore",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::ResetSettings => "恢復預設前",
+            Self::ImportSettings => "匯入設定前",
+            Self::ImportPhrases => "匯入詞彙前",
+            Self::Sync => "同步前",
+            Self::ClearStats => "清除統計前",
+            Self::Restore => "還原備份前",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::all().into_iter().find(|reason| reason.as_str() == s)
+    }
+
+    pub fn all() -> [Self; 6] {
+        [
+            Self::ResetSettings,
+            Self::ImportSettings,
+            Self::ImportPhrases,
+            Self::Sync,
+            Self::ClearStats,
+            Self::Restore,
+        ]
+    }
+}
+
+/// 一份備份（檔名為「Unix 秒數-原因.json」）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// 建立時間（Unix 秒數）
+    pub created: u64,
+    pub reason: Option<BackupReason>,
+}
+
+impl Backup {
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?;
+        let (created, reason) = stem.split_once('-').unwrap_or((stem, ""));
+        Some(Self {
+            created: created.parse().ok()?,
+            reason: BackupReason::from_str(reason),
+            path,
+        })
+    }
+
+    /// 「月/日 時:分 原因」（UTC）
+    pub fn label(&self) -> String {
+        format!(
+            "{} {:02}:{:02} {}",
+            stats::day_label(self.created / 86400),
+            self.created % 86400 / 3600,
+            self.created % 3600 / 60,
+            self.reason.map_or("", |reason| reason.display_name())
+        )
+    }
+}
+
+/// 預設的備份目錄
+pub fn backup_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("rustarray30").join(BACKUP_DIR))
+}
+
+/// 在覆寫資料之前備份目前的設定與使用者資料，回傳備份檔路徑
+pub fn create(reason: BackupReason) -> Result<PathBuf> {
+    let dir = backup_dir().ok_or_else(|| Array30Error::ConfigError("無法取得設定目錄".to_string()))?;
+    create_in(&dir, &bundle::default_roots(), Config::config_file_path().as_deref(), reason)
+}
+
+/// 列出預設備份目錄中的備份（新的在前）
+pub fn list() -> Vec<Backup> {
+    backup_dir().map(|dir| list_in(&dir)).unwrap_or_default()
+}
+
+/// 從備份還原（還原前先備份目前的資料），回傳寫入的檔案數
+pub fn restore(backup: &Path) -> Result<usize> {
+    let bundle = Bundle::load(backup)?;
+    create(BackupReason::Restore)?;
+    bundle.import(&bundle::default_roots(), Config::config_file_path().as_deref())
+}
+
+fn create_in(dir: &Path, roots: &[BundleRoot], settings: Option<&Path>, reason: BackupReason) -> Result<PathBuf> {
+    let bundle = Bundle::export(roots, settings)?;
+    std::fs::create_dir_all(dir)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    // 同一秒內的多次備份往後順延，保持新舊順序
+    let created = list_in(dir).first().map_or(now, |newest| now.max(newest.created + 1));
+    let path = dir.join(format!("{}-{}.json", created, reason.as_str()));
+    bundle.save(&path)?;
+    prune(dir, MAX_BACKUPS);
+    Ok(path)
+}
+
+fn list_in(dir: &Path) -> Vec<Backup> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(Backup::from_path)
+        .collect();
+    backups.sort_by_key(|backup| Reverse(backup.created));
+    backups
+}
+
+/// 只保留最新的 `keep` 份備份
+fn prune(dir: &Path, keep: usize) {
+    for backup in list_in(dir).into_iter().skip(keep) {
+        if let Err(e) = std::fs::remove_file(&backup.path) {
+            tracing::warn!(path = %backup.path.display(), "無法刪除舊備份：{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_list_and_prune() {
+        let base = std::env::temp_dir().join(format!("rustarray30-backup-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("data")).unwrap();
+        std::fs::write(base.join("data/user.txt"), "ab\t甲\n").unwrap();
+        let roots = [BundleRoot {
+            prefix: "data",
+            dir: base.join("data"),
+            skip: &[],
+        }];
+        let dir = base.join(BACKUP_DIR);
+
+        let first = create_in(&dir, &roots, None, BackupReason::ImportPhrases).unwrap();
+        std::fs::write(base.join("data/user.txt"), "").unwrap();
+        let second = create_in(&dir, &roots, None, BackupReason::ResetSettings).unwrap();
+        let backups = list_in(&dir);
+        assert_eq!(backups.iter().map(|b| &b.path).collect::<Vec<_>>(), [&second, &first]);
+        assert_eq!(backups[1].reason, Some(BackupReason::ImportPhrases));
+        assert!(backups[1].label().ends_with("匯入詞彙前"));
+
+        // 從較舊的備份還原自訂字詞
+        Bundle::load(&first).unwrap().import(&roots, None).unwrap();
+        assert_eq!(std::fs::read_to_string(base.join("data/user.txt")).unwrap(), "ab\t甲\n");
+
+        prune(&dir, 1);
+        assert_eq!(list_in(&dir), [backups[0].clone()]);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+// Headless batch conversion for Array30
+// 無介面批次轉換
+
+use crate::dict::Dictionary;
+use crate::input_engine::InputEngine;
+
+/// 將鍵序轉換為文字
+/// 以全新的引擎模擬按鍵輸入，結尾未完成的組字會以第一候選上屏
+/// 例如 "ab cd' " 會依序輸入 ab、空白、cd、'、空白
+pub fn convert(dict: Dictionary, keys: &str) -> String {
+    let mut engine = InputEngine::new(dict);
+    convert_with(&mut engine, keys)
+}
+
+/// 使用既有引擎轉換鍵序
+/// 與 `convert` 相同，但保留引擎（及其字典）供後續重複使用
+pub fn convert_with(engine: &mut InputEngine, keys: &str) -> String {
+    let mut output = engine.feed_str(keys);
+    output.push_str(&flush(engine));
+    output
+}
+
+/// 無法轉換的碼的處理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnknownCode {
+    /// 原樣保留
+    #[default]
+    Keep,
+    /// 略過
+    Skip,
+    /// 停止並回報錯誤
+    Error,
+}
+
+impl UnknownCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnknownCode::Keep => "keep",
+            UnknownCode::Skip => "skip",
+            UnknownCode::Error => "error",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "keep" => Some(UnknownCode::Keep),
+            "skip" => Some(UnknownCode::Skip),
+            "error" => Some(UnknownCode::Error),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [UnknownCode; 3] {
+        [UnknownCode::Keep, UnknownCode::Skip, UnknownCode::Error]
+    }
+}
+
+/// 以空白分隔的碼逐一轉換（每個碼為一次組字，結尾加 ' 為詞彙）
+/// 無法轉換的碼原樣保留
+/// 例如 "ab abcd'" 會轉換為「甲測試」
+pub fn convert_tokens(engine: &mut InputEngine, line: &str) -> String {
+    convert_codes(engine, line, None, UnknownCode::Keep).unwrap_or_default()
+}
+
+/// 以指定分隔符號（None 為空白）切開的碼逐一轉換
+/// `unknown` 為 Error 時遇到無法轉換的碼回傳該碼
+pub fn convert_codes(
+    engine: &mut InputEngine,
+    line: &str,
+    delimiter: Option<&str>,
+    unknown: UnknownCode,
+) -> Result<String, String> {
+    let tokens: Box<dyn Iterator<Item = &str>> = match delimiter {
+        Some(delimiter) => Box::new(line.split(delimiter).map(str::trim).filter(|t| !t.is_empty())),
+        None => Box::new(line.split_whitespace()),
+    };
+
+    let mut output = String::new();
+    for token in tokens {
+        let text = convert_with(engine, token);
+        if !text.is_empty() {
+            output.push_str(&text);
+            continue;
+        }
+        match unknown {
+            UnknownCode::Keep => output.push_str(token),
+            UnknownCode::Skip => {}
+            UnknownCode::Error => return Err(token.to_string()),
+        }
+    }
+    Ok(output)
+}
+
+/// 將未完成的組字以第一候選上屏
+/// 沒有候選時捨棄該組字碼
+pub fn flush(engine: &mut InputEngine) -> String {
+    if engine.state().current_code.is_empty() {
+        return String::new();
+    }
+    let output = engine.feed_str(" ");
+    engine.handle_key('\x1b');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_engine::EngineOptions;
+
+    fn create_test_dict() -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.insert_char("ab", "甲");
+        dict.insert_char("cd", "乙");
+        dict.insert_char("cd", "丙");
+        dict.insert_phrase("abcd", "測試");
+        dict
+    }
+
+    #[test]
+    fn test_convert_chars() {
+        assert_eq!(convert(create_test_dict(), "ab cd "), "甲乙");
+        assert_eq!(convert(create_test_dict(), "ab cd2"), "甲丙");
+    }
+
+    #[test]
+    fn test_convert_phrase() {
+        assert_eq!(convert(create_test_dict(), "abcd' ab "), "測試甲");
+    }
+
+    #[test]
+    fn test_convert_flushes_pending_code() {
+        assert_eq!(convert(create_test_dict(), "ab cd"), "甲乙");
+        // 無候選的碼會被捨棄
+        assert_eq!(convert(create_test_dict(), "ab zz"), "甲");
+    }
+
+    #[test]
+    fn test_convert_tokens() {
+        let mut engine = InputEngine::new(create_test_dict());
+        assert_eq!(convert_tokens(&mut engine, "ab  abcd'\tcd"), "甲測試乙");
+        assert_eq!(convert_tokens(&mut engine, "ab zz"), "甲zz");
+        assert_eq!(convert_tokens(&mut engine, ""), "");
+    }
+
+    #[test]
+    fn test_convert_codes() {
+        let mut engine = InputEngine::new(create_test_dict());
+        assert_eq!(
+            convert_codes(&mut engine, "ab,zz, cd", Some(","), UnknownCode::Skip),
+            Ok("甲乙".to_string())
+        );
+        assert_eq!(
+            convert_codes(&mut engine, "ab zz", None, UnknownCode::Error),
+            Err("zz".to_string())
+        );
+    }
+
+    #[test]
+    fn test_convert_with_reuses_engine() {
+        let mut engine = InputEngine::new(create_test_dict());
+        assert_eq!(convert_with(&mut engine, "ab"), "甲");
+        assert_eq!(convert_with(&mut engine, "cd"), "乙");
+        assert_eq!(engine.state().output, "甲乙");
+        assert!(engine.state().current_code.is_empty());
+    }
+
+    #[test]
+    fn test_convert_with_output_limit() {
+        let options = EngineOptions {
+            max_output_chars: 4,
+            ..EngineOptions::default()
+        };
+        let mut engine = InputEngine::with_options(create_test_dict(), options);
+        assert_eq!(convert_with(&mut engine, "ab cd ab cd abcd' "), "甲乙甲乙測試");
+        assert_eq!(convert_with(&mut engine, "cd2ab"), "丙甲");
+        assert!(engine.state().output.chars().count() <= 4);
+    }
+}
@@ -0,0 +1,43 @@
+// Remote dictionary server
+// 遠端字典服務：以 HTTP 提供查碼與反查，供設定 remote_dict_url 的輸入法共用同一份字表與詞庫
+
+use clap::Parser;
+use rustarray30::config::Config;
+use rustarray30::{logging, remote, tables};
+use std::net::TcpListener;
+use std::sync::Arc;
+
+/// 行列 30 遠端字典服務
+#[derive(Parser)]
+#[command(name = "rustarray30-dictd", version)]
+struct Cli {
+    /// 監聽的位址
+    #[arg(long, default_value = "127.0.0.1:8030", value_name = "ADDR")]
+    listen: String,
+
+    /// 設定檔組合名稱（字表位置與輸入法變體）
+    #[arg(long, env = "ARRAY30_PROFILE", value_name = "NAME")]
+    profile: Option<String>,
+
+    /// 使用大字集字表
+    #[arg(short, long)]
+    big: bool,
+
+    /// 顯示較多記錄（可重複）
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    logging::init(cli.verbose, false, None).map_err(|e| format!("無法初始化記錄：{}", e))?;
+    let config = Config::load_profile(cli.profile.as_deref().unwrap_or_default())?;
+    let options = tables::TableOptions::from_config(&config);
+    let use_big_char = cli.big || config.big_char;
+    let dict = tables::load_dictionary(&options, config.array_variant, use_big_char)?;
+
+    let listener = TcpListener::bind(&cli.listen)?;
+    println!("遠端字典服務：http://{}/lookup?code=…、/reverse?text=…", listener.local_addr()?);
+    remote::serve(listener, Arc::new(dict))?;
+    Ok(())
+}
@@ -0,0 +1,179 @@
+// Output buffers
+// 輸出分頁：多份獨立的輸出文件，目前分頁的內容放在引擎的輸出區
+
+use crate::input_engine::InputEngine;
+use crate::state::OutputDoc;
+
+/// 一個輸出分頁
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OutputBuffer {
+    name: String,
+    /// 非目前分頁的內容（目前分頁的內容在引擎中，此處為空）
+    doc: OutputDoc,
+}
+
+impl OutputBuffer {
+    fn new(number: usize) -> Self {
+        Self {
+            name: format!("輸出 {}", number),
+            doc: OutputDoc::default(),
+        }
+    }
+}
+
+/// 輸出分頁（至少保留一個）
+#[derive(Debug, Clone)]
+pub struct OutputBuffers {
+    buffers: Vec<OutputBuffer>,
+    active: usize,
+    /// 下一個新分頁的編號
+    next_number: usize,
+}
+
+impl Default for OutputBuffers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputBuffers {
+    pub fn new() -> Self {
+        Self {
+            buffers: vec![OutputBuffer::new(1)],
+            active: 0,
+            next_number: 2,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// 目前分頁的索引
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    pub fn name(&self, index: usize) -> &str {
+        self.buffers.get(index).map_or("", |b| b.name.as_str())
+    }
+
+    /// 分頁的文字；目前分頁從引擎的輸出區取得
+    pub fn text<'a>(&'a self, index: usize, engine: &'a InputEngine) -> &'a str {
+        if index == self.active {
+            &engine.state().output
+        } else {
+            self.buffers.get(index).map_or("", |b| b.doc.text.as_str())
+        }
+    }
+
+    /// 切換到指定分頁，回傳是否有切換
+    pub fn switch(&mut self, index: usize, engine: &mut InputEngine) -> bool {
+        if index == self.active || index >= self.buffers.len() {
+            return false;
+        }
+        // 目前分頁的內容移出引擎，再把目標分頁的內容換入
+        engine.swap_output(&mut self.buffers[self.active].doc);
+        engine.swap_output(&mut self.buffers[index].doc);
+        self.active = index;
+        true
+    }
+
+    /// 新增空白分頁並切換過去，回傳其索引
+    pub fn add(&mut self, engine: &mut InputEngine) -> usize {
+        self.buffers.push(OutputBuffer::new(self.next_number));
+        self.next_number += 1;
+        let index = self.buffers.len() - 1;
+        self.switch(index, engine);
+        index
+    }
+
+    /// 關閉分頁（最後一個分頁無法關閉），回傳是否有關閉
+    /// 關閉目前分頁時切換到右側（沒有則左側）的分頁
+    pub fn close(&mut self, index: usize, engine: &mut InputEngine) -> bool {
+        if self.buffers.len() <= 1 || index >= self.buffers.len() {
+            return false;
+        }
+        if index == self.active {
+            let next = if index + 1 < self.buffers.len() { index + 1 } else { index - 1 };
+            self.switch(next, engine);
+        }
+        self.buffers.remove(index);
+        if self.active > index {
+            self.active -= 1;
+        }
+        true
+    }
+
+    /// 重新命名分頁；空白名稱不套用
+    pub fn rename(&mut self, index: usize, name: &str) {
+        let name = name.trim();
+        if let Some(buffer) = self.buffers.get_mut(index).filter(|_| !name.is_empty()) {
+            buffer.name = name.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::Dictionary;
+
+    #[test]
+    fn test_switch_keeps_text_and_undo() {
+        let mut engine = InputEngine::new(Dictionary::new());
+        engine.toggle_english_mode();
+        let mut buffers = OutputBuffers::new();
+
+        engine.feed_str("ab");
+        assert_eq!(buffers.add(&mut engine), 1);
+        assert_eq!(buffers.name(1), "輸出 2");
+        assert!(engine.state().output.is_empty());
+        engine.feed_str("xy");
+        assert_eq!(buffers.text(0, &engine), "ab");
+        assert_eq!(buffers.text(1, &engine), "xy");
+
+        assert!(buffers.switch(0, &mut engine));
+        assert_eq!(engine.state().output, "ab");
+        assert!(engine.undo());
+        assert_eq!(engine.state().output, "a");
+
+        assert!(buffers.switch(1, &mut engine));
+        assert!(engine.undo());
+        assert_eq!(engine.state().output, "x");
+    }
+
+    #[test]
+    fn test_close_and_rename() {
+        let mut engine = InputEngine::new(Dictionary::new());
+        engine.toggle_english_mode();
+        let mut buffers = OutputBuffers::new();
+        assert!(!buffers.close(0, &mut engine));
+
+        engine.feed_str("a");
+        buffers.add(&mut engine);
+        engine.feed_str("b");
+        buffers.add(&mut engine);
+        engine.feed_str("c");
+
+        // 關閉左側分頁時目前分頁的索引跟著前移
+        assert!(buffers.close(0, &mut engine));
+        assert_eq!(buffers.active(), 1);
+        assert_eq!(buffers.name(0), "輸出 2");
+
+        // 關閉目前（最右側）分頁時切換到左側
+        assert!(buffers.close(1, &mut engine));
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(engine.state().output, "b");
+
+        buffers.rename(0, "  草稿 ");
+        buffers.rename(0, " ");
+        assert_eq!(buffers.name(0), "草稿");
+        assert_eq!(buffers.add(&mut engine), 1);
+        assert_eq!(buffers.name(1), "輸出 4");
+    }
+}
@@ -0,0 +1,211 @@
+// Settings import/export bundle
+// 設定匯出／匯入：將設定檔、設定檔組合與使用者資料打包為單一檔案，方便在電腦間搬移
+
+use crate::backup::BACKUP_DIR;
+use crate::error::{Array30Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// 封包格式版本
+const BUNDLE_FORMAT: u32 = 1;
+/// 封包中預設設定檔的名稱
+const SETTINGS_ENTRY: &str = "config/settings.ini";
+
+/// 設定封包（JSON 格式，檔案以 "前綴/相對路徑" 為鍵）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: u32,
+    pub files: BTreeMap<String, String>,
+}
+
+/// 打包的目錄：(封包中的前綴, 目錄, 略過的子目錄)
+pub struct BundleRoot {
+    pub prefix: &'static str,
+    pub dir: PathBuf,
+    pub skip: &'static [&'static str],
+}
+
+/// 預設打包的目錄：設定目錄與使用者資料目錄（略過自動備份與可重新安裝的表格）
+pub fn default_roots() -> Vec<BundleRoot> {
+    let mut roots = Vec::new();
+    if let Some(dir) = dirs::config_dir() {
+        roots.push(BundleRoot {
+            prefix: "config",
+            dir: dir.join("rustarray30"),
+            skip: &[BACKUP_DIR],
+        });
+    }
+    if let Some(dir) = dirs::data_dir() {
+        roots.push(BundleRoot {
+            prefix: "data",
+            dir: dir.join("rustarray30"),
+            skip: &["table"],
+        });
+    }
+    roots
+}
+
+/// 遞迴收集目錄下的文字檔
+fn collect_files(dir: &Path, prefix: &str, skip: &[&str], files: &mut BTreeMap<String, String>) -> io::Result<()> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let key = format!("{}/{}", prefix, name);
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if !skip.contains(&name.as_str()) {
+                collect_files(&entry.path(), &key, &[], files)?;
+            }
+        } else if file_type.is_file() {
+            match std::fs::read_to_string(entry.path()) {
+                Ok(content) => {
+                    files.insert(key, content);
+                }
+                Err(e) => tracing::warn!(path = %entry.path().display(), "略過無法讀取的檔案：{}", e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 將封包中的鍵轉為目標路徑；拒絕絕對路徑與 ..
+fn resolve_entry(roots: &[BundleRoot], key: &str) -> Option<PathBuf> {
+    let (prefix, rest) = key.split_once('/')?;
+    let root = roots.iter().find(|root| root.prefix == prefix)?;
+    let relative = Path::new(rest);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    Some(root.dir.join(relative))
+}
+
+impl Bundle {
+    /// 收集各目錄的檔案；`settings` 為目前使用的設定檔（可能不在設定目錄中）
+    pub fn export(roots: &[BundleRoot], settings: Option<&Path>) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        for root in roots {
+            collect_files(&root.dir, root.prefix, root.skip, &mut files)?;
+        }
+        if let Some(content) = settings.and_then(|path| std::fs::read_to_string(path).ok()) {
+            files.insert(SETTINGS_ENTRY.to_string(), content);
+        }
+        Ok(Self {
+            format: BUNDLE_FORMAT,
+            files,
+        })
+    }
+
+    /// 將封包內容寫回各目錄，回傳寫入的檔案數
+    /// 預設設定檔寫到 `settings`（未指定時寫到設定目錄）
+    pub fn import(&self, roots: &[BundleRoot], settings: Option<&Path>) -> Result<usize> {
+        if self.format > BUNDLE_FORMAT {
+            return Err(Array30Error::ConfigError(format!("不支援的封包版本：{}", self.format)));
+        }
+
+        // 先檢查全部路徑，避免只匯入一部分
+        let mut targets = Vec::new();
+        for (key, content) in &self.files {
+            let path = match (key.as_str(), settings) {
+                (SETTINGS_ENTRY, Some(settings)) => settings.to_path_buf(),
+                _ => resolve_entry(roots, key)
+                    .ok_or_else(|| Array30Error::ConfigError(format!("無效的封包項目：{}", key)))?,
+            };
+            targets.push((path, content));
+        }
+
+        for (path, content) in &targets {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(path, content)?;
+        }
+        Ok(targets.len())
+    }
+
+    /// 從檔案讀取封包
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| Array30Error::ParseError {
+            path: path.to_path_buf(),
+            line: e.line(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// 將封包寫入檔案
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_roots(base: &Path) -> Vec<BundleRoot> {
+        vec![
+            BundleRoot {
+                prefix: "config",
+                dir: base.join("config"),
+                skip: &[],
+            },
+            BundleRoot {
+                prefix: "data",
+                dir: base.join("data"),
+                skip: &["table"],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let base = std::env::temp_dir().join(format!("rustarray30-bundle-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("config/profiles")).unwrap();
+        std::fs::create_dir_all(base.join("data/table")).unwrap();
+        std::fs::write(base.join("config/profiles/work.ini"), "big_char=true").unwrap();
+        std::fs::write(base.join("data/table/big.cin2"), "略過").unwrap();
+        std::fs::write(base.join("local.ini"), "font_size=30").unwrap();
+
+        let roots = test_roots(&base);
+        let bundle = Bundle::export(&roots, Some(&base.join("local.ini"))).unwrap();
+        assert_eq!(bundle.files.len(), 2);
+        assert_eq!(bundle.files["config/settings.ini"], "font_size=30");
+
+        let file = base.join("bundle.json");
+        bundle.save(&file).unwrap();
+        let loaded = Bundle::load(&file).unwrap();
+        assert_eq!(loaded, bundle);
+
+        let target = base.join("other");
+        let count = loaded.import(&test_roots(&target), None).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(
+            std::fs::read_to_string(target.join("config/profiles/work.ini")).unwrap(),
+            "big_char=true"
+        );
+        assert!(target.join("config/settings.ini").exists());
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_import_rejects_unsafe_paths() {
+        let roots = test_roots(Path::new("/nonexistent"));
+        assert!(resolve_entry(&roots, "config/../escape").is_none());
+        assert!(resolve_entry(&roots, "config//etc/passwd").is_none());
+        assert!(resolve_entry(&roots, "other/file").is_none());
+        assert!(resolve_entry(&roots, "data/user.txt").is_some());
+
+        let mut bundle = Bundle::export(&[], None).unwrap();
+        bundle.files.insert("config/../x".to_string(), String::new());
+        assert!(bundle.import(&roots, None).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+// C API for embedding the engine
+// C 介面：供 C/C++ 輸入法框架與編輯器嵌入行列 30 引擎
+//
+// 標頭檔 include/rustarray30.h 由 cbindgen 產生：
+//   cbindgen --config cbindgen.toml --output include/rustarray30.h
+//
+// 回傳的字串與陣列由引擎持有，在下一次呼叫同一個取得函式前有效。
+
+use crate::dict::Dictionary;
+use crate::error::Result;
+use crate::input_engine::{InputEngine, KeyResult};
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+/// 按鍵處理結果
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Array30KeyResult {
+    /// 無變化
+    NoChange = 0,
+    /// 需要更新介面顯示
+    NeedUpdate = 1,
+    /// 已確認輸出，可呼叫 array30_take_commit 取出
+    Committed = 2,
+    /// 按鍵被拒絕（錯碼、超過碼長或無候選可確認）
+    Rejected = 3,
+}
+
+impl From<KeyResult> for Array30KeyResult {
+    fn from(result: KeyResult) -> Self {
+        match result {
+            KeyResult::NoChange => Array30KeyResult::NoChange,
+            KeyResult::NeedUpdate => Array30KeyResult::NeedUpdate,
+            KeyResult::Committed => Array30KeyResult::Committed,
+            KeyResult::Rejected(_) => Array30KeyResult::Rejected,
+        }
+    }
+}
+
+/// 引擎把手（對 C 而言為不透明型別）
+pub struct Array30Engine {
+    engine: InputEngine,
+    commit: CString,
+    code: CString,
+    candidates: Vec<CString>,
+    candidate_ptrs: Vec<*const c_char>,
+}
+
+impl Array30Engine {
+    fn new(engine: InputEngine) -> Self {
+        Self {
+            engine,
+            commit: CString::default(),
+            code: CString::default(),
+            candidates: Vec::new(),
+            candidate_ptrs: Vec::new(),
+        }
+    }
+}
+
+/// 轉為 C 字串（移除內含的 NUL 字元）
+fn to_cstring(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+/// 將 C 字串轉為路徑；null 或非 UTF-8 時回傳 None
+unsafe fn to_path<'a>(s: *const c_char) -> Option<&'a Path> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok().map(Path::new)
+}
+
+/// 載入詞庫與字表；有設定檔與使用者資料（feature "cli"）時一併套用自訂字詞與語料詞頻
+fn load_dict(phrase_file: Option<&Path>, char_file: &Path) -> Result<Dictionary> {
+    #[cfg(feature = "cli")]
+    return crate::tables::load_files(phrase_file, char_file);
+    #[cfg(not(feature = "cli"))]
+    Dictionary::load_tables(phrase_file, char_file)
+}
+
+/// 載入字表建立引擎
+/// `phrase_file` 可為 null（不載入詞庫）；載入失敗回傳 null
+///
+/// # Safety
+/// `char_file` 必須為有效的 NUL 結尾 UTF-8 字串，`phrase_file` 為 null 或有效字串
+#[no_mangle]
+pub unsafe extern "C" fn array30_engine_new(char_file: *const c_char, phrase_file: *const c_char) -> *mut Array30Engine {
+    let Some(char_file) = to_path(char_file) else {
+        return ptr::null_mut();
+    };
+    match load_dict(to_path(phrase_file), char_file) {
+        Ok(dict) => Box::into_raw(Box::new(Array30Engine::new(InputEngine::new(dict)))),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// 釋放引擎
+///
+/// # Safety
+/// `engine` 必須為 `array30_engine_new` 回傳的指標或 null，且之後不再使用
+#[no_mangle]
+pub unsafe extern "C" fn array30_engine_free(engine: *mut Array30Engine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// 處理按鍵（Unicode 字元；退格為 0x08、Enter 為 0x0A、Esc 為 0x1B）
+///
+/// # Safety
+/// `engine` 必須為有效的引擎指標或 null
+#[no_mangle]
+pub unsafe extern "C" fn array30_handle_key(engine: *mut Array30Engine, key: u32) -> Array30KeyResult {
+    match (engine.as_mut(), char::from_u32(key)) {
+        (Some(e), Some(c)) => e.engine.handle_key(c).into(),
+        _ => Array30KeyResult::NoChange,
+    }
+}
+
+/// 取得目前頁面的候選字詞
+/// 回傳字串指標陣列，數量寫入 `count`
+///
+/// # Safety
+/// `engine` 必須為有效的引擎指標或 null；`count` 必須為有效指標或 null
+#[no_mangle]
+pub unsafe extern "C" fn array30_get_candidates(engine: *mut Array30Engine, count: *mut usize) -> *const *const c_char {
+    let Some(e) = engine.as_mut() else {
+        if !count.is_null() {
+            *count = 0;
+        }
+        return ptr::null();
+    };
+
+    e.candidates = e
+        .engine
+        .current_page_candidates()
+        .iter()
+        .map(|cand| to_cstring(&cand.text))
+        .collect();
+    e.candidate_ptrs = e.candidates.iter().map(|s| s.as_ptr()).collect();
+    if !count.is_null() {
+        *count = e.candidate_ptrs.len();
+    }
+    e.candidate_ptrs.as_ptr()
+}
+
+/// 選擇目前頁面第 `index` 個候選
+///
+/// # Safety
+/// `engine` 必須為有效的引擎指標或 null
+#[no_mangle]
+pub unsafe extern "C" fn array30_select_candidate(engine: *mut Array30Engine, index: usize) -> bool {
+    engine.as_mut().is_some_and(|e| e.engine.select_candidate(index))
+}
+
+/// 取得目前輸入的碼
+///
+/// # Safety
+/// `engine` 必須為有效的引擎指標或 null
+#[no_mangle]
+pub unsafe extern "C" fn array30_get_code(engine: *mut Array30Engine) -> *const c_char {
+    match engine.as_mut() {
+        Some(e) => {
+            e.code = to_cstring(&e.engine.state().current_code);
+            e.code.as_ptr()
+        }
+        None => ptr::null(),
+    }
+}
+
+/// 取出已上屏的文字（沒有時為空字串）
+///
+/// # Safety
+/// `engine` 必須為有效的引擎指標或 null
+#[no_mangle]
+pub unsafe extern "C" fn array30_take_commit(engine: *mut Array30Engine) -> *const c_char {
+    match engine.as_mut() {
+        Some(e) => {
+            e.commit = to_cstring(&e.engine.take_output());
+            e.commit.as_ptr()
+        }
+        None => ptr::null(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_flow() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("ab", "甲");
+        dict.insert_char("ab", "乙");
+        let engine = Box::into_raw(Box::new(Array30Engine::new(InputEngine::new(dict))));
+
+        unsafe {
+            assert_eq!(array30_handle_key(engine, 'a' as u32), Array30KeyResult::NeedUpdate);
+            array30_handle_key(engine, 'b' as u32);
+            assert_eq!(CStr::from_ptr(array30_get_code(engine)).to_str().unwrap(), "ab");
+
+            let mut count = 0;
+            let candidates = array30_get_candidates(engine, &mut count);
+            assert_eq!(count, 2);
+            assert_eq!(CStr::from_ptr(*candidates.add(1)).to_str().unwrap(), "乙");
+
+            assert!(array30_select_candidate(engine, 0));
+            assert_eq!(CStr::from_ptr(array30_take_commit(engine)).to_str().unwrap(), "甲");
+            assert_eq!(CStr::from_ptr(array30_take_commit(engine)).to_str().unwrap(), "");
+
+            array30_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn test_null_arguments() {
+        unsafe {
+            assert!(array30_engine_new(ptr::null(), ptr::null()).is_null());
+            assert_eq!(array30_handle_key(ptr::null_mut(), 'a' as u32), Array30KeyResult::NoChange);
+            let mut count = 5;
+            assert!(array30_get_candidates(ptr::null_mut(), &mut count).is_null());
+            assert_eq!(count, 0);
+            array30_engine_free(ptr::null_mut());
+        }
+    }
+}
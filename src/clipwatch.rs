@@ -0,0 +1,85 @@
+// Clipboard watching for reverse annotation
+// 剪貼簿監看：定期讀取系統剪貼簿，複製了新的中文文字時交給反查標註顯示行列碼
+
+use std::time::{Duration, Instant};
+
+/// 讀取剪貼簿的間隔
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 是否含有漢字（基本區、擴充區與相容區）
+pub fn has_chinese(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(c,
+            '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{323AF}')
+    })
+}
+
+/// 剪貼簿監看狀態
+#[derive(Default)]
+pub struct ClipboardWatch {
+    clipboard: Option<arboard::Clipboard>,
+    /// 無法開啟剪貼簿後不再重試
+    unavailable: bool,
+    /// 上次讀到（或自己複製）的文字
+    last_text: Option<String>,
+    last_poll: Option<Instant>,
+}
+
+impl ClipboardWatch {
+    /// 記住自己複製到剪貼簿的文字，之後讀到時不算新內容
+    pub fn ignore(&mut self, text: &str) {
+        self.last_text = Some(text.to_string());
+    }
+
+    /// 到了讀取間隔時讀取剪貼簿，有新的中文文字時回傳
+    pub fn poll(&mut self) -> Option<String> {
+        if self.unavailable || self.last_poll.is_some_and(|at| at.elapsed() < POLL_INTERVAL) {
+            return None;
+        }
+        self.last_poll = Some(Instant::now());
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => {
+                    tracing::warn!("無法開啟剪貼簿，停止監看：{}", e);
+                    self.unavailable = true;
+                    return None;
+                }
+            }
+        }
+        let text = self.clipboard.as_mut()?.get_text().ok()?;
+        self.update(text)
+    }
+
+    /// 比對剪貼簿文字：與上次不同且含有漢字時回傳
+    fn update(&mut self, text: String) -> Option<String> {
+        if self.last_text.as_deref() == Some(text.as_str()) {
+            return None;
+        }
+        self.last_text = Some(text.clone());
+        has_chinese(&text).then_some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update() {
+        assert!(has_chinese("abc 行列"));
+        assert!(has_chinese("\u{20000}"));
+        assert!(!has_chinese("abc，。ㄅ"));
+
+        let mut watch = ClipboardWatch::default();
+        assert_eq!(watch.update("行列".to_string()), Some("行列".to_string()));
+        assert_eq!(watch.update("行列".to_string()), None);
+        assert_eq!(watch.update("hello".to_string()), None);
+        watch.ignore("輸出");
+        assert_eq!(watch.update("輸出".to_string()), None);
+        assert_eq!(watch.update("行列".to_string()), Some("行列".to_string()));
+    }
+}
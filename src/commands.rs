@@ -0,0 +1,209 @@
+// Console command prompt
+// 終端機指令列：不離開打字畫面即可查碼、反查、加入自訂字詞與查看字典統計
+
+use crate::dict::Dictionary;
+use crate::input_engine::{CandidateFilter, InputEngine};
+use crate::userdict::{UserDict, UserEntry};
+
+/// 指令列說明
+pub const HELP: &[&str] = &[
+    ":lookup <碼>          列出碼對應的字與詞（:l）",
+    ":reverse <文字>       反查字詞的行列碼（:r）",
+    ":adduser <碼> <字詞>  加入自訂字詞並存檔（:add）",
+    ":stats                字典統計",
+    ":filter [篩選]        設定候選篩選，不加參數列出可用的篩選",
+    ":tables [目錄|embedded]  找不到表格時重新載入，可指定表格目錄或安裝內建表格",
+    ":help                 顯示本說明（:h）",
+    "Enter 執行，Esc 取消",
+];
+
+/// 指令列的指令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    Lookup(String),
+    Reverse(String),
+    AddUser { code: String, text: String },
+    Stats,
+    /// 候選篩選，None 為顯示目前的設定
+    Filter(Option<CandidateFilter>),
+    /// 重新載入表格：指定的表格目錄或 "embedded"，None 為原位置重試（由終端機介面處理）
+    Tables(Option<String>),
+    Help,
+}
+
+impl ConsoleCommand {
+    /// 解析指令（不含開頭的 :），錯誤時回傳說明
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, args) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(name, args)| (name, args.trim()));
+        let require = |usage: &str| if args.is_empty() { Err(format!("用法：{}", usage)) } else { Ok(args) };
+        match name {
+            "lookup" | "l" => require(":lookup <碼>").map(|code| Self::Lookup(code.to_lowercase())),
+            "reverse" | "r" => require(":reverse <文字>").map(|text| Self::Reverse(text.to_string())),
+            "adduser" | "add" => match args.split_once(char::is_whitespace) {
+                Some((code, text)) => Ok(Self::AddUser {
+                    code: code.to_string(),
+                    text: text.trim().to_string(),
+                }),
+                None => Err("用法：:adduser <碼> <字詞>".to_string()),
+            },
+            "stats" => Ok(Self::Stats),
+            "filter" => match args {
+                "" => Ok(Self::Filter(None)),
+                name => CandidateFilter::from_str(name)
+                    .map(|filter| Self::Filter(Some(filter)))
+                    .ok_or_else(|| format!("未知的篩選：{}（輸入 :filter 查看可用的篩選）", name)),
+            },
+            "tables" => Ok(Self::Tables((!args.is_empty()).then(|| args.to_string()))),
+            "help" | "h" | "?" => Ok(Self::Help),
+            _ => Err(format!("未知的指令：{}（輸入 :help 查看說明）", name)),
+        }
+    }
+
+    /// 執行指令，回傳要顯示的各行；加入的自訂字詞只更新記憶體，由呼叫端存檔
+    pub fn execute(&self, engine: &mut InputEngine, user_dict: &mut UserDict) -> Result<Vec<String>, String> {
+        match self {
+            Self::Lookup(code) => Ok(lookup_lines(engine.dict(), code)),
+            Self::Reverse(text) => Ok(reverse_lines(engine.dict(), text)),
+            Self::AddUser { code, text } => {
+                let entry = UserEntry::new(code, text);
+                let options = engine.options();
+                user_dict.validate(&entry, None, options.variant, options.max_code_len(), engine.dict())?;
+                let line = format!("已加入{}：{} {}", entry.kind().display_name(), entry.code, entry.text);
+                user_dict.add(entry, engine.dict_mut());
+                Ok(vec![line])
+            }
+            Self::Stats => {
+                let mut lines = stats_lines(engine.dict());
+                lines.push(format!("自訂字詞：{}", user_dict.entries().len()));
+                Ok(lines)
+            }
+            Self::Filter(Some(filter)) => {
+                engine.set_candidate_filter(*filter);
+                Ok(vec![format!("候選篩選：{}", filter.display_name())])
+            }
+            Self::Filter(None) => {
+                let current = engine.options().candidate_filter;
+                Ok(CandidateFilter::all()
+                    .iter()
+                    .map(|filter| {
+                        let mark = if *filter == current { "＊" } else { "　" };
+                        format!("{}{} {}", mark, filter.as_str(), filter.display_name())
+                    })
+                    .collect())
+            }
+            Self::Tables(_) => Err("表格已載入".to_string()),
+            Self::Help => Ok(HELP.iter().map(|line| line.to_string()).collect()),
+        }
+    }
+}
+
+/// 碼對應的字與詞，各列出序號與字詞
+pub fn lookup_lines(dict: &Dictionary, code: &str) -> Vec<String> {
+    let list = |words: &[String]| {
+        words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| format!("{}{}", i + 1, word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let chars = dict.lookup_chars(code).unwrap_or_default();
+    let phrases = dict.lookup_phrases(code).unwrap_or_default();
+    if chars.is_empty() && phrases.is_empty() {
+        return vec![format!("{}：查無字詞", code)];
+    }
+    let mut lines = Vec::new();
+    if !chars.is_empty() {
+        lines.push(format!("{} 單字：{}", code, list(chars)));
+    }
+    if !phrases.is_empty() {
+        lines.push(format!("{} 詞彙：{}", code, list(phrases)));
+    }
+    lines
+}
+
+/// 逐字列出行列碼；整段文字為詞時先列出詞碼
+pub fn reverse_lines(dict: &Dictionary, text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    if text.chars().count() > 1 {
+        let codes = dict.reverse_lookup(text);
+        if !codes.is_empty() {
+            lines.push(format!("{}：{}", text, codes.join(" ")));
+        }
+    }
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        let codes = dict.reverse_lookup(&c.to_string());
+        if codes.is_empty() {
+            lines.push(format!("{}：查無字碼", c));
+        } else {
+            lines.push(format!("{}：{}", c, codes.join(" ")));
+        }
+    }
+    lines
+}
+
+/// 字典統計
+pub fn stats_lines(dict: &Dictionary) -> Vec<String> {
+    let (char_count, phrase_count) = dict.stats();
+    vec![format!("字碼數：{}", char_count), format!("詞碼數：{}", phrase_count)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ConsoleCommand::parse("lookup AB"), Ok(ConsoleCommand::Lookup("ab".to_string())));
+        assert_eq!(ConsoleCommand::parse(" r  行列 "), Ok(ConsoleCommand::Reverse("行列".to_string())));
+        assert_eq!(
+            ConsoleCommand::parse("adduser x;cg 台灣 加油"),
+            Ok(ConsoleCommand::AddUser {
+                code: "x;cg".to_string(),
+                text: "台灣 加油".to_string()
+            })
+        );
+        assert_eq!(ConsoleCommand::parse("stats"), Ok(ConsoleCommand::Stats));
+        assert_eq!(
+            ConsoleCommand::parse("filter chars_only"),
+            Ok(ConsoleCommand::Filter(Some(CandidateFilter::CharsOnly)))
+        );
+        assert_eq!(ConsoleCommand::parse("filter"), Ok(ConsoleCommand::Filter(None)));
+        assert_eq!(ConsoleCommand::parse("tables"), Ok(ConsoleCommand::Tables(None)));
+        assert_eq!(
+            ConsoleCommand::parse("tables embedded"),
+            Ok(ConsoleCommand::Tables(Some("embedded".to_string())))
+        );
+        assert!(ConsoleCommand::parse("filter rare").is_err());
+        assert!(ConsoleCommand::parse("lookup").unwrap_err().starts_with("用法"));
+        assert!(ConsoleCommand::parse("adduser ab").unwrap_err().starts_with("用法"));
+        assert!(ConsoleCommand::parse("bogus").unwrap_err().contains("bogus"));
+    }
+
+    #[test]
+    fn test_execute() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("a", "一");
+        let mut engine = InputEngine::new(dict);
+        let mut user_dict = UserDict::default();
+
+        let lookup = ConsoleCommand::Lookup("a".to_string());
+        assert_eq!(lookup.execute(&mut engine, &mut user_dict), Ok(vec!["a 單字：1一".to_string()]));
+
+        let add = ConsoleCommand::parse("add ab 二").unwrap();
+        assert_eq!(add.execute(&mut engine, &mut user_dict), Ok(vec!["已加入字表：ab 二".to_string()]));
+        assert_eq!(engine.dict().reverse_lookup("二"), ["ab"]);
+        assert!(add.execute(&mut engine, &mut user_dict).is_err());
+
+        let reverse = ConsoleCommand::Reverse("一三".to_string());
+        assert_eq!(
+            reverse.execute(&mut engine, &mut user_dict),
+            Ok(vec!["一：a".to_string(), "三：查無字碼".to_string()])
+        );
+        let stats = ConsoleCommand::Stats.execute(&mut engine, &mut user_dict).unwrap();
+        assert_eq!(stats.last().map(String::as_str), Some("自訂字詞：1"));
+    }
+}
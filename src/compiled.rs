@@ -0,0 +1,353 @@
+// Compiled table format for memory-mapped loading
+// 編譯字表：依碼排序的索引加上字詞文字區，啟動時直接記憶體映射、以二分搜尋查碼，不需逐行解析或建立雜湊表
+//
+// 檔案格式（整數皆為 little-endian u32，位置相對於文字區開頭）：
+//   識別碼 "A30TBL02"
+//   名稱的位置與長度、文字區的位置與長度
+//   六個區段（單字、詞彙、簡碼選單、造詞取碼、單字詞頻、詞彙詞頻）各自的索引位置與筆數
+//   索引：每筆為鍵的位置、長度與值的位置、長度，依鍵排序
+//   文字區：UTF-8，同一碼下的字詞以換行分隔；詞頻區段的值為「字詞\t詞頻」
+
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::ops::{Bound, Deref, Range};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// 編譯字表的副檔名
+pub const EXTENSION: &str = "a30t";
+/// 同一碼下字詞之間的分隔（字詞不會含換行）
+pub const SEPARATOR: char = '\n';
+/// 檔頭的識別碼與版本
+const MAGIC: &[u8; 8] = b"A30TBL02";
+const SECTION_COUNT: usize = 6;
+/// 檔頭：識別碼、名稱、文字區與各區段索引的位置
+const HEADER_LEN: usize = MAGIC.len() + 16 + SECTION_COUNT * 8;
+/// 每筆索引：鍵與值的位置與長度
+const ENTRY_LEN: usize = 16;
+
+/// 一個區段的內容：(鍵, 同一鍵下的字詞)
+pub type SectionEntries<'a> = Vec<(&'a str, Vec<&'a str>)>;
+
+/// 編譯字表的區段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// 單字碼 -> 字
+    Chars,
+    /// 詞彙碼 -> 詞
+    Phrases,
+    /// 簡碼選單：碼 -> 十個字
+    Quick,
+    /// 字 -> 造詞取碼用的字碼
+    PrimaryCodes,
+    /// 單字碼 -> 有詞頻的字與詞頻
+    CharFrequencies,
+    /// 詞彙碼 -> 有詞頻的詞與詞頻
+    PhraseFrequencies,
+}
+
+impl Section {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 是否為編譯字表的檔名
+pub fn is_compiled(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+/// 寫出編譯字表；各區段的 (鍵, 值) 不需事先排序，值為同一鍵下的字詞
+/// 先寫到同目錄的暫存檔再改名取代，執行中的輸入法映射的舊檔內容不受影響
+pub fn write(path: &Path, name: &str, sections: [SectionEntries; SECTION_COUNT]) -> io::Result<()> {
+    let too_large = || io::Error::new(io::ErrorKind::InvalidData, "字表超過編譯字表的大小上限");
+    let offset = |n: usize| u32::try_from(n).map_err(|_| too_large());
+
+    let mut blob = String::from(name);
+    let mut indexes = Vec::with_capacity(SECTION_COUNT);
+    for mut entries in sections {
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries.dedup_by(|a, b| a.0 == b.0);
+        let mut index = Vec::with_capacity(entries.len() * ENTRY_LEN);
+        for (key, words) in entries {
+            let value = words.join(&SEPARATOR.to_string());
+            for (start, text) in [(blob.len(), key), (blob.len() + key.len(), value.as_str())] {
+                index.extend(offset(start)?.to_le_bytes());
+                index.extend(offset(text.len())?.to_le_bytes());
+            }
+            blob.push_str(key);
+            blob.push_str(&value);
+        }
+        indexes.push(index);
+    }
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend(MAGIC);
+    let index_len: usize = indexes.iter().map(Vec::len).sum();
+    let blob_start = HEADER_LEN + index_len;
+    for n in [0, name.len(), blob_start, blob.len()] {
+        header.extend(offset(n)?.to_le_bytes());
+    }
+    let mut index_start = HEADER_LEN;
+    for index in &indexes {
+        header.extend(offset(index_start)?.to_le_bytes());
+        header.extend(offset(index.len() / ENTRY_LEN)?.to_le_bytes());
+        index_start += index.len();
+    }
+
+    let mut bytes = header;
+    bytes.extend(indexes.concat());
+    bytes.extend(blob.as_bytes());
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = std::path::PathBuf::from(temp);
+    std::fs::write(&temp, bytes)
+        .and_then(|()| std::fs::rename(&temp, path))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp);
+        })
+}
+
+/// 編譯字表的內容：記憶體映射的檔案或讀入的位元組
+enum Data {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for Data {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Data::Mapped(map) => map,
+            Data::Owned(bytes) => bytes,
+        }
+    }
+}
+
+/// 每個碼解碼後的字詞，第一次查詢時才填入
+type DecodedSlots = Box<[OnceLock<Box<[String]>>]>;
+
+/// 開啟的編譯字表
+pub struct CompiledTable {
+    data: Data,
+    blob: Range<usize>,
+    name: Range<usize>,
+    /// 各區段索引的開頭與筆數
+    sections: [(usize, usize); SECTION_COUNT],
+    /// 各區段的字詞總數
+    word_counts: [usize; SECTION_COUNT],
+    /// 單字與詞彙解碼成字串陣列的結果，每個碼第一次查詢時才解碼
+    decoded: [DecodedSlots; 2],
+}
+
+impl fmt::Debug for CompiledTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompiledTable")
+            .field("name", &self.name())
+            .field("sections", &self.sections)
+            .field("bytes", &self.data.len())
+            .finish()
+    }
+}
+
+impl CompiledTable {
+    /// 以記憶體映射開啟編譯字表
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: 以唯讀方式映射；開啟時檢查全部索引與 UTF-8，之後只讀取。
+        // 使用期間檔案不應被改寫（write 以改名取代整個檔案，不會就地修改）
+        let map = unsafe { Mmap::map(&file)? };
+        Self::parse(Data::Mapped(map))
+    }
+
+    /// 從位元組讀取編譯字表
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<Self> {
+        Self::parse(Data::Owned(bytes))
+    }
+
+    /// 檢查檔頭、索引範圍與鍵的順序
+    fn parse(data: Data) -> io::Result<Self> {
+        let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, format!("編譯字表格式錯誤：{}", reason));
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(invalid("不是編譯字表或版本不符"));
+        }
+        let header: Vec<usize> = data[MAGIC.len()..HEADER_LEN].chunks_exact(4).map(|b| read_u32(b, 0)).collect();
+        let blob = header[2]..header[2].saturating_add(header[3]);
+        let text = data.get(blob.clone()).ok_or_else(|| invalid("文字區超出檔案範圍"))?;
+        let text = std::str::from_utf8(text).map_err(|_| invalid("文字區不是 UTF-8"))?;
+        let slice = |start: usize, len: usize| text.get(start..start.saturating_add(len));
+        let name = header[0]..header[0].saturating_add(header[1]);
+        slice(name.start, name.len()).ok_or_else(|| invalid("名稱超出文字區"))?;
+
+        let mut sections = [(0, 0); SECTION_COUNT];
+        let mut word_counts = [0; SECTION_COUNT];
+        for (i, pair) in header[4..].chunks_exact(2).enumerate() {
+            let (start, count) = (pair[0], pair[1]);
+            let index = data
+                .get(start..start.saturating_add(count.saturating_mul(ENTRY_LEN)))
+                .ok_or_else(|| invalid("索引超出檔案範圍"))?;
+            let mut previous: Option<&str> = None;
+            for entry in index.chunks_exact(ENTRY_LEN) {
+                let key = slice(read_u32(entry, 0), read_u32(entry, 4)).ok_or_else(|| invalid("鍵超出文字區"))?;
+                let value = slice(read_u32(entry, 8), read_u32(entry, 12)).ok_or_else(|| invalid("值超出文字區"))?;
+                if previous.is_some_and(|previous| previous >= key) {
+                    return Err(invalid("索引未依鍵排序"));
+                }
+                previous = Some(key);
+                word_counts[i] += value.split(SEPARATOR).count();
+            }
+            sections[i] = (start, count);
+        }
+
+        let slots = |count: usize| (0..count).map(|_| OnceLock::new()).collect();
+        Ok(Self {
+            decoded: [slots(sections[0].1), slots(sections[1].1)],
+            data,
+            blob,
+            name,
+            sections,
+            word_counts,
+        })
+    }
+
+    fn text(&self) -> &str {
+        let bytes = &self.data[self.blob.clone()];
+        // SAFETY: parse 已確認文字區為 UTF-8，內容不會改變
+        unsafe { std::str::from_utf8_unchecked(bytes) }
+    }
+
+    /// 字表名稱
+    pub fn name(&self) -> &str {
+        &self.text()[self.name.clone()]
+    }
+
+    /// 區段的筆數
+    pub fn len(&self, section: Section) -> usize {
+        self.sections[section.index()].1
+    }
+
+    /// 區段的字詞總數
+    pub fn word_count(&self, section: Section) -> usize {
+        self.word_counts[section.index()]
+    }
+
+    fn entry(&self, section: Section, i: usize) -> &[u8] {
+        let start = self.sections[section.index()].0 + i * ENTRY_LEN;
+        &self.data[start..start + ENTRY_LEN]
+    }
+
+    /// 第 i 筆的鍵
+    pub fn key(&self, section: Section, i: usize) -> &str {
+        let entry = self.entry(section, i);
+        let start = read_u32(entry, 0);
+        &self.text()[start..start + read_u32(entry, 4)]
+    }
+
+    /// 第 i 筆的值：以換行分隔的字詞
+    pub fn value(&self, section: Section, i: usize) -> &str {
+        let entry = self.entry(section, i);
+        let start = read_u32(entry, 8);
+        &self.text()[start..start + read_u32(entry, 12)]
+    }
+
+    /// 第一個符合範圍下界的位置
+    pub fn lower_bound(&self, section: Section, bound: Bound<&str>) -> usize {
+        let (mut low, mut high) = (0, self.len(section));
+        while low < high {
+            let mid = (low + high) / 2;
+            let below = match bound {
+                Bound::Included(key) => self.key(section, mid) < key,
+                Bound::Excluded(key) => self.key(section, mid) <= key,
+                Bound::Unbounded => false,
+            };
+            if below {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// 以二分搜尋找出鍵的位置
+    pub fn find(&self, section: Section, key: &str) -> Option<usize> {
+        let i = self.lower_bound(section, Bound::Included(key));
+        (i < self.len(section) && self.key(section, i) == key).then_some(i)
+    }
+
+    /// 單字或詞彙第 i 筆的字詞，第一次查詢時解碼
+    pub fn decoded(&self, section: Section, i: usize) -> &[String] {
+        let slots = match section {
+            Section::Chars => &self.decoded[0],
+            Section::Phrases => &self.decoded[1],
+            _ => return &[],
+        };
+        slots[i].get_or_init(|| self.value(section, i).split(SEPARATOR).map(String::from).collect())
+    }
+
+    /// 詞頻區段中碼下字詞的詞頻
+    pub fn frequency(&self, section: Section, code: &str, word: &str) -> Option<u32> {
+        let value = self.value(section, self.find(section, code)?);
+        value.split(SEPARATOR).find_map(|entry| {
+            let (w, freq) = entry.rsplit_once('\t')?;
+            if w == word {
+                freq.parse().ok()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> usize {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_open() {
+        let path = std::env::temp_dir().join(format!("rustarray30-compiled-{}.a30t", std::process::id()));
+        let chars = vec![("b", vec!["乙"]), ("a", vec!["甲", "由"]), ("ab", vec!["申"])];
+        let phrases = vec![("x;cg", vec!["台灣"])];
+        let freqs = vec![("a", vec!["由\t3"])];
+        write(&path, "測試", [chars, phrases, Vec::new(), vec![("甲", vec!["a"])], freqs, Vec::new()]).unwrap();
+
+        let table = CompiledTable::open(&path).unwrap();
+        assert!(is_compiled(&path));
+        assert_eq!(table.name(), "測試");
+        assert_eq!((table.len(Section::Chars), table.word_count(Section::Chars)), (3, 4));
+        let i = table.find(Section::Chars, "a").unwrap();
+        assert_eq!(table.decoded(Section::Chars, i), ["甲", "由"]);
+        assert_eq!(table.find(Section::Chars, "c"), None);
+        assert_eq!(table.key(Section::Chars, table.lower_bound(Section::Chars, Bound::Excluded("a"))), "ab");
+        assert_eq!(table.value(Section::PrimaryCodes, 0), "a");
+        assert_eq!(table.len(Section::Quick), 0);
+        assert_eq!(table.frequency(Section::CharFrequencies, "a", "由"), Some(3));
+        assert_eq!(table.frequency(Section::CharFrequencies, "a", "甲"), None);
+
+        // 重新編譯時以新檔取代，已開啟的字表仍讀到原本的內容
+        write(&path, "新表", [vec![("a", vec!["丙"])], Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new()]).unwrap();
+        let b = table.find(Section::Chars, "b").unwrap();
+        assert_eq!(table.decoded(Section::Chars, b), ["乙"]);
+        assert_eq!(CompiledTable::open(&path).unwrap().name(), "新表");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(CompiledTable::from_bytes(b"%gen_inp\n%chardef begin\n".to_vec()).is_err());
+        let path = std::env::temp_dir().join(format!("rustarray30-compiled-bad-{}.a30t", std::process::id()));
+        write(&path, "", [vec![("a", vec!["甲"])], Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new()]).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        // 文字區截斷後索引超出範圍
+        bytes.truncate(bytes.len() - 1);
+        assert!(CompiledTable::from_bytes(bytes).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,11 +1,44 @@
 // Configuration management for Array30 Input Method
 // 設定檔管理
 
+use crate::error::{Array30Error, Result};
+use crate::input_engine::{
+    CandidateFilter, CandidateOrder, EngineOptions, MissPolicy, PhraseMix, PunctuationMode, SpaceBehavior, TimeoutAction,
+    MAX_PAGE_SIZE, PAGE_SIZE,
+};
+use crate::keymap::{ArrayVariant, Keymap, KeymapProfile};
+use crate::shortcuts::{parse_chords, Action, Shortcuts};
+use crate::transform::{Transform, TransformChain};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 const DEFAULT_FONT_SIZE: f32 = 20.0;
+const DEFAULT_CANDIDATE_FONT_SIZE: f32 = 32.0;
+const DEFAULT_OUTPUT_FONT_SIZE: f32 = 24.0;
+/// 介面縮放比例的範圍（0 為依螢幕自動判斷）
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 3.0;
+/// 終端機等待按鍵的間隔範圍（毫秒）
+pub const DEFAULT_CONSOLE_POLL_MS: u64 = 100;
+pub const MIN_CONSOLE_POLL_MS: u64 = 10;
+pub const MAX_CONSOLE_POLL_MS: u64 = 1000;
+/// 輸出區預設的字元數上限（超過時較早的內容移到輸出歷史）
+const DEFAULT_MAX_OUTPUT_CHARS: usize = 100_000;
 const CONFIG_FILENAME: &str = "settings.ini";
+/// 指定設定檔路徑的環境變數
+pub const CONFIG_ENV: &str = "ARRAY30_CONFIG";
+/// 設定檔組合所在的子目錄
+const PROFILES_DIR: &str = "profiles";
+const DEFAULT_GLOBAL_HOTKEY_WINDOW: &str = "Ctrl+Shift+Space";
+const DEFAULT_GLOBAL_HOTKEY_LANGUAGE: &str = "Ctrl+Alt+Space";
+/// 最近檔案清單的長度上限
+const MAX_RECENT_FILES: usize = 10;
+/// [transform] 區段 replace 的樣式與取代字串的分隔
+const REPLACE_SEPARATOR: &str = "=>";
+/// 樣式中的分隔字串存檔時改寫為等效的 `=\>`
+const ESCAPED_SEPARATOR: &str = "=\\>";
 
 /// 字根表位置
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -49,15 +82,188 @@ impl RootTablePosition {
             _ => None,
         }
     }
+
+    pub fn all() -> [RootTablePosition; 4] {
+        [
+            RootTablePosition::Up,
+            RootTablePosition::Down,
+            RootTablePosition::Left,
+            RootTablePosition::Right,
+        ]
+    }
+}
+
+/// 直接輸出方式（僅 Windows）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectOutputMethod {
+    /// 切換到目標視窗並模擬按鍵（相容性最好）
+    #[default]
+    SendInput,
+    /// 送出 WM_CHAR 訊息（不切換焦點）
+    WmChar,
+}
+
+impl DirectOutputMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectOutputMethod::SendInput => "send_input",
+            DirectOutputMethod::WmChar => "wm_char",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            DirectOutputMethod::SendInput => "模擬按鍵（SendInput）",
+            DirectOutputMethod::WmChar => "字元訊息（WM_CHAR）",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "send_input" | "sendinput" => Some(DirectOutputMethod::SendInput),
+            "wm_char" | "wmchar" => Some(DirectOutputMethod::WmChar),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [DirectOutputMethod; 2] {
+        [DirectOutputMethod::SendInput, DirectOutputMethod::WmChar]
+    }
+}
+
+/// 提示回饋（按鍵被拒絕、翻到頁首頁尾、查無字詞）的呈現方式
+/// 終端機以響鈴作為聲音；GUI 的聲音只在 Windows 上支援
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackStyle {
+    /// 不提示
+    #[default]
+    Off,
+    /// 響鈴或系統提示音
+    Sound,
+    /// 閃爍視窗或編輯區
+    Flash,
+    /// 聲音與閃爍
+    Both,
+}
+
+impl FeedbackStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeedbackStyle::Off => "off",
+            FeedbackStyle::Sound => "sound",
+            FeedbackStyle::Flash => "flash",
+            FeedbackStyle::Both => "both",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            FeedbackStyle::Off => "不提示",
+            FeedbackStyle::Sound => "聲音",
+            FeedbackStyle::Flash => "閃爍",
+            FeedbackStyle::Both => "聲音與閃爍",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" => Some(FeedbackStyle::Off),
+            "sound" | "bell" | "beep" => Some(FeedbackStyle::Sound),
+            "flash" => Some(FeedbackStyle::Flash),
+            "both" => Some(FeedbackStyle::Both),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [FeedbackStyle; 4] {
+        [FeedbackStyle::Off, FeedbackStyle::Sound, FeedbackStyle::Flash, FeedbackStyle::Both]
+    }
+
+    pub fn sound(&self) -> bool {
+        matches!(self, FeedbackStyle::Sound | FeedbackStyle::Both)
+    }
+
+    pub fn flash(&self) -> bool {
+        matches!(self, FeedbackStyle::Flash | FeedbackStyle::Both)
+    }
+}
+
+/// 介面主題
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    /// 跟隨系統
+    #[default]
+    System,
+    /// 深色
+    Dark,
+    /// 淺色
+    Light,
+}
+
+impl ThemeMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemeMode::System => "system",
+            ThemeMode::Dark => "dark",
+            ThemeMode::Light => "light",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ThemeMode::System => "跟隨系統",
+            ThemeMode::Dark => "深色",
+            ThemeMode::Light => "淺色",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "system" => Some(ThemeMode::System),
+            "dark" => Some(ThemeMode::Dark),
+            "light" => Some(ThemeMode::Light),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [ThemeMode; 3] {
+        [ThemeMode::System, ThemeMode::Dark, ThemeMode::Light]
+    }
+}
+
+/// 解析 #RRGGBB 格式的顏色
+pub fn parse_hex_color(s: &str) -> Option<[u8; 3]> {
+    let hex = s.trim().strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// 將顏色轉為 #RRGGBB（未設定時為空字串）
+pub fn format_hex_color(color: Option<[u8; 3]>) -> String {
+    color
+        .map(|[r, g, b]| format!("#{:02x}{:02x}{:02x}", r, g, b))
+        .unwrap_or_default()
 }
 
 /// 應用程式設定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    /// 字型檔案路徑
+    /// 字型檔案路徑（空字串為自動選用已安裝字型中優先的一個）
     pub font_path: String,
-    /// 字型大小
+    /// 字型大小（一般介面）
     pub font_size: f32,
+    /// 候選列字型大小
+    pub candidate_font_size: f32,
+    /// 輸出區字型大小
+    pub output_font_size: f32,
+    /// 介面縮放比例（0 為依螢幕 DPI 自動判斷）
+    pub ui_scale: f32,
     /// 顯示行列字根表
     pub show_root_table: bool,
     /// 字根表圖片縮放比例 (0.1 - 2.0)
@@ -68,18 +274,173 @@ pub struct Config {
     pub window_height: f32,
     /// 字根表位置
     pub root_table_position: RootTablePosition,
+    /// 自訂字根表圖片路徑（空字串使用內建圖片）
+    pub root_table_image: String,
+    /// 實體鍵盤配置
+    pub keymap_profile: KeymapProfile,
+    /// 自訂鍵盤對應檔路徑（keymap_profile = custom 時使用）
+    pub keymap_file: String,
+    /// 顯示螢幕鍵盤
+    pub show_keyboard: bool,
+    /// 螢幕鍵盤標示可接續的鍵
+    pub keyboard_next_key_hint: bool,
+    /// 候選字下方顯示行列碼標示
+    pub show_candidate_codes: bool,
+    /// 輸入法變體（行列 30/40/10）
+    pub array_variant: ArrayVariant,
+    /// 快速鍵（[shortcuts] 區段）
+    pub shortcuts: Shortcuts,
+    /// 全域快速鍵：顯示/隱藏視窗（空字串停用，僅 Windows）
+    pub global_hotkey_window: String,
+    /// 全域快速鍵：切換中／英文（空字串停用，僅 Windows）
+    pub global_hotkey_language: String,
+    /// 上屏時將文字送到前景的外部視窗（僅 Windows）
+    pub direct_output: bool,
+    /// 直接輸出方式
+    pub direct_output_method: DirectOutputMethod,
+    /// 直接輸出時在目標視窗的文字游標旁顯示候選窗（僅 Windows）
+    pub caret_candidates: bool,
+    /// 關閉視窗時縮到系統匣而不結束程式（僅 Windows）
+    pub minimize_to_tray: bool,
+    /// 啟動時隱藏視窗（有系統匣時縮到系統匣，否則最小化）
+    pub start_minimized: bool,
+    /// 表格目錄（空字串依序搜尋預設位置）
+    pub table_dir: String,
+    /// 自訂字表檔（空字串使用輸入法變體的預設字表）
+    pub char_table: String,
+    /// 自訂詞庫檔（空字串使用輸入法變體的預設詞庫）
+    pub phrase_table: String,
+    /// 使用大字集字表
+    pub big_char: bool,
+    /// 使用標準字表時，查無候選的碼改查大字集（載入字表後在背景讀取大字集）
+    pub big_fallback: bool,
+    /// 合併 emoji 與全形符號補充表（表格目錄的 symbols.cin，碼接在 W+數字之後）
+    pub symbol_table: bool,
+    /// 將輸出歷史寫入使用者資料目錄，重新啟動後仍保留
+    pub persist_history: bool,
+    /// 讀音註解檔（字<Tab>拼音或注音，空字串不載入）
+    pub annotation_file: String,
+    /// 遠端字典服務網址（http://主機:埠/路徑，空字串不使用），查到的字詞排在本機字詞之後
+    pub remote_dict_url: String,
+    /// 同步資料夾（Dropbox、OneDrive 等，空字串不同步），自訂字詞、統計與設定檔在此合併
+    pub sync_folder: String,
+    /// 終端機介面使用彩色（false 或設定 NO_COLOR 環境變數時為單色）
+    pub console_color: bool,
+    /// 終端機介面等待按鍵的間隔（毫秒），只影響離開與視窗大小改變的反應速度
+    pub console_poll_ms: u64,
+    /// 提示回饋的呈現方式
+    pub feedback: FeedbackStyle,
+    /// 目前的設定檔組合（空字串為預設設定檔，不寫入檔案）
+    #[serde(skip)]
+    pub profile: String,
+    /// 每頁候選數（[engine] 區段）
+    pub page_size: usize,
+    /// 碼長上限（0 為輸入法變體的預設）
+    pub max_code_len: usize,
+    /// 空白鍵的行為
+    pub space_behavior: SpaceBehavior,
+    /// 碼打滿且只有一個候選時自動上屏
+    pub auto_commit: bool,
+    /// 標點符號輸出方式
+    pub punctuation_mode: PunctuationMode,
+    /// 候選排序方式
+    pub candidate_order: CandidateOrder,
+    /// 同碼的字與詞的合併方式
+    pub phrase_mix: PhraseMix,
+    /// 候選篩選
+    pub candidate_filter: CandidateFilter,
+    /// 官方行列按鍵相容模式（簡碼與特別碼）
+    pub official_keys: bool,
+    /// 碼無候選時確認鍵的處理方式
+    pub miss_policy: MissPolicy,
+    /// 顯示延伸碼的預覽候選
+    pub prefix_preview: bool,
+    /// 輸出區字元數上限（0 為不限制）
+    pub max_output_chars: usize,
+    /// 組字閒置逾時（秒，0 為不逾時）
+    /// 常駐服務與 PIME 前端沒有計時器，在下一個請求時才處理
+    pub composition_timeout: u64,
+    /// 組字逾時的處理方式
+    pub timeout_action: TimeoutAction,
+    /// 介面主題（[theme] 區段）
+    pub theme: ThemeMode,
+    /// 強調色（未設定時使用主題預設）
+    pub accent_color: Option<[u8; 3]>,
+    /// 候選字的反白顏色（未設定時使用強調色）
+    pub candidate_highlight_color: Option<[u8; 3]>,
+    /// 上屏文字的逐字對應檔，例如繁轉簡（[transform] 區段，空字串不轉換）
+    pub char_map_file: String,
+    /// 上屏的半形數字轉全形
+    pub fullwidth_digits: bool,
+    /// 上屏文字的正規表示式取代（樣式, 取代字串），依序套用
+    pub replacements: Vec<(String, String)>,
+    /// 最近開啟或儲存的輸出檔（[recent] 區段，最新的在前）
+    pub recent_files: Vec<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            font_path: get_default_font_path(),
+            font_path: String::new(),
             font_size: DEFAULT_FONT_SIZE,
+            candidate_font_size: DEFAULT_CANDIDATE_FONT_SIZE,
+            output_font_size: DEFAULT_OUTPUT_FONT_SIZE,
+            ui_scale: 0.0,
             show_root_table: true,
             root_table_scale: 0.5,
             window_width: 1600.0,
             window_height: 900.0,
             root_table_position: RootTablePosition::Up,
+            root_table_image: String::new(),
+            keymap_profile: KeymapProfile::Qwerty,
+            keymap_file: String::new(),
+            show_keyboard: true,
+            keyboard_next_key_hint: true,
+            show_candidate_codes: false,
+            array_variant: ArrayVariant::Array30,
+            shortcuts: Shortcuts::default(),
+            global_hotkey_window: DEFAULT_GLOBAL_HOTKEY_WINDOW.to_string(),
+            global_hotkey_language: DEFAULT_GLOBAL_HOTKEY_LANGUAGE.to_string(),
+            direct_output: false,
+            direct_output_method: DirectOutputMethod::SendInput,
+            caret_candidates: false,
+            minimize_to_tray: false,
+            start_minimized: false,
+            table_dir: String::new(),
+            char_table: String::new(),
+            phrase_table: String::new(),
+            big_char: false,
+            big_fallback: false,
+            symbol_table: false,
+            persist_history: false,
+            annotation_file: String::new(),
+            remote_dict_url: String::new(),
+            sync_folder: String::new(),
+            console_color: true,
+            console_poll_ms: DEFAULT_CONSOLE_POLL_MS,
+            feedback: FeedbackStyle::Off,
+            profile: String::new(),
+            page_size: PAGE_SIZE,
+            max_code_len: 0,
+            space_behavior: SpaceBehavior::CommitFirst,
+            auto_commit: false,
+            punctuation_mode: PunctuationMode::Half,
+            candidate_order: CandidateOrder::Table,
+            phrase_mix: PhraseMix::default(),
+            candidate_filter: CandidateFilter::default(),
+            official_keys: false,
+            miss_policy: MissPolicy::Keep,
+            prefix_preview: true,
+            max_output_chars: DEFAULT_MAX_OUTPUT_CHARS,
+            composition_timeout: 0,
+            timeout_action: TimeoutAction::Clear,
+            theme: ThemeMode::System,
+            accent_color: None,
+            candidate_highlight_color: None,
+            char_map_file: String::new(),
+            fullwidth_digits: false,
+            replacements: Vec::new(),
+            recent_files: Vec::new(),
         }
     }
 }
@@ -87,7 +448,12 @@ impl Default for Config {
 impl Config {
     /// 設定檔路徑
     pub fn config_file_path() -> Option<PathBuf> {
-        // 優先使用當前目錄
+        // 環境變數指定的設定檔優先
+        if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+
+        // 其次使用當前目錄
         let local_path = PathBuf::from(CONFIG_FILENAME);
         if local_path.exists() {
             return Some(local_path);
@@ -101,7 +467,7 @@ impl Config {
             // 如果目錄不存在，嘗試建立
             if !app_config_dir.exists() {
                 if let Err(e) = std::fs::create_dir_all(&app_config_dir) {
-                    eprintln!("無法建立設定目錄: {}", e);
+                    tracing::warn!("無法建立設定目錄：{}", e);
                     return Some(local_path);
                 }
             }
@@ -112,6 +478,74 @@ impl Config {
         Some(local_path)
     }
 
+    /// 設定檔組合目錄（設定目錄下的 profiles/）
+    pub fn profiles_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rustarray30").join(PROFILES_DIR))
+    }
+
+    /// 設定檔組合的路徑；名稱無效時回傳 None
+    pub fn profile_path(name: &str) -> Option<PathBuf> {
+        if !is_valid_profile_name(name) {
+            return None;
+        }
+        Self::profiles_dir().map(|dir| dir.join(format!("{}.ini", name)))
+    }
+
+    /// 列出已建立的設定檔組合（依名稱排序）
+    pub fn list_profiles() -> Vec<String> {
+        let Some(dir) = Self::profiles_dir() else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ini"))
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+            .filter(|name| is_valid_profile_name(name))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// 目前設定檔組合的檔案路徑
+    fn file_path(&self) -> Option<PathBuf> {
+        if self.profile.is_empty() {
+            Self::config_file_path()
+        } else {
+            Self::profile_path(&self.profile)
+        }
+    }
+
+    /// 載入設定檔組合；名稱為空時載入預設設定檔
+    /// 組合不存在時以預設設定檔的內容建立
+    pub fn load_profile(name: &str) -> Result<Self> {
+        if name.is_empty() {
+            return Ok(Self::load());
+        }
+        let path = Self::profile_path(name)
+            .ok_or_else(|| Array30Error::ConfigError(format!("無效的設定檔組合名稱：{}", name)))?;
+
+        let existing = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| Self::parse_ini(&content).ok());
+        let created = existing.is_none();
+        let mut config = existing.unwrap_or_else(Self::load);
+        config.profile = name.to_string();
+
+        if created {
+            config
+                .save()
+                .map_err(|e| Array30Error::ConfigError(format!("無法建立設定檔組合 {}：{}", path.display(), e)))?;
+            tracing::info!(path = %path.display(), "已建立設定檔組合");
+        } else {
+            tracing::debug!(path = %path.display(), "已載入設定檔組合");
+        }
+        Ok(config)
+    }
+
     /// 載入設定檔
     pub fn load() -> Self {
         if let Some(path) = Self::config_file_path() {
@@ -119,9 +553,11 @@ impl Config {
                 if let Ok(content) = std::fs::read_to_string(&path) {
                     // 嘗試解析 INI 檔案
                     if let Ok(config) = Self::parse_ini(&content) {
+                        tracing::debug!(path = %path.display(), "已載入設定檔");
                         return config;
                     }
                 }
+                tracing::warn!(path = %path.display(), "無法讀取設定檔，改用預設值");
             }
         }
 
@@ -132,14 +568,69 @@ impl Config {
     }
 
     /// 解析 INI 格式設定檔
-    fn parse_ini(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    fn parse_ini(content: &str) -> Result<Self> {
         let mut font_path = String::new();
         let mut font_size = DEFAULT_FONT_SIZE;
+        let mut candidate_font_size = DEFAULT_CANDIDATE_FONT_SIZE;
+        let mut output_font_size = DEFAULT_OUTPUT_FONT_SIZE;
+        let mut ui_scale = 0.0;
         let mut show_root_table = true;
         let mut root_table_scale = 0.5;
         let mut window_width = 1600.0;
         let mut window_height = 900.0;
         let mut root_table_position = RootTablePosition::Up;
+        let mut root_table_image = String::new();
+        let mut keymap_profile = KeymapProfile::Qwerty;
+        let mut keymap_file = String::new();
+        let mut show_keyboard = true;
+        let mut keyboard_next_key_hint = true;
+        let mut show_candidate_codes = false;
+        let mut array_variant = ArrayVariant::Array30;
+        let mut shortcuts = Shortcuts::default();
+        let mut global_hotkey_window = DEFAULT_GLOBAL_HOTKEY_WINDOW.to_string();
+        let mut global_hotkey_language = DEFAULT_GLOBAL_HOTKEY_LANGUAGE.to_string();
+        let mut direct_output = false;
+        let mut direct_output_method = DirectOutputMethod::SendInput;
+        let mut caret_candidates = false;
+        let mut minimize_to_tray = false;
+        let mut start_minimized = false;
+        let mut table_dir = String::new();
+        let mut char_table = String::new();
+        let mut phrase_table = String::new();
+        let mut big_char = false;
+        let mut big_fallback = false;
+        let mut symbol_table = false;
+        let mut persist_history = false;
+        let mut console_color = true;
+        let mut console_poll_ms = DEFAULT_CONSOLE_POLL_MS;
+        let mut feedback = FeedbackStyle::Off;
+        let mut annotation_file = String::new();
+        let mut remote_dict_url = String::new();
+        let mut sync_folder = String::new();
+        let mut page_size = PAGE_SIZE;
+        let mut max_code_len = 0;
+        let mut space_behavior = SpaceBehavior::CommitFirst;
+        let mut auto_commit = false;
+        let mut punctuation_mode = PunctuationMode::Half;
+        let mut candidate_order = CandidateOrder::Table;
+        let mut phrase_mix = PhraseMix::default();
+        let mut official_keys = false;
+        let mut candidate_filter = CandidateFilter::default();
+        let mut miss_policy = MissPolicy::Keep;
+        let mut prefix_preview = true;
+        let mut max_output_chars = DEFAULT_MAX_OUTPUT_CHARS;
+        let mut composition_timeout = 0;
+        let mut timeout_action = TimeoutAction::Clear;
+        let mut theme = ThemeMode::System;
+        let mut accent_color = None;
+        let mut candidate_highlight_color = None;
+        let mut char_map_file = String::new();
+        let mut fullwidth_digits = false;
+        let mut replacements = Vec::new();
+        let mut recent_files = Vec::new();
+
+        // 目前所在區段（空字串為最上層）
+        let mut section = String::new();
 
         for line in content.lines() {
             let line = line.trim();
@@ -148,11 +639,125 @@ impl Config {
                 continue;
             }
 
+            // 區段標頭
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len() - 1].trim().to_lowercase();
+                continue;
+            }
+
             // 解析 key=value
             if let Some((key, value)) = line.split_once('=') {
                 let key = key.trim();
                 let value = value.trim();
 
+                if section == "shortcuts" {
+                    if let (Some(action), Some(chords)) = (Action::from_str(key), parse_chords(value)) {
+                        shortcuts.set(action, chords);
+                    }
+                    continue;
+                }
+
+                if section == "engine" {
+                    match key {
+                        "page_size" => {
+                            if let Ok(size) = value.parse::<usize>() {
+                                page_size = size.clamp(1, MAX_PAGE_SIZE);
+                            }
+                        }
+                        "max_code_len" => {
+                            if let Ok(len) = value.parse::<usize>() {
+                                max_code_len = len.min(8);
+                            }
+                        }
+                        "space_behavior" => {
+                            if let Some(behavior) = SpaceBehavior::from_str(value) {
+                                space_behavior = behavior;
+                            }
+                        }
+                        "auto_commit" => auto_commit = parse_bool(value),
+                        "punctuation_mode" => {
+                            if let Some(mode) = PunctuationMode::from_str(value) {
+                                punctuation_mode = mode;
+                            }
+                        }
+                        "candidate_order" => {
+                            if let Some(order) = CandidateOrder::from_str(value) {
+                                candidate_order = order;
+                            }
+                        }
+                        "phrase_mix" => {
+                            if let Some(mix) = PhraseMix::from_str(value) {
+                                phrase_mix = mix;
+                            }
+                        }
+                        "official_keys" => official_keys = parse_bool(value),
+                        "candidate_filter" => {
+                            if let Some(filter) = CandidateFilter::from_str(value) {
+                                candidate_filter = filter;
+                            }
+                        }
+                        "miss_policy" => {
+                            if let Some(policy) = MissPolicy::from_str(value) {
+                                miss_policy = policy;
+                            }
+                        }
+                        "prefix_preview" => prefix_preview = parse_bool(value),
+                        "max_output_chars" => {
+                            if let Ok(limit) = value.parse::<usize>() {
+                                max_output_chars = limit;
+                            }
+                        }
+                        "composition_timeout" => {
+                            if let Ok(secs) = value.parse::<u64>() {
+                                composition_timeout = secs;
+                            }
+                        }
+                        "timeout_action" => {
+                            if let Some(action) = TimeoutAction::from_str(value) {
+                                timeout_action = action;
+                            }
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                if section == "theme" {
+                    match key {
+                        "mode" => {
+                            if let Some(mode) = ThemeMode::from_str(value) {
+                                theme = mode;
+                            }
+                        }
+                        "accent_color" => accent_color = parse_hex_color(value),
+                        "candidate_highlight_color" => candidate_highlight_color = parse_hex_color(value),
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                if section == "transform" {
+                    match key {
+                        "char_map_file" => char_map_file = value.to_string(),
+                        "fullwidth_digits" => fullwidth_digits = parse_bool(value),
+                        "replace" => {
+                            if let Some((pattern, replacement)) = value.split_once(REPLACE_SEPARATOR) {
+                                let pattern = pattern.trim().replace(ESCAPED_SEPARATOR, REPLACE_SEPARATOR);
+                                replacements.push((pattern, replacement.trim().to_string()));
+                            }
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                if section == "recent" {
+                    if key == "file" && !value.is_empty() && recent_files.len() < MAX_RECENT_FILES {
+                        recent_files.push(value.to_string());
+                    }
+                    continue;
+                }
+
                 match key {
                     "font_path" => font_path = value.to_string(),
                     "font_size" => {
@@ -160,11 +765,22 @@ impl Config {
                             font_size = size.max(10.0).min(72.0);
                         }
                     }
-                    "show_root_table" => {
-                        show_root_table = value.eq_ignore_ascii_case("true") ||
-                                         value == "1" ||
-                                         value.eq_ignore_ascii_case("yes");
+                    "candidate_font_size" => {
+                        if let Ok(size) = value.parse::<f32>() {
+                            candidate_font_size = size.clamp(10.0, 96.0);
+                        }
+                    }
+                    "output_font_size" => {
+                        if let Ok(size) = value.parse::<f32>() {
+                            output_font_size = size.clamp(10.0, 96.0);
+                        }
+                    }
+                    "ui_scale" => {
+                        if let Ok(scale) = value.parse::<f32>() {
+                            ui_scale = if scale <= 0.0 { 0.0 } else { scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE) };
+                        }
                     }
+                    "show_root_table" => show_root_table = parse_bool(value),
                     "root_table_scale" => {
                         if let Ok(scale) = value.parse::<f32>() {
                             root_table_scale = scale.max(0.1).min(2.0);
@@ -185,128 +801,567 @@ impl Config {
                             root_table_position = pos;
                         }
                     }
+                    "root_table_image" => root_table_image = value.to_string(),
+                    "keymap_profile" => {
+                        if let Some(profile) = KeymapProfile::from_str(value) {
+                            keymap_profile = profile;
+                        }
+                    }
+                    "keymap_file" => keymap_file = value.to_string(),
+                    "show_keyboard" => show_keyboard = parse_bool(value),
+                    "keyboard_next_key_hint" => keyboard_next_key_hint = parse_bool(value),
+                    "show_candidate_codes" => show_candidate_codes = parse_bool(value),
+                    "global_hotkey_window" => global_hotkey_window = value.to_string(),
+                    "global_hotkey_language" => global_hotkey_language = value.to_string(),
+                    "direct_output" => direct_output = parse_bool(value),
+                    "direct_output_method" => {
+                        if let Some(method) = DirectOutputMethod::from_str(value) {
+                            direct_output_method = method;
+                        }
+                    }
+                    "caret_candidates" => caret_candidates = parse_bool(value),
+                    "minimize_to_tray" => minimize_to_tray = parse_bool(value),
+                    "start_minimized" => start_minimized = parse_bool(value),
+                    "table_dir" => table_dir = value.to_string(),
+                    "char_table" => char_table = value.to_string(),
+                    "phrase_table" => phrase_table = value.to_string(),
+                    "big_char" => big_char = parse_bool(value),
+                    "big_fallback" => big_fallback = parse_bool(value),
+                    "symbol_table" => symbol_table = parse_bool(value),
+                    "persist_history" => persist_history = parse_bool(value),
+                    "console_color" => console_color = parse_bool(value),
+                    "console_poll_ms" => {
+                        if let Ok(ms) = value.parse::<u64>() {
+                            console_poll_ms = ms.clamp(MIN_CONSOLE_POLL_MS, MAX_CONSOLE_POLL_MS);
+                        }
+                    }
+                    "feedback" => {
+                        if let Some(style) = FeedbackStyle::from_str(value) {
+                            feedback = style;
+                        }
+                    }
+                    "annotation_file" => annotation_file = value.to_string(),
+                    "remote_dict_url" => remote_dict_url = value.to_string(),
+                    "sync_folder" => sync_folder = value.to_string(),
+                    "array_variant" => {
+                        if let Some(variant) = ArrayVariant::from_str(value) {
+                            array_variant = variant;
+                        }
+                    }
                     _ => {}
                 }
             }
         }
 
-        // 如果沒有設定字型，使用預設
-        if font_path.is_empty() {
-            font_path = get_default_font_path();
-        }
-
         Ok(Self {
             font_path,
             font_size,
+            candidate_font_size,
+            output_font_size,
+            ui_scale,
             show_root_table,
             root_table_scale,
             window_width,
             window_height,
             root_table_position,
+            root_table_image,
+            keymap_profile,
+            keymap_file,
+            show_keyboard,
+            keyboard_next_key_hint,
+            show_candidate_codes,
+            array_variant,
+            shortcuts,
+            global_hotkey_window,
+            global_hotkey_language,
+            direct_output,
+            direct_output_method,
+            caret_candidates,
+            minimize_to_tray,
+            start_minimized,
+            table_dir,
+            char_table,
+            phrase_table,
+            big_char,
+            big_fallback,
+            symbol_table,
+            persist_history,
+            console_color,
+            console_poll_ms,
+            feedback,
+            annotation_file,
+            remote_dict_url,
+            sync_folder,
+            profile: String::new(),
+            page_size,
+            max_code_len,
+            space_behavior,
+            auto_commit,
+            punctuation_mode,
+            candidate_order,
+            phrase_mix,
+            candidate_filter,
+            official_keys,
+            miss_policy,
+            prefix_preview,
+            max_output_chars,
+            composition_timeout,
+            timeout_action,
+            theme,
+            accent_color,
+            candidate_highlight_color,
+            char_map_file,
+            fullwidth_digits,
+            replacements,
+            recent_files,
         })
     }
 
     /// 儲存設定檔
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(path) = Self::config_file_path() {
-            let content = format!(
-                "# Array30 Input Method Settings\n\
-                 # 設定檔\n\
-                 \n\
-                 # Font file path (字型檔案路徑)\n\
-                 font_path={}\n\
-                 \n\
-                 # Font size in points (字型大小)\n\
-                 font_size={}\n\
-                 \n\
-                 # Show root table image (顯示字根表)\n\
-                 show_root_table={}\n\
-                 \n\
-                 # Root table image scale (字根表縮放比例 0.1-2.0)\n\
-                 root_table_scale={}\n\
-                 \n\
-                 # Window size (視窗大小)\n\
-                 window_width={}\n\
-                 window_height={}\n\
-                 \n\
-                 # Root table position (字根表位置: up/down/left/right)\n\
-                 root_table_position={}",
-                self.font_path,
-                self.font_size,
-                self.show_root_table,
-                self.root_table_scale,
-                self.window_width,
-                self.window_height,
-                self.root_table_position.as_str()
-            );
-
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = self.file_path() {
+            let content = self.to_ini();
+            // 設定檔組合的目錄可能尚未建立
+            if !self.profile.is_empty() {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+            }
             std::fs::write(&path, content)?;
             Ok(())
         } else {
-            Err("無法取得設定檔路徑".into())
+            Err(Array30Error::ConfigError("無法取得設定檔路徑".to_string()))
         }
     }
 
-    /// 載入字型資料
-    pub fn load_font_data(&self) -> Option<Vec<u8>> {
-        std::fs::read(&self.font_path).ok()
+    /// 設定檔內容（INI 格式，附英文與中文說明）
+    fn to_ini(&self) -> String {
+        let mut content = format!(
+            "# Array30 Input Method Settings\n\
+             # 設定檔\n\
+             \n\
+             # Font file path (字型檔案路徑)\n\
+             font_path={}\n\
+             \n\
+             # Font sizes in points: UI, candidates, output area (字型大小：介面、候選列、輸出區)\n\
+             font_size={}\n\
+             candidate_font_size={}\n\
+             output_font_size={}\n\
+             \n\
+             # UI scale, 0 to follow the monitor DPI (介面縮放比例，0 依螢幕 DPI 自動調整)\n\
+             ui_scale={}\n\
+             \n\
+             # Show root table image (顯示字根表)\n\
+             show_root_table={}\n\
+             \n\
+             # Root table image scale (字根表縮放比例 0.1-2.0)\n\
+             root_table_scale={}\n\
+             \n\
+             # Window size (視窗大小)\n\
+             window_width={}\n\
+             window_height={}\n\
+             \n\
+             # Root table position (字根表位置: up/down/left/right)\n\
+             root_table_position={}\n\
+             \n\
+             # Custom root table image, empty for the bundled one (自訂字根表圖片)\n\
+             root_table_image={}\n\
+             \n\
+             # Keyboard layout (鍵盤配置: qwerty/dvorak/colemak/custom)\n\
+             keymap_profile={}\n\
+             \n\
+             # Custom keymap file for keymap_profile=custom (自訂鍵盤對應檔)\n\
+             keymap_file={}\n\
+             \n\
+             # Show on-screen Array30 keyboard (顯示螢幕鍵盤)\n\
+             show_keyboard={}\n\
+             \n\
+             # Highlight keys that extend the current code (標示可接續的鍵)\n\
+             keyboard_next_key_hint={}\n\
+             \n\
+             # Show code labels under each candidate (候選字下方顯示行列碼)\n\
+             show_candidate_codes={}\n\
+             \n\
+             # Array variant and its tables (輸入法變體: array30/array40/array10)\n\
+             array_variant={}\n\
+             \n\
+             # System-wide hotkeys, empty to disable (全域快速鍵，留空停用)\n\
+             global_hotkey_window={}\n\
+             global_hotkey_language={}\n\
+             \n\
+             # Send committed text to the focused window (直接輸出: send_input/wm_char)\n\
+             direct_output={}\n\
+             direct_output_method={}\n\
+             \n\
+             # Show candidates next to the text caret of that window (候選窗跟隨文字游標)\n\
+             caret_candidates={}\n\
+             \n\
+             # Hide to the tray on close, start hidden (關閉時縮到系統匣、啟動時隱藏視窗)\n\
+             minimize_to_tray={}\n\
+             start_minimized={}\n\
+             \n\
+             # Table paths, empty for the defaults (表格目錄與自訂字表／詞庫，留空使用預設)\n\
+             table_dir={}\n\
+             char_table={}\n\
+             phrase_table={}\n\
+             \n\
+             # Use the big character table (使用大字集字表)\n\
+             big_char={}\n\
+             \n\
+             # Look up the big table when the regular table has no candidates (標準字表查無時改查大字集)\n\
+             big_fallback={}\n\
+             \n\
+             # Merge the emoji and full-width symbol table into the W+digit menus (合併 emoji 與全形符號補充表)\n\
+             symbol_table={}\n\
+             \n\
+             # Keep the output history across restarts (保存輸出歷史)\n\
+             persist_history={}\n\
+             \n\
+             # Feedback on rejected keys, page ends and empty phrase lookups (提示回饋: off/sound/flash/both)\n\
+             feedback={}\n\
+             \n\
+             # Readings and Unihan definitions shown with candidates (讀音與釋義註解檔)\n\
+             annotation_file={}\n\
+             \n\
+             # Remote dictionary service, http://host:port/path (遠端字典服務網址)\n\
+             remote_dict_url={}\n\
+             \n\
+             # Shared folder for syncing user data between machines (同步資料夾)\n\
+             sync_folder={}\n\
+             \n\
+             # Colored console candidates, false for monochrome (終端機彩色顯示，false 為單色)\n\
+             console_color={}\n\
+             \n\
+             # Console key polling interval in ms, 10-1000 (終端機等待按鍵的間隔，毫秒)\n\
+             console_poll_ms={}",
+            self.font_path,
+            self.font_size,
+            self.candidate_font_size,
+            self.output_font_size,
+            self.ui_scale,
+            self.show_root_table,
+            self.root_table_scale,
+            self.window_width,
+            self.window_height,
+            self.root_table_position.as_str(),
+            self.root_table_image,
+            self.keymap_profile.as_str(),
+            self.keymap_file,
+            self.show_keyboard,
+            self.keyboard_next_key_hint,
+            self.show_candidate_codes,
+            self.array_variant.as_str(),
+            self.global_hotkey_window,
+            self.global_hotkey_language,
+            self.direct_output,
+            self.direct_output_method.as_str(),
+            self.caret_candidates,
+            self.minimize_to_tray,
+            self.start_minimized,
+            self.table_dir,
+            self.char_table,
+            self.phrase_table,
+            self.big_char,
+            self.big_fallback,
+            self.symbol_table,
+            self.persist_history,
+            self.feedback.as_str(),
+            self.annotation_file,
+            self.remote_dict_url,
+            self.sync_folder,
+            self.console_color,
+            self.console_poll_ms
+        );
+        content.push_str(&format!(
+            "\n\n[engine]\n\
+             # Candidates per page, 1-10 (每頁候選數)\n\
+             page_size={}\n\
+             \n\
+             # Maximum code length, 0 for the variant default (碼長上限，0 為預設)\n\
+             max_code_len={}\n\
+             \n\
+             # Space key (空白鍵: commit_first/next_page)\n\
+             space_behavior={}\n\
+             \n\
+             # Commit a full-length code with a single candidate (碼打滿且唯一候選時自動上屏)\n\
+             auto_commit={}\n\
+             \n\
+             # Punctuation width (標點符號: half/full)\n\
+             punctuation_mode={}\n\
+             \n\
+             # Candidate order (候選排序: table/basic_first)\n\
+             candidate_order={}\n\
+             \n\
+             # Characters and phrases sharing a code (同碼字詞: separate/chars_first/phrases_first)\n\
+             phrase_mix={}\n\
+             \n\
+             # Official Array30 keys: Space commits the short code, digits continue special codes (官方行列按鍵相容)\n\
+             official_keys={}\n\
+             \n\
+             # Candidate filter (候選篩選: all/chars_only/phrases_only/exclude_rare/exclude_extensions)\n\
+             candidate_filter={}\n\
+             \n\
+             # Confirming a code without candidates (無候選時確認: keep/commit_raw)\n\
+             miss_policy={}\n\
+             \n\
+             # Show candidates of longer codes while typing (顯示延伸碼預覽)\n\
+             prefix_preview={}\n\
+             \n\
+             # Output area size in characters, older text moves to the history; 0 for no limit (輸出區字數上限)\n\
+             max_output_chars={}\n\
+             \n\
+             # Seconds before an idle half-typed code is handled, 0 to keep it (組字閒置逾時秒數，0 為不逾時)\n\
+             composition_timeout={}\n\
+             \n\
+             # What to do with it (逾時處理: clear/commit)\n\
+             timeout_action={}",
+            self.page_size,
+            self.max_code_len,
+            self.space_behavior.as_str(),
+            self.auto_commit,
+            self.punctuation_mode.as_str(),
+            self.candidate_order.as_str(),
+            self.phrase_mix.as_str(),
+            self.official_keys,
+            self.candidate_filter.as_str(),
+            self.miss_policy.as_str(),
+            self.prefix_preview,
+            self.max_output_chars,
+            self.composition_timeout,
+            self.timeout_action.as_str()
+        ));
+        content.push_str(&format!(
+            "\n\n[theme]\n\
+             # Color theme (介面主題: system/dark/light)\n\
+             mode={}\n\
+             \n\
+             # Colors as #RRGGBB, empty for the theme default (顏色，留空使用主題預設)\n\
+             accent_color={}\n\
+             candidate_highlight_color={}",
+            self.theme.as_str(),
+            format_hex_color(self.accent_color),
+            format_hex_color(self.candidate_highlight_color)
+        ));
+        content.push_str(&format!(
+            "\n\n[transform]\n\
+             # Character map applied to committed text, e.g. OpenCC TSCharacters.txt for simplified (上屏文字逐字對應檔，例如繁轉簡)\n\
+             char_map_file={}\n\
+             \n\
+             # Full-width digits (數字轉全形)\n\
+             fullwidth_digits={}\n\
+             \n\
+             # Regex replacements applied in order, pattern => replacement; write => in a pattern as =\\> (正規表示式取代，依序套用)\n",
+            self.char_map_file, self.fullwidth_digits
+        ));
+        for (pattern, replacement) in &self.replacements {
+            let pattern = pattern.replace(REPLACE_SEPARATOR, ESCAPED_SEPARATOR);
+            content.push_str(&format!("replace={} {} {}\n", pattern, REPLACE_SEPARATOR, replacement));
+        }
+        content.push('\n');
+        content.push_str(&self.shortcuts.to_ini_section());
+        content.push_str("\n[recent]\n# Recently opened or saved output files (最近的輸出檔)\n");
+        for file in &self.recent_files {
+            content.push_str(&format!("file={}\n", file));
+        }
+        content
+    }
+
+    /// 將檔案移到最近檔案清單的最前面
+    pub fn add_recent_file(&mut self, path: &str) {
+        self.recent_files.retain(|f| f != path);
+        self.recent_files.insert(0, path.to_string());
+        self.recent_files.truncate(MAX_RECENT_FILES);
     }
-}
 
-/// 取得預設字型路徑 (Microsoft JhengHei)
-#[cfg(target_os = "windows")]
-fn get_default_font_path() -> String {
-    let font_paths = [
-        r"C:\Windows\Fonts\msjh.ttc",
-        r"C:\Windows\Fonts\MSJH.TTC",
-        r"C:\Windows\Fonts\msjh.ttf",
-        r"C:\Windows\Fonts\MSJH.TTF",
-    ];
+    /// 取得鍵盤重映射表
+    /// 自訂對應檔無法載入時退回 QWERTY
+    pub fn keymap(&self) -> Keymap {
+        match self.keymap_profile {
+            KeymapProfile::Custom => Keymap::load_file(&self.keymap_file).unwrap_or_else(|e| {
+                tracing::warn!("無法載入鍵盤對應檔 {}：{}", self.keymap_file, e);
+                Keymap::qwerty()
+            }),
+            profile => Keymap::for_profile(profile),
+        }
+    }
 
-    for path in &font_paths {
-        if Path::new(path).exists() {
-            return path.to_string();
+    /// 實際使用的介面縮放比例
+    /// ui_scale 為 0 時採用系統回報的 DPI 比例（native）；系統回報 100% 但螢幕很寬
+    /// （例如未調整縮放的 4K 螢幕）時，依螢幕的實體像素寬度放大
+    pub fn effective_ui_scale(&self, native: f32, monitor_width: Option<f32>) -> f32 {
+        if self.ui_scale > 0.0 {
+            return self.ui_scale;
+        }
+        let by_width = match monitor_width {
+            Some(width) if width >= 3200.0 => 2.0,
+            Some(width) if width >= 2400.0 => 1.5,
+            _ => 1.0,
+        };
+        native.max(by_width)
+    }
+
+    /// 依設定建立引擎選項
+    pub fn engine_options(&self) -> EngineOptions {
+        self.engine_options_with(self.output_transforms())
+    }
+
+    /// 依設定建立引擎選項，使用已建立的上屏轉換串列
+    pub fn engine_options_with(&self, transforms: TransformChain) -> EngineOptions {
+        EngineOptions {
+            miss_policy: self.miss_policy,
+            prefix_preview: self.prefix_preview,
+            max_output_chars: self.max_output_chars,
+            composition_timeout: (self.composition_timeout > 0).then(|| Duration::from_secs(self.composition_timeout)),
+            timeout_action: self.timeout_action,
+            keymap: self.keymap(),
+            variant: self.array_variant,
+            page_size: self.page_size,
+            max_code_len: self.max_code_len,
+            space_behavior: self.space_behavior,
+            auto_commit: self.auto_commit,
+            punctuation: self.punctuation_mode,
+            candidate_order: self.candidate_order,
+            phrase_mix: self.phrase_mix,
+            official_keys: self.official_keys,
+            candidate_filter: self.candidate_filter,
+            transforms,
+        }
+    }
+
+    /// 依 [transform] 區段建立上屏文字的轉換串列：逐字對應、數字轉全形、正規表示式取代
+    /// 無法讀取的對應檔與錯誤的樣式略過
+    pub fn output_transforms(&self) -> TransformChain {
+        let mut chain = TransformChain::default();
+        if !self.char_map_file.is_empty() {
+            match Transform::load_char_map(Path::new(&self.char_map_file)) {
+                Ok(map) => chain.push(map),
+                Err(e) => tracing::warn!(path = %self.char_map_file, "無法讀取逐字對應檔：{}", e),
+            }
         }
+        if self.fullwidth_digits {
+            chain.push(Transform::FullWidthDigits);
+        }
+        for (pattern, replacement) in &self.replacements {
+            match Transform::replace(pattern, replacement) {
+                Ok(replace) => chain.push(replace),
+                Err(e) => tracing::warn!(%pattern, "略過錯誤的取代樣式：{}", e),
+            }
+        }
+        chain
     }
 
-    // 如果找不到，返回第一個選項（讓系統處理錯誤）
-    font_paths[1].to_string()
+    /// 載入字型資料；未設定字型時使用 `fallback`（GUI 傳入已安裝字型中優先的一個）
+    pub fn load_font_data(&self, fallback: Option<&FontInfo>) -> Result<Vec<u8>> {
+        let path = match fallback {
+            Some(font) if self.font_path.is_empty() => &font.path,
+            _ => &self.font_path,
+        };
+        std::fs::read(path).map_err(|e| Array30Error::FontError {
+            path: PathBuf::from(path),
+            reason: e.to_string(),
+        })
+    }
 }
 
-/// 取得預設字型路徑 (非 Windows)
-#[cfg(not(target_os = "windows"))]
-fn get_default_font_path() -> String {
-    // 非 Windows 系統使用空字串，讓 egui 使用預設字型
-    String::new()
+/// 設定檔組合名稱不可為空，也不可含路徑分隔字元或以 . 開頭
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.chars().any(|c| matches!(c, '/' | '\\' | ':') || c.is_control())
 }
 
-/// 列出 Windows 字型目錄中的字型檔案
-#[cfg(target_os = "windows")]
-pub fn list_system_fonts() -> Vec<FontInfo> {
-    let fonts_dir = PathBuf::from(r"C:\Windows\Fonts");
-    let mut font_list = Vec::new();
-
-    if let Ok(entries) = std::fs::read_dir(&fonts_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                let ext_lower = ext.to_string_lossy().to_lowercase();
-                if ext_lower == "ttf" || ext_lower == "ttc" || ext_lower == "otf" {
-                    let file_name = path.file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string();
-                    let display_name = format_font_name(&file_name);
-
-                    font_list.push(FontInfo {
-                        name: display_name,
-                        file_name,
-                        path: path.to_string_lossy().to_string(),
-                    });
-                }
+/// 解析布林值設定（true/1/yes 為真）
+fn parse_bool(value: &str) -> bool {
+    value.eq_ignore_ascii_case("true") || value == "1" || value.eq_ignore_ascii_case("yes")
+}
+
+/// 系統與使用者字型目錄
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if cfg!(target_os = "windows") {
+        paths.push(PathBuf::from(r"C:\Windows\Fonts"));
+        // 僅為目前使用者安裝的字型
+        paths.extend(dirs::data_local_dir().map(|dir| dir.join("Microsoft").join("Windows").join("Fonts")));
+    } else if cfg!(target_os = "macos") {
+        paths.push(PathBuf::from("/System/Library/Fonts"));
+        paths.push(PathBuf::from("/Library/Fonts"));
+        paths.extend(dirs::home_dir().map(|dir| dir.join("Library").join("Fonts")));
+    } else {
+        paths.push(PathBuf::from("/usr/share/fonts"));
+        paths.push(PathBuf::from("/usr/local/share/fonts"));
+        paths.extend(dirs::home_dir().map(|dir| dir.join(".fonts")));
+        paths.extend(dirs::data_dir().map(|dir| dir.join("fonts")));
+    }
+    paths
+}
+
+/// 是否為可載入的字型檔（ttf/ttc/otf）
+fn is_font_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| ext == "ttf" || ext == "ttc" || ext == "otf")
+}
+
+/// 遞迴收集目錄中的字型檔（不跟隨目錄連結）
+fn collect_font_files(dir: &Path, files: &mut BTreeSet<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => collect_font_files(&path, files),
+            Ok(_) if is_font_file(&path) => {
+                files.insert(path);
             }
+            _ => {}
         }
     }
+}
+
+/// 透過 fontconfig（fc-list）列出字型檔；Windows 或未安裝 fontconfig 時為 None
+fn fontconfig_font_files() -> Option<BTreeSet<PathBuf>> {
+    if cfg!(target_os = "windows") {
+        return None;
+    }
+    let output = std::process::Command::new("fc-list")
+        .args(["--format", "%{file}\\n"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let files: BTreeSet<PathBuf> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(PathBuf::from)
+        .filter(|path| is_font_file(path))
+        .collect();
+    (!files.is_empty()).then_some(files)
+}
+
+/// 列出系統字型（常用中文字型在前）
+/// 優先使用 fontconfig，否則掃描系統與使用者字型目錄
+pub fn list_system_fonts() -> Vec<FontInfo> {
+    let files = fontconfig_font_files().unwrap_or_else(|| {
+        let mut files = BTreeSet::new();
+        for dir in system_font_dirs() {
+            collect_font_files(&dir, &mut files);
+        }
+        files
+    });
+
+    let mut font_list: Vec<FontInfo> = files
+        .into_iter()
+        .map(|path| {
+            let file_name = path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            FontInfo {
+                name: format_font_name(&file_name),
+                file_name,
+                path: path.to_string_lossy().to_string(),
+            }
+        })
+        .collect();
 
     // 排序：常用字型優先
     font_list.sort_by(|a, b| {
@@ -338,6 +1393,16 @@ fn format_font_name(filename: &str) -> String {
         "mingliu.ttc" | "mingliu.ttf" => "PMingLiU (新細明體)",
         "simhei.ttf" => "SimHei (黑體)",
         "simsun.ttc" => "SimSun (宋體)",
+        "notosanscjk-regular.ttc" => "Noto Sans CJK (思源黑體)",
+        "notosanstc-regular.otf" | "notosanscjktc-regular.otf" => "Noto Sans TC (思源黑體)",
+        "notoserifcjk-regular.ttc" => "Noto Serif CJK (思源宋體)",
+        "wqy-microhei.ttc" => "WenQuanYi Micro Hei (文泉驛微米黑)",
+        "wqy-zenhei.ttc" => "WenQuanYi Zen Hei (文泉驛正黑)",
+        "uming.ttc" => "AR PL UMing (文鼎明體)",
+        "ukai.ttc" => "AR PL UKai (文鼎楷書)",
+        "pingfang.ttc" => "PingFang (蘋方)",
+        "stheiti medium.ttc" | "stheiti light.ttc" => "STHeiti (華文黑體)",
+        "hiragino sans gb.ttc" => "Hiragino Sans GB (冬青黑體)",
         _ => {
             // 移除副檔名，返回字串切片
             let end = filename.len().saturating_sub(4);
@@ -361,16 +1426,24 @@ fn get_font_priority(filename: &str) -> i32 {
         "kaiu.ttf" => 80,
         "mingliu.ttc" => 70,
         "mingliu.ttf" => 69,
+        // Linux 與 macOS 常見的中文字型
+        "notosanstc-regular.otf" | "notosanscjktc-regular.otf" => 96,
+        "notosanscjk-regular.ttc" => 95,
+        "pingfang.ttc" => 94,
+        "wqy-microhei.ttc" => 88,
+        "wqy-zenhei.ttc" => 87,
+        "stheiti medium.ttc" => 86,
+        "stheiti light.ttc" => 85,
+        "hiragino sans gb.ttc" => 84,
+        "notoserifcjk-regular.ttc" => 75,
+        "uming.ttc" => 68,
+        "ukai.ttc" => 67,
+        // 沒有中文字型時的一般字型
+        "notosans-regular.ttf" | "dejavusans.ttf" | "liberationsans-regular.ttf" => 10,
         _ => 0,
     }
 }
 
-/// 列出系統字型（非 Windows，返回空列表）
-#[cfg(not(target_os = "windows"))]
-pub fn list_system_fonts() -> Vec<FontInfo> {
-    Vec::new()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -378,7 +1451,177 @@ mod tests {
     #[test]
     fn test_default_config() {
         let config = Config::default();
-        assert!(!config.font_path.is_empty());
+        // 字型在 GUI 啟動時才選用，不在載入設定時列舉系統字型
+        assert!(config.font_path.is_empty());
         assert_eq!(config.font_size, 20.0);
     }
+
+    #[test]
+    fn test_collect_font_files() {
+        let dir = std::env::temp_dir().join(format!("rustarray30-fonts-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("truetype/wqy")).unwrap();
+        std::fs::write(dir.join("truetype/wqy/wqy-microhei.ttc"), "").unwrap();
+        std::fs::write(dir.join("NotoSansTC-Regular.OTF"), "").unwrap();
+        std::fs::write(dir.join("fonts.dir"), "").unwrap();
+
+        let mut files = BTreeSet::new();
+        collect_font_files(&dir, &mut files);
+        assert_eq!(files.len(), 2);
+        assert!(files.contains(&dir.join("truetype/wqy/wqy-microhei.ttc")));
+        assert_eq!(format_font_name("wqy-microhei.ttc"), "WenQuanYi Micro Hei (文泉驛微米黑)");
+        assert!(get_font_priority("NotoSansTC-Regular.otf") > get_font_priority("wqy-microhei.ttc"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_font_sizes() {
+        let config = Config::parse_ini("font_size=18\ncandidate_font_size=200\n").unwrap();
+        assert_eq!(config.font_size, 18.0);
+        assert_eq!(config.candidate_font_size, 96.0);
+        assert_eq!(config.output_font_size, DEFAULT_OUTPUT_FONT_SIZE);
+        assert_eq!(config.ui_scale, 0.0);
+    }
+
+    #[test]
+    fn test_ui_scale() {
+        let config = Config::parse_ini("ui_scale=5\n").unwrap();
+        assert_eq!(config.ui_scale, MAX_UI_SCALE);
+        assert_eq!(config.effective_ui_scale(2.0, Some(3840.0)), MAX_UI_SCALE);
+
+        // 自動：取系統 DPI 比例與依螢幕寬度推算的比例中較大者
+        let auto = Config::parse_ini("ui_scale=-1\n").unwrap();
+        assert_eq!(auto.ui_scale, 0.0);
+        assert_eq!(auto.effective_ui_scale(1.0, Some(1920.0)), 1.0);
+        assert_eq!(auto.effective_ui_scale(1.0, Some(3840.0)), 2.0);
+        assert_eq!(auto.effective_ui_scale(1.25, Some(2560.0)), 1.5);
+        assert_eq!(auto.effective_ui_scale(2.5, None), 2.5);
+    }
+
+    #[test]
+    fn test_console_poll_ms() {
+        assert_eq!(Config::default().console_poll_ms, DEFAULT_CONSOLE_POLL_MS);
+        assert_eq!(Config::parse_ini("console_poll_ms=1\n").unwrap().console_poll_ms, MIN_CONSOLE_POLL_MS);
+        assert_eq!(Config::parse_ini("console_poll_ms=250\n").unwrap().console_poll_ms, 250);
+        assert_eq!(Config::parse_ini("console_poll_ms=x\n").unwrap().console_poll_ms, DEFAULT_CONSOLE_POLL_MS);
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let mut config = Config::parse_ini("console_color=false\nconsole_poll_ms=250\n").unwrap();
+        config.replacements = vec![("a+".to_string(), "b".to_string()), ("x=>y".to_string(), "=>".to_string())];
+        let content = config.to_ini();
+        assert!(content.contains("replace=x=\\>y => =>\n"));
+        assert_eq!(Config::parse_ini(&content).unwrap().output_transforms().apply("x=>y"), "=>");
+        assert!(content.contains(
+            "console_color=false\n\n# Console key polling interval in ms, 10-1000 (終端機等待按鍵的間隔，毫秒)\nconsole_poll_ms=250\n\n[engine]\n"
+        ));
+        // 每行為空行、說明、區段或 key=value，行首沒有空白
+        for line in content.lines() {
+            assert!(!line.starts_with(char::is_whitespace), "{:?}", line);
+            assert!(line.is_empty() || line.starts_with(['#', '[']) || line.contains('='), "{:?}", line);
+        }
+        assert_eq!(Config::parse_ini(&content).unwrap().to_ini(), content);
+    }
+
+    #[test]
+    fn test_parse_keymap_profile() {
+        let config = Config::parse_ini("keymap_profile=dvorak\nkeymap_file=my.map").unwrap();
+        assert_eq!(config.keymap_profile, KeymapProfile::Dvorak);
+        assert_eq!(config.keymap_file, "my.map");
+        assert_eq!(config.engine_options().keymap.remap('o'), 's');
+
+        let config = Config::parse_ini("keymap_profile=unknown").unwrap();
+        assert_eq!(config.keymap_profile, KeymapProfile::Qwerty);
+    }
+
+    #[test]
+    fn test_parse_root_table() {
+        let config = Config::parse_ini("root_table_scale=5\nroot_table_position=left\nroot_table_image=my.png").unwrap();
+        assert_eq!(config.root_table_scale, 2.0);
+        assert_eq!(config.root_table_position, RootTablePosition::Left);
+        assert_eq!(config.root_table_image, "my.png");
+    }
+
+    #[test]
+    fn test_parse_engine_section() {
+        let config = Config::parse_ini(
+            "[engine]\npage_size=20\nspace_behavior=next_page\nauto_commit=true\npunctuation_mode=full\ncandidate_order=bogus\nphrase_mix=Phrases_First\nofficial_keys=true\ncandidate_filter=exclude_rare\nmax_output_chars=500\ncomposition_timeout=10\ntimeout_action=commit\n",
+        )
+        .unwrap();
+        let options = config.engine_options();
+        assert_eq!(options.page_size, MAX_PAGE_SIZE);
+        assert_eq!(options.space_behavior, SpaceBehavior::NextPage);
+        assert!(options.auto_commit);
+        assert_eq!(options.punctuation, PunctuationMode::Full);
+        assert_eq!(options.candidate_order, CandidateOrder::Table);
+        assert_eq!(options.phrase_mix, PhraseMix::PhrasesFirst);
+        assert!(options.official_keys);
+        assert_eq!(options.candidate_filter, CandidateFilter::ExcludeRare);
+        assert_eq!(options.max_code_len(), 4);
+        assert_eq!(options.max_output_chars, 500);
+        assert_eq!(options.composition_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(options.timeout_action, TimeoutAction::Commit);
+    }
+
+    #[test]
+    fn test_parse_theme_section() {
+        let config = Config::parse_ini("[theme]\nmode=Dark\naccent_color=#FF8000\ncandidate_highlight_color=red\n").unwrap();
+        assert_eq!(config.theme, ThemeMode::Dark);
+        assert_eq!(config.accent_color, Some([0xff, 0x80, 0x00]));
+        assert_eq!(config.candidate_highlight_color, None);
+        assert_eq!(format_hex_color(config.accent_color), "#ff8000");
+        assert_eq!(parse_hex_color("#12345"), None);
+    }
+
+    #[test]
+    fn test_parse_transform_section() {
+        let config = Config::parse_ini(
+            "[transform]\nfullwidth_digits=true\nreplace=(\\d+)年 => $1 年\nreplace=a=b => c\nreplace=( => x\nreplace=no separator\n",
+        )
+        .unwrap();
+        assert!(config.fullwidth_digits);
+        assert_eq!(config.replacements.len(), 3);
+        assert_eq!(config.replacements[1], ("a=b".to_string(), "c".to_string()));
+        // 錯誤的樣式略過，不影響其他轉換
+        assert_eq!(config.output_transforms().apply("2024年a=b("), "２０２４ 年c(");
+        assert!(Config::default().output_transforms().is_empty());
+    }
+
+    #[test]
+    fn test_recent_files() {
+        let mut config = Config::parse_ini("[recent]\nfile=a.txt\nfile=\nfile=b.txt\n").unwrap();
+        assert_eq!(config.recent_files, ["a.txt", "b.txt"]);
+        config.add_recent_file("b.txt");
+        assert_eq!(config.recent_files, ["b.txt", "a.txt"]);
+        for i in 0..20 {
+            config.add_recent_file(&format!("{}.txt", i));
+        }
+        assert_eq!(config.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(config.recent_files[0], "19.txt");
+    }
+
+    #[test]
+    fn test_profile_names() {
+        assert!(is_valid_profile_name("work"));
+        assert!(is_valid_profile_name("練習"));
+        assert!(!is_valid_profile_name(""));
+        assert!(!is_valid_profile_name("../settings"));
+        assert!(!is_valid_profile_name("a/b"));
+        assert!(Config::profile_path("..").is_none());
+
+        let config = Config::parse_ini("big_char=yes").unwrap();
+        assert!(config.big_char);
+        assert!(config.profile.is_empty());
+    }
+
+    #[test]
+    fn test_parse_shortcuts_section() {
+        let config = Config::parse_ini("font_size=24\n[shortcuts]\nundo=Ctrl+U, F2\nclear=Bogus+X\n").unwrap();
+        assert_eq!(config.font_size, 24.0);
+        assert_eq!(config.shortcuts.chords_text(Action::Undo), "Ctrl+U, F2");
+        // 無法解析的設定保留預設值
+        assert_eq!(config.shortcuts.chords_text(Action::Clear), "Ctrl+L");
+    }
 }
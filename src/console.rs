@@ -1,152 +1,1282 @@
-// Console interface for Linux/Unix
-// 終端機介面（Linux 文字模式）
-
-use crate::dict::Dictionary;
-use crate::input_engine::InputEngine;
-use crossterm::{
-    event::{self, KeyCode, KeyEvent, KeyModifiers},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
-};
+// Console interface (all platforms)
+// 終端機介面（各平台共用，Windows --console 與 Linux 走同一份程式）：以 ratatui 排版，只重繪有變動的儲存格
+
+use crate::commands::ConsoleCommand;
+use crate::config::{Config, FeedbackStyle};
+use crate::dict::{Annotations, Dictionary};
+use crate::error::{self, Array30Error};
+use crate::input_engine::{InputEngine, KeyResult};
+use crate::keymap;
+use crate::remote;
+use crate::shortcuts::{self, Action, KeyChord, Shortcuts};
+use crate::tables::{self, MissingTables};
+use crate::userdict::UserDict;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Margin, Position, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState};
+use ratatui::{Frame, Terminal};
+use std::cell::Cell;
+use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// 候選列開頭的標籤
+const CANDIDATE_PREFIX: &str = "候選：";
+/// 指令結果區最多顯示的列數
+const MAX_COMMAND_ROWS: usize = 10;
+/// 按鍵提示列顯示最近按下的組碼鍵數
+const RECENT_KEY_COUNT: usize = 8;
+/// 提示回饋閃爍編輯區框線的時間
+const FLASH_DURATION: Duration = Duration::from_millis(200);
 
 pub struct ConsoleApp {
     engine: InputEngine,
+    shortcuts: Shortcuts,
+    palette: Palette,
+    /// 輸出區自最底端往上捲動的列數（0 為跟著最新的輸出）
+    output_scroll: usize,
+    /// 上次繪製時輸出區的總列數與可見列數，捲動時據此限制範圍
+    output_view: Cell<(usize, usize)>,
+    /// 剪貼簿（保留到程式結束；X11 下剪貼簿內容由持有者提供，關閉後就會消失）
+    clipboard: Option<arboard::Clipboard>,
+    /// 狀態列附加的訊息（例如複製結果），下次按鍵時清除
+    notice: Option<String>,
+    /// 輸出區的自動存檔（--output）
+    output_file: Option<OutputFile>,
+    /// 使用者自訂字詞（:adduser 加入後存檔）
+    user_dict: UserDict,
+    /// 輸入中的指令（按 : 開啟指令列時為 Some）
+    command_line: Option<String>,
+    /// 上一個指令的結果，按 Esc 或其他鍵時關閉
+    command_output: Vec<String>,
+    /// 是否顯示按鍵提示列（F1 切換）
+    key_hints: bool,
+    /// 是否在編輯區逐一列出本頁候選的碼位、讀音與釋義（F2 切換）
+    candidate_detail: bool,
+    /// 最近按下的組碼鍵（已依鍵盤配置轉換），提示列以行列標示顯示
+    recent_keys: String,
+    /// 瀏覽模式下輸出區的游標（字元索引）；None 為一般輸入模式
+    nav_cursor: Option<usize>,
+    /// 上一個按鍵是 Esc（連按兩次 Esc 進入瀏覽模式）
+    pending_esc: bool,
+    /// 等待按鍵的間隔（console_poll_ms）
+    poll_interval: Duration,
+    /// 找不到表格、以空字典啟動時的表格位置（可用 :tables 重新載入）
+    missing_tables: Option<MissingTables>,
+    /// 提示回饋的呈現方式
+    feedback: FeedbackStyle,
+    /// 提示回饋閃爍的結束時間
+    flash_until: Option<Instant>,
     should_quit: bool,
 }
 
+/// 終端機介面的配色
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Palette {
+    /// 候選序號
+    index: Style,
+    /// 反白的候選（空白鍵上屏的字）
+    selected: Style,
+    /// 字候選
+    char: Style,
+    /// 詞候選
+    phrase: Style,
+    /// 鍵盤輸入區
+    raw_keys: Style,
+    /// 提示回饋閃爍時的編輯區框線
+    flash: Style,
+}
+
+impl Palette {
+    fn colored() -> Self {
+        Self {
+            index: Style::new().fg(Color::Yellow),
+            selected: Style::new().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD),
+            char: Style::new(),
+            phrase: Style::new().fg(Color::Green),
+            raw_keys: Style::new().fg(Color::DarkGray),
+            flash: Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// 單色：只用粗體、底線、反白等字型效果
+    fn monochrome() -> Self {
+        Self {
+            index: Style::new().add_modifier(Modifier::DIM),
+            selected: Style::new().add_modifier(Modifier::REVERSED),
+            char: Style::new(),
+            phrase: Style::new().add_modifier(Modifier::UNDERLINED),
+            raw_keys: Style::new().add_modifier(Modifier::DIM),
+            flash: Style::new().add_modifier(Modifier::REVERSED),
+        }
+    }
+
+    /// 依設定選擇配色；設定 NO_COLOR 環境變數或終端機為 dumb 時改用單色
+    fn detect(console_color: bool) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+            || std::env::var("TERM").is_ok_and(|term| term == "dumb");
+        if console_color && !no_color {
+            Self::colored()
+        } else {
+            Self::monochrome()
+        }
+    }
+}
+
+/// 依顯示寬度（全形字佔兩格）把文字切成不超過 width 的列
+/// 中文沒有空白可斷字，因此逐字換行；全形字放不下時整個移到下一列
+fn wrap_lines(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(2);
+    let mut rows = Vec::new();
+    for line in text.split('\n') {
+        let mut row = String::new();
+        let mut row_width = 0;
+        for c in line.chars() {
+            let w = c.width().unwrap_or(0);
+            if row_width + w > width {
+                rows.push(std::mem::take(&mut row));
+                row_width = 0;
+            }
+            row.push(c);
+            row_width += w;
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+/// 游標在換行後的列與列內字元位置（與 wrap_lines 的換行方式一致）
+fn cursor_row_col(text: &str, pos: usize, width: usize) -> (usize, usize) {
+    let split = text.char_indices().nth(pos).map_or(text.len(), |(i, _)| i);
+    let before = wrap_lines(&text[..split], width);
+    let row = before.len() - 1;
+    let last = &before[row];
+    // 游標下的字放不進這一列時會換到下一列
+    match text[split..].chars().next() {
+        Some(c) if c != '\n' && last.width() + c.width().unwrap_or(0) > width.max(2) => (row + 1, 0),
+        _ => (row, last.chars().count()),
+    }
+}
+
+/// 游標所在行的範圍（字元索引）：行首與行尾（換行字元或文字結尾的位置）
+fn line_span(chars: &[char], pos: usize) -> (usize, usize) {
+    let pos = pos.min(chars.len());
+    let start = chars[..pos].iter().rposition(|&c| c == '\n').map_or(0, |i| i + 1);
+    let end = chars[pos..].iter().position(|&c| c == '\n').map_or(chars.len(), |i| pos + i);
+    (start, end)
+}
+
+/// 輸出區的自動存檔：輸出區的內容接在檔案原有內容之後，每次變動立即寫入
+/// 刪除或復原時截斷再重寫變動的部分，使檔案始終為「原有內容＋輸出區」
+struct OutputFile {
+    file: File,
+    path: PathBuf,
+    /// 不再隨輸出區變動的長度（位元組）：開啟時原有的內容與清除前的輸出
+    base_len: u64,
+    /// 已寫入檔案的輸出區內容
+    written: String,
+}
+
+impl OutputFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let base_len = file.metadata()?.len();
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            base_len,
+            written: String::new(),
+        })
+    }
+
+    /// 與輸出區同步：只重寫與上次寫入內容不同的部分
+    fn sync(&mut self, output: &str) -> io::Result<()> {
+        if output == self.written {
+            return Ok(());
+        }
+        let common = self
+            .written
+            .char_indices()
+            .zip(output.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(self.written.len().min(output.len()), |((i, _), _)| i);
+        if common < self.written.len() {
+            self.file.set_len(self.base_len + common as u64)?;
+        }
+        self.file.write_all(&output.as_bytes()[common..])?;
+        self.written = output.to_string();
+        Ok(())
+    }
+
+    /// 輸出區較早的內容被移出時，檔案保留這段內容，之後只同步其後的部分
+    fn archive(&mut self, archived: &str) -> io::Result<()> {
+        if !self.written.starts_with(archived) {
+            self.sync(archived)?;
+        }
+        self.base_len += archived.len() as u64;
+        self.written.drain(..archived.len());
+        Ok(())
+    }
+
+    /// 清除輸出區前保留已寫入的內容，之後的輸出另起一行
+    fn seal(&mut self) -> io::Result<()> {
+        if !self.written.is_empty() && !self.written.ends_with('\n') {
+            self.file.write_all(b"\n")?;
+            self.base_len += 1;
+        }
+        self.base_len += self.written.len() as u64;
+        self.written.clear();
+        Ok(())
+    }
+
+    /// 結束時確保內容寫入磁碟
+    fn finish(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file.sync_all()
+    }
+}
+
 impl ConsoleApp {
-    pub fn new(dict: Dictionary) -> Self {
+    pub fn new(dict: Dictionary, config: Config) -> Self {
+        let mut engine = InputEngine::with_options(dict, config.engine_options());
+        if !config.annotation_file.is_empty() {
+            match Annotations::load(Path::new(&config.annotation_file)) {
+                Ok(annotations) => engine.set_annotations(Arc::new(annotations)),
+                Err(e) => tracing::warn!(path = %config.annotation_file, "無法讀取讀音註解檔：{}", e),
+            }
+        }
+        engine.set_remote(remote::from_url(&config.remote_dict_url));
+        engine.enable_feedback();
         Self {
-            engine: InputEngine::new(dict),
+            engine,
+            shortcuts: config.shortcuts,
+            palette: Palette::detect(config.console_color),
+            output_scroll: 0,
+            output_view: Cell::new((0, 0)),
+            clipboard: None,
+            notice: None,
+            output_file: None,
+            user_dict: UserDict::default(),
+            command_line: None,
+            command_output: Vec::new(),
+            key_hints: true,
+            candidate_detail: false,
+            recent_keys: String::new(),
+            nav_cursor: None,
+            pending_esc: false,
+            poll_interval: Duration::from_millis(config.console_poll_ms),
+            missing_tables: None,
+            feedback: config.feedback,
+            flash_until: None,
             should_quit: false,
         }
     }
 
     pub fn run(&mut self) -> io::Result<()> {
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
+        // 進入替代畫面與 raw mode；結束或 panic 時由 ratatui 還原終端機
+        let mut terminal = ratatui::try_init()?;
+        let result = self.event_loop(&mut terminal);
+        ratatui::try_restore()?;
+        if let Some(ref mut output_file) = self.output_file {
+            output_file.finish()?;
+            println!("輸出已存到 {}", output_file.path.display());
+        }
+        println!("行列 30 輸入法 - 再見！");
+        result
+    }
 
+    fn event_loop<B: ratatui::backend::Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
         self.should_quit = false;
+        // 只有畫面內容改變或終端機大小改變時才重繪；ratatui 只輸出與上一個畫面不同的儲存格
+        let mut dirty = true;
 
         while !self.should_quit {
-            // 繪製介面
-            self.draw(&mut stdout)?;
+            if dirty {
+                terminal.draw(|frame| self.draw(frame))?;
+                dirty = false;
+            }
 
-            // 讀取按鍵
-            if event::poll(std::time::Duration::from_millis(100))? {
-                if let event::Event::Key(key) = event::read()? {
-                    self.handle_key_event(key);
+            dirty |= self.tick();
+            if event::poll(self.poll_interval)? {
+                match event::read()? {
+                    // Windows 會同時回報按下與放開，只處理按下
+                    Event::Key(key) if key.kind != KeyEventKind::Release => {
+                        dirty |= self.handle_key_event(key);
+                    }
+                    Event::Resize(..) => {
+                        terminal.autoresize()?;
+                        dirty = true;
+                    }
+                    _ => {}
                 }
             }
         }
+        Ok(())
+    }
 
-        // 清理
-        disable_raw_mode()?;
-        execute!(stdout, Clear(ClearType::All))?;
-        println!("行列 30 輸入法 - 再見！");
+    /// 繪製介面：鍵盤輸入、編輯區（碼與候選）、輸出區、提示與狀態列
+    fn draw(&self, frame: &mut Frame) {
+        // 編輯區的高度隨候選換行的列數增加（加上上下框線）
+        let compose_lines = self.compose_lines(frame.area().width.saturating_sub(2));
+        let compose_height = u16::try_from(compose_lines.len() + 2).unwrap_or(u16::MAX);
+        // 指令結果依顯示寬度換行，最多 MAX_COMMAND_ROWS 列
+        let command_rows: Vec<String> = self
+            .command_output
+            .iter()
+            .flat_map(|line| wrap_lines(line, usize::from(frame.area().width.saturating_sub(2))))
+            .collect();
+        let command_height = if command_rows.is_empty() { 0 } else { command_rows.len().min(MAX_COMMAND_ROWS) + 2 };
+        let key_hint_lines = if self.key_hints { self.key_hint_lines() } else { Vec::new() };
+        let [input_area, compose_area, output_area, command_area, hint_area, status_area, key_hint_area, help_area] =
+            Layout::vertical([
+                Constraint::Length(3),
+                Constraint::Length(compose_height),
+                Constraint::Min(3),
+                Constraint::Length(u16::try_from(command_height).unwrap_or(u16::MAX)),
+                Constraint::Length(3),
+                Constraint::Length(1),
+                Constraint::Length(u16::try_from(key_hint_lines.len()).unwrap_or(u16::MAX)),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
 
-        Ok(())
+        frame.render_widget(self.input_widget(), input_area);
+        let mut compose_block = Block::bordered().title(" 編輯區 ");
+        if self.flash_until.is_some() {
+            compose_block = compose_block.border_style(self.palette.flash);
+        }
+        frame.render_widget(Paragraph::new(compose_lines).block(compose_block), compose_area);
+        self.draw_output(frame, output_area);
+        if !command_rows.is_empty() {
+            let lines: Vec<Line> = command_rows.into_iter().take(MAX_COMMAND_ROWS).map(Line::raw).collect();
+            frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" 指令結果（Esc 關閉）")), command_area);
+        }
+        frame.render_widget(self.hint_widget(), hint_area);
+        frame.render_widget(self.status_widget(), status_area);
+        frame.render_widget(Paragraph::new(key_hint_lines), key_hint_area);
+
+        // 最後一列：指令列或按鍵說明
+        match self.command_line {
+            Some(ref line) => {
+                frame.render_widget(Paragraph::new(format!(":{}", line)), help_area);
+                let x = help_area.x.saturating_add(u16::try_from(1 + line.width()).unwrap_or(u16::MAX));
+                frame.set_cursor_position(Position::new(x.min(help_area.right().saturating_sub(1)), help_area.y));
+            }
+            None => frame.render_widget(
+                Paragraph::new(": 指令，Esc Esc 瀏覽輸出，F1 按鍵提示，F2 候選詳細資訊，Ctrl+↑↓ 捲動輸出區，Ctrl+Y 複製輸出，按 Ctrl+C 或 Ctrl+Q 離開").dim(),
+                help_area,
+            ),
+        }
     }
 
-    fn draw(&self, stdout: &mut io::Stdout) -> io::Result<()> {
-        execute!(stdout, Clear(ClearType::All), crossterm::cursor::MoveTo(0, 0))?;
+    /// 鍵盤輸入區
+    fn input_widget(&self) -> Paragraph<'_> {
+        Paragraph::new(self.engine.state().raw_keys.as_str())
+            .style(self.palette.raw_keys)
+            .block(Block::bordered().title(" 行列 30 輸入法 - 鍵盤輸入 "))
+    }
 
+    /// 編輯區各列：目前的碼、本頁候選（第一個反白，空白鍵上屏；字與詞分色）與延伸碼預覽
+    fn compose_lines(&self, width: u16) -> Vec<Line<'_>> {
         let state = self.engine.state();
-        let candidates = self.engine.current_page_candidates();
+        let mut lines = Vec::new();
+        if state.current_code.is_empty() {
+            lines.push(Line::raw("（空）"));
+        } else {
+            lines.push(Line::from(vec![Span::raw("碼："), Span::raw(state.current_code.as_str()).bold()]));
 
-        // 第一行：標題
-        println!("行列 30 輸入法 - 終端機模式");
-        println!();
-
-        // 第二行：鍵盤輸入區
-        println!("鍵盤輸入：{}", state.raw_keys);
-        println!();
-
-        // 第三行：編輯區
-        if !state.current_code.is_empty() {
-            println!("編輯區：碼 = {}", state.current_code);
-            if !candidates.is_empty() {
-                print!("候選：");
-                for (i, cand) in candidates.iter().enumerate() {
-                    print!("[{}]{} ", i + 1, cand.text);
-                }
-                println!();
+            if self.engine.current_page_candidates().is_empty() {
+                lines.push(Line::raw("無候選字"));
             } else {
-                println!("編輯區：無候選字");
+                lines.extend(self.candidate_lines(usize::from(width)));
+                if self.candidate_detail {
+                    lines.extend(self.candidate_detail_lines().into_iter().map(|line| Line::raw(line).dim()));
+                }
+                lines.push(Line::raw(self.page_line()).dim());
+            }
+            if let Some(short) = self.engine.short_code() {
+                lines.push(Line::from(vec![Span::raw("空白鍵："), Span::styled(short.text.as_str(), self.palette.selected)]));
             }
+
+            let preview = self.engine.preview_candidates();
+            if !preview.is_empty() {
+                let items: Vec<String> = preview.iter().map(|c| format!("{}({})", c.text, c.code)).collect();
+                lines.push(Line::raw(format!("預覽：{}", items.join(" "))).dim());
+            }
+        }
+        lines
+    }
+
+    /// 候選詳細資訊：每個候選一列，列出碼位、讀音與釋義，例如「1 行 U+884C xíng、háng go, walk」
+    fn candidate_detail_lines(&self) -> Vec<String> {
+        self.engine
+            .current_page_candidates()
+            .iter()
+            .enumerate()
+            .map(|(i, cand)| format!("{} {}", i + 1, cand.detail()))
+            .collect()
+    }
+
+    /// 候選頁碼與總數，例如「候選 第 2/5 頁（共 41 個）」
+    fn page_line(&self) -> String {
+        format!(
+            "候選 第 {}/{} 頁（共 {} 個）",
+            self.engine.page_index() + 1,
+            self.engine.page_count(),
+            self.engine.candidate_count()
+        )
+    }
+
+    /// 本頁候選排成等寬的欄：每格是序號加字詞，依顯示寬度補空白對齊
+    /// 一列放不下時換到下一列，續列縮排到標籤之後
+    fn candidate_lines(&self, width: usize) -> Vec<Line<'_>> {
+        let candidates = self.engine.current_page_candidates();
+        // 字與詞同時列出時，各段第一個候選前加上段落標記
+        let marker = |i: usize| self.engine.section_marker(i).unwrap_or("");
+        let cell_width = |i: usize, text: &str| marker(i).width() + (i + 1).to_string().len() + text.width();
+        let column_width = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, cand)| cell_width(i, &cand.text))
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let indent = CANDIDATE_PREFIX.width();
+        let columns = (width.saturating_sub(indent) / column_width).max(1);
+
+        let mut lines = Vec::new();
+        for (row, chunk) in candidates.chunks(columns).enumerate() {
+            let label = if row == 0 { CANDIDATE_PREFIX.to_string() } else { " ".repeat(indent) };
+            let mut spans = vec![Span::raw(label)];
+            for (j, cand) in chunk.iter().enumerate() {
+                let i = row * columns + j;
+                let style = if i == 0 {
+                    self.palette.selected
+                } else if cand.is_phrase {
+                    self.palette.phrase
+                } else {
+                    self.palette.char
+                };
+                spans.push(Span::styled(marker(i), self.palette.index));
+                spans.push(Span::styled(format!("{}", i + 1), self.palette.index));
+                spans.push(Span::styled(cand.text.as_str(), style));
+                spans.push(Span::raw(" ".repeat(column_width - cell_width(i, &cand.text))));
+            }
+            lines.push(Line::from(spans));
+        }
+        lines
+    }
+
+    /// 輸出區：依顯示寬度換行，預設顯示最後幾列；可往上捲動，超過高度時在右框顯示捲軸與列數
+    fn draw_output(&self, frame: &mut Frame, area: Rect) {
+        let block = match self.nav_cursor {
+            Some(_) => Block::bordered().title(" 輸出區（瀏覽：hjkl 移動，x 刪除，p 貼上，i 回到輸入） "),
+            None => Block::bordered().title(" 輸出區 "),
+        };
+        let output = &self.engine.state().output;
+        if output.is_empty() {
+            self.output_view.set((0, 0));
+            frame.render_widget(Paragraph::new("（空）".dim()).block(block), area);
+            return;
+        }
+        let inner = block.inner(area);
+        let height = usize::from(inner.height);
+        // 捲軸畫在右框上，不佔文字寬度
+        let width = usize::from(inner.width);
+        let rows = wrap_lines(output, width);
+        self.output_view.set((rows.len(), height));
+
+        let max_scroll = rows.len().saturating_sub(height);
+        let mut start = max_scroll - self.output_scroll.min(max_scroll);
+        // 瀏覽模式時捲動到游標所在的列
+        let cursor = self.nav_cursor.map(|pos| cursor_row_col(output, pos, width));
+        if let Some((row, _)) = cursor {
+            start = start.min(row).max((row + 1).saturating_sub(height));
+        }
+        let end = (start + height).min(rows.len());
+        let lines: Vec<Line> = rows[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, row)| match cursor {
+                Some((cursor_row, col)) if cursor_row == start + i => {
+                    let split = row.char_indices().nth(col).map_or(row.len(), |(i, _)| i);
+                    let (before, rest) = row.split_at(split);
+                    let mut chars = rest.chars();
+                    let under = chars.next().map_or_else(|| " ".to_string(), String::from);
+                    Line::from(vec![
+                        Span::raw(before.to_string()),
+                        Span::raw(under).reversed(),
+                        Span::raw(chars.as_str().to_string()),
+                    ])
+                }
+                _ => Line::raw(row.clone()),
+            })
+            .collect();
+        let block = if max_scroll > 0 {
+            block.title(Line::raw(format!(" {}-{}/{} ", start + 1, end, rows.len())).right_aligned())
         } else {
-            println!("編輯區：（空）");
+            block
+        };
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+
+        if max_scroll > 0 {
+            let mut state = ScrollbarState::new(max_scroll).position(start).viewport_content_length(height);
+            frame.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight).begin_symbol(None).end_symbol(None),
+                area.inner(Margin { vertical: 1, horizontal: 0 }),
+                &mut state,
+            );
+        }
+    }
+
+    /// 捲動輸出區；正數往上（較舊的內容），負數往下
+    fn scroll_output(&mut self, delta: isize) {
+        let (rows, height) = self.output_view.get();
+        let max_scroll = rows.saturating_sub(height);
+        self.output_scroll = self.output_scroll.saturating_add_signed(delta).min(max_scroll);
+    }
+
+    /// 輸出區的捲動鍵：Ctrl+↑↓ 逐列捲動；沒有組字時 PageUp/PageDown（不含 Shift）整頁捲動
+    /// 組字中的 PageUp/PageDown 仍用於候選翻頁
+    fn scroll_delta(&self, key: &KeyEvent) -> Option<isize> {
+        let page = isize::try_from(self.output_view.get().1.saturating_sub(1).max(1)).unwrap_or(1);
+        let composing = !self.engine.state().current_code.is_empty();
+        match key.code {
+            KeyCode::Up if key.modifiers == KeyModifiers::CONTROL => Some(1),
+            KeyCode::Down if key.modifiers == KeyModifiers::CONTROL => Some(-1),
+            KeyCode::PageUp if key.modifiers.is_empty() && !composing => Some(page),
+            KeyCode::PageDown if key.modifiers.is_empty() && !composing => Some(-page),
+            _ => None,
         }
-        println!();
+    }
+
+    /// 提示區
+    fn hint_widget(&self) -> Paragraph<'_> {
+        Paragraph::new(self.engine.state().get_hint()).block(Block::bordered().title(" 提示 "))
+    }
 
-        // 第四行：輸出區
-        let output = if state.output.is_empty() {
-            "（空）"
+    /// 按鍵提示列：最近按下的組碼鍵的行列標示（如 1- 5v 0^）與目前的快速鍵
+    fn key_hint_lines(&self) -> Vec<Line<'_>> {
+        let keys = if self.recent_keys.is_empty() {
+            Span::raw("（尚未輸入）").dim()
         } else {
-            &state.output
+            Span::raw(keymap::code_labels(&self.recent_keys)).style(self.palette.raw_keys)
         };
-        println!("輸出區：{}", output);
-        println!();
+        let bindings: Vec<String> = Action::all()
+            .into_iter()
+            // 浮動候選列只在 GUI 使用
+            .filter(|&action| action != Action::ToggleCandidateBar)
+            .filter(|&action| !self.shortcuts.chords(action).is_empty())
+            .map(|action| format!("{} {}", self.shortcuts.chords_text(action), action.display_name()))
+            .collect();
+        vec![
+            Line::from(vec![Span::raw(" 按鍵 "), keys]),
+            Line::raw(format!(" {}", bindings.join("｜"))).dim(),
+        ]
+    }
+
+    /// 記錄組碼鍵供提示列顯示，只保留最近 RECENT_KEY_COUNT 個
+    fn record_recent_key(&mut self, c: char) {
+        let options = self.engine.options();
+        let c = options.keymap.remap(c);
+        if self.engine.is_english_mode() || !options.variant.is_code_key(c) {
+            return;
+        }
+        self.recent_keys.push(c.to_ascii_lowercase());
+        if let Some((start, _)) = self.recent_keys.char_indices().rev().nth(RECENT_KEY_COUNT - 1) {
+            self.recent_keys.drain(..start);
+        }
+    }
 
-        // 第五行：提示區
-        let hint = state.get_hint();
-        println!("提示：{}", hint);
-        println!();
-        println!("按 Ctrl+C 或 Ctrl+Q 離開");
+    /// 狀態列
+    fn status_widget(&self) -> Paragraph<'_> {
+        Paragraph::new(self.status_line()).reversed()
+    }
 
-        stdout.flush()?;
-        Ok(())
+    /// 狀態列：中英、組字模式、字表、候選頁碼與字典項目數
+    fn status_line(&self) -> String {
+        let language = if self.engine.is_english_mode() { "英" } else { "中" };
+        let page = self
+            .engine
+            .page_indicator()
+            .map_or_else(|| "-/-".to_string(), |(page, count)| format!("{}/{}", page, count));
+        let (chars, phrases) = self.engine.entry_counts();
+        let mut line = format!(
+            " {} | {} | {} | 第 {} 頁 | 字 {}／詞 {} ",
+            language,
+            self.engine.compose_mode().display_name(),
+            self.engine.table_name(),
+            page,
+            chars,
+            phrases
+        );
+        if self.missing_tables.is_some() {
+            line.push_str("| 未載入表格（:tables） ");
+        }
+        if let Some(ref notice) = self.notice {
+            line.push_str(&format!("| {} ", notice));
+        }
+        line
+    }
+
+    /// 複製輸出區到系統剪貼簿，結果顯示在狀態列
+    fn copy_output(&mut self) {
+        let text = self.engine.get_output_text();
+        if text.is_empty() {
+            self.notice = Some("輸出區是空的".to_string());
+            return;
+        }
+        self.notice = Some(match self.clipboard().map(|clipboard| clipboard.set_text(&text)) {
+            Ok(Ok(())) => format!("已複製 {} 字元", text.chars().count()),
+            Ok(Err(e)) => format!("無法複製到剪貼簿：{}", e),
+            Err(e) => format!("無法開啟剪貼簿：{}", e),
+        });
+    }
+
+    /// 第一次使用時才開啟剪貼簿
+    fn clipboard(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        let clipboard = match self.clipboard.take() {
+            Some(clipboard) => clipboard,
+            None => arboard::Clipboard::new()?,
+        };
+        Ok(self.clipboard.insert(clipboard))
+    }
+
+    /// 瀏覽模式的按鍵：hjkl／方向鍵移動游標、0 與 $ 到行首行尾、x 刪除游標下的字、
+    /// p 在游標後貼上剪貼簿、i 或 Esc 回到輸入（新的字仍接在輸出區最後）
+    fn handle_nav_key(&mut self, key: KeyEvent) {
+        let Some(cursor) = self.nav_cursor else {
+            return;
+        };
+        let chars: Vec<char> = self.engine.state().output.chars().collect();
+        let (start, end) = line_span(&chars, cursor);
+        let column = cursor - start;
+        // 一般模式的游標停在字上，不停在行尾的換行
+        let last_in_line = |start: usize, end: usize| end.saturating_sub(1).max(start);
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            if matches!(key.code, KeyCode::Char('c') | KeyCode::Char('q')) {
+                self.should_quit = true;
+            }
+            return;
+        }
+        let cursor = match key.code {
+            KeyCode::Char('h') | KeyCode::Left => cursor.saturating_sub(1).max(start),
+            KeyCode::Char('l') | KeyCode::Right => (cursor + 1).min(last_in_line(start, end)),
+            KeyCode::Char('0') | KeyCode::Home => start,
+            KeyCode::Char('$') | KeyCode::End => last_in_line(start, end),
+            KeyCode::Char('j') | KeyCode::Down if end < chars.len() => {
+                let (next_start, next_end) = line_span(&chars, end + 1);
+                (next_start + column).min(last_in_line(next_start, next_end))
+            }
+            KeyCode::Char('k') | KeyCode::Up if start > 0 => {
+                let (prev_start, prev_end) = line_span(&chars, start - 1);
+                (prev_start + column).min(last_in_line(prev_start, prev_end))
+            }
+            KeyCode::Char('x') | KeyCode::Delete if cursor < end => {
+                self.engine.delete_output_at(cursor);
+                self.sync_output_file();
+                cursor.min(last_in_line(start, end - 1))
+            }
+            KeyCode::Char('p') => {
+                let pasted = match self.clipboard().map(|clipboard| clipboard.get_text()) {
+                    Ok(Ok(text)) => text.replace("\r\n", "\n"),
+                    Ok(Err(e)) => {
+                        self.notice = Some(format!("無法讀取剪貼簿：{}", e));
+                        return;
+                    }
+                    Err(e) => {
+                        self.notice = Some(format!("無法開啟剪貼簿：{}", e));
+                        return;
+                    }
+                };
+                self.paste_at_cursor(&pasted)
+            }
+            KeyCode::Char('i') | KeyCode::Esc => {
+                self.nav_cursor = None;
+                return;
+            }
+            _ => cursor,
+        };
+        self.nav_cursor = Some(cursor);
+    }
+
+    /// 在游標後插入文字，回傳新的游標位置（貼上的最後一個字）
+    fn paste_at_cursor(&mut self, text: &str) -> usize {
+        let Some(cursor) = self.nav_cursor else {
+            return 0;
+        };
+        let count = text.chars().count();
+        if count == 0 {
+            return cursor;
+        }
+        let chars: Vec<char> = self.engine.state().output.chars().collect();
+        // 空行或空的輸出區貼在游標處，否則貼在游標下的字之後
+        let at = if cursor < line_span(&chars, cursor).1 { cursor + 1 } else { cursor };
+        self.engine.insert_output_at(at, text);
+        self.sync_output_file();
+        at + count - 1
     }
 
-    fn handle_key_event(&mut self, key: KeyEvent) {
+    /// 閒置過久的組字依設定清除或上屏、結束閃爍、合併背景查到的遠端字詞，
+    /// 回傳畫面是否需要重繪
+    fn tick(&mut self) -> bool {
+        let flash_ended = self.flash_until.is_some_and(|until| Instant::now() >= until);
+        if flash_ended {
+            self.flash_until = None;
+        }
+        let redraw = flash_ended | self.engine.poll_remote();
+        if self.command_line.is_some() || self.nav_cursor.is_some() {
+            return redraw;
+        }
+        let output_len = self.engine.state().output.len();
+        if self.engine.tick(Instant::now()) == KeyResult::NoChange {
+            return redraw;
+        }
+        if self.engine.state().output.len() != output_len {
+            self.output_scroll = 0;
+        }
+        self.sync_output_file();
+        true
+    }
+
+    /// 處理按鍵，回傳畫面是否需要重繪
+    fn handle_key_event(&mut self, key: KeyEvent) -> bool {
+        let had_notice = self.notice.take().is_some();
+        let after_esc = std::mem::take(&mut self.pending_esc);
+        if self.nav_cursor.is_some() {
+            self.handle_nav_key(key);
+            return true;
+        }
+        if let Some(delta) = self.scroll_delta(&key) {
+            let scroll = self.output_scroll;
+            self.scroll_output(delta);
+            return had_notice || self.output_scroll != scroll;
+        }
+        if self.command_line.is_some() {
+            self.handle_command_key(key);
+            return true;
+        }
+        let had_command_output = !self.command_output.is_empty();
+        if had_command_output {
+            self.command_output.clear();
+            if key.code == KeyCode::Esc {
+                return true;
+            }
+        }
+        // 中文模式且沒有組字時，: 開啟指令列
+        let shift_only = (key.modifiers - KeyModifiers::SHIFT).is_empty();
+        let composing = !self.engine.state().current_code.is_empty();
+        if key.code == KeyCode::Char(':') && shift_only && !composing && !self.engine.is_english_mode() {
+            self.command_line = Some(String::new());
+            return true;
+        }
+
+        // 沒有組字時連按兩次 Esc 進入瀏覽模式，游標在最後一個字
+        if key.code == KeyCode::Esc && key.modifiers.is_empty() {
+            if after_esc && !composing {
+                let len = self.engine.state().output.chars().count();
+                self.nav_cursor = Some(len.saturating_sub(1));
+                return true;
+            }
+            self.pending_esc = true;
+        }
+
+        // 有新的輸出時回到最底端
+        let output_len = self.engine.state().output.len();
+        let result = self.dispatch_key(key);
+        if self.engine.state().output.len() != output_len {
+            self.output_scroll = 0;
+        }
+        self.sync_output_file();
+        let flashed = self.present_feedback();
+        had_notice || had_command_output || self.notice.is_some() || flashed || result != KeyResult::NoChange
+    }
+
+    /// 依設定呈現引擎的提示回饋：響鈴、閃爍編輯區框線；回傳是否開始閃爍
+    fn present_feedback(&mut self) -> bool {
+        if self.engine.take_feedback().is_empty() || self.feedback == FeedbackStyle::Off {
+            return false;
+        }
+        if self.feedback.sound() {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|()| stdout.flush());
+        }
+        if self.feedback.flash() {
+            self.flash_until = Some(Instant::now() + FLASH_DURATION);
+        }
+        self.feedback.flash()
+    }
+
+    /// 指令列的按鍵：Enter 執行、Esc 或 Ctrl+C 取消、退格刪字（刪光時關閉指令列）
+    fn handle_command_key(&mut self, key: KeyEvent) {
+        let Some(ref mut line) = self.command_line else {
+            return;
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => {
+                let line = std::mem::take(line);
+                self.command_line = None;
+                self.run_command(&line);
+            }
+            KeyCode::Esc => self.command_line = None,
+            KeyCode::Char('c') if ctrl => self.command_line = None,
+            KeyCode::Backspace if line.is_empty() => self.command_line = None,
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Char(c) if !ctrl => line.push(c),
+            _ => {}
+        }
+    }
+
+    /// 執行指令，結果或錯誤顯示在指令結果區
+    fn run_command(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let result = ConsoleCommand::parse(line).and_then(|command| {
+            if let (ConsoleCommand::Tables(target), Some(_)) = (&command, &self.missing_tables) {
+                return self.reload_tables(target.as_deref());
+            }
+            let mut lines = command.execute(&mut self.engine, &mut self.user_dict)?;
+            if matches!(command, ConsoleCommand::AddUser { .. }) {
+                if let Err(e) = self.save_user_dict() {
+                    lines.push(format!("無法儲存自訂字詞：{}", e));
+                }
+            }
+            Ok(lines)
+        });
+        self.command_output = result.unwrap_or_else(|e| vec![e]);
+    }
+
+    /// 找不到表格時的提示
+    fn missing_tables_lines(missing: &MissingTables) -> Vec<String> {
+        let mut lines = vec![
+            format!("{}，目前沒有字表可用", missing.error),
+            ":tables <目錄>  從指定的表格目錄載入".to_string(),
+            format!("下載表格：{}，放到上述目錄後輸入 :tables 重試", tables::TABLE_DOWNLOAD_URL),
+        ];
+        if tables::has_embedded_tables() {
+            lines.push(":tables embedded  安裝內建的表格到使用者資料目錄".to_string());
+        }
+        lines
+    }
+
+    /// 重新載入表格：`target` 為表格目錄或 embedded（安裝內建表格），None 為原位置重試
+    fn reload_tables(&mut self, target: Option<&str>) -> Result<Vec<String>, String> {
+        let Some(missing) = self.missing_tables.as_mut() else {
+            return Err("表格已載入".to_string());
+        };
+        match target {
+            Some("embedded") => {
+                let dest = tables::data_table_dir().ok_or("無法取得使用者資料目錄")?;
+                tables::install_embedded_tables(&dest).map_err(|e| format!("無法安裝內建表格：{}", e))?;
+                missing.options.table_dir = dest.display().to_string();
+            }
+            Some(dir) => missing.options.table_dir = dir.to_string(),
+            None => {}
+        }
+        let dict = missing.reload().map_err(|e| {
+            missing.error = e.to_string();
+            format!("仍無法載入：{}", e)
+        })?;
+        let table_dir = missing.options.table_dir();
+        self.engine.load_dict(dict);
+        self.missing_tables = None;
+        Ok(vec![format!("已從 {} 載入表格", table_dir.display())])
+    }
+
+    fn save_user_dict(&self) -> error::Result<()> {
+        let path = UserDict::file_path().ok_or_else(|| Array30Error::ConfigError("無法取得使用者資料目錄".to_string()))?;
+        self.user_dict.save(&path)
+    }
+
+    /// 清除輸出區時，自動存檔保留已寫入的內容
+    fn seal_output_file(&mut self) {
+        let Some(ref mut output_file) = self.output_file else {
+            return;
+        };
+        if let Err(e) = output_file.seal() {
+            tracing::warn!(path = %output_file.path.display(), "無法寫入輸出檔：{}", e);
+        }
+    }
+
+    /// 將輸出區寫入自動存檔；失敗時顯示在狀態列
+    /// 超過字數上限而移出輸出區的內容保留在自動存檔中
+    fn sync_output_file(&mut self) {
+        let archived = self.engine.take_archived_output();
+        if !archived.is_empty() {
+            let count: usize = archived.iter().map(|text| text.chars().count()).sum();
+            let kept = if self.output_file.is_some() { "，已保留在輸出檔" } else { "" };
+            self.notice = Some(format!("輸出區已達上限，移出較早的 {} 字元{}", count, kept));
+        }
+        let Some(ref mut output_file) = self.output_file else {
+            return;
+        };
+        let result = archived
+            .iter()
+            .try_for_each(|text| output_file.archive(text))
+            .and_then(|()| output_file.sync(&self.engine.state().output));
+        if let Err(e) = result {
+            tracing::warn!(path = %output_file.path.display(), "無法寫入輸出檔：{}", e);
+            self.notice = Some(format!("無法寫入輸出檔：{}", e));
+        }
+    }
+
+    /// 將按鍵交給快速鍵或引擎，回傳引擎的處理結果
+    fn dispatch_key(&mut self, key: KeyEvent) -> KeyResult {
+        // 快速鍵
+        if let Some(action) = self.lookup_shortcut(&key) {
+            if action == Action::CopyOutput {
+                self.copy_output();
+                return KeyResult::NoChange;
+            }
+            if action == Action::ToggleKeyHints {
+                self.key_hints = !self.key_hints;
+                return KeyResult::NeedUpdate;
+            }
+            if action == Action::ToggleCandidateDetail {
+                self.candidate_detail = !self.candidate_detail;
+                return KeyResult::NeedUpdate;
+            }
+            if action == Action::Clear {
+                self.seal_output_file();
+            }
+            return shortcuts::dispatch(&mut self.engine, action);
+        }
+
         match key.code {
             // 退出
             KeyCode::Char('c') | KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.should_quit = true;
+                KeyResult::NoChange
             }
 
             // 退格
-            KeyCode::Backspace => {
-                self.engine.handle_key('\x08');
-            }
+            KeyCode::Backspace => self.engine.handle_key('\x08'),
 
             // Enter
-            KeyCode::Enter => {
-                self.engine.handle_key('\n');
-            }
+            KeyCode::Enter => self.engine.handle_key('\n'),
 
             // 空白
-            KeyCode::Char(' ') => {
-                self.engine.handle_key(' ');
-            }
+            KeyCode::Char(' ') => self.engine.handle_key(' '),
 
             // Esc
-            KeyCode::Esc => {
-                self.engine.handle_key('\x1b');
-            }
+            KeyCode::Esc => self.engine.handle_key('\x1b'),
 
             // 一般字元
             KeyCode::Char(c) => {
-                self.engine.handle_key(c);
-            }
-
-            // 分頁（PageDown/PageUp 或 tab/shift+tab）
-            KeyCode::PageDown | KeyCode::Tab => {
-                self.engine.next_page();
-            }
-            KeyCode::PageUp => {
-                if key.modifiers.contains(KeyModifiers::SHIFT) {
-                    self.engine.prev_page();
+                if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                    self.record_recent_key(c);
                 }
+                self.engine.handle_key(c)
             }
 
-            _ => {}
+            _ => KeyResult::NoChange,
         }
     }
+
+    fn lookup_shortcut(&self, key: &KeyEvent) -> Option<shortcuts::Action> {
+        KeyChord::from_key_event(key).and_then(|chord| self.shortcuts.lookup(&chord))
+    }
 }
 
-pub fn run_console(dict: Dictionary) -> io::Result<()> {
-    let mut app = ConsoleApp::new(dict);
+/// 執行終端機介面；指定 output 時將輸出區持續寫入該檔案
+/// `missing_tables` 為找不到表格時以空字典啟動，啟動後顯示安裝表格的方式
+pub fn run_console(
+    dict: Dictionary,
+    config: Config,
+    output: Option<&Path>,
+    missing_tables: Option<MissingTables>,
+) -> io::Result<()> {
+    let mut app = ConsoleApp::new(dict, config);
+    if let Some(missing) = missing_tables {
+        app.command_output = ConsoleApp::missing_tables_lines(&missing);
+        app.missing_tables = Some(missing);
+    }
+    app.user_dict = UserDict::load_default();
+    app.user_dict.apply_to(app.engine.dict_mut());
+    if let Some(path) = output {
+        app.output_file = Some(OutputFile::open(path)?);
+        tracing::info!(path = %path.display(), "輸出區將寫入檔案");
+    }
     app.run()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::ArrayVariant;
+    use crate::tables::TableOptions;
+    use ratatui::backend::TestBackend;
+
+    #[test]
+    fn test_wrap_lines() {
+        assert_eq!(wrap_lines("", 10), [""]);
+        assert_eq!(wrap_lines("abcd", 2), ["ab", "cd"]);
+        // 全形字佔兩格，放不下的全形字移到下一列
+        assert_eq!(wrap_lines("行列三十", 4), ["行列", "三十"]);
+        assert_eq!(wrap_lines("a行列", 4), ["a行", "列"]);
+        assert_eq!(wrap_lines("ab\n\ncd", 10), ["ab", "", "cd"]);
+    }
+
+    #[test]
+    fn test_scroll_output() {
+        let mut app = ConsoleApp::new(Dictionary::new(), Config::default());
+        app.engine.toggle_english_mode();
+        app.engine.feed_str(&"0123456789".repeat(10));
+        // 寬 12 的終端機，輸出區內部寬 10、高 7，共 10 列
+        let mut terminal = Terminal::new(TestBackend::new(12, 22)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert_eq!(app.output_view.get(), (10, 7));
+
+        let ctrl_up = KeyEvent::new(KeyCode::Up, KeyModifiers::CONTROL);
+        for _ in 0..5 {
+            app.handle_key_event(ctrl_up);
+        }
+        assert_eq!(app.output_scroll, 3);
+        app.handle_key_event(KeyEvent::from(KeyCode::PageDown));
+        assert_eq!(app.output_scroll, 0);
+        app.handle_key_event(KeyEvent::from(KeyCode::PageUp));
+        assert_eq!(app.output_scroll, 3);
+
+        // 有新的輸出時回到最底端
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('x')));
+        assert_eq!(app.output_scroll, 0);
+    }
+
+    #[test]
+    fn test_output_file_mirrors_output() {
+        let path = std::env::temp_dir().join(format!("rustarray30-output-{}.txt", std::process::id()));
+        std::fs::write(&path, "舊的\n").unwrap();
+        let mut file = OutputFile::open(&path).unwrap();
+        file.sync("行列").unwrap();
+        file.sync("行列輸入法").unwrap();
+        // 刪除或復原時只重寫變動的部分
+        file.sync("行列三十").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "舊的\n行列三十");
+
+        // 清除輸出區後保留已寫入的內容
+        file.seal().unwrap();
+        file.sync("").unwrap();
+        file.sync("好").unwrap();
+        // 移出輸出區的內容留在檔案中
+        file.archive("好").unwrap();
+        file.sync("").unwrap();
+        file.finish().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "舊的\n行列三十\n好");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_command_prompt() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("a", "一");
+        let mut app = ConsoleApp::new(dict, Config::default());
+        let press = |app: &mut ConsoleApp, code: KeyCode| app.handle_key_event(KeyEvent::from(code));
+
+        for c in ":l a".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.command_line.as_deref(), Some("l a"));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.command_line, None);
+        assert_eq!(app.command_output, ["a 單字：1一"]);
+        assert!(app.engine.state().output.is_empty());
+
+        // Esc 關閉結果；組字中的 : 交給引擎
+        press(&mut app, KeyCode::Esc);
+        assert!(app.command_output.is_empty());
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Char(':'));
+        assert_eq!(app.command_line, None);
+
+        press(&mut app, KeyCode::Esc);
+        for c in ":bogus".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(app.command_output[0].starts_with("未知的指令"));
+    }
+
+    #[test]
+    fn test_redraw_only_on_change() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("a", "一");
+        let mut app = ConsoleApp::new(dict, Config::default());
+        let mut press = |code: KeyCode| app.handle_key_event(KeyEvent::from(code));
+
+        assert!(press(KeyCode::Char('a')));
+        assert!(press(KeyCode::Char(' ')));
+        // 沒有對應動作的按鍵不重繪
+        assert!(!press(KeyCode::F(12)));
+        assert!(!press(KeyCode::Insert));
+    }
+
+    #[test]
+    fn test_feedback_flash() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("a", "一");
+        let config = Config {
+            feedback: FeedbackStyle::Flash,
+            ..Config::default()
+        };
+        let mut app = ConsoleApp::new(dict, config);
+        assert!(app.handle_key_event(KeyEvent::from(KeyCode::Char('a'))));
+        assert!(app.flash_until.is_none());
+        // 查無此碼
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('b')));
+        assert!(app.flash_until.is_some());
+        app.flash_until = Some(Instant::now());
+        assert!(app.tick());
+        assert!(app.flash_until.is_none());
+    }
+
+    #[test]
+    fn test_key_hints() {
+        let mut app = ConsoleApp::new(Dictionary::new(), Config::default());
+        for c in "abp1xyzqwer".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        // 選字鍵 1 不記錄，只保留最近 8 個組碼鍵
+        assert_eq!(app.recent_keys, "pxyzqwer");
+        assert!(app.key_hint_lines()[1].to_string().contains("F1 按鍵提示"));
+
+        assert!(app.key_hints);
+        app.handle_key_event(KeyEvent::from(KeyCode::F(1)));
+        assert!(!app.key_hints);
+    }
+
+    #[test]
+    fn test_candidate_detail() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("a", "一");
+        dict.insert_char("a", "丁");
+        let mut app = ConsoleApp::new(dict, Config::default());
+        app.engine.set_annotations(Arc::new(Annotations::parse("一\tyī\nU+4E00\tkDefinition\tone\n")));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('a')));
+        assert!(!app.candidate_detail);
+        app.handle_key_event(KeyEvent::from(KeyCode::F(2)));
+        assert!(app.candidate_detail);
+        assert_eq!(app.candidate_detail_lines(), ["1 一 U+4E00 yī one", "2 丁 U+4E01"]);
+    }
+
+    #[test]
+    fn test_nav_mode() {
+        let mut app = ConsoleApp::new(Dictionary::new(), Config::default());
+        app.engine.toggle_english_mode();
+        app.engine.feed_str("abc");
+        app.engine.insert_output("\nde");
+        let press = |app: &mut ConsoleApp, code: KeyCode| app.handle_key_event(KeyEvent::from(code));
+        press(&mut app, KeyCode::Esc);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.nav_cursor, Some(5));
+
+        // 上移時保留欄位，行尾停在最後一個字
+        for key in [KeyCode::Char('h'), KeyCode::Char('k'), KeyCode::Char('$'), KeyCode::Char('x')] {
+            press(&mut app, key);
+        }
+        assert_eq!(app.engine.state().output, "ab\nde");
+        assert_eq!(app.nav_cursor, Some(1));
+        assert_eq!(app.paste_at_cursor("行列"), 3);
+        assert_eq!(app.engine.state().output, "ab行列\nde");
+
+        // 瀏覽模式的游標換列位置與 wrap_lines 一致
+        assert_eq!(cursor_row_col("ab行列\nde", 3, 4), (1, 0));
+        assert_eq!(cursor_row_col("ab行列\nde", 5, 4), (2, 0));
+        assert_eq!(cursor_row_col("ab行列\nde", 4, 4), (1, 1));
+
+        press(&mut app, KeyCode::Char('i'));
+        assert_eq!(app.nav_cursor, None);
+        press(&mut app, KeyCode::Char('z'));
+        assert_eq!(app.engine.state().output, "ab行列\ndez");
+    }
+
+    #[test]
+    fn test_copy_empty_output_notice() {
+        let mut app = ConsoleApp::new(Dictionary::new(), Config::default());
+        app.handle_key_event(KeyEvent::from(KeyCode::F(5)));
+        assert!(app.status_line().ends_with("| 輸出區是空的 "));
+        // 下次按鍵時清除訊息
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(app.notice, None);
+    }
+
+    #[test]
+    fn test_draw_candidates_and_status() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("a", "一");
+        dict.insert_char("a", "二");
+        let config = Config {
+            console_color: false,
+            ..Config::default()
+        };
+        let mut app = ConsoleApp::new(dict, config);
+        assert_eq!(app.palette, Palette::monochrome());
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('a')));
+
+        let mut terminal = Terminal::new(TestBackend::new(50, 18)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        // 全形字後面的儲存格被它蓋住，不列入畫面文字
+        let mut screen = String::new();
+        let mut covered = 0;
+        for cell in terminal.backend().buffer().content() {
+            if covered > 0 {
+                covered -= 1;
+                continue;
+            }
+            screen.push_str(cell.symbol());
+            covered = Line::raw(cell.symbol()).width().saturating_sub(1);
+        }
+        assert!(screen.contains("候選：1一 2二"));
+        assert!(screen.contains("候選 第 1/1 頁（共 2 個）"));
+
+        // 第一個候選反白，序號與其他候選使用各自的樣式
+        let buffer = terminal.backend().buffer();
+        let cell = |symbol: &str| buffer.content().iter().find(|c| c.symbol() == symbol).unwrap().clone();
+        assert!(cell("一").modifier.contains(Modifier::REVERSED));
+        assert!(!cell("二").modifier.contains(Modifier::REVERSED));
+        assert!(cell("2").modifier.contains(Modifier::DIM));
+
+        // 欄寬依顯示寬度計算，放不下時換列並縮排對齊
+        let lines = app.candidate_lines(CANDIDATE_PREFIX.width() + 4);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].to_string(), "      2二 ");
+        assert_eq!(lines[0].width(), lines[1].width());
+    }
+
+    #[test]
+    fn test_reload_tables() {
+        let dir = std::env::temp_dir().join(format!("rustarray30-console-tables-{}", std::process::id()));
+        let options = TableOptions {
+            table_dir: dir.join("missing").display().to_string(),
+            ..TableOptions::default()
+        };
+        let mut app = ConsoleApp::new(Dictionary::new(), Config::default());
+        app.missing_tables = Some(MissingTables {
+            options,
+            variant: ArrayVariant::Array30,
+            use_big_char: false,
+            error: "找不到表格檔".to_string(),
+        });
+        assert!(app.status_line().contains("未載入表格"));
+
+        app.run_command("tables");
+        assert!(app.command_output[0].starts_with("仍無法載入"));
+
+        let (phrase_file, char_file) = tables::table_files(&dir, ArrayVariant::Array30, false);
+        std::fs::create_dir_all(char_file.parent().unwrap()).unwrap();
+        std::fs::write(&char_file, "%chardef begin\na\t一\n%chardef end\n").unwrap();
+        std::fs::write(phrase_file.unwrap(), "ab\t一二\n").unwrap();
+        app.run_command(&format!("tables {}", dir.display()));
+        assert!(app.missing_tables.is_none());
+        assert_eq!(app.engine.dict().lookup_chars("a"), Some(&["一".to_string()][..]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+// IPC service mode over a Unix socket
+// 常駐服務模式：以 Unix socket 與 JSON 協定提供輸入法引擎
+//
+// 每行一個 JSON 請求，回應也是一行 JSON，例如：
+//   → {"cmd":"keys","keys":"ab"}
+//   ← {"ok":true,"code":"ab","candidates":["甲","乙"],"page":0,"pages":1,"commit":"","english_mode":false}
+
+use crate::dict::Dictionary;
+use crate::input_engine::{EngineOptions, InputEngine};
+use crate::shared::SharedEngine;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Instant;
+
+/// socket 檔名
+pub const SOCKET_NAME: &str = "rustarray30.sock";
+
+/// 預設 socket 路徑（$XDG_RUNTIME_DIR，否則為暫存目錄）
+pub fn default_socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(SOCKET_NAME)
+}
+
+/// 用戶端請求
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    /// 單一按鍵（字元或 backspace/enter/escape/space）
+    Key { key: String },
+    /// 依序送出多個字元
+    Keys { keys: String },
+    /// 選擇目前頁面第 index 個候選
+    Select { index: usize },
+    NextPage,
+    PrevPage,
+    /// 切換中文/英文模式
+    ToggleLanguage,
+    /// 捨棄組字中的碼
+    Reset,
+    /// 只查詢目前狀態
+    State,
+}
+
+/// 回應
+#[derive(Debug, Default, Serialize)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    code: String,
+    candidates: Vec<String>,
+    page: usize,
+    pages: usize,
+    /// 本次請求產生的上屏文字
+    commit: String,
+    english_mode: bool,
+}
+
+impl Response {
+    fn error(message: String) -> Self {
+        Self {
+            error: Some(message),
+            ..Self::default()
+        }
+    }
+}
+
+/// 將按鍵名稱轉為引擎使用的字元
+fn parse_key(key: &str) -> Option<char> {
+    match key {
+        "backspace" => Some('\x08'),
+        "enter" => Some('\n'),
+        "escape" => Some('\x1b'),
+        "space" => Some(' '),
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// 處理一行請求並回傳一行回應（不含換行）
+fn handle_request(engine: &SharedEngine, line: &str) -> String {
+    let request = match serde_json::from_str::<Request>(line) {
+        Ok(request) => {
+            tracing::trace!(?request, "收到請求");
+            request
+        }
+        Err(e) => return to_json(&Response::error(format!("無效的請求：{}", e))),
+    };
+
+    let response = engine.with(|engine| {
+        // 沒有常駐計時器：組字逾時在下一個請求時處理，上屏結果隨回應的 commit 送出
+        engine.tick(Instant::now());
+        match request {
+            Request::Key { key } => match parse_key(&key) {
+                Some(c) => {
+                    engine.handle_key(c);
+                }
+                None => return Response::error(format!("未知按鍵：{}", key)),
+            },
+            Request::Keys { keys } => {
+                for c in keys.chars() {
+                    engine.handle_key(c);
+                }
+            }
+            Request::Select { index } => {
+                engine.select_candidate(index);
+            }
+            Request::NextPage => {
+                engine.next_page();
+            }
+            Request::PrevPage => {
+                engine.prev_page();
+            }
+            Request::ToggleLanguage => engine.toggle_english_mode(),
+            Request::Reset => {
+                engine.handle_key('\x1b');
+            }
+            Request::State => {}
+        }
+        snapshot(engine)
+    });
+    to_json(&response)
+}
+
+/// 取得引擎狀態並取出待上屏的文字
+fn snapshot(engine: &mut InputEngine) -> Response {
+    Response {
+        ok: true,
+        error: None,
+        code: engine.state().current_code.clone(),
+        candidates: engine
+            .current_page_candidates()
+            .iter()
+            .map(|cand| cand.text.clone())
+            .collect(),
+        page: engine.page_index(),
+        pages: engine.page_count(),
+        commit: engine.take_output(),
+        english_mode: engine.state().english_mode,
+    }
+}
+
+fn to_json(response: &Response) -> String {
+    serde_json::to_string(response).unwrap_or_else(|_| r#"{"ok":false}"#.to_string())
+}
+
+/// 處理單一連線，直到用戶端關閉
+fn serve_client(engine: SharedEngine, stream: UnixStream) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", handle_request(&engine, &line))?;
+    }
+    Ok(())
+}
+
+/// 以常駐服務模式執行
+/// 所有連線共用同一個引擎與字典
+pub fn run_daemon(
+    dict: Dictionary,
+    options: EngineOptions,
+    socket_path: PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // 移除上次未正常結束留下的 socket 檔
+    if socket_path.exists() {
+        std::fs::remove_file(&socket_path)?;
+    }
+    let listener = UnixListener::bind(&socket_path)?;
+    let engine = SharedEngine::from_engine(InputEngine::with_options(dict, options));
+
+    println!("服務已啟動：{}", socket_path.display());
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                tracing::debug!("用戶端已連線");
+                let engine = engine.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve_client(engine, stream) {
+                        tracing::warn!("連線錯誤：{}", e);
+                    }
+                });
+            }
+            Err(e) => tracing::warn!("無法接受連線：{}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_engine() -> SharedEngine {
+        let mut dict = Dictionary::new();
+        dict.insert_char("ab", "甲");
+        dict.insert_char("ab", "乙");
+        SharedEngine::new(dict)
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let engine = create_test_engine();
+
+        let response: serde_json::Value =
+            serde_json::from_str(&handle_request(&engine, r#"{"cmd":"keys","keys":"ab"}"#)).unwrap();
+        assert_eq!(response["ok"], true);
+        assert_eq!(response["code"], "ab");
+        assert_eq!(response["candidates"], serde_json::json!(["甲", "乙"]));
+
+        let response: serde_json::Value =
+            serde_json::from_str(&handle_request(&engine, r#"{"cmd":"select","index":1}"#)).unwrap();
+        assert_eq!(response["commit"], "乙");
+        assert_eq!(response["code"], "");
+
+        let response: serde_json::Value =
+            serde_json::from_str(&handle_request(&engine, r#"{"cmd":"key","key":"bogus"}"#)).unwrap();
+        assert_eq!(response["ok"], false);
+        assert!(handle_request(&engine, "not json").contains("\"ok\":false"));
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("a"), Some('a'));
+        assert_eq!(parse_key("enter"), Some('\n'));
+        assert_eq!(parse_key("ab"), None);
+        assert_eq!(parse_key(""), None);
+    }
+}
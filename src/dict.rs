@@ -1,18 +1,457 @@
 // Dictionary loading for Array30
 // 字典與詞庫載入
 
-use std::collections::HashMap;
+use crate::compiled::{self, CompiledTable, Section};
+use crate::error::{Array30Error, Result};
+use crate::keymap::ArrayVariant;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::{self, BufRead, BufReader};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// 簡碼選單的空位（cin2 的 %nullcandidate）
+pub const QUICK_PLACEHOLDER: &str = "□";
+
+/// 表格檔種類（依副檔名判斷）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableKind {
+    /// .cin2/.cin 字表
+    Chars,
+    /// .txt 詞庫
+    Phrases,
+}
+
+impl TableKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "cin2" | "cin" => Some(TableKind::Chars),
+            "txt" => Some(TableKind::Phrases),
+            _ => None,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TableKind::Chars => "字表",
+            TableKind::Phrases => "詞庫",
+        }
+    }
+
+    /// 編譯字表中對應的區段
+    fn section(self) -> Section {
+        match self {
+            TableKind::Chars => Section::Chars,
+            TableKind::Phrases => Section::Phrases,
+        }
+    }
+
+    /// 編譯字表中對應的詞頻區段
+    fn frequency_section(self) -> Section {
+        match self {
+            TableKind::Chars => Section::CharFrequencies,
+            TableKind::Phrases => Section::PhraseFrequencies,
+        }
+    }
+}
+
+/// 字典瀏覽的搜尋方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// 依碼的前綴
+    CodePrefix,
+    /// 依單字（可一次輸入多個字）
+    Character,
+    /// 依詞彙中的文字片段
+    Phrase,
+}
+
+impl SearchMode {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SearchMode::CodePrefix => "碼的前綴",
+            SearchMode::Character => "單字",
+            SearchMode::Phrase => "詞彙片段",
+        }
+    }
+
+    pub fn all() -> [SearchMode; 3] {
+        [SearchMode::CodePrefix, SearchMode::Character, SearchMode::Phrase]
+    }
+}
+
+/// 字典搜尋結果的一筆項目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictEntry {
+    pub code: String,
+    pub word: String,
+    pub kind: TableKind,
+}
+
+/// 單獨讀入的表格檔，確認後再載入或合併到使用中的字典
+#[derive(Debug, Clone)]
+pub struct TableFile {
+    pub kind: TableKind,
+    /// 字表的 % 開頭設定（如 ename、cname），詞庫沒有
+    pub metadata: Vec<(String, String)>,
+    /// 碼與字詞的配對數
+    pub entries: usize,
+    dict: Dictionary,
+}
+
+impl TableFile {
+    /// 讀取表格檔；副檔名不支援時回傳 UnsupportedTable
+    pub fn load(path: &Path) -> Result<Self> {
+        let kind = TableKind::from_path(path).ok_or_else(|| Array30Error::UnsupportedTable {
+            path: path.to_path_buf(),
+        })?;
+        let mut dict = Dictionary::new();
+        let mut metadata = Vec::new();
+        match kind {
+            TableKind::Chars => {
+                dict.load_cin2_file(path)?;
+                metadata = read_cin_metadata(path)?;
+            }
+            TableKind::Phrases => dict.load_phrase_file(path)?,
+        }
+        let (chars, phrases) = dict.entry_counts();
+        let entries = chars + phrases;
+        Ok(Self {
+            kind,
+            metadata,
+            entries,
+            dict,
+        })
+    }
+
+    /// 取得設定值（如 cname）
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        self.metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// 套用到字典：`merge` 為假時先清空同種類的表
+    pub fn apply_to(self, dict: &mut Dictionary, merge: bool) {
+        if !merge {
+            dict.detach_compiled();
+            dict.frequencies.clear(self.kind);
+            match self.kind {
+                TableKind::Chars => {
+                    dict.char_table.clear();
+                    dict.quick_table.clear();
+                    dict.primary_codes.clear();
+                    dict.fallback = None;
+                }
+                TableKind::Phrases => dict.phrase_table.clear(),
+            }
+        }
+        let kind = self.kind;
+        dict.merge(self.dict);
+        // 取代後的表即為主字表
+        if !merge {
+            dict.sources.clear(kind);
+        }
+    }
+}
+
+/// 逐行讀取表格檔；檔案不存在時為 TableNotFound，不是 UTF-8 的行為 ParseError
+fn table_lines(path: &Path) -> Result<impl Iterator<Item = Result<String>>> {
+    let file = File::open(path).map_err(|e| Array30Error::table_io(path, e))?;
+    let path = path.to_path_buf();
+    Ok(BufReader::new(file).lines().enumerate().map(move |(i, line)| {
+        line.map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => Array30Error::ParseError {
+                path: path.clone(),
+                line: i + 1,
+                reason: "不是有效的 UTF-8".to_string(),
+            },
+            _ => Array30Error::Io(e),
+        })
+    }))
+}
+
+/// 讀取 cin 檔 %chardef 之前的單行設定（略過 keyname 等區塊）
+fn read_cin_metadata(path: &Path) -> Result<Vec<(String, String)>> {
+    let mut metadata = Vec::new();
+    let mut in_block = false;
+    for line in table_lines(path)? {
+        let line = line?;
+        let Some(rest) = line.trim().strip_prefix('%') else {
+            continue;
+        };
+        let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let value = value.trim();
+        match (key, value) {
+            ("chardef", "begin") => break,
+            (_, "begin") => in_block = true,
+            (_, "end") => in_block = false,
+            _ if !in_block => metadata.push((key.to_string(), value.to_string())),
+            _ => {}
+        }
+    }
+    Ok(metadata)
+}
+
+/// 讀音與釋義註解，支援兩種格式（可混用）：
+/// - 字詞<Tab>讀音，同一字詞可有多行
+/// - Unihan 資料（Unihan_Readings.txt）：U+884C<Tab>kMandarin<Tab>xíng，kDefinition 為釋義，其餘欄位略過
+#[derive(Debug, Clone, Default)]
+pub struct Annotations {
+    readings: HashMap<String, Vec<String>>,
+    definitions: HashMap<String, String>,
+}
+
+impl Annotations {
+    /// 讀取註解檔
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        std::fs::read_to_string(path).map(|content| Self::parse(&content))
+    }
+
+    /// 解析註解內容；略過空行與 # 開頭的註解，讀音欄中的 Tab 視為空白
+    pub fn parse(content: &str) -> Self {
+        let mut annotations = Self::default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((c, field, value)) = parse_unihan_line(line) {
+                match field {
+                    "kMandarin" => {
+                        for reading in value.split_whitespace() {
+                            annotations.add_reading(&c.to_string(), reading.to_string());
+                        }
+                    }
+                    "kDefinition" => {
+                        annotations.definitions.insert(c.to_string(), value.to_string());
+                    }
+                    _ => {}
+                }
+            } else if let Some((word, reading)) = line.split_once('\t') {
+                let reading = reading.split('\t').map(str::trim).collect::<Vec<_>>().join(" ");
+                annotations.add_reading(word.trim(), reading);
+            }
+        }
+        annotations
+    }
+
+    fn add_reading(&mut self, word: &str, reading: String) {
+        let entry = self.readings.entry(word.to_string()).or_default();
+        if !reading.is_empty() && !entry.contains(&reading) {
+            entry.push(reading);
+        }
+    }
+
+    /// 字詞的所有讀音
+    pub fn readings(&self, word: &str) -> &[String] {
+        self.readings.get(word).map_or(&[], |r| r.as_slice())
+    }
+
+    /// 字的釋義
+    pub fn definition(&self, word: &str) -> Option<&str> {
+        self.definitions.get(word).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty() && self.definitions.is_empty()
+    }
+}
+
+/// 解析 Unihan 資料行：U+XXXX<Tab>欄位<Tab>內容
+fn parse_unihan_line(line: &str) -> Option<(char, &str, &str)> {
+    let mut fields = line.splitn(3, '\t');
+    let hex = fields.next()?.strip_prefix("U+")?;
+    let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+    Some((c, fields.next()?, fields.next()?.trim()))
+}
+
+/// 同一碼下的字詞：記憶體中的表為字串陣列，編譯字表為以換行分隔的文字
+#[derive(Debug, Clone, Copy)]
+enum Words<'a> {
+    List(&'a [String]),
+    Packed(&'a str),
+}
+
+impl<'a> Words<'a> {
+    fn iter(self) -> impl Iterator<Item = &'a str> {
+        let (list, packed) = match self {
+            Words::List(list) => (Some(list.iter().map(String::as_str)), None),
+            Words::Packed(text) => (None, Some(text.split(compiled::SEPARATOR))),
+        };
+        list.into_iter().flatten().chain(packed.into_iter().flatten())
+    }
+
+    fn contains(self, word: &str) -> bool {
+        self.iter().any(|w| w == word)
+    }
+
+    fn is_empty(self) -> bool {
+        matches!(self, Words::List([]))
+    }
+}
+
+/// 合併兩個依碼排序的序列；同一碼只取第一個序列的
+fn merge_sorted<'a>(
+    first: impl Iterator<Item = (&'a str, Words<'a>)>,
+    second: impl Iterator<Item = (&'a str, Words<'a>)>,
+) -> impl Iterator<Item = (&'a str, Words<'a>)> {
+    let (mut first, mut second) = (first.peekable(), second.peekable());
+    std::iter::from_fn(move || match (first.peek(), second.peek()) {
+        (Some(a), Some(b)) => match a.0.cmp(b.0) {
+            Ordering::Less => first.next(),
+            Ordering::Greater => second.next(),
+            Ordering::Equal => {
+                second.next();
+                first.next()
+            }
+        },
+        (Some(_), None) => first.next(),
+        (None, _) => second.next(),
+    })
+}
+
+/// 字詞的來源；候選排序時依此處的順序排列（主字表在前，自訂字詞在後）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum WordSource {
+    /// 主字表與詞庫
+    #[default]
+    Table,
+    /// 之後合併的表（符號補充表、使用者加入的表格檔）
+    Supplement,
+    /// 使用者自訂字詞
+    User,
+}
+
+impl WordSource {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Table => "字表",
+            Self::Supplement => "補充表",
+            Self::User => "自訂字詞",
+        }
+    }
+}
+
+/// 字詞欄位後可接 Tab 與詞頻（整數）；沒有或無法解析時視為整欄都是字詞
+fn split_frequency(field: &str) -> (&str, Option<u32>) {
+    match field.rsplit_once('\t') {
+        Some((word, freq)) => match freq.trim().parse() {
+            Ok(freq) => (word.trim(), Some(freq)),
+            Err(_) => (field, None),
+        },
+        None => (field, None),
+    }
+}
+
+/// 依 (種類, 碼, 字詞) 記錄的來源或詞頻
+/// 以種類與碼分層保存，查詢時直接借用碼與字詞，不需建立字串
+#[derive(Debug, Clone)]
+struct WordMap<T> {
+    tables: [HashMap<String, HashMap<String, T>>; 2],
+}
+
+impl<T> Default for WordMap<T> {
+    fn default() -> Self {
+        Self {
+            tables: [HashMap::new(), HashMap::new()],
+        }
+    }
+}
+
+impl<T: Copy> WordMap<T> {
+    fn table(&self, kind: TableKind) -> &HashMap<String, HashMap<String, T>> {
+        &self.tables[kind as usize]
+    }
+
+    fn get(&self, kind: TableKind, code: &str, word: &str) -> Option<T> {
+        self.table(kind).get(code)?.get(word).copied()
+    }
+
+    fn insert(&mut self, kind: TableKind, code: &str, word: &str, value: T) {
+        self.tables[kind as usize]
+            .entry(code.to_string())
+            .or_default()
+            .insert(word.to_string(), value);
+    }
+
+    fn remove(&mut self, kind: TableKind, code: &str, word: &str) {
+        let table = &mut self.tables[kind as usize];
+        if let Some(words) = table.get_mut(code) {
+            words.remove(word);
+            if words.is_empty() {
+                table.remove(code);
+            }
+        }
+    }
+
+    /// 清除一種表的全部記錄
+    fn clear(&mut self, kind: TableKind) {
+        self.tables[kind as usize].clear();
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tables.iter().all(HashMap::is_empty)
+    }
+}
 
 /// 字典結構
+/// 開啟編譯字表時，記憶體中的表為其上的變更：同一碼以記憶體中的為準，空的字詞清單表示已刪除
 #[derive(Debug, Clone)]
 pub struct Dictionary {
     /// 單字碼表：code -> vec of characters
-    char_table: HashMap<String, Vec<String>>,
+    char_table: BTreeMap<String, Vec<String>>,
     /// 詞彙碼表：code -> vec of phrases
-    phrase_table: HashMap<String, Vec<String>>,
+    phrase_table: BTreeMap<String, Vec<String>>,
+    /// 簡碼選單（cin2 的 %quick）：一、二碼對應的十個字，依序由數字鍵選取，空位為 QUICK_PLACEHOLDER
+    pub(crate) quick_table: BTreeMap<String, Vec<String>>,
+    /// 各字在字表中第一次出現的碼（字表先列主碼，後列簡碼與異碼；造詞取碼用）
+    primary_codes: HashMap<String, String>,
+    /// 字表名稱（cin 檔的 %cname 或 %ename，沒有時為檔名）
+    name: String,
+    /// 查無候選時改查的後備字表（常用字表搭配大字集），在背景讀取
+    fallback: Option<Arc<Fallback>>,
+    /// 記憶體映射的編譯字表（compile-table 產生）
+    compiled: Option<Arc<CompiledTable>>,
+    /// 不是來自主字表的字詞的來源（沒有記錄的為 WordSource::Table）
+    sources: WordMap<WordSource>,
+    /// 表格檔第三欄的詞頻（沒有記錄的為 0），編譯字表的詞頻在其詞頻區段
+    frequencies: WordMap<u32>,
+    /// 由使用者語料統計的字詞頻率（train 產生），有記錄時優先於表格的詞頻
+    corpus_frequencies: HashMap<String, u32>,
+}
+
+/// 後備字表：設定後在背景讀取，讀取完成前查不到後備字；讀取失敗後不再重試
+#[derive(Debug)]
+struct Fallback {
+    path: PathBuf,
+    table: OnceLock<Option<Box<Dictionary>>>,
+}
+
+impl Fallback {
+    /// 讀取後備字表；其他執行緒正在讀取時等待結果
+    fn load(&self) -> Option<&Dictionary> {
+        let table = self.table.get_or_init(|| {
+            let loaded = if compiled::is_compiled(&self.path) {
+                Dictionary::load_compiled(&self.path)
+            } else {
+                let mut dict = Dictionary::new();
+                dict.load_cin2_file(&self.path).map(|()| dict)
+            };
+            match loaded {
+                Ok(dict) => {
+                    tracing::info!(path = %self.path.display(), "已載入後備字表");
+                    Some(Box::new(dict))
+                }
+                Err(e) => {
+                    tracing::warn!(path = %self.path.display(), "無法載入後備字表：{}", e);
+                    None
+                }
+            }
+        });
+        table.as_deref()
+    }
 }
 
 impl Default for Dictionary {
@@ -24,19 +463,24 @@ impl Default for Dictionary {
 impl Dictionary {
     pub fn new() -> Self {
         Self {
-            char_table: HashMap::new(),
-            phrase_table: HashMap::new(),
+            char_table: BTreeMap::new(),
+            phrase_table: BTreeMap::new(),
+            quick_table: BTreeMap::new(),
+            primary_codes: HashMap::new(),
+            name: String::new(),
+            fallback: None,
+            compiled: None,
+            sources: WordMap::default(),
+            frequencies: WordMap::default(),
+            corpus_frequencies: HashMap::new(),
         }
     }
 
     /// 載入詞彙檔 (array30-phrase-20210725.txt)
     /// 格式: ,,,/	燦爛
     /// 第一欄是碼，第二欄是詞彙，以 tab 分隔
-    pub fn load_phrase_file<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-
-        for line in reader.lines() {
+    pub fn load_phrase_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        for line in table_lines(path.as_ref())? {
             let line = line?;
             let line = line.trim();
 
@@ -45,12 +489,16 @@ impl Dictionary {
                 continue;
             }
 
-            // 解析行：格式為 "code\tword"
+            // 解析行：格式為 "code\tword"，可再接 "\tfreq"
             if let Some((code_part, word_part)) = line.split_once('\t') {
                 let code = code_part.trim().to_string();
-                let word = word_part.trim().to_string();
+                let (word, freq) = split_frequency(word_part.trim());
+                let word = word.to_string();
 
                 if !code.is_empty() && !word.is_empty() {
+                    if let Some(freq) = freq {
+                        self.frequencies.insert(TableKind::Phrases, &code, &word, freq);
+                    }
                     self.phrase_table
                         .entry(code)
                         .or_insert_with(Vec::new)
@@ -64,16 +512,42 @@ impl Dictionary {
 
     /// 載入 cin2 格式的字表
     /// %chardef 開始後的行為 "code\tchar"
-    pub fn load_cin2_file<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-
+    pub fn load_cin2_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
         let mut in_chardef = false;
+        let mut in_quick = false;
+        let (mut cname, mut ename) = (None, None);
 
-        for line in reader.lines() {
+        for line in table_lines(path)? {
             let line = line?;
             let line = line.trim();
 
+            // 字表名稱
+            if let Some(name) = line.strip_prefix("%cname") {
+                cname = Some(name.trim().to_string());
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("%ename") {
+                ename = Some(name.trim().to_string());
+                continue;
+            }
+
+            // 簡碼選單：每行為碼與連在一起的十個字
+            match line {
+                "%quick begin" => in_quick = true,
+                "%quick end" => in_quick = false,
+                _ if in_quick => {
+                    if let Some((code, chars)) = line.split_once(char::is_whitespace) {
+                        let chars = chars.trim().chars().map(String::from).collect();
+                        self.quick_table.insert(code.to_string(), chars);
+                    }
+                }
+                _ => {}
+            }
+            if in_quick || line == "%quick end" {
+                continue;
+            }
+
             // 檢查是否進入 chardef 區塊
             if line == "%chardef begin" {
                 in_chardef = true;
@@ -94,12 +568,17 @@ impl Dictionary {
                 continue;
             }
 
-            // 解析行：格式為 "code\tchar"
+            // 解析行：格式為 "code\tchar"，可再接 "\tfreq"
             if let Some((code_part, char_part)) = line.split_once('\t') {
                 let code = code_part.trim().to_string();
-                let char_str = char_part.trim().to_string();
+                let (char_str, freq) = split_frequency(char_part.trim());
+                let char_str = char_str.to_string();
 
                 if !code.is_empty() && !char_str.is_empty() {
+                    if let Some(freq) = freq {
+                        self.frequencies.insert(TableKind::Chars, &code, &char_str, freq);
+                    }
+                    self.primary_codes.entry(char_str.clone()).or_insert_with(|| code.clone());
                     self.char_table
                         .entry(code)
                         .or_insert_with(Vec::new)
@@ -108,27 +587,686 @@ impl Dictionary {
             }
         }
 
+        self.name = cname
+            .or(ename)
+            .filter(|name| !name.is_empty())
+            .or_else(|| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .unwrap_or_default();
         Ok(())
     }
 
+    /// 開啟編譯字表（含詞庫）；簡碼選單很小，直接讀入
+    pub fn load_compiled(path: &Path) -> Result<Self> {
+        let table = CompiledTable::open(path).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => Array30Error::ParseError {
+                path: path.to_path_buf(),
+                line: 0,
+                reason: e.to_string(),
+            },
+            _ => Array30Error::table_io(path, e),
+        })?;
+        let mut dict = Self::new();
+        for i in 0..table.len(Section::Quick) {
+            let chars = table.value(Section::Quick, i).chars().map(String::from).collect();
+            dict.quick_table.insert(table.key(Section::Quick, i).to_string(), chars);
+        }
+        dict.name = table.name().to_string();
+        dict.compiled = Some(Arc::new(table));
+        Ok(dict)
+    }
+
+    /// 只載入詞庫與字表（不含補充表與自訂字詞）；字表為編譯字表時已含詞庫，不再讀取詞庫檔
+    /// 詞庫或字表失敗時會記錄缺少的檔案位置
+    pub fn load_tables(phrase_file: Option<&Path>, char_file: &Path) -> Result<Self> {
+        if compiled::is_compiled(char_file) {
+            return Self::load_compiled(char_file).inspect_err(|e| {
+                tracing::error!(path = %char_file.display(), "無法開啟編譯字表：{}", e);
+            });
+        }
+        let mut dict = Self::new();
+
+        if let Some(phrase_file) = phrase_file {
+            if let Err(e) = dict.load_phrase_file(phrase_file) {
+                tracing::error!(path = %phrase_file.display(), "無法載入詞庫檔：{}", e);
+                return Err(e);
+            }
+            tracing::debug!(path = %phrase_file.display(), "已載入詞庫");
+        }
+
+        if let Err(e) = dict.load_cin2_file(char_file) {
+            tracing::error!(path = %char_file.display(), "無法載入字表檔：{}", e);
+            return Err(e);
+        }
+        Ok(dict)
+    }
+
+    /// 將字表、詞庫、簡碼選單與造詞取碼資料寫成編譯字表
+    pub fn write_compiled(&self, path: &Path) -> Result<()> {
+        let entries = |kind: TableKind| -> compiled::SectionEntries {
+            self.entries(kind).map(|(code, words)| (code, words.iter().collect())).collect()
+        };
+        let quick = self
+            .quick_table
+            .iter()
+            .map(|(code, chars)| (code.as_str(), chars.iter().map(String::as_str).collect()))
+            .collect();
+        let mut primary: compiled::SectionEntries = Vec::new();
+        if let Some(table) = self.compiled.as_deref() {
+            for i in 0..table.len(Section::PrimaryCodes) {
+                primary.push((table.key(Section::PrimaryCodes, i), vec![table.value(Section::PrimaryCodes, i)]));
+            }
+        }
+        primary.extend(self.primary_codes.iter().map(|(c, code)| (c.as_str(), vec![code.as_str()])));
+        // 詞頻區段：每個碼下有詞頻的「字詞\t詞頻」
+        let frequency_lines = |kind: TableKind| -> Vec<(&str, Vec<String>)> {
+            self.entries(kind)
+                .filter_map(|(code, words)| {
+                    let lines: Vec<String> = words
+                        .iter()
+                        .filter_map(|word| Some(format!("{}\t{}", word, self.table_frequency(kind, code, word)?)))
+                        .collect();
+                    (!lines.is_empty()).then_some((code, lines))
+                })
+                .collect()
+        };
+        let (char_freqs, phrase_freqs) = (frequency_lines(TableKind::Chars), frequency_lines(TableKind::Phrases));
+        fn borrowed<'a>(lines: &'a [(&'a str, Vec<String>)]) -> compiled::SectionEntries<'a> {
+            lines.iter().map(|(code, words)| (*code, words.iter().map(String::as_str).collect())).collect()
+        }
+        compiled::write(
+            path,
+            &self.name,
+            [
+                entries(TableKind::Chars),
+                entries(TableKind::Phrases),
+                quick,
+                primary,
+                borrowed(&char_freqs),
+                borrowed(&phrase_freqs),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 依碼排序列出記憶體中的表與編譯字表的內容（從指定的下界開始）
+    fn entries_from<'a>(&'a self, kind: TableKind, from: Bound<&str>) -> impl Iterator<Item = (&'a str, Words<'a>)> {
+        let memory = self
+            .table(kind)
+            .range::<str, _>((from, Bound::Unbounded))
+            .map(|(code, words)| (code.as_str(), Words::List(words)));
+        let section = kind.section();
+        let compiled = self.compiled.as_deref().map(|table| {
+            (table.lower_bound(section, from)..table.len(section))
+                .map(move |i| (table.key(section, i), Words::Packed(table.value(section, i))))
+        });
+        merge_sorted(memory, compiled.into_iter().flatten()).filter(|(_, words)| !words.is_empty())
+    }
+
+    fn entries(&self, kind: TableKind) -> impl Iterator<Item = (&str, Words<'_>)> {
+        self.entries_from(kind, Bound::Unbounded)
+    }
+
+    /// 依碼排序列出全部 (碼, 字詞) 配對
+    pub fn word_pairs(&self, kind: TableKind) -> impl Iterator<Item = (&str, &str)> {
+        self.entries(kind).flat_map(|(code, words)| words.iter().map(move |word| (code, word)))
+    }
+
+    /// 碼下的字詞（不解碼）
+    fn words(&self, kind: TableKind, code: &str) -> Option<Words<'_>> {
+        let words = match self.table(kind).get(code) {
+            Some(words) => Words::List(words),
+            None => {
+                let table = self.compiled.as_deref()?;
+                Words::Packed(table.value(kind.section(), table.find(kind.section(), code)?))
+            }
+        };
+        (!words.is_empty()).then_some(words)
+    }
+
+    fn lookup(&self, kind: TableKind, code: &str) -> Option<&[String]> {
+        match self.table(kind).get(code) {
+            Some(words) => (!words.is_empty()).then_some(words.as_slice()),
+            None => {
+                let table = self.compiled.as_deref()?;
+                let i = table.find(kind.section(), code)?;
+                Some(table.decoded(kind.section(), i))
+            }
+        }
+    }
+
+    /// 記憶體中的碼下字詞，編譯字表中有此碼時先複製過來
+    fn words_mut(&mut self, kind: TableKind, code: &str) -> &mut Vec<String> {
+        let seed = match self.compiled.as_deref() {
+            Some(table) if !self.table(kind).contains_key(code) => table
+                .find(kind.section(), code)
+                .map(|i| table.decoded(kind.section(), i).to_vec()),
+            _ => None,
+        };
+        self.table_mut(kind).entry(code.to_string()).or_insert_with(|| seed.unwrap_or_default())
+    }
+
+    /// 把編譯字表的內容複製到記憶體中，之後不再使用編譯字表（整張替換字表或詞庫前）
+    fn detach_compiled(&mut self) {
+        let Some(table) = self.compiled.take() else {
+            return;
+        };
+        for kind in [TableKind::Chars, TableKind::Phrases] {
+            let section = kind.section();
+            for i in 0..table.len(section) {
+                let code = table.key(section, i);
+                if !self.table(kind).contains_key(code) {
+                    self.table_mut(kind).insert(code.to_string(), table.decoded(section, i).to_vec());
+                }
+            }
+            // 記憶體中沒有記錄的詞頻沿用編譯字表的（與 table_frequency 的查詢順序相同）
+            let section = kind.frequency_section();
+            for i in 0..table.len(section) {
+                let code = table.key(section, i);
+                let lines = table.value(section, i).split(compiled::SEPARATOR);
+                for (word, freq) in lines.filter_map(|line| line.rsplit_once('\t')) {
+                    if let (None, Ok(freq)) = (self.frequencies.get(kind, code, word), freq.parse()) {
+                        self.frequencies.insert(kind, code, word, freq);
+                    }
+                }
+            }
+            self.table_mut(kind).retain(|_, words| !words.is_empty());
+        }
+        for i in 0..table.len(Section::PrimaryCodes) {
+            let c = table.key(Section::PrimaryCodes, i).to_string();
+            self.primary_codes.insert(c, table.value(Section::PrimaryCodes, i).to_string());
+        }
+    }
+
+    /// (碼數, 字詞數)；編譯字表的部分用預先算好的數量，只逐一檢查記憶體中的碼
+    fn counts(&self, kind: TableKind) -> (usize, usize) {
+        let table = self.table(kind);
+        let Some(compiled) = self.compiled.as_deref() else {
+            return (table.len(), table.values().map(Vec::len).sum());
+        };
+        let section = kind.section();
+        let (mut codes, mut words) = (compiled.len(section), compiled.word_count(section));
+        for (code, list) in table {
+            if let Some(i) = compiled.find(section, code) {
+                codes -= 1;
+                words -= compiled.value(section, i).split(compiled::SEPARATOR).count();
+            }
+            if !list.is_empty() {
+                codes += 1;
+                words += list.len();
+            }
+        }
+        (codes, words)
+    }
+
+    /// 字詞的來源
+    pub fn word_source(&self, kind: TableKind, code: &str, word: &str) -> WordSource {
+        if self.sources.is_empty() {
+            return WordSource::Table;
+        }
+        self.sources.get(kind, code, word).unwrap_or_default()
+    }
+
+    /// 字詞的詞頻：語料統計中有此字詞時用統計的次數，否則用表格的詞頻欄（沒有時為 0）
+    pub fn word_frequency(&self, kind: TableKind, code: &str, word: &str) -> u32 {
+        if let Some(&freq) = self.corpus_frequencies.get(word) {
+            return freq;
+        }
+        self.table_frequency(kind, code, word).unwrap_or(0)
+    }
+
+    /// 表格的詞頻：記憶體中的記錄優先，其次為編譯字表的詞頻區段
+    fn table_frequency(&self, kind: TableKind, code: &str, word: &str) -> Option<u32> {
+        self.frequencies.get(kind, code, word).or_else(|| {
+            let table = self.compiled.as_deref()?;
+            table.frequency(kind.frequency_section(), code, word)
+        })
+    }
+
+    /// 設定語料統計的字詞頻率
+    pub fn set_corpus_frequencies(&mut self, frequencies: HashMap<String, u32>) {
+        self.corpus_frequencies = frequencies;
+    }
+
+    /// 依候選順序列出碼下的字詞：先依來源（主字表、補充表、自訂字詞），
+    /// 同來源依詞頻由高到低，其餘依表格中的順序（穩定排序，結果與載入與合併的先後無關）
+    pub fn ordered_words(&self, kind: TableKind, code: &str) -> Vec<&str> {
+        let Some(words) = self.words(kind, code) else {
+            return Vec::new();
+        };
+        let mut words: Vec<&str> = words.iter().collect();
+        if self.is_ranked() {
+            words.sort_by_cached_key(|word| self.word_rank(kind, code, word));
+        }
+        words
+    }
+
+    /// 是否有來源或詞頻資料（沒有時候選維持表格中的順序）
+    fn is_ranked(&self) -> bool {
+        !self.sources.is_empty()
+            || !self.frequencies.is_empty()
+            || !self.corpus_frequencies.is_empty()
+            || self.compiled.as_deref().is_some_and(|table| {
+                table.len(Section::CharFrequencies) > 0 || table.len(Section::PhraseFrequencies) > 0
+            })
+    }
+
+    /// 候選排序鍵：先依來源，同來源詞頻高的在前
+    fn word_rank(&self, kind: TableKind, code: &str, word: &str) -> (WordSource, std::cmp::Reverse<u32>) {
+        (self.word_source(kind, code, word), std::cmp::Reverse(self.word_frequency(kind, code, word)))
+    }
+
+    /// 同 ordered_words，另附字詞在 lookup_chars、lookup_phrases 結果中的位置（候選只記位置時用）
+    pub fn ordered_positions(&self, kind: TableKind, code: &str) -> Vec<(usize, &str)> {
+        let Some(words) = self.lookup(kind, code) else {
+            return Vec::new();
+        };
+        let mut words: Vec<(usize, &str)> = words.iter().map(String::as_str).enumerate().collect();
+        if self.is_ranked() {
+            words.sort_by_cached_key(|(_, word)| self.word_rank(kind, code, word));
+        }
+        words
+    }
+
+    /// 查找簡碼選單
+    pub fn lookup_quick(&self, code: &str) -> Option<&[String]> {
+        self.quick_table.get(code).map(|v| v.as_slice())
+    }
+
     /// 查找單字候選
     pub fn lookup_chars(&self, code: &str) -> Option<&[String]> {
-        self.char_table.get(code).map(|v| v.as_slice())
+        self.lookup(TableKind::Chars, code)
+    }
+
+    /// 設定後備字表（通常為大字集），None 為不使用
+    /// 後備字表在背景執行緒讀取，按鍵查詢時不會等待讀檔
+    pub fn set_fallback(&mut self, path: Option<PathBuf>) {
+        self.fallback = path.map(|path| {
+            let fallback = Arc::new(Fallback {
+                path,
+                table: OnceLock::new(),
+            });
+            let loading = fallback.clone();
+            std::thread::spawn(move || {
+                loading.load();
+            });
+            fallback
+        });
+    }
+
+    /// 等待後備字表讀取完成（批次轉換需要完整的結果時使用）
+    pub fn wait_fallback(&self) {
+        if let Some(fallback) = &self.fallback {
+            fallback.load();
+        }
+    }
+
+    /// 在後備字表中查找單字候選；後備字表尚未讀取完成時為 None
+    pub fn lookup_fallback_chars(&self, code: &str) -> Option<&[String]> {
+        self.fallback.as_ref()?.table.get()?.as_deref()?.lookup_chars(code)
     }
 
     /// 查找詞彙候選
     pub fn lookup_phrases(&self, code: &str) -> Option<&[String]> {
-        self.phrase_table.get(code).map(|v| v.as_slice())
+        self.lookup(TableKind::Phrases, code)
+    }
+
+    /// 查找以指定前綴延伸的單字碼（不含前綴本身）
+    /// 依碼長、再依碼排序，回傳 (碼, 字) 配對
+    pub fn lookup_char_prefix(&self, prefix: &str) -> Vec<(&str, &str)> {
+        let mut codes: Vec<(&str, Words)> = self
+            .entries_from(TableKind::Chars, Bound::Excluded(prefix))
+            .take_while(|(code, _)| code.starts_with(prefix))
+            .collect();
+        codes.sort_by_key(|(code, _)| code.len());
+
+        codes
+            .into_iter()
+            .flat_map(|(code, chars)| chars.iter().map(move |c| (code, c)))
+            .collect()
+    }
+
+    /// 同 lookup_char_prefix，但只到 `max_len` 碼，且逐一產生：依碼長分次掃描，
+    /// 只取前幾個（如候選預覽）時不必走完前綴下的全部碼
+    pub fn char_prefix_iter<'a>(&'a self, prefix: &'a str, max_len: usize) -> impl Iterator<Item = (&'a str, &'a str)> {
+        (prefix.len() + 1..=max_len).flat_map(move |len| {
+            self.entries_from(TableKind::Chars, Bound::Excluded(prefix))
+                .take_while(move |(code, _)| code.starts_with(prefix))
+                .filter(move |(code, _)| code.len() == len)
+                .flat_map(|(code, chars)| chars.iter().map(move |c| (code, c)))
+        })
+    }
+
+    /// 取得可接續指定前綴、組成有效單字碼的下一個鍵
+    pub fn next_keys(&self, prefix: &str) -> BTreeSet<char> {
+        self.entries_from(TableKind::Chars, Bound::Excluded(prefix))
+            .take_while(|(code, _)| code.starts_with(prefix))
+            .filter_map(|(code, _)| code[prefix.len()..].chars().next())
+            .collect()
     }
 
     /// 檢查碼是否存在（單字或詞彙）
     pub fn has_code(&self, code: &str) -> bool {
-        self.char_table.contains_key(code) || self.phrase_table.contains_key(code)
+        self.words(TableKind::Chars, code).is_some() || self.words(TableKind::Phrases, code).is_some()
+    }
+
+    /// 檢查是否有以指定前綴開頭的碼（單字或詞彙，含前綴本身）
+    pub fn has_prefix(&self, prefix: &str) -> bool {
+        let starts = |kind: TableKind| {
+            self.entries_from(kind, Bound::Included(prefix))
+                .next()
+                .is_some_and(|(code, _)| code.starts_with(prefix))
+        };
+        // 後備字表已讀取完成時也算
+        let fallback = self.fallback.as_ref().and_then(|f| f.table.get()).and_then(Option::as_deref);
+        starts(TableKind::Chars) || starts(TableKind::Phrases) || fallback.is_some_and(|dict| dict.has_prefix(prefix))
     }
 
     /// 取得統計資訊
     pub fn stats(&self) -> (usize, usize) {
-        (self.char_table.len(), self.phrase_table.len())
+        (self.counts(TableKind::Chars).0, self.counts(TableKind::Phrases).0)
+    }
+
+    /// 字與詞的項目數（同一碼下的每個字詞各算一項）
+    pub fn entry_counts(&self) -> (usize, usize) {
+        (self.counts(TableKind::Chars).1, self.counts(TableKind::Phrases).1)
+    }
+
+    /// 字表名稱
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 反查字詞的所有碼（單字碼在前，詞碼在後）
+    pub fn reverse_lookup(&self, word: &str) -> Vec<&str> {
+        self.entries(TableKind::Chars)
+            .chain(self.entries(TableKind::Phrases))
+            .filter(|(_, words)| words.contains(word))
+            .map(|(code, _)| code)
+            .collect()
+    }
+
+    /// 搜尋字典（字典瀏覽用）
+    /// 碼的前綴：字在前、詞在後，各依碼排序；單字：依輸入的字順序列出各字的碼
+    pub fn search(&self, mode: SearchMode, query: &str) -> Vec<DictEntry> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut results = Vec::new();
+        let mut push = |kind: TableKind, code: &str, word: &str| {
+            results.push(DictEntry {
+                code: code.to_string(),
+                word: word.to_string(),
+                kind,
+            })
+        };
+
+        match mode {
+            SearchMode::CodePrefix => {
+                let query = query.to_lowercase();
+                for kind in [TableKind::Chars, TableKind::Phrases] {
+                    let matches = self
+                        .entries_from(kind, Bound::Included(query.as_str()))
+                        .take_while(|(code, _)| code.starts_with(query.as_str()));
+                    for (code, words) in matches {
+                        for word in words.iter() {
+                            push(kind, code, word);
+                        }
+                    }
+                }
+            }
+            SearchMode::Character => {
+                for c in query.chars().filter(|c| !c.is_whitespace()) {
+                    let c = c.to_string();
+                    for (code, words) in self.entries(TableKind::Chars) {
+                        for word in words.iter().filter(|word| *word == c) {
+                            push(TableKind::Chars, code, word);
+                        }
+                    }
+                }
+            }
+            SearchMode::Phrase => {
+                for (code, words) in self.entries(TableKind::Phrases) {
+                    for word in words.iter().filter(|word| word.contains(query)) {
+                        push(TableKind::Phrases, code, word);
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    fn table(&self, kind: TableKind) -> &BTreeMap<String, Vec<String>> {
+        match kind {
+            TableKind::Chars => &self.char_table,
+            TableKind::Phrases => &self.phrase_table,
+        }
+    }
+
+    fn table_mut(&mut self, kind: TableKind) -> &mut BTreeMap<String, Vec<String>> {
+        match kind {
+            TableKind::Chars => &mut self.char_table,
+            TableKind::Phrases => &mut self.phrase_table,
+        }
+    }
+
+    /// 在碼下加入字表的單字（排在既有字之後），已有時略過
+    /// 字第一次加入的碼即為造詞取碼用的字碼
+    pub fn insert_char(&mut self, code: &str, ch: &str) -> bool {
+        if !self.insert(TableKind::Chars, code, ch) {
+            return false;
+        }
+        self.primary_codes.entry(ch.to_string()).or_insert_with(|| code.to_string());
+        true
+    }
+
+    /// 在碼下加入詞庫的詞彙（排在既有詞彙之後），已有時略過
+    pub fn insert_phrase(&mut self, code: &str, phrase: &str) -> bool {
+        self.insert(TableKind::Phrases, code, phrase)
+    }
+
+    /// 加入表格本身的字詞（不同於 add_word，不記為自訂字詞）
+    fn insert(&mut self, kind: TableKind, code: &str, word: &str) -> bool {
+        if code.is_empty() || word.is_empty() {
+            return false;
+        }
+        let words = self.words_mut(kind, code);
+        if words.iter().any(|w| w == word) {
+            return false;
+        }
+        words.push(word.to_string());
+        true
+    }
+
+    /// 加入多筆字詞（如另一個字典的 iter），回傳實際加入的筆數
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = DictEntry>) -> usize {
+        let mut added = 0;
+        for entry in entries {
+            let inserted = match entry.kind {
+                TableKind::Chars => self.insert_char(&entry.code, &entry.word),
+                TableKind::Phrases => self.insert_phrase(&entry.code, &entry.word),
+            };
+            added += usize::from(inserted);
+        }
+        added
+    }
+
+    /// 移除碼下的全部字詞，回傳移除的字詞
+    pub fn remove(&mut self, kind: TableKind, code: &str) -> Vec<String> {
+        let in_compiled = self.compiled.as_deref().is_some_and(|table| table.find(kind.section(), code).is_some());
+        let removed = if in_compiled {
+            std::mem::take(self.words_mut(kind, code))
+        } else {
+            self.table_mut(kind).remove(code).unwrap_or_default()
+        };
+        for word in &removed {
+            self.sources.remove(kind, code, word);
+            self.frequencies.remove(kind, code, word);
+        }
+        removed
+    }
+
+    /// 依碼排序列出全部字詞：字在前、詞在後
+    pub fn iter(&self) -> impl Iterator<Item = DictEntry> + '_ {
+        [TableKind::Chars, TableKind::Phrases].into_iter().flat_map(move |kind| {
+            self.word_pairs(kind).map(move |(code, word)| DictEntry {
+                code: code.to_string(),
+                word: word.to_string(),
+                kind,
+            })
+        })
+    }
+
+    /// 碼下是否已有該字詞
+    pub fn contains_word(&self, kind: TableKind, code: &str, word: &str) -> bool {
+        self.words(kind, code).is_some_and(|words| words.contains(word))
+    }
+
+    /// 在碼下加入自訂字詞（排在既有字詞之後），已有時略過
+    pub fn add_word(&mut self, kind: TableKind, code: &str, word: &str) -> bool {
+        let words = self.words_mut(kind, code);
+        if words.iter().any(|w| w == word) {
+            return false;
+        }
+        words.push(word.to_string());
+        self.sources.insert(kind, code, word, WordSource::User);
+        true
+    }
+
+    /// 從碼下移除字詞，碼下沒有其他字詞時一併移除該碼
+    /// 編譯字表中的碼移除後留下空的清單，遮住編譯字表的內容
+    pub fn remove_word(&mut self, kind: TableKind, code: &str, word: &str) -> bool {
+        if !self.contains_word(kind, code, word) {
+            return false;
+        }
+        let in_compiled = self.compiled.as_deref().is_some_and(|table| table.find(kind.section(), code).is_some());
+        let words = self.words_mut(kind, code);
+        words.retain(|w| w != word);
+        if words.is_empty() && !in_compiled {
+            self.table_mut(kind).remove(code);
+        }
+        self.sources.remove(kind, code, word);
+        self.frequencies.remove(kind, code, word);
+        true
+    }
+
+    /// 建立單字反查索引：字 -> 碼（依碼排序）
+    pub fn char_reverse_index(&self) -> HashMap<&str, Vec<&str>> {
+        let mut index: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (code, chars) in self.entries(TableKind::Chars) {
+            for c in chars.iter() {
+                let codes = index.entry(c).or_default();
+                // 同一碼下重複的字只記一次
+                if codes.last() != Some(&code) {
+                    codes.push(code);
+                }
+            }
+        }
+        index
+    }
+
+    /// 造詞取碼用的字碼：字表中第一次出現的碼；沒有記錄或含非組碼鍵（依輸入法變體）時取最長的碼
+    pub fn primary_code(&self, c: &str, variant: ArrayVariant) -> Option<&str> {
+        let valid = |code: &str| !code.is_empty() && code.chars().all(|c| variant.is_code_key(c));
+        // 編譯字表的記錄來自主字表，比之後合併的表優先
+        let compiled = self
+            .compiled
+            .as_deref()
+            .and_then(|table| Some(table.value(Section::PrimaryCodes, table.find(Section::PrimaryCodes, c)?)));
+        if let Some(code) = compiled.or(self.primary_codes.get(c).map(String::as_str)).filter(|code| valid(code)) {
+            return Some(code);
+        }
+        self.entries(TableKind::Chars)
+            .filter(|(code, chars)| valid(code) && chars.contains(c))
+            .map(|(code, _)| code)
+            .min_by_key(|code| std::cmp::Reverse(code.len()))
+    }
+
+    /// 依行列的詞彙取碼規則產生詞碼：二字詞取各字的首尾碼；三字詞取首字的首尾碼與第二、三字的首碼；
+    /// 四字以上取前三字與末字的首碼（一碼的字首尾碼只取一次）。不到兩字或有字查無字碼時為 None
+    pub fn encode_phrase(&self, text: &str, variant: ArrayVariant) -> Option<String> {
+        let codes = text
+            .chars()
+            .map(|c| self.primary_code(&c.to_string(), variant))
+            .collect::<Option<Vec<_>>>()?;
+        let first = |code: &str| code.chars().next().map(String::from).unwrap_or_default();
+        let ends = |code: &str| {
+            let mut keys = first(code);
+            if code.chars().count() > 1 {
+                keys.extend(code.chars().last());
+            }
+            keys
+        };
+        match codes.as_slice() {
+            [] | [_] => None,
+            [a, b] => Some(ends(a) + &ends(b)),
+            [a, b, c] => Some(ends(a) + &first(b) + &first(c)),
+            [a, b, c, .., last] => Some(first(a) + &first(b) + &first(c) + &first(last)),
+        }
+    }
+
+    /// 逐字標註行列碼（反查單字表），查無字碼的字元碼為空
+    pub fn annotate(&self, text: &str) -> Vec<(char, Vec<String>)> {
+        let index = self.char_reverse_index();
+        text.chars()
+            .map(|c| {
+                let mut buf = [0; 4];
+                let codes = index
+                    .get(&*c.encode_utf8(&mut buf))
+                    .map(|codes| codes.iter().map(|code| code.to_string()).collect())
+                    .unwrap_or_default();
+                (c, codes)
+            })
+            .collect()
+    }
+
+    /// 合併另一個字典，同一碼下已有的字詞不重複加入
+    /// 新加入的字詞記為補充表（另一個字典中的自訂字詞仍為自訂字詞），並帶入詞頻
+    pub fn merge(&mut self, other: Dictionary) {
+        for kind in [TableKind::Chars, TableKind::Phrases] {
+            for (code, words) in other.entries(kind) {
+                let mut added = Vec::new();
+                let existing = self.words_mut(kind, code);
+                for word in words.iter() {
+                    if !existing.iter().any(|w| w == word) {
+                        existing.push(word.to_string());
+                        added.push(word);
+                    }
+                }
+                for word in added {
+                    let source = other.word_source(kind, code, word).max(WordSource::Supplement);
+                    self.sources.insert(kind, code, word, source);
+                    let freq = other.word_frequency(kind, code, word);
+                    if freq > 0 {
+                        self.frequencies.insert(kind, code, word, freq);
+                    }
+                }
+            }
+        }
+        for (code, chars) in other.quick_table {
+            self.quick_table.entry(code).or_insert(chars);
+        }
+        for (c, code) in other.primary_codes {
+            self.primary_codes.entry(c).or_insert(code);
+        }
+    }
+
+    /// 檢查字表內容
+    /// 回傳 (碼, 問題說明) 清單：含非組碼鍵的碼、同一碼下重複的字詞
+    pub fn validate(&self, is_code_key: impl Fn(char) -> bool) -> Vec<(String, String)> {
+        let mut issues = Vec::new();
+        for (code, words) in self.entries(TableKind::Chars).chain(self.entries(TableKind::Phrases)) {
+            if let Some(c) = code.chars().find(|&c| !is_code_key(c)) {
+                issues.push((code.to_string(), format!("含非組碼鍵「{}」", c)));
+            }
+            let words: Vec<&str> = words.iter().collect();
+            for (i, word) in words.iter().enumerate() {
+                if words[..i].contains(word) {
+                    issues.push((code.to_string(), format!("重複的字詞「{}」", word)));
+                }
+            }
+        }
+        issues
     }
 }
 
@@ -143,10 +1281,263 @@ mod tests {
         assert!(!dict.has_code("test"));
     }
 
+    #[test]
+    fn test_lookup_char_prefix() {
+        let mut dict = Dictionary::new();
+        for (code, ch) in [("a", "一"), ("ab", "二"), ("abc", "三"), ("ac", "四"), ("b", "五")] {
+            dict.char_table.entry(code.to_string()).or_default().push(ch.to_string());
+        }
+        assert_eq!(
+            dict.lookup_char_prefix("a"),
+            vec![("ab", "二"), ("ac", "四"), ("abc", "三")]
+        );
+        assert!(dict.lookup_char_prefix("b").is_empty());
+        let lazy: Vec<_> = dict.char_prefix_iter("a", 4).collect();
+        assert_eq!(lazy, dict.lookup_char_prefix("a"));
+        assert_eq!(dict.char_prefix_iter("a", 2).collect::<Vec<_>>(), [("ab", "二"), ("ac", "四")]);
+
+        let next: Vec<char> = dict.next_keys("a").into_iter().collect();
+        assert_eq!(next, ['b', 'c']);
+        assert!(dict.next_keys("abc").is_empty());
+    }
+
+    #[test]
+    fn test_reverse_lookup_and_validate() {
+        let mut dict = Dictionary::new();
+        for (code, ch) in [("ab", "甲"), ("cd", "甲"), ("w1", "，"), ("ab", "甲")] {
+            dict.char_table.entry(code.to_string()).or_default().push(ch.to_string());
+        }
+        dict.phrase_table.entry("abcd".to_string()).or_default().push("甲乙".to_string());
+
+        assert_eq!(dict.reverse_lookup("甲"), vec!["ab", "cd"]);
+        let annotated = dict.annotate("甲，丙");
+        assert_eq!(annotated[0], ('甲', vec!["ab".to_string(), "cd".to_string()]));
+        assert_eq!(annotated[1].1, vec!["w1".to_string()]);
+        assert!(annotated[2].1.is_empty());
+        assert_eq!(dict.reverse_lookup("甲乙"), vec!["abcd"]);
+        assert!(dict.reverse_lookup("丙").is_empty());
+
+        let issues = dict.validate(|c| c.is_ascii_lowercase());
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].0, "ab");
+        assert_eq!(issues[1].0, "w1");
+    }
+
+    #[test]
+    fn test_search() {
+        let mut dict = Dictionary::new();
+        for (code, ch) in [("a", "一"), ("ab", "二"), ("ab", "甲"), ("b", "三"), ("cd", "甲")] {
+            dict.char_table.entry(code.to_string()).or_default().push(ch.to_string());
+        }
+        for (code, phrase) in [("abcd", "甲乙"), ("bcde", "丙甲"), ("cdef", "丁戊")] {
+            dict.phrase_table.entry(code.to_string()).or_default().push(phrase.to_string());
+        }
+
+        let codes = |entries: Vec<DictEntry>| entries.into_iter().map(|e| (e.code, e.word)).collect::<Vec<_>>();
+        let found = dict.search(SearchMode::CodePrefix, "A");
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[3].kind, TableKind::Phrases);
+        assert_eq!(
+            codes(found),
+            [("a", "一"), ("ab", "二"), ("ab", "甲"), ("abcd", "甲乙")].map(|(c, w)| (c.to_string(), w.to_string()))
+        );
+        assert_eq!(
+            codes(dict.search(SearchMode::Character, "三 甲")),
+            [("b", "三"), ("ab", "甲"), ("cd", "甲")].map(|(c, w)| (c.to_string(), w.to_string()))
+        );
+        assert_eq!(dict.search(SearchMode::Phrase, "甲").len(), 2);
+        assert!(dict.search(SearchMode::Phrase, "  ").is_empty());
+    }
+
+    #[test]
+    fn test_table_file_load_and_merge() {
+        let dir = std::env::temp_dir().join(format!("rustarray30-tablefile-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("extra.cin2");
+        std::fs::write(
+            &path,
+            "%ename extra\n%cname 擴充\n%keyname begin\na\t1-\n%keyname end\n%quick begin\na\t甲□乙\n%quick end\n%chardef begin\nab\t甲\nab\t乙\ncd\t丙\n%chardef end\n",
+        )
+        .unwrap();
+
+        let table = TableFile::load(&path).unwrap();
+        assert_eq!(table.kind, TableKind::Chars);
+        assert_eq!(table.entries, 3);
+        assert_eq!(table.meta("cname"), Some("擴充"));
+        assert_eq!(table.dict.name(), "擴充");
+        assert_eq!(table.dict.lookup_quick("a"), Some(&["甲", QUICK_PLACEHOLDER, "乙"].map(String::from)[..]));
+        assert_eq!(table.metadata.len(), 2);
+
+        let mut dict = Dictionary::new();
+        dict.char_table.entry("ab".to_string()).or_default().push("甲".to_string());
+        dict.phrase_table.entry("abcd".to_string()).or_default().push("甲乙".to_string());
+        table.clone().apply_to(&mut dict, true);
+        assert_eq!(dict.lookup_chars("ab"), Some(&["甲".to_string(), "乙".to_string()][..]));
+        assert_eq!(dict.stats(), (2, 1));
+        assert_eq!(dict.entry_counts(), (3, 1));
+
+        table.apply_to(&mut dict, false);
+        assert_eq!(dict.stats(), (2, 1));
+        assert!(TableFile::load(&dir.join("x.json")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_encode_phrase() {
+        let path = std::env::temp_dir().join(format!("rustarray30-phrasecode-{}.cin2", std::process::id()));
+        std::fs::write(
+            &path,
+            "%chardef begin\nz\t大\nna.\t家\nx;\t台\ncyxg\t灣\nzps\t電\nuwl.\t腦\nkq\t化\n;d\t中\nfaff\t華\ntsas\t民\npqax\t國\nak\t大\nna\t家\nuwlm\t腦\n%chardef end\n",
+        )
+        .unwrap();
+        let mut dict = Dictionary::new();
+        dict.load_cin2_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(dict.encode_phrase("台灣", ArrayVariant::Array30).as_deref(), Some("x;cg"));
+        assert_eq!(dict.encode_phrase("大家", ArrayVariant::Array30).as_deref(), Some("zn."));
+        assert_eq!(dict.encode_phrase("電腦化", ArrayVariant::Array30).as_deref(), Some("zsuk"));
+        assert_eq!(dict.encode_phrase("中華民國", ArrayVariant::Array30).as_deref(), Some(";ftp"));
+        assert_eq!(dict.encode_phrase("大", ArrayVariant::Array30), None);
+        assert_eq!(dict.encode_phrase("大甲", ArrayVariant::Array30), None);
+
+        // 沒有字表順序時取最長的碼
+        let mut dict = Dictionary::new();
+        dict.char_table.insert("z".to_string(), vec!["大".to_string()]);
+        dict.char_table.insert("ak".to_string(), vec!["大".to_string()]);
+        assert_eq!(dict.primary_code("大", ArrayVariant::Array30), Some("ak"));
+
+        // 行列 10 的字碼只有數字鍵
+        dict.char_table.insert("12".to_string(), vec!["家".to_string()]);
+        assert_eq!(dict.primary_code("家", ArrayVariant::Array30), None);
+        assert_eq!(dict.primary_code("家", ArrayVariant::Array10), Some("12"));
+        dict.char_table.insert("34".to_string(), vec!["人".to_string()]);
+        assert_eq!(dict.encode_phrase("大家", ArrayVariant::Array30), None);
+        assert_eq!(dict.encode_phrase("家人", ArrayVariant::Array10).as_deref(), Some("1234"));
+    }
+
+    #[test]
+    fn test_annotations_load() {
+        let path = std::env::temp_dir().join(format!("rustarray30-annotations-{}.txt", std::process::id()));
+        std::fs::write(&path, "# 讀音\n行\txíng\tㄒㄧㄥˊ\n行\tháng\tㄏㄤˊ\n行\txíng\tㄒㄧㄥˊ\n無讀音\n").unwrap();
+
+        let annotations = Annotations::load(&path).unwrap();
+        assert_eq!(annotations.readings("行"), ["xíng ㄒㄧㄥˊ", "háng ㄏㄤˊ"]);
+        assert!(annotations.readings("列").is_empty());
+        assert!(annotations.readings("無讀音").is_empty());
+        assert!(!annotations.is_empty());
+        assert_eq!(annotations.definition("行"), None);
+
+        std::fs::write(&path, "U+884C\tkMandarin\txíng háng\nU+884C\tkDefinition\tgo, walk; move, travel\nU+884C\tkTotalStrokes\t6\n").unwrap();
+        let annotations = Annotations::load(&path).unwrap();
+        assert_eq!(annotations.readings("行"), ["xíng", "háng"]);
+        assert_eq!(annotations.definition("行"), Some("go, walk; move, travel"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_compiled_overlay() {
+        let mut dict = Dictionary::new();
+        for (code, ch) in [("a", "一"), ("ab", "二"), ("ab", "甲"), ("cd", "丙")] {
+            dict.char_table.entry(code.to_string()).or_default().push(ch.to_string());
+        }
+        dict.phrase_table.insert("abcd".to_string(), vec!["甲丙".to_string()]);
+        dict.quick_table.insert("a".to_string(), vec!["二".to_string()]);
+        dict.primary_codes.insert("甲".to_string(), "ab".to_string());
+        dict.frequencies.insert(TableKind::Chars, "ab", "甲", 7);
+        let path = std::env::temp_dir().join(format!("rustarray30-compiled-{}.a30t", std::process::id()));
+        dict.write_compiled(&path).unwrap();
+
+        let mut dict = Dictionary::load_compiled(&path).unwrap();
+        assert_eq!(dict.lookup_chars("ab"), Some(&["二".to_string(), "甲".to_string()][..]));
+        assert_eq!(dict.lookup_quick("a"), Some(&["二".to_string()][..]));
+        assert_eq!(dict.primary_code("甲", ArrayVariant::Array30), Some("ab"));
+        assert_eq!((dict.stats(), dict.entry_counts()), ((3, 1), (4, 1)));
+        // 詞頻隨編譯字表保存
+        assert_eq!(dict.word_frequency(TableKind::Chars, "ab", "甲"), 7);
+        assert_eq!(dict.ordered_words(TableKind::Chars, "ab"), ["甲", "二"]);
+
+        // 記憶體中的變更遮住編譯字表
+        assert!(dict.add_word(TableKind::Chars, "ab", "乙"));
+        assert!(dict.remove_word(TableKind::Chars, "cd", "丙"));
+        assert!(!dict.has_code("cd"));
+        assert_eq!(dict.lookup_char_prefix("a"), [("ab", "二"), ("ab", "甲"), ("ab", "乙")]);
+        assert_eq!(dict.reverse_lookup("甲"), ["ab"]);
+        assert_eq!((dict.stats(), dict.entry_counts()), ((2, 1), (4, 1)));
+
+        dict.detach_compiled();
+        assert_eq!(dict.char_table.len(), 2);
+        assert_eq!(dict.word_frequency(TableKind::Chars, "ab", "甲"), 7);
+        assert_eq!(dict.lookup_phrases("abcd"), Some(&["甲丙".to_string()][..]));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_ordered_words() {
+        let dir = std::env::temp_dir().join(format!("rustarray30-order-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let main = dir.join("main.cin2");
+        std::fs::write(&main, "%chardef begin\nab\t甲\nab\t乙\t5\ncd\t丙\n%chardef end\n").unwrap();
+        let extra = dir.join("extra.cin2");
+        std::fs::write(&extra, "%chardef begin\nab\t丁\nab\t乙\nab\t戊\t3\n%chardef end\n").unwrap();
+
+        let mut dict = Dictionary::new();
+        dict.load_cin2_file(&main).unwrap();
+        assert_eq!(dict.ordered_words(TableKind::Chars, "ab"), ["乙", "甲"]);
+        assert_eq!(dict.word_frequency(TableKind::Chars, "ab", "乙"), 5);
+
+        // 自訂字詞先加入、補充表後合併，仍排在補充表之後
+        dict.add_word(TableKind::Chars, "ab", "己");
+        TableFile::load(&extra).unwrap().apply_to(&mut dict, true);
+        assert_eq!(dict.lookup_chars("ab").unwrap(), ["甲", "乙", "己", "丁", "戊"]);
+        assert_eq!(dict.ordered_words(TableKind::Chars, "ab"), ["乙", "甲", "戊", "丁", "己"]);
+        assert_eq!(dict.ordered_positions(TableKind::Chars, "ab")[..2], [(1, "乙"), (0, "甲")]);
+        assert_eq!(dict.word_source(TableKind::Chars, "ab", "乙"), WordSource::Table);
+        assert_eq!(dict.word_source(TableKind::Chars, "ab", "戊"), WordSource::Supplement);
+
+        // 取代主字表後，新表的字詞為主字表
+        TableFile::load(&extra).unwrap().apply_to(&mut dict, false);
+        assert_eq!(dict.ordered_words(TableKind::Chars, "ab"), ["戊", "丁", "乙"]);
+        assert_eq!(dict.word_source(TableKind::Chars, "ab", "戊"), WordSource::Table);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_lookup_empty() {
         let dict = Dictionary::new();
         assert!(dict.lookup_chars("abc").is_none());
         assert!(dict.lookup_phrases("abc").is_none());
     }
+
+    #[test]
+    fn test_build_dictionary() {
+        let mut dict = Dictionary::new();
+        assert!(dict.insert_char("ab", "甲"));
+        assert!(!dict.insert_char("ab", "甲"));
+        assert!(dict.insert_char("abc", "甲"));
+        assert!(dict.insert_phrase("abcd", "甲乙"));
+        assert!(!dict.insert_phrase("", "甲乙"));
+        assert_eq!(dict.primary_code("甲", ArrayVariant::Array30), Some("ab"));
+        assert_eq!(dict.word_source(TableKind::Chars, "ab", "甲"), WordSource::Table);
+
+        let mut copy = Dictionary::new();
+        assert_eq!(copy.extend(dict.iter()), 3);
+        let entry = |code: &str, word: &str, kind| DictEntry {
+            code: code.to_string(),
+            word: word.to_string(),
+            kind,
+        };
+        assert_eq!(
+            copy.iter().collect::<Vec<_>>(),
+            [
+                entry("ab", "甲", TableKind::Chars),
+                entry("abc", "甲", TableKind::Chars),
+                entry("abcd", "甲乙", TableKind::Phrases),
+            ]
+        );
+        assert_eq!(copy.remove(TableKind::Chars, "ab"), ["甲"]);
+        assert!(copy.remove(TableKind::Chars, "ab").is_empty());
+        assert!(!copy.has_code("ab"));
+        assert_eq!(copy.entry_counts(), (1, 1));
+    }
 }
@@ -0,0 +1,165 @@
+// Send committed text to the focused external window (Windows)
+// 直接輸出：將上屏文字送到其他程式的視窗
+
+use crate::config::DirectOutputMethod;
+use std::ptr;
+use windows_sys::Win32::Foundation::{HWND, POINT};
+use windows_sys::Win32::Graphics::Gdi::ClientToScreen;
+use windows_sys::Win32::System::Threading::GetCurrentProcessId;
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, GetGUIThreadInfo, GetWindowThreadProcessId, PostMessageW, SetForegroundWindow,
+    GUITHREADINFO, WM_CHAR,
+};
+
+/// 直接輸出目標
+/// 記住最後一個位於前景的外部視窗，上屏時把文字送過去
+pub struct DirectOutput {
+    method: DirectOutputMethod,
+    target: HWND,
+}
+
+impl DirectOutput {
+    pub fn new(method: DirectOutputMethod) -> Self {
+        Self {
+            method,
+            target: ptr::null_mut(),
+        }
+    }
+
+    pub fn set_method(&mut self, method: DirectOutputMethod) {
+        self.method = method;
+    }
+
+    /// 是否已有輸出目標
+    pub fn has_target(&self) -> bool {
+        !self.target.is_null()
+    }
+
+    /// 記錄目前的前景視窗（忽略本程式自己的視窗）
+    /// 應在每個畫面更新時呼叫
+    pub fn track_foreground(&mut self) {
+        // SAFETY: 只查詢視窗與行程 id，不解參考任何指標
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() {
+                return;
+            }
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, &mut pid);
+            if pid != GetCurrentProcessId() {
+                self.target = hwnd;
+            }
+        }
+    }
+
+    /// 目標視窗文字游標下緣的螢幕座標（實體像素）
+    /// 以 GetGUIThreadInfo 查詢；程式未使用系統游標（如部分瀏覽器）時為 None
+    pub fn caret_position(&self) -> Option<(i32, i32)> {
+        let info = self.thread_info()?;
+        if info.hwndCaret.is_null() {
+            return None;
+        }
+        let mut point = POINT {
+            x: info.rcCaret.left,
+            y: info.rcCaret.bottom,
+        };
+        // SAFETY: hwndCaret 由系統提供，point 為有效的可寫入位置
+        let ok = unsafe { ClientToScreen(info.hwndCaret, &mut point) } != 0;
+        ok.then_some((point.x, point.y))
+    }
+
+    /// 查詢目標視窗所屬執行緒的焦點與游標資訊
+    fn thread_info(&self) -> Option<GUITHREADINFO> {
+        if self.target.is_null() {
+            return None;
+        }
+        // SAFETY: GUITHREADINFO 以 cbSize 初始化後交給系統填寫
+        unsafe {
+            let thread = GetWindowThreadProcessId(self.target, ptr::null_mut());
+            let mut info: GUITHREADINFO = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+            (GetGUIThreadInfo(thread, &mut info) != 0).then_some(info)
+        }
+    }
+
+    /// 將文字送到目標視窗
+    /// 回傳是否成功送出
+    pub fn send(&self, text: &str) -> bool {
+        if text.is_empty() || self.target.is_null() {
+            return false;
+        }
+        match self.method {
+            DirectOutputMethod::SendInput => self.send_input(text),
+            DirectOutputMethod::WmChar => self.post_chars(text),
+        }
+    }
+
+    /// 切換到目標視窗並以 SendInput 模擬 Unicode 按鍵
+    /// 焦點會留在目標視窗
+    fn send_input(&self, text: &str) -> bool {
+        let inputs = unicode_inputs(text);
+        // SAFETY: inputs 在呼叫期間有效，cbsize 為 INPUT 的大小
+        unsafe {
+            SetForegroundWindow(self.target);
+            let sent = SendInput(inputs.len() as u32, inputs.as_ptr(), std::mem::size_of::<INPUT>() as i32);
+            sent as usize == inputs.len()
+        }
+    }
+
+    /// 以 WM_CHAR 訊息送到目標視窗中取得焦點的控制項
+    /// 不切換焦點，但部分程式（如終端機、遊戲）不處理 WM_CHAR
+    fn post_chars(&self, text: &str) -> bool {
+        let hwnd = self
+            .thread_info()
+            .map(|info| info.hwndFocus)
+            .filter(|hwnd| !hwnd.is_null())
+            .unwrap_or(self.target);
+        // SAFETY: 只送出視窗訊息，不解參考任何指標
+        unsafe {
+            text.encode_utf16()
+                .all(|unit| PostMessageW(hwnd, WM_CHAR, unit as usize, 1) != 0)
+        }
+    }
+}
+
+/// 將文字轉為 Unicode 按鍵輸入（每個 UTF-16 單元一組按下/放開）
+fn unicode_inputs(text: &str) -> Vec<INPUT> {
+    text.encode_utf16()
+        .flat_map(|unit| {
+            [0, KEYEVENTF_KEYUP].map(|flags| INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: INPUT_0 {
+                    ki: KEYBDINPUT {
+                        wVk: 0,
+                        wScan: unit,
+                        dwFlags: KEYEVENTF_UNICODE | flags,
+                        time: 0,
+                        dwExtraInfo: 0,
+                    },
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_inputs() {
+        // 「測」為單一 UTF-16 單元，「𠀀」為代理對
+        assert_eq!(unicode_inputs("測").len(), 2);
+        assert_eq!(unicode_inputs("測𠀀").len(), 6);
+
+        let inputs = unicode_inputs("a");
+        // SAFETY: unicode_inputs 只建立鍵盤輸入
+        let (down, up) = unsafe { (inputs[0].Anonymous.ki, inputs[1].Anonymous.ki) };
+        assert_eq!(down.wScan, 'a' as u16);
+        assert_eq!(down.dwFlags, KEYEVENTF_UNICODE);
+        assert_eq!(up.dwFlags, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP);
+    }
+}
@@ -0,0 +1,93 @@
+// Crate-level error type
+// 錯誤型別：載入字表、設定檔與字型時的錯誤分類，前端可依種類處理（例如找不到字表時提示安裝表格）
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 函式庫的錯誤
+#[derive(Debug)]
+pub enum Array30Error {
+    /// 找不到字表、詞庫或補充表檔
+    TableNotFound { path: PathBuf },
+    /// 不支援的表格檔（依副檔名判斷）
+    UnsupportedTable { path: PathBuf },
+    /// 檔案內容無法解析（行號由 1 起算，0 表示不分行的檔案，例如編譯字表）
+    ParseError { path: PathBuf, line: usize, reason: String },
+    /// 匯入檔的文字編碼錯誤（不是 UTF-8 或 UTF-16）
+    Encoding(String),
+    /// 設定檔或設定檔組合錯誤
+    ConfigError(String),
+    /// 字型檔無法讀取
+    FontError { path: PathBuf, reason: String },
+    /// 其他讀寫錯誤
+    Io(io::Error),
+}
+
+/// 函式庫的回傳型別
+pub type Result<T> = std::result::Result<T, Array30Error>;
+
+impl Array30Error {
+    /// 讀取表格檔的錯誤：檔案不存在時為 TableNotFound
+    pub fn table_io(path: &Path, error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::NotFound {
+            Self::TableNotFound { path: path.to_path_buf() }
+        } else {
+            Self::Io(error)
+        }
+    }
+}
+
+impl fmt::Display for Array30Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TableNotFound { path } => write!(f, "找不到表格檔 {}", path.display()),
+            Self::UnsupportedTable { path } => write!(f, "不支援的表格檔格式：{}", path.display()),
+            Self::ParseError { path, line: 0, reason } => write!(f, "{}：{}", path.display(), reason),
+            Self::ParseError { path, line, reason } => write!(f, "{} 第 {} 行：{}", path.display(), line, reason),
+            Self::Encoding(reason) => write!(f, "文字編碼錯誤：{}", reason),
+            Self::ConfigError(reason) => write!(f, "設定錯誤：{}", reason),
+            Self::FontError { path, reason } => write!(f, "無法讀取字型 {}：{}", path.display(), reason),
+            Self::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Array30Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Array30Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_io() {
+        let path = Path::new("table/missing.cin2");
+        let error = Array30Error::table_io(path, io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(&error, Array30Error::TableNotFound { path: p } if p == path));
+        assert_eq!(error.to_string(), "找不到表格檔 table/missing.cin2");
+        let error = Array30Error::table_io(path, io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(error, Array30Error::Io(_)));
+
+        let mut dict = crate::dict::Dictionary::new();
+        assert!(matches!(dict.load_cin2_file(path), Err(Array30Error::TableNotFound { .. })));
+        let file = std::env::temp_dir().join(format!("rustarray30-error-{}.txt", std::process::id()));
+        std::fs::write(&file, b"ab\t\xe5\x8f\xb0\ncd\t\xff\n").unwrap();
+        let error = dict.load_phrase_file(&file).unwrap_err();
+        std::fs::remove_file(&file).unwrap();
+        assert!(matches!(&error, Array30Error::ParseError { path: p, line: 2, .. } if *p == file));
+        assert_eq!(error.to_string(), format!("{} 第 2 行：不是有效的 UTF-8", file.display()));
+    }
+}
@@ -0,0 +1,182 @@
+// Export user data to other Array30 input methods
+// 匯出自訂字詞：官方 Windows 行列輸入法的自建詞彙檔與 OpenVanilla 的 cin 表格，兩邊可保持同步
+
+use crate::error::{Array30Error, Result};
+use crate::import;
+use crate::keymap::Array30Key;
+use crate::userdict::UserEntry;
+use std::path::Path;
+
+/// OpenVanilla 表格的英文與中文名稱
+const CIN_ENAME: &str = "Array30User";
+const CIN_CNAME: &str = "行列自訂字詞";
+
+/// 匯出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// 官方 Windows 行列輸入法：UTF-16 LE（含 BOM）、CRLF 換行，每行「碼 字詞」
+    WindowsArray,
+    /// OpenVanilla 的 cin 表格（%gen_inp、%keyname、%chardef）
+    OpenVanilla,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::WindowsArray => "windows",
+            Self::OpenVanilla => "openvanilla",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::WindowsArray => "官方 Windows 行列",
+            Self::OpenVanilla => "OpenVanilla",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "windows" => Some(Self::WindowsArray),
+            "openvanilla" | "ov" => Some(Self::OpenVanilla),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [Self; 2] {
+        [Self::WindowsArray, Self::OpenVanilla]
+    }
+
+    /// 匯出檔的副檔名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::WindowsArray => "txt",
+            Self::OpenVanilla => "cin",
+        }
+    }
+
+    /// 將字詞轉為此格式的檔案內容
+    pub fn encode(&self, entries: &[UserEntry]) -> Vec<u8> {
+        match self {
+            Self::WindowsArray => to_windows_array(entries),
+            Self::OpenVanilla => to_openvanilla_cin(entries).into_bytes(),
+        }
+    }
+
+    /// 讀回此格式的檔案內容
+    pub fn decode(&self, bytes: &[u8]) -> Result<Vec<UserEntry>> {
+        match self {
+            Self::WindowsArray => parse_windows_array(bytes),
+            Self::OpenVanilla => std::str::from_utf8(bytes)
+                .map(parse_openvanilla_cin)
+                .map_err(|e| Array30Error::Encoding(e.to_string())),
+        }
+    }
+}
+
+/// 匯出字詞到檔案
+pub fn export(format: ExportFormat, entries: &[UserEntry], path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, format.encode(entries))?;
+    Ok(())
+}
+
+/// 官方 Windows 行列輸入法的自建詞彙檔
+pub fn to_windows_array(entries: &[UserEntry]) -> Vec<u8> {
+    let mut text = String::new();
+    for entry in entries {
+        text.push_str(&format!("{} {}\r\n", entry.code, entry.text));
+    }
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    bytes
+}
+
+/// 讀取官方 Windows 行列輸入法的自建詞彙檔（UTF-16 LE，BOM 可省略）
+pub fn parse_windows_array(bytes: &[u8]) -> Result<Vec<UserEntry>> {
+    let bytes = bytes.strip_prefix(&[0xFF, 0xFE]).unwrap_or(bytes);
+    let text = import::decode_utf16(bytes, u16::from_le_bytes)?;
+    Ok(parse_code_lines(text.lines()))
+}
+
+/// OpenVanilla 的 cin 表格；%keyname 列出三十個鍵的行列標示
+pub fn to_openvanilla_cin(entries: &[UserEntry]) -> String {
+    let mut content = format!(
+        "%gen_inp\n%ename {}\n%cname {}\n%selkey 1234567890\n%keyname begin\n",
+        CIN_ENAME, CIN_CNAME
+    );
+    for key in Array30Key::all() {
+        content.push_str(&format!("{} {}\n", key.code_char(), key.label()));
+    }
+    content.push_str("%keyname end\n%chardef begin\n");
+    for entry in entries {
+        content.push_str(&format!("{} {}\n", entry.code, entry.text));
+    }
+    content.push_str("%chardef end\n");
+    content
+}
+
+/// 讀取 cin 表格 %chardef 區塊內的字詞
+pub fn parse_openvanilla_cin(content: &str) -> Vec<UserEntry> {
+    let chardef = content
+        .lines()
+        .skip_while(|line| line.trim() != "%chardef begin")
+        .skip(1)
+        .take_while(|line| line.trim() != "%chardef end");
+    parse_code_lines(chardef)
+}
+
+/// 每行「碼 字詞」，碼之後的空白或 Tab 為分隔，字詞可含空白；略過空行與 # 註解
+fn parse_code_lines<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<UserEntry> {
+    lines
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(code, text)| UserEntry::new(code, text))
+        .filter(|entry| !entry.code.is_empty() && !entry.text.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<UserEntry> {
+        vec![
+            UserEntry::new("ab", "二"),
+            UserEntry::new("x;cg", "台灣 加油"),
+            UserEntry::new("./,", "行列"),
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for format in ExportFormat::all() {
+            let bytes = format.encode(&sample());
+            assert_eq!(format.decode(&bytes).unwrap(), sample(), "{:?}", format);
+            assert_eq!(ExportFormat::from_str(format.as_str()), Some(format));
+        }
+
+        let path = std::env::temp_dir().join(format!("rustarray30-export-{}.cin", std::process::id()));
+        export(ExportFormat::OpenVanilla, &sample(), &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(parse_openvanilla_cin(&content), sample());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_formats() {
+        let bytes = to_windows_array(&sample()[..1]);
+        assert_eq!(bytes, [0xFF, 0xFE, b'a', 0, b'b', 0, b' ', 0, 0x8C, 0x4E, b'\r', 0, b'\n', 0]);
+        assert!(parse_windows_array(&[0xFF, 0xFE, b'a']).is_err());
+
+        let cin = to_openvanilla_cin(&sample());
+        assert!(cin.starts_with("%gen_inp\n"));
+        assert!(cin.contains("\na 1-\n"));
+        assert!(cin.contains("\n%chardef begin\nab 二\n"));
+        // %keyname 區塊的鍵不算字詞
+        assert_eq!(parse_openvanilla_cin(&cin).len(), 3);
+    }
+}
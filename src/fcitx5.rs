@@ -0,0 +1,309 @@
+// Fcitx5 addon C ABI shim
+// Fcitx5 外掛的 C ABI 介面（由 fcitx5/ 目錄下的 C++ 外掛呼叫）
+//
+// 字串回傳值由引擎持有，在下一次呼叫同一引擎的函式前有效。
+
+use crate::config::Config;
+use crate::input_engine::InputEngine;
+use crate::keysym;
+use crate::logging;
+use crate::state::InputMode;
+use crate::tables::{self, TableOptions};
+use crate::input_engine::KeyResult;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+use std::time::Instant;
+
+/// 提供給 Fcitx5 外掛的引擎把手
+pub struct Fcitx5Engine {
+    engine: InputEngine,
+    /// 最近一次回傳的字串（維持指標有效）
+    text: CString,
+}
+
+impl Fcitx5Engine {
+    fn new(engine: InputEngine) -> Self {
+        Self {
+            engine,
+            text: CString::default(),
+        }
+    }
+
+    /// 保存字串並回傳其指標
+    /// 字串中的 NUL 字元會被移除
+    fn hold(&mut self, s: String) -> *const c_char {
+        self.text = CString::new(s.replace('\0', "")).unwrap_or_default();
+        self.text.as_ptr()
+    }
+}
+
+/// 將指標轉為引擎參考
+///
+/// # Safety
+/// `engine` 必須為 `rustarray30_fcitx5_new` 回傳且尚未釋放的指標，或為 null
+unsafe fn engine_mut<'a>(engine: *mut Fcitx5Engine) -> Option<&'a mut Fcitx5Engine> {
+    engine.as_mut()
+}
+
+/// 建立引擎並載入設定中的輸入法變體字表
+/// `table_dir` 為 null 時使用設定檔的表格位置；載入失敗回傳 null
+///
+/// # Safety
+/// `table_dir` 必須為 null 或有效的 NUL 結尾 UTF-8 字串
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_new(table_dir: *const c_char) -> *mut Fcitx5Engine {
+    let table_dir = if table_dir.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(table_dir).to_str() {
+            Ok(dir) => Some(dir.to_string()),
+            Err(_) => return ptr::null_mut(),
+        }
+    };
+
+    logging::init_from_env();
+    let config = Config::load();
+    let table_options = TableOptions::from_config(&config).with_overrides(table_dir, None, None);
+    match tables::load_dictionary(&table_options, config.array_variant, false) {
+        Ok(dict) => {
+            let engine = InputEngine::with_options(dict, config.engine_options());
+            Box::into_raw(Box::new(Fcitx5Engine::new(engine)))
+        }
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// 釋放引擎
+///
+/// # Safety
+/// `engine` 必須為 `rustarray30_fcitx5_new` 回傳的指標，且之後不再使用
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_free(engine: *mut Fcitx5Engine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// 處理按鍵（keysym 與修飾鍵狀態與 Fcitx5 的 Key::sym()/states() 相同）
+/// 回傳是否由輸入法處理
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_key_event(
+    engine: *mut Fcitx5Engine,
+    keysym: u32,
+    states: u32,
+    is_release: bool,
+) -> bool {
+    let Some(e) = engine_mut(engine) else {
+        return false;
+    };
+    let states = if is_release { states | keysym::RELEASE_MASK } else { states };
+    keysym::process_key(&mut e.engine, keysym, states)
+}
+
+/// 距離組字逾時還有多少毫秒（沒有組字或未設定逾時時為 -1），供外掛排定計時器
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_timeout_ms(engine: *mut Fcitx5Engine) -> i64 {
+    engine_mut(engine)
+        .and_then(|e| e.engine.composition_deadline())
+        .map_or(-1, |deadline| deadline.saturating_duration_since(Instant::now()).as_millis() as i64)
+}
+
+/// 組字逾時時依設定清除或上屏，回傳是否需要重新整理
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_tick(engine: *mut Fcitx5Engine) -> bool {
+    engine_mut(engine).is_some_and(|e| e.engine.tick(Instant::now()) != KeyResult::NoChange)
+}
+
+/// 捨棄組字中的碼（輸入情境重設或失去焦點時）
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_reset(engine: *mut Fcitx5Engine) {
+    if let Some(e) = engine_mut(engine) {
+        e.engine.handle_key('\x1b');
+    }
+}
+
+/// 取出待上屏的文字（沒有時為空字串）
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_take_commit(engine: *mut Fcitx5Engine) -> *const c_char {
+    match engine_mut(engine) {
+        Some(e) => {
+            let commit = e.engine.take_output();
+            e.hold(commit)
+        }
+        None => ptr::null(),
+    }
+}
+
+/// 預編輯文字（目前的碼）
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_preedit(engine: *mut Fcitx5Engine) -> *const c_char {
+    match engine_mut(engine) {
+        Some(e) => {
+            let code = e.engine.state().current_code.clone();
+            e.hold(code)
+        }
+        None => ptr::null(),
+    }
+}
+
+/// 是否處於詞彙輸入模式（碼中含 '）
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_phrase_mode(engine: *mut Fcitx5Engine) -> bool {
+    engine_mut(engine).is_some_and(|e| e.engine.state().mode == InputMode::PhraseInput)
+}
+
+/// 目前頁面的候選數
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_candidate_count(engine: *mut Fcitx5Engine) -> u32 {
+    engine_mut(engine).map_or(0, |e| e.engine.current_page_candidates().len() as u32)
+}
+
+/// 目前頁面第 `index` 個候選（超出範圍時為 null）
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_candidate(engine: *mut Fcitx5Engine, index: u32) -> *const c_char {
+    let Some(e) = engine_mut(engine) else {
+        return ptr::null();
+    };
+    match e.engine.current_page_candidates().get(index as usize) {
+        Some(cand) => {
+            let text = cand.text.clone();
+            e.hold(text)
+        }
+        None => ptr::null(),
+    }
+}
+
+/// 候選的選字鍵標籤（如 "1234567890"），依輸入法變體而定
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_selection_keys(engine: *mut Fcitx5Engine) -> *const c_char {
+    match engine_mut(engine) {
+        Some(e) => {
+            let keys = e.engine.options().variant.selection_keys().to_string();
+            e.hold(keys)
+        }
+        None => ptr::null(),
+    }
+}
+
+/// 選擇目前頁面第 `index` 個候選
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_select(engine: *mut Fcitx5Engine, index: u32) -> bool {
+    engine_mut(engine).is_some_and(|e| e.engine.select_candidate(index as usize))
+}
+
+/// 翻頁；`next` 為 true 時到下一頁
+/// 回傳頁面是否改變
+///
+/// # Safety
+/// 見 `engine_mut`
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_page(engine: *mut Fcitx5Engine, next: bool) -> bool {
+    engine_mut(engine).is_some_and(|e| if next { e.engine.next_page() } else { e.engine.prev_page() })
+}
+
+/// 是否有上一頁／下一頁
+///
+/// # Safety
+/// 見 `engine_mut`；`has_prev`、`has_next` 必須為有效指標
+#[no_mangle]
+pub unsafe extern "C" fn rustarray30_fcitx5_page_state(
+    engine: *mut Fcitx5Engine,
+    has_prev: *mut bool,
+    has_next: *mut bool,
+) {
+    let (prev, next) = match engine_mut(engine) {
+        Some(e) => {
+            let page = e.engine.page_index();
+            (page > 0, page + 1 < e.engine.page_count())
+        }
+        None => (false, false),
+    };
+    if !has_prev.is_null() {
+        *has_prev = prev;
+    }
+    if !has_next.is_null() {
+        *has_next = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::Dictionary;
+
+    fn create_test_engine() -> *mut Fcitx5Engine {
+        let mut dict = Dictionary::new();
+        dict.insert_char("ab", "甲");
+        dict.insert_char("ab", "乙");
+        Box::into_raw(Box::new(Fcitx5Engine::new(InputEngine::new(dict))))
+    }
+
+    unsafe fn text(ptr: *const c_char) -> String {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_key_flow() {
+        unsafe {
+            let engine = create_test_engine();
+            assert!(rustarray30_fcitx5_key_event(engine, 'a' as u32, 0, false));
+            assert!(!rustarray30_fcitx5_key_event(engine, 'a' as u32, 0, true));
+            assert!(rustarray30_fcitx5_key_event(engine, 'b' as u32, 0, false));
+            assert_eq!(text(rustarray30_fcitx5_preedit(engine)), "ab");
+            assert_eq!(rustarray30_fcitx5_candidate_count(engine), 2);
+            assert_eq!(text(rustarray30_fcitx5_candidate(engine, 1)), "乙");
+            assert!(rustarray30_fcitx5_candidate(engine, 5).is_null());
+
+            let (mut prev, mut next) = (true, true);
+            rustarray30_fcitx5_page_state(engine, &mut prev, &mut next);
+            assert!(!prev && !next);
+
+            assert!(rustarray30_fcitx5_select(engine, 1));
+            assert_eq!(text(rustarray30_fcitx5_take_commit(engine)), "乙");
+            assert_eq!(text(rustarray30_fcitx5_take_commit(engine)), "");
+            rustarray30_fcitx5_free(engine);
+        }
+    }
+
+    #[test]
+    fn test_null_engine() {
+        unsafe {
+            assert!(!rustarray30_fcitx5_key_event(ptr::null_mut(), 'a' as u32, 0, false));
+            assert!(rustarray30_fcitx5_preedit(ptr::null_mut()).is_null());
+            rustarray30_fcitx5_free(ptr::null_mut());
+        }
+    }
+}
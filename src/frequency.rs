@@ -0,0 +1,136 @@
+// Corpus-based frequency training
+// 語料詞頻：統計使用者文章中字與詞出現的次數，寫成詞頻檔供候選排序使用
+
+use crate::dict::{Dictionary, TableKind};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 詞頻檔名（位於使用者資料目錄，每行「字詞<Tab>次數」）
+const FREQUENCY_FILENAME: &str = "frequency.txt";
+
+/// 字詞出現次數
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frequencies {
+    counts: HashMap<String, u32>,
+}
+
+impl Frequencies {
+    /// 統計語料中字典收錄的字與詞；詞可重疊計算（「行列輸入」同時算「行列」與「輸入」）
+    pub fn train(dict: &Dictionary, corpus: &str) -> Self {
+        let chars: HashSet<&str> = dict.word_pairs(TableKind::Chars).map(|(_, word)| word).collect();
+        let phrases: HashSet<&str> = dict.word_pairs(TableKind::Phrases).map(|(_, word)| word).collect();
+        let max_len = phrases.iter().map(|phrase| phrase.chars().count()).max().unwrap_or(0);
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        let mut count = |word: &str| {
+            let n = counts.entry(word.to_string()).or_default();
+            *n = n.saturating_add(1);
+        };
+        for line in corpus.lines() {
+            let starts: Vec<usize> = line.char_indices().map(|(i, _)| i).chain([line.len()]).collect();
+            for i in 0..starts.len() - 1 {
+                let c = &line[starts[i]..starts[i + 1]];
+                if chars.contains(c) {
+                    count(c);
+                }
+                for end in starts.iter().skip(i + 2).take(max_len.saturating_sub(1)) {
+                    let phrase = &line[starts[i]..*end];
+                    if phrases.contains(phrase) {
+                        count(phrase);
+                    }
+                }
+            }
+        }
+        Self { counts }
+    }
+
+    pub fn get(&self, word: &str) -> u32 {
+        self.counts.get(word).copied().unwrap_or(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// 交給字典作為候選排序的詞頻
+    pub fn apply_to(self, dict: &mut Dictionary) {
+        dict.set_corpus_frequencies(self.counts);
+    }
+
+    /// 詞頻檔路徑
+    pub fn file_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rustarray30").join(FREQUENCY_FILENAME))
+    }
+
+    /// 讀取詞頻檔；略過空行、# 開頭的註解與次數無法解析的行
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let counts = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('\t'))
+            .filter_map(|(word, count)| Some((word.trim().to_string(), count.trim().parse().ok()?)))
+            .collect();
+        Ok(Self { counts })
+    }
+
+    /// 讀取預設位置的詞頻檔，不存在或無法讀取時為空
+    pub fn load_default() -> Self {
+        let Some(path) = Self::file_path().filter(|p| p.exists()) else {
+            return Self::default();
+        };
+        Self::load(&path).unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), "無法讀取詞頻檔：{}", e);
+            Self::default()
+        })
+    }
+
+    /// 寫入詞頻檔，次數多的在前
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut counts: Vec<(&String, &u32)> = self.counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        let mut content = String::from("# Frequency: text<Tab>count (語料詞頻)\n");
+        for (word, count) in counts {
+            content.push_str(&format!("{}\t{}\n", word, count));
+        }
+        std::fs::write(path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_train_and_order() {
+        let mut dict = Dictionary::new();
+        for (code, c) in [("ab", "行"), ("ab", "列"), ("cd", "輸"), ("ef", "入")] {
+            dict.insert_char(code, c);
+        }
+        for (code, phrase) in [("acde", "行列"), ("acde", "列行"), ("cdef", "輸入")] {
+            dict.insert_phrase(code, phrase);
+        }
+        let freq = Frequencies::train(&dict, "列列行列輸入，列行\n輸入");
+        assert_eq!((freq.get("列"), freq.get("行"), freq.get("輸入")), (4, 2, 2));
+        assert_eq!((freq.get("列行"), freq.get("行列"), freq.get("，")), (2, 1, 0));
+
+        let path = std::env::temp_dir().join(format!("rustarray30-frequency-{}.txt", std::process::id()));
+        freq.save(&path).unwrap();
+        let loaded = Frequencies::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, freq);
+
+        loaded.apply_to(&mut dict);
+        assert_eq!(dict.ordered_words(TableKind::Chars, "ab"), ["列", "行"]);
+        assert_eq!(dict.ordered_words(TableKind::Phrases, "acde"), ["列行", "行列"]);
+    }
+}
@@ -1,51 +1,534 @@
-// Windows GUI using egui/eframe
-// Windows 圖形介面
+// GUI using egui/eframe
+// 圖形介面（系統匣、全域快速鍵與直接輸出僅 Windows）
 
-use crate::config::{Config, FontInfo, RootTablePosition};
-use crate::dict::Dictionary;
-use crate::input_engine::InputEngine;
+use crate::backup::{self, Backup, BackupReason};
+use crate::buffers::OutputBuffers;
+use crate::bundle::{self, Bundle};
+use crate::clipwatch::{self, ClipboardWatch};
+use crate::config::{
+    is_valid_profile_name, Config, FeedbackStyle, FontInfo, RootTablePosition, ThemeMode, MAX_UI_SCALE, MIN_UI_SCALE,
+};
+#[cfg(target_os = "windows")]
+use crate::config::DirectOutputMethod;
+#[cfg(target_os = "windows")]
+use crate::direct_output::DirectOutput;
+use crate::dict::{Annotations, DictEntry, Dictionary, SearchMode, TableFile, TableKind};
+use crate::error::{self, Array30Error};
+use crate::export::{self, ExportFormat};
+use crate::import::{self, ImportFormat};
+use crate::history::{self, History};
+use crate::input_engine::{
+    CandidateFilter, CandidateOrder, EngineOptions, InputEngine, KeyResult, MissPolicy, PhraseMix, PunctuationMode, RejectReason, SpaceBehavior,
+    TimeoutAction, MAX_PAGE_SIZE,
+};
+use crate::journal::OutputJournal;
+use crate::keymap::{self, Array30Key, KeyRow, KeymapProfile};
+use crate::practice::{self, Lesson, Progress, Session};
+use crate::remote::{self, DictBackend};
+use crate::shortcuts::{self, Action, ChordKey, KeyChord, Shortcuts};
+use crate::state::Candidate;
+use crate::stats::{self, Stats};
+use crate::sync;
+#[cfg(target_os = "windows")]
+use crate::hotkey::{GlobalHotkeys, HotkeyCommand};
+use crate::tables::{self, TableChoice, TableOptions};
+use crate::transform::TransformChain;
+use crate::unicode;
+use crate::userdict::{UserDict, UserEntry};
+#[cfg(target_os = "windows")]
+use crate::tray::{Tray, TrayCommand};
 use eframe::egui;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use egui::accesskit;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 
+/// 螢幕鍵盤按鍵高亮持續時間
+const KEY_HIGHLIGHT_DURATION: Duration = Duration::from_millis(300);
+/// 按鍵被拒絕提示的顯示時間
+const REJECTION_TOAST_DURATION: Duration = Duration::from_millis(1200);
+/// 提示回饋閃爍視窗邊框的時間
+const FLASH_DURATION: Duration = Duration::from_millis(200);
+/// 視窗隱藏或閒置時檢查全域快速鍵、系統匣命令與前景視窗的間隔
 #[cfg(target_os = "windows")]
-use crossterm::{
-    event::{self, KeyCode, KeyEvent, KeyModifiers},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
-};
+const BACKGROUND_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// 等待遠端字典查詢結果時的重繪間隔
+const REMOTE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// 內建的行列字根表圖片
+const BUNDLED_ROOT_TABLE: &[u8] = include_bytes!("../table/行列字根表v2023.jpg");
+/// 字根表縮放按鈕的每次增減量
+const ROOT_TABLE_SCALE_STEP: f32 = 0.1;
+/// 字根表面板最多佔視窗的比例
+const ROOT_TABLE_MAX_FRACTION: f32 = 0.6;
+/// 字典瀏覽每頁顯示的筆數
+const DICT_BROWSER_PAGE_SIZE: usize = 50;
+/// 使用統計圖表顯示的天數
+const STATS_CHART_DAYS: u64 = 14;
+/// 使用統計寫入檔案的最短間隔
+const STATS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+/// 浮動候選列視窗大小
+const CANDIDATE_BAR_SIZE: [f32; 2] = [480.0, 64.0];
+/// 游標候選窗與文字游標下緣的間距
+#[cfg(target_os = "windows")]
+const CARET_POPUP_GAP: f32 = 4.0;
+/// 候選字使用的文字樣式名稱
+const CANDIDATE_STYLE: &str = "candidate";
+/// 輸出區使用的文字樣式名稱
+const OUTPUT_STYLE: &str = "output";
+
+/// 候選字的文字樣式
+fn candidate_style() -> egui::TextStyle {
+    egui::TextStyle::Name(CANDIDATE_STYLE.into())
+}
+
+/// 輸出區的文字樣式
+fn output_style() -> egui::TextStyle {
+    egui::TextStyle::Name(OUTPUT_STYLE.into())
+}
+
+/// 候選按鈕文字：開啟碼標示時分兩行，第二行為字／詞標記與行列碼
+fn candidate_text(ui: &egui::Ui, label: &str, cand: &Candidate, show_codes: bool) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let format = |style: egui::TextStyle| egui::TextFormat {
+        font_id: style.resolve(ui.style()),
+        color: egui::Color32::PLACEHOLDER,
+        ..Default::default()
+    };
+    job.append(label, 0.0, format(candidate_style()));
+    if show_codes {
+        let marker = if cand.is_phrase { "詞" } else { "字" };
+        let codes = format!("\n{} {}", marker, keymap::code_labels(&cand.code));
+        job.append(&codes, 0.0, format(egui::TextStyle::Small));
+    }
+    job
+}
+
+/// 候選的無障礙名稱：序號、字詞與行列碼，例如「第 1 個候選：行，碼 4^ 8-」
+fn candidate_accessible_name(index: usize, cand: &Candidate) -> String {
+    let kind = match (cand.is_phrase, cand.big_set) {
+        (true, _) => "詞",
+        (false, true) => "大字集字",
+        (false, false) => "字",
+    };
+    format!("第 {} 個候選{}：{}，碼 {}", index + 1, kind, cand.text, keymap::code_labels(&cand.code))
+}
+
+/// 組字碼的無障礙名稱
+fn code_accessible_name(code: &str) -> String {
+    if code.is_empty() {
+        "組字碼：空".to_string()
+    } else {
+        format!("組字碼：{}", keymap::code_labels(code))
+    }
+}
+
+/// 以 name 取代元件給螢幕閱讀器的名稱；live 不為 Off 時內容改變會主動朗讀
+fn set_accessible_name(ui: &egui::Ui, response: &egui::Response, name: String, live: accesskit::Live) {
+    ui.ctx().accesskit_node_builder(response.id, |node| {
+        node.set_name(name);
+        if live != accesskit::Live::Off {
+            node.set_live(live);
+        }
+    });
+}
+
+/// 字根表位置選單，回傳是否有變更
+fn root_table_position_selector(ui: &mut egui::Ui, id: &str, position: &mut RootTablePosition) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(position.display_name())
+        .show_ui(ui, |ui| {
+            for pos in RootTablePosition::all() {
+                changed |= ui.selectable_value(position, pos, pos.display_name()).changed();
+            }
+        });
+    changed
+}
+
+/// 反查標註的匯出文字：每字之後以括號列出各碼的字根標示，查無字碼的字元原樣保留
+fn annotation_text(annotations: &[(char, Vec<String>)]) -> String {
+    let mut text = String::new();
+    for (c, codes) in annotations {
+        text.push(*c);
+        if !codes.is_empty() {
+            let labels: Vec<String> = codes.iter().map(|code| keymap::code_labels(code)).collect();
+            text.push_str(&format!("（{}）", labels.join("／")));
+        }
+    }
+    text
+}
+
+/// 候選字的提示：各字的碼位與 Unicode 區塊、其他行列碼、讀音與釋義
+/// 反查要走過整個字典，結果依字詞快取在 egui 暫存資料，字典變更後重查
+fn candidate_tooltip(ui: &mut egui::Ui, cand: &Candidate, engine: &InputEngine) {
+    for c in cand.text.chars() {
+        ui.label(format!("{}  {}  {}", c, unicode::codepoint(c), unicode::block_name(c)));
+    }
+    let id = egui::Id::new(("candidate_codes", &cand.text));
+    let revision = engine.dict_revision();
+    let codes = match ui.data(|d| d.get_temp::<(u64, Vec<String>)>(id)) {
+        Some((cached, codes)) if cached == revision => codes,
+        _ => {
+            let codes: Vec<String> = engine.dict().reverse_lookup(&cand.text).into_iter().map(String::from).collect();
+            ui.data_mut(|d| d.insert_temp(id, (revision, codes.clone())));
+            codes
+        }
+    };
+    let others: Vec<String> = codes
+        .iter()
+        .filter(|code| **code != cand.code)
+        .map(|code| keymap::code_labels(code))
+        .collect();
+    if !others.is_empty() {
+        ui.label(format!("其他碼：{}", others.join("／")));
+    }
+    if !cand.readings.is_empty() {
+        ui.label(format!("讀音：{}", cand.readings.join("、")));
+    }
+    if let Some(ref definition) = cand.definition {
+        ui.label(format!("釋義：{}", definition));
+    }
+    if cand.big_set {
+        ui.label("大字集字（標準字表沒有此碼）");
+    }
+}
+
+/// 可編輯輸出區的元件 ID
+fn output_edit_id() -> egui::Id {
+    egui::Id::new("output_edit")
+}
+
+/// 套用介面主題：深色與淺色兩組外觀都換上強調色，系統模式下切換時也會生效
+fn apply_theme(ctx: &egui::Context, mode: ThemeMode, accent: Option<[u8; 3]>) {
+    ctx.set_theme(match mode {
+        ThemeMode::System => egui::ThemePreference::System,
+        ThemeMode::Dark => egui::ThemePreference::Dark,
+        ThemeMode::Light => egui::ThemePreference::Light,
+    });
+    for theme in [egui::Theme::Dark, egui::Theme::Light] {
+        let mut visuals = theme.default_visuals();
+        if let Some([r, g, b]) = accent {
+            let color = egui::Color32::from_rgb(r, g, b);
+            visuals.selection.bg_fill = color;
+            visuals.hyperlink_color = color;
+            visuals.widgets.hovered.bg_stroke.color = color;
+        }
+        ctx.set_visuals_of(theme, visuals);
+    }
+}
+
+/// 候選字的反白顏色（未設定時使用強調色）
+fn candidate_highlight(ui: &egui::Ui, color: Option<[u8; 3]>) -> egui::Color32 {
+    match color {
+        Some([r, g, b]) => egui::Color32::from_rgb(r, g, b),
+        None => ui.visuals().selection.bg_fill,
+    }
+}
+
+/// 顏色設定列：勾選後才可自訂，取消勾選恢復主題預設
+fn color_setting(ui: &mut egui::Ui, label: &str, color: &mut Option<[u8; 3]>, fallback: egui::Color32) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        let mut custom = color.is_some();
+        if ui.checkbox(&mut custom, label).changed() {
+            *color = custom.then(|| [fallback.r(), fallback.g(), fallback.b()]);
+            changed = true;
+        }
+        if let Some(rgb) = color {
+            changed |= ui.color_edit_button_srgb(rgb).changed();
+        } else {
+            ui.weak("（主題預設）");
+        }
+    });
+    changed
+}
 
 /// 目前顯示的面板
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Panel {
     Main,
     Settings,
+    Dictionary,
+    UserDict,
+    Reverse,
+    Stats,
+    Practice,
+}
+
+/// 背景載入字表的結果
+struct TableLoad {
+    options: TableOptions,
+    use_big_char: bool,
+    phrase_file: Option<PathBuf>,
+    char_file: PathBuf,
+    result: error::Result<Dictionary>,
+}
+
+/// 輸出歷史側欄中對某筆記錄的操作
+#[derive(Debug, Clone, Copy)]
+enum HistoryAction {
+    Copy(usize),
+    Insert(usize),
+    Remove(usize),
+}
+
+/// 輸出分頁列上的操作
+#[derive(Debug, Clone, Copy)]
+enum BufferAction {
+    Switch(usize),
+    Add,
+    Close(usize),
+    Copy(usize),
+    Rename(usize),
+}
+
+/// 重新命名輸出分頁的編輯欄位
+fn buffer_rename_id(index: usize) -> egui::Id {
+    egui::Id::new(("buffer_rename", index))
+}
+
+/// 建立引擎、帶入讀音註解並開啟選字記錄（供使用統計）與上屏記錄（供輸出日誌）
+/// 上屏轉換串列的快取：[transform] 設定未變更時不重讀逐字對應檔
+#[derive(Default)]
+struct TransformCache {
+    /// 建立串列時的設定（尚未建立時為 None）
+    char_map_file: Option<String>,
+    fullwidth_digits: bool,
+    replacements: Vec<(String, String)>,
+    chain: TransformChain,
+}
+
+impl TransformCache {
+    /// 依設定建立引擎選項，沿用未變更的轉換串列
+    fn engine_options(&mut self, config: &Config) -> EngineOptions {
+        if self.char_map_file.as_ref() != Some(&config.char_map_file)
+            || self.fullwidth_digits != config.fullwidth_digits
+            || self.replacements != config.replacements
+        {
+            self.chain = config.output_transforms();
+            self.char_map_file = Some(config.char_map_file.clone());
+            self.fullwidth_digits = config.fullwidth_digits;
+            self.replacements = config.replacements.clone();
+        }
+        config.engine_options_with(self.chain.clone())
+    }
+}
+
+fn new_engine(
+    dict: Dictionary,
+    options: EngineOptions,
+    annotations: Arc<Annotations>,
+    remote: Option<Arc<dyn DictBackend>>,
+) -> InputEngine {
+    let mut engine = InputEngine::with_options(dict, options);
+    engine.set_annotations(annotations);
+    engine.set_remote(remote);
+    engine.enable_commit_log();
+    engine.enable_commit_record();
+    engine.enable_feedback();
+    engine
+}
+
+/// 長條圖：每條下方標示名稱，滑鼠移上顯示數值
+fn bar_chart(ui: &mut egui::Ui, bars: &[(String, f32)], value_text: impl Fn(f32) -> String) {
+    let bar_width = 32.0;
+    let height = 120.0;
+    let label_height = ui.text_style_height(&egui::TextStyle::Small) + 4.0;
+    let max = bars.iter().map(|(_, v)| *v).fold(0.0, f32::max).max(f32::EPSILON);
+    let size = egui::vec2(bar_width * bars.len() as f32, height + label_height);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+
+    let mut hovered = None;
+    for (i, (label, value)) in bars.iter().enumerate() {
+        let left = rect.left() + bar_width * i as f32;
+        let bottom = rect.top() + height;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left + 4.0, bottom - height * value / max),
+            egui::pos2(left + bar_width - 4.0, bottom),
+        );
+        painter.rect_filled(bar, 2.0, visuals.selection.bg_fill);
+        painter.text(
+            egui::pos2(left + bar_width / 2.0, bottom + 2.0),
+            egui::Align2::CENTER_TOP,
+            label,
+            egui::TextStyle::Small.resolve(ui.style()),
+            visuals.weak_text_color(),
+        );
+        let column = egui::Rect::from_x_y_ranges(left..=left + bar_width, rect.y_range());
+        if response.hover_pos().is_some_and(|pos| column.contains(pos)) {
+            hovered = Some(format!("{}：{}", label, value_text(*value)));
+        }
+    }
+    if let Some(text) = hovered {
+        response.on_hover_text(text);
+    }
 }
 
 pub struct GuiApp {
     engine: InputEngine,
-    phrase_file_path: PathBuf,
+    /// 上屏轉換串列（設定變更時才重建）
+    transforms: TransformCache,
+    phrase_file_path: Option<PathBuf>,
     cin2_file_path: PathBuf,
+    /// 表格位置（切換字表時沿用）
+    table_options: TableOptions,
+    /// 是否使用大字集字表（切換字表時沿用）
+    use_big_char: bool,
     clipboard_content: String,
+    /// 輸出區編輯框的緩衝區（每個畫面由輸出區複製，沿用記憶體）
+    output_edit: String,
+    /// 使用統計（按鍵與選字上屏）
+    stats: Stats,
+    /// 統計有尚未寫入檔案的變更
+    stats_dirty: bool,
+    stats_saved_at: Instant,
+    /// 輸出日誌（無法開啟時為 None）
+    journal: Option<OutputJournal>,
+    /// 輸出區不是只有新上屏的變動（復原、清除、編輯、儲存等），下次寫入日誌時以目前的輸出重設
+    journal_stale: bool,
+    /// 上次異常結束時留下、等待使用者決定是否恢復的輸出
+    recovered_output: Option<String>,
+    /// 打字練習課程（表格目錄 lessons/）
+    lessons: Vec<Lesson>,
+    practice_progress: Progress,
+    /// 進行中的練習
+    practice: Option<Session>,
+    /// 最近一次送出的碼是否正確
+    practice_feedback: Option<bool>,
+    /// 輸出分頁（目前分頁的內容在引擎的輸出區）
+    output_buffers: OutputBuffers,
+    /// 重新命名中的分頁與編輯中的名稱
+    renaming_buffer: Option<(usize, String)>,
     show_about: bool,
     config: Config,
     current_panel: Panel,
     available_fonts: Vec<FontInfo>,
     selected_font_index: usize,
     temp_font_size: f32,
-    temp_show_root_table: bool,
-    temp_root_table_scale: f32,
+    temp_candidate_font_size: f32,
+    temp_output_font_size: f32,
+    /// 介面縮放比例（編輯中，0 為自動）
+    temp_ui_scale: f32,
+    /// 主題設定（編輯中，變更即時預覽）
+    temp_theme: ThemeMode,
+    temp_accent_color: Option<[u8; 3]>,
+    temp_candidate_highlight_color: Option<[u8; 3]>,
     temp_window_width: f32,
     temp_window_height: f32,
-    temp_root_table_position: RootTablePosition,
+    /// 自訂字根表圖片路徑（編輯中）
+    temp_root_table_image: String,
+    temp_keymap_profile: KeymapProfile,
+    temp_keymap_file: String,
+    /// 快速鍵編輯中的文字（依 Action::all() 順序）
+    temp_shortcuts: Vec<String>,
+    shortcut_error: Option<String>,
     root_table_image: Option<egui::ColorImage>,
     root_table_texture: Option<egui::TextureHandle>,
     needs_font_reload: bool,
+    /// 螢幕鍵盤各鍵最後按下的時間
+    key_press_times: HashMap<Array30Key, Instant>,
+    /// 最近一次被拒絕的按鍵原因與時間（短暫提示用）
+    rejection: Option<(RejectReason, Instant)>,
+    /// 引擎狀態在畫面繪製後才改變，需要再重繪一次
+    engine_changed: bool,
+    /// 提示回饋閃爍的開始時間
+    flash_at: Option<Instant>,
+    /// 系統匣圖示（建立失敗時為 None）
+    #[cfg(target_os = "windows")]
+    tray: Option<Tray>,
+    /// 主視窗是否顯示
+    window_visible: bool,
+    /// 啟動後待隱藏主視窗（start_minimized 或 --minimized）
+    pending_minimize: bool,
+    /// 已要求結束程式（關閉視窗時不再縮到系統匣）
+    quitting: bool,
+    /// 浮動候選列模式（隱藏主視窗）
+    candidate_bar: bool,
+    /// 在候選下方列出各候選的碼位、讀音與釋義
+    candidate_detail: bool,
+    /// 全域快速鍵（註冊失敗時為 None）
+    #[cfg(target_os = "windows")]
+    global_hotkeys: Option<GlobalHotkeys>,
+    #[cfg(target_os = "windows")]
+    temp_global_hotkey_window: String,
+    #[cfg(target_os = "windows")]
+    temp_global_hotkey_language: String,
+    /// 直接輸出到外部視窗
+    #[cfg(target_os = "windows")]
+    direct_output: DirectOutput,
+    /// 已建立的設定檔組合
+    profiles: Vec<String>,
+    /// 新設定檔組合名稱（編輯中）
+    temp_profile_name: String,
+    profile_error: Option<String>,
+    /// 設定封包路徑（編輯中）
+    temp_bundle_path: String,
+    /// 匯出／匯入結果
+    bundle_status: Option<String>,
+    /// 同步資料夾（編輯中）
+    temp_sync_folder: String,
+    sync_status: Option<String>,
+    /// 自動備份（新的在前）與選取要還原的備份
+    backups: Vec<Backup>,
+    selected_backup: usize,
+    backup_status: Option<String>,
+    /// 輸出檔另存／開啟結果
+    output_file_status: Option<String>,
+    /// 清除或複製過的輸出
+    history: History,
+    /// 顯示輸出歷史側欄
+    show_history: bool,
+    /// 背景載入中的字表
+    table_loading: Option<mpsc::Receiver<TableLoad>>,
+    /// 切換字表的結果
+    table_status: Option<String>,
+    /// 拖放進來、等待確認的表格檔
+    pending_table: Option<(PathBuf, error::Result<TableFile>)>,
+    /// 啟動時找不到表格的錯誤訊息（以空白字典執行，載入成功後清除）
+    missing_tables: Option<String>,
+    /// 設定檔儲存失敗的訊息（顯示在狀態列，下次儲存成功後清除）
+    config_save_error: Option<String>,
+    /// 字典瀏覽的搜尋方式與關鍵字
+    dict_search_mode: SearchMode,
+    dict_search_query: String,
+    /// 字典瀏覽的搜尋結果（None 表示需重新搜尋）
+    dict_results: Option<Vec<DictEntry>>,
+    dict_page: usize,
+    /// 使用者自訂字詞與編輯中的欄位
+    user_dict: UserDict,
+    temp_user_code: String,
+    temp_user_text: String,
+    /// 修改中的自訂字詞（None 表示新增）
+    user_dict_editing: Option<usize>,
+    user_dict_status: Option<String>,
+    /// 反查標註的原文與結果（None 表示需重新反查）
+    reverse_text: String,
+    reverse_annotations: Option<Vec<(char, Vec<String>)>>,
+    reverse_status: Option<String>,
+    /// 監看剪貼簿，複製了新的中文時自動反查（None 為未監看）
+    clipboard_watch: Option<ClipboardWatch>,
+    /// 候選提示中的讀音與釋義註解（與引擎共用）
+    annotations: Arc<Annotations>,
+    /// 遠端字典（設定 remote_dict_url 時，與引擎共用快取）
+    remote: Option<Arc<dyn DictBackend>>,
+    temp_annotation_file: String,
+    annotation_status: Option<String>,
 }
 
 impl GuiApp {
-    pub fn new(dict: Dictionary, phrase_file: PathBuf, cin2_file: PathBuf) -> Self {
-        let config = Config::load();
+    pub fn new(
+        ctx: &egui::Context,
+        config: Config,
+        dict: Dictionary,
+        phrase_file: Option<PathBuf>,
+        cin2_file: PathBuf,
+        table_options: TableOptions,
+        use_big_char: bool,
+    ) -> Self {
         let font_size = config.font_size;
 
         // 載入系統字型列表
@@ -57,402 +540,2977 @@ impl GuiApp {
             .position(|f| f.path == config.font_path)
             .unwrap_or(0);
 
+        // 表格目錄下的練習課程
+        let lessons = practice::load_lessons(&table_options.table_dir());
+
         // 載入字根表圖片
-        let root_table_image = Self::load_root_table_image();
+        let root_table_image = Self::load_root_table_image(&config.root_table_image);
+
+        apply_theme(ctx, config.theme, config.accent_color);
+        // 縮放比例由設定決定，停用 egui 內建的 Ctrl +/- 縮放
+        ctx.options_mut(|o| o.zoom_with_keyboard = false);
+
+        // 建立系統匣圖示
+        #[cfg(target_os = "windows")]
+        let tray = match Tray::new(ctx, config.array_variant, false) {
+            Ok(tray) => Some(tray),
+            Err(e) => {
+                tracing::warn!("無法建立系統匣圖示：{}", e);
+                None
+            }
+        };
+
+        let annotations = Arc::new(Self::load_annotations(&config.annotation_file).unwrap_or_else(|e| {
+            tracing::warn!(path = %config.annotation_file, "無法讀取讀音註解檔：{}", e);
+            Annotations::default()
+        }));
+
+        let remote = remote::from_url(&config.remote_dict_url);
+
+        // 開啟輸出日誌；上次異常結束時留下的輸出於啟動後詢問是否恢復
+        let (journal, recovered_output) = match OutputJournal::file_path().map(|path| OutputJournal::open(&path)) {
+            Some(Ok((journal, recovered))) => (Some(journal), Some(recovered).filter(|text| !text.is_empty())),
+            Some(Err(e)) => {
+                tracing::warn!("無法開啟輸出日誌：{}", e);
+                (None, None)
+            }
+            None => (None, None),
+        };
+
+        let mut transforms = TransformCache::default();
+        let options = transforms.engine_options(&config);
 
         Self {
-            engine: InputEngine::new(dict),
+            engine: new_engine(dict, options, annotations.clone(), remote.clone()),
+            transforms,
             phrase_file_path: phrase_file,
             cin2_file_path: cin2_file,
+            table_options,
+            use_big_char,
             clipboard_content: String::new(),
+            output_edit: String::new(),
+            stats: Stats::load_default(),
+            stats_dirty: false,
+            stats_saved_at: Instant::now(),
+            journal,
+            journal_stale: false,
+            recovered_output,
+            lessons,
+            practice_progress: Progress::load_default(),
+            practice: None,
+            practice_feedback: None,
+            output_buffers: OutputBuffers::new(),
+            renaming_buffer: None,
             show_about: false,
             config: config.clone(),
             current_panel: Panel::Main,
             available_fonts,
             selected_font_index,
             temp_font_size: font_size,
-            temp_show_root_table: config.show_root_table,
-            temp_root_table_scale: config.root_table_scale,
+            temp_candidate_font_size: config.candidate_font_size,
+            temp_output_font_size: config.output_font_size,
+            temp_ui_scale: config.ui_scale,
+            temp_theme: config.theme,
+            temp_accent_color: config.accent_color,
+            temp_candidate_highlight_color: config.candidate_highlight_color,
             temp_window_width: config.window_width,
             temp_window_height: config.window_height,
-            temp_root_table_position: config.root_table_position,
+            temp_root_table_image: config.root_table_image.clone(),
+            temp_keymap_profile: config.keymap_profile,
+            temp_keymap_file: config.keymap_file.clone(),
+            temp_shortcuts: Action::all()
+                .iter()
+                .map(|a| config.shortcuts.chords_text(*a))
+                .collect(),
+            shortcut_error: None,
             root_table_image,
             root_table_texture: None,
             needs_font_reload: true,
+            key_press_times: HashMap::new(),
+            rejection: None,
+            engine_changed: false,
+            flash_at: None,
+            #[cfg(target_os = "windows")]
+            tray,
+            window_visible: true,
+            pending_minimize: config.start_minimized,
+            quitting: false,
+            candidate_bar: false,
+            candidate_detail: false,
+            #[cfg(target_os = "windows")]
+            global_hotkeys: Self::register_global_hotkeys(ctx, &config),
+            #[cfg(target_os = "windows")]
+            temp_global_hotkey_window: config.global_hotkey_window.clone(),
+            #[cfg(target_os = "windows")]
+            temp_global_hotkey_language: config.global_hotkey_language.clone(),
+            #[cfg(target_os = "windows")]
+            direct_output: DirectOutput::new(config.direct_output_method),
+            profiles: Config::list_profiles(),
+            temp_profile_name: String::new(),
+            profile_error: None,
+            temp_bundle_path: String::new(),
+            bundle_status: None,
+            temp_sync_folder: config.sync_folder.clone(),
+            sync_status: None,
+            backups: backup::list(),
+            selected_backup: 0,
+            backup_status: None,
+            output_file_status: None,
+            history: Self::load_history(&config),
+            show_history: false,
+            table_loading: None,
+            table_status: None,
+            pending_table: None,
+            missing_tables: None,
+            config_save_error: None,
+            dict_search_mode: SearchMode::CodePrefix,
+            dict_search_query: String::new(),
+            dict_results: None,
+            dict_page: 0,
+            user_dict: UserDict::load_default(),
+            temp_user_code: String::new(),
+            temp_user_text: String::new(),
+            user_dict_editing: None,
+            user_dict_status: None,
+            reverse_text: String::new(),
+            reverse_annotations: None,
+            reverse_status: None,
+            clipboard_watch: None,
+            annotations,
+            remote,
+            temp_annotation_file: config.annotation_file.clone(),
+            annotation_status: None,
         }
     }
 
-    /// 套用字型設定到 egui context
-    fn apply_font_settings(&mut self, ctx: &egui::Context) {
-        if self.needs_font_reload {
-            if let Some(font_data) = self.config.load_font_data() {
-                let mut fonts = egui::FontDefinitions::default();
-
-                // 加入自定義字型作為主要字型
-                fonts.font_data.insert(
-                    "custom_font".to_owned(),
-                    egui::FontData::from_owned(font_data),
-                );
-
-                // 設定字型家族
-                fonts
-                    .families
-                    .entry(egui::FontFamily::Proportional)
-                    .or_default()
-                    .insert(0, "custom_font".to_owned());
-
-                fonts
-                    .families
-                    .entry(egui::FontFamily::Monospace)
-                    .or_default()
-                    .push("custom_font".to_owned());
+    /// 將新上屏的文字送到外部視窗
+    /// 依上屏記錄轉送，手動編輯、開啟檔案或切換分頁換入的文字不會送出
+    #[cfg(target_os = "windows")]
+    fn forward_direct_output(&mut self, committed: &[String]) {
+        if self.config.direct_output && !committed.is_empty() {
+            self.direct_output.send(&committed.concat());
+        }
+    }
 
-                ctx.set_fonts(fonts);
+    /// 依設定註冊全域快速鍵
+    #[cfg(target_os = "windows")]
+    fn register_global_hotkeys(ctx: &egui::Context, config: &Config) -> Option<GlobalHotkeys> {
+        let bindings = [
+            (config.global_hotkey_window.as_str(), HotkeyCommand::ShowWindow),
+            (config.global_hotkey_language.as_str(), HotkeyCommand::ToggleLanguage),
+        ];
+        match GlobalHotkeys::new(ctx, &bindings) {
+            Ok(hotkeys) => Some(hotkeys),
+            Err(e) => {
+                tracing::warn!("無法註冊全域快速鍵：{}", e);
+                None
             }
-
-            // 設定預設字型大小
-            let mut style = (*ctx.style()).clone();
-            style.text_styles = [
-                (egui::TextStyle::Heading, egui::FontId::new(self.config.font_size * 1.5, egui::FontFamily::Proportional)),
-                (egui::TextStyle::Body, egui::FontId::new(self.config.font_size, egui::FontFamily::Proportional)),
-                (egui::TextStyle::Button, egui::FontId::new(self.config.font_size, egui::FontFamily::Proportional)),
-                (egui::TextStyle::Small, egui::FontId::new(self.config.font_size * 0.8, egui::FontFamily::Proportional)),
-            ].into();
-
-            ctx.set_style(style);
-            self.needs_font_reload = false;
         }
     }
 
-    /// 載入字根表圖片
-    fn load_root_table_image() -> Option<egui::ColorImage> {
-        let image_path = std::path::Path::new("table").join("行列字根表v2023.jpg");
-        if let Ok(image_data) = std::fs::read(&image_path) {
-            if let Ok(img) = image::load_from_memory(&image_data) {
-                let rgba = img.to_rgba8();
-                let size = [rgba.width() as usize, rgba.height() as usize];
-                let pixels = rgba.into_raw();
-                return Some(egui::ColorImage::from_rgba_unmultiplied(size, &pixels));
-            }
+    /// 是否有系統匣圖示可縮入
+    fn has_tray(&self) -> bool {
+        #[cfg(target_os = "windows")]
+        {
+            self.tray.is_some()
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            false
         }
-        eprintln!("無法載入字根表圖片：{:?}", image_path);
-        None
     }
 
-    /// 取得或建立字根表紋理
-    fn get_root_table_texture(&mut self, ctx: &egui::Context) -> Option<&egui::TextureHandle> {
-        if self.root_table_texture.is_none() {
-            if let Some(ref image) = self.root_table_image {
-                let texture = ctx.load_texture(
-                    "root_table",
-                    image.clone(),
-                    egui::TextureOptions::LINEAR,
-                );
-                self.root_table_texture = Some(texture);
-            }
+    /// 隱藏主視窗：有系統匣時縮到系統匣，否則最小化
+    fn hide_window(&mut self, ctx: &egui::Context) {
+        if self.has_tray() {
+            self.window_visible = false;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+        } else {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
         }
-        self.root_table_texture.as_ref()
     }
-}
 
-impl eframe::App for GuiApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // 套用字型設定
-        self.apply_font_settings(ctx);
+    /// 啟動時隱藏視窗，以及關閉視窗時改為縮到系統匣
+    fn handle_window_state(&mut self, ctx: &egui::Context) {
+        // eframe 在第一個畫面繪製後才顯示視窗，因此在之後的畫面才隱藏
+        if self.pending_minimize && ctx.cumulative_pass_nr() > 0 {
+            self.pending_minimize = false;
+            self.hide_window(ctx);
+        }
 
-        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
-            egui::menu::bar(ui, |ui| {
-                ui.menu_button("檔案", |ui| {
-                    if ui.button("重新載入詞庫").clicked() {
-                        // TODO: 實作重新載入
-                    }
-                    if ui.button("清除輸出").clicked() {
-                        self.engine.clear_output();
-                    }
-                    if ui.button("退出").clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                    }
-                });
+        let close_requested = ctx.input(|i| i.viewport().close_requested());
+        if close_requested && self.config.minimize_to_tray && !self.quitting && self.has_tray() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.hide_window(ctx);
+        }
+    }
 
-                ui.menu_button("檢視", |ui| {
-                    let main_label = if self.current_panel == Panel::Main {
-                        "• 主畫面"
-                    } else {
-                        "主畫面"
-                    };
-                    if ui.button(main_label).clicked() {
-                        self.current_panel = Panel::Main;
-                    }
+    /// 處理全域快速鍵命令
+    #[cfg(target_os = "windows")]
+    fn handle_global_hotkeys(&mut self, ctx: &egui::Context) {
+        let Some(ref hotkeys) = self.global_hotkeys else {
+            return;
+        };
+        let commands: Vec<HotkeyCommand> = std::iter::from_fn(|| hotkeys.poll()).collect();
 
-                    let settings_label = if self.current_panel == Panel::Settings {
-                        "• 設定"
+        for command in commands {
+            match command {
+                HotkeyCommand::ShowWindow => {
+                    let focused = ctx.input(|i| i.viewport().focused).unwrap_or(false);
+                    if self.candidate_bar {
+                        self.set_candidate_bar(ctx, false);
+                    } else if self.window_visible && focused {
+                        self.window_visible = false;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
                     } else {
-                        "設定"
-                    };
-                    if ui.button(settings_label).clicked() {
-                        self.current_panel = Panel::Settings;
-                    }
-                });
-
-                ui.menu_button("說明", |ui| {
-                    if ui.button("關於").clicked() {
-                        self.show_about = true;
+                        self.window_visible = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
                     }
-                });
-            });
-        });
-
-        // 根據當前面板顯示不同內容
-        match self.current_panel {
-            Panel::Main => self.show_main_panel(ctx),
-            Panel::Settings => self.show_settings_panel(ctx),
+                }
+                HotkeyCommand::ToggleLanguage => self.engine.toggle_english_mode(),
+            }
         }
+    }
 
-        // 關於對話框
-        if self.show_about {
-            egui::Window::new("關於行列 30 輸入法")
-                .collapsible(false)
-                .resizable(false)
-                .show(ctx, |ui| {
-                    ui.label("行列 30 輸入法");
-                    ui.label("Rust 實作版本");
-                    ui.separator();
-                    ui.label("操作說明：");
-                    ui.label("• 直接輸入英文字母作為行列碼");
-                    ui.label("• 按 ' 進入詞彙輸入模式");
-                    ui.label("• 數字鍵 1-9 選擇候選字");
-                    ui.label("• 空白鍵或 Enter 確認第一候選");
-                    ui.label("• Backspace 刪除");
-                    ui.label("• Esc 清空編輯區");
-                    ui.separator();
-                    if ui.button("關閉").clicked() {
-                        self.show_about = false;
-                    }
-                });
+    /// 切換浮動候選列模式
+    /// 開啟時隱藏主視窗，關閉時恢復
+    fn set_candidate_bar(&mut self, ctx: &egui::Context, enabled: bool) {
+        self.candidate_bar = enabled;
+        self.window_visible = !enabled;
+        ctx.send_viewport_cmd_to(egui::ViewportId::ROOT, egui::ViewportCommand::Visible(!enabled));
+        if !enabled {
+            ctx.send_viewport_cmd_to(egui::ViewportId::ROOT, egui::ViewportCommand::Focus);
         }
     }
-}
 
-impl GuiApp {
-    fn show_main_panel(&mut self, ctx: &egui::Context) {
-        // 根據字根表位置決定面板配置
-        if self.config.show_root_table && self.config.root_table_position != RootTablePosition::Down {
-            // 先顯示字根表（上方、左側、右側）
-            self.show_root_table_panel(ctx);
+    /// 候選列視窗大小：候選字型放大時加高視窗
+    fn candidate_bar_size(&self) -> [f32; 2] {
+        [
+            CANDIDATE_BAR_SIZE[0],
+            CANDIDATE_BAR_SIZE[1].max(self.config.candidate_font_size * 1.6)
+                + if self.config.show_candidate_codes { self.config.font_size } else { 0.0 },
+        ]
+    }
+
+    /// 一列顯示目前的碼與本頁候選，點選即上屏
+    fn show_candidate_row(&mut self, ui: &mut egui::Ui) {
+        let state = self.engine.state();
+        if state.english_mode {
+            ui.label("英");
         }
+        let code = ui.strong(&state.current_code);
+        set_accessible_name(ui, &code, code_accessible_name(&state.current_code), accesskit::Live::Polite);
+        ui.separator();
 
-        // 主要內容區
-        match self.config.root_table_position {
-            RootTablePosition::Left => {
-                egui::SidePanel::left("main_content")
-                    .default_width(600.0)
-                    .show(ctx, |ui| {
-                        self.show_main_content(ui, ctx);
-                    });
+        // 反白第一個候選（空白鍵上屏的字）
+        ui.visuals_mut().selection.bg_fill = candidate_highlight(ui, self.config.candidate_highlight_color);
+        let mut clicked = None;
+        for (i, cand) in self.engine.current_page_candidates().iter().enumerate() {
+            if let Some(marker) = self.engine.section_marker(i) {
+                ui.weak(marker);
             }
-            RootTablePosition::Right => {
-                egui::SidePanel::right("main_content")
-                    .default_width(600.0)
-                    .show(ctx, |ui| {
-                        self.show_main_content(ui, ctx);
-                    });
-            }
-            _ => {
-                // Up 或 Down 使用中央面板
-                egui::CentralPanel::default().show(ctx, |ui| {
-                    self.show_main_content(ui, ctx);
-                });
+            let label = format!("{}{}", i + 1, cand.text);
+            let text = candidate_text(ui, &label, cand, self.config.show_candidate_codes);
+            let response = ui
+                .selectable_label(i == 0, text)
+                .on_hover_ui(|ui| candidate_tooltip(ui, cand, &self.engine));
+            set_accessible_name(ui, &response, candidate_accessible_name(i, cand), accesskit::Live::Off);
+            if response.clicked() {
+                clicked = Some(i);
             }
         }
-
-        // 字根表在下方
-        if self.config.show_root_table && self.config.root_table_position == RootTablePosition::Down {
-            egui::TopBottomPanel::bottom("root_table_bottom")
-                .default_height(400.0)
-                .show(ctx, |ui| {
-                    self.show_root_table_content(ui, ctx);
-                });
+        if let Some(index) = clicked {
+            self.engine_changed |= self.engine.select_candidate(index);
         }
     }
 
-    fn show_main_content(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
-        ui.heading("行列 30 輸入法");
-        ui.separator();
-
-        // 複製需要使用的狀態資料
-        let raw_keys = self.engine.state().raw_keys.clone();
-        let current_code = self.engine.state().current_code.clone();
-        let output = self.engine.state().output.clone();
-        let hint = self.engine.state().get_hint();
-        let candidates: Vec<_> = self.engine.current_page_candidates().to_vec();
-        let has_candidates = !candidates.is_empty();
-
-        // 鍵盤輸入區
-        ui.group(|ui| {
-            ui.label("鍵盤輸入區：");
-            ui.horizontal(|ui| {
-                ui.label(&raw_keys);
-            });
-        });
+    /// 直接輸出時，在目標視窗的文字游標下方顯示不搶焦點的候選窗
+    #[cfg(target_os = "windows")]
+    fn show_caret_candidates(&mut self, ctx: &egui::Context) {
+        if self.candidate_bar || !self.config.direct_output || !self.config.caret_candidates {
+            return;
+        }
+        if self.engine.state().current_code.is_empty() {
+            return;
+        }
+        let Some((x, y)) = self.direct_output.caret_position() else {
+            return;
+        };
+        // 游標座標為實體像素，視窗位置以點（含介面縮放）表示
+        let scale = ctx.pixels_per_point();
+        let position = egui::pos2(x as f32 / scale, y as f32 / scale + CARET_POPUP_GAP);
+        let builder = egui::ViewportBuilder::default()
+            .with_title("行列 30 候選窗")
+            .with_position(position)
+            .with_inner_size(self.candidate_bar_size())
+            .with_decorations(false)
+            .with_taskbar(false)
+            .with_active(false)
+            .with_window_level(egui::WindowLevel::AlwaysOnTop);
 
-        // 編輯區
-        ui.group(|ui| {
-            ui.label("編輯區：");
-            if !current_code.is_empty() {
-                ui.horizontal(|ui| {
-                    ui.label(format!("碼：{}", current_code));
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("caret_candidates"),
+            builder,
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.horizontal(|ui| self.show_candidate_row(ui));
                 });
+            },
+        );
+    }
 
-                // 候選列表
-                if has_candidates {
-                    ui.separator();
-                    ui.label("候選字/詞：");
-                    ui.horizontal_wrapped(|ui| {
-                        for (i, cand) in candidates.iter().enumerate() {
-                            let cand_text = cand.text.clone();
-                            if ui.button(format!("[{}] {}", i + 1, cand_text)).clicked() {
-                                self.engine.select_candidate(i);
-                            }
-                        }
-                    });
+    /// 顯示浮動候選列：只有目前的碼與候選，永遠置頂且無邊框
+    fn show_candidate_bar(&mut self, ctx: &egui::Context) {
+        let builder = egui::ViewportBuilder::default()
+            .with_title("行列 30 候選列")
+            .with_inner_size(self.candidate_bar_size())
+            .with_decorations(false)
+            .with_window_level(egui::WindowLevel::AlwaysOnTop);
 
-                    // 分頁按鈕
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("candidate_bar"),
+            builder,
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
                     ui.horizontal(|ui| {
-                        if ui.button("◄ 上一頁").clicked() {
-                            self.engine.prev_page();
-                        }
-                        if ui.button("下一頁 ►").clicked() {
-                            self.engine.next_page();
+                        // 拖曳把手（無邊框視窗無法以標題列移動）
+                        let handle = ui.add(egui::Label::new("⠿").sense(egui::Sense::drag()));
+                        if handle.drag_started() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
                         }
+
+                        self.show_candidate_row(ui);
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("⤢").on_hover_text("回到完整視窗").clicked() {
+                                self.set_candidate_bar(ctx, false);
+                            }
+                        });
                     });
-                } else {
-                    ui.label("（無候選字）");
+                });
+
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.set_candidate_bar(ctx, false);
                 }
-            } else {
-                ui.label("（空）");
-            }
-        });
 
-        // 輸出區
-        ui.group(|ui| {
-            ui.label("輸出區：");
-            egui::ScrollArea::vertical()
-                .max_height(100.0)
-                .show(ui, |ui| {
-                    if output.is_empty() {
-                        ui.label("（空）");
+                self.handle_keyboard_input(ctx);
+                self.show_rejection_toast(ctx);
+            },
+        );
+    }
+
+    /// 處理目前視窗的鍵盤輸入
+    fn handle_keyboard_input(&mut self, ctx: &egui::Context) {
+        // 編輯輸出區或分頁名稱時，按鍵交給該欄位
+        if self.renaming_buffer.is_some() || ctx.memory(|m| m.has_focus(output_edit_id())) {
+            return;
+        }
+        let events = ctx.input(|i| i.events.clone());
+
+        // 觸發快速鍵的按鍵不再當作文字輸入
+        let mut skip_text = false;
+        for event in &events {
+            if let egui::Event::Key { key, pressed: true, modifiers, .. } = event {
+                if let Some(action) = self.lookup_shortcut(*key, modifiers) {
+                    if action == Action::ToggleCandidateBar {
+                        self.set_candidate_bar(ctx, !self.candidate_bar);
+                    } else if action == Action::CopyOutput {
+                        self.record_history();
+                        self.copy_to_clipboard(self.engine.get_output_text());
+                    } else if action == Action::ToggleCandidateDetail {
+                        self.candidate_detail = !self.candidate_detail;
+                    } else if action == Action::ToggleKeyHints {
+                        self.config.show_keyboard = !self.config.show_keyboard;
+                        self.save_config();
                     } else {
-                        ui.label(&output);
+                        if action == Action::Clear {
+                            self.record_history();
+                        }
+                        let result = shortcuts::dispatch(&mut self.engine, action);
+                        self.note_key_result(result);
+                        self.journal_stale |= matches!(action, Action::Clear | Action::Undo);
+                    }
+                    skip_text = !modifiers.ctrl;
+                    continue;
+                }
+                self.handle_egui_key(key);
+            }
+            if let egui::Event::Text(text) = event {
+                if std::mem::take(&mut skip_text) {
+                    continue;
+                }
+                for c in text.chars() {
+                    // 只處理可見字元
+                    if c.is_ascii() && !c.is_ascii_control() {
+                        self.record_key_press(c);
+                        let result = self.engine.handle_key(c);
+                        self.note_key_result(result);
                     }
+                }
+            }
+        }
+    }
+
+    /// 記錄被拒絕的按鍵，其他結果清除提示；引擎有變化時標記需要重繪
+    fn note_key_result(&mut self, result: KeyResult) {
+        match result {
+            KeyResult::Rejected(reason) => self.rejection = Some((reason, Instant::now())),
+            KeyResult::NoChange => return,
+            KeyResult::NeedUpdate | KeyResult::Committed => self.rejection = None,
+        }
+        self.engine_changed = true;
+    }
+
+    /// 只在需要時請求重繪：引擎狀態改變時立即重繪，按鍵高亮、日誌寫入、組字逾時與背景輪詢則排定計時器
+    /// （輸入事件與文字游標閃爍由 egui 自行重繪）
+    fn schedule_repaint(&mut self, ctx: &egui::Context) {
+        if std::mem::take(&mut self.engine_changed) {
+            ctx.request_repaint();
+        }
+        let now = Instant::now();
+        self.key_press_times.retain(|_, t| now.duration_since(*t) < KEY_HIGHLIGHT_DURATION);
+        if let Some(pressed) = self.key_press_times.values().min() {
+            ctx.request_repaint_after(KEY_HIGHLIGHT_DURATION.saturating_sub(now.duration_since(*pressed)));
+        }
+        if let Some(delay) = self.journal.as_ref().and_then(OutputJournal::sync_due_in) {
+            ctx.request_repaint_after(delay);
+        }
+        if let Some(deadline) = self.engine.composition_deadline() {
+            ctx.request_repaint_after(deadline.saturating_duration_since(now));
+        }
+        if self.engine.remote_pending() {
+            ctx.request_repaint_after(REMOTE_POLL_INTERVAL);
+        }
+        #[cfg(target_os = "windows")]
+        if self.tray.is_some() || self.global_hotkeys.is_some() || self.config.direct_output {
+            ctx.request_repaint_after(BACKGROUND_POLL_INTERVAL);
+        }
+    }
+
+    /// 按鍵被拒絕時，在視窗上方短暫顯示原因並逐漸淡出
+    fn show_rejection_toast(&self, ctx: &egui::Context) {
+        let Some((reason, at)) = self.rejection else {
+            return;
+        };
+        let elapsed = at.elapsed();
+        if elapsed >= REJECTION_TOAST_DURATION {
+            return;
+        }
+        let fade = 1.0 - elapsed.as_secs_f32() / REJECTION_TOAST_DURATION.as_secs_f32();
+        egui::Area::new(egui::Id::new("rejection_toast"))
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .order(egui::Order::Tooltip)
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.set_opacity(fade);
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    let label = ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", reason.display_name()));
+                    set_accessible_name(ui, &label, reason.display_name().to_string(), accesskit::Live::Assertive);
                 });
-        });
+            });
+        ctx.request_repaint_after(Duration::from_millis(50));
+    }
 
-        // 提示區
-        ui.group(|ui| {
-            ui.label("提示：");
-            ui.label(hint);
-        });
+    /// 依設定呈現引擎的提示回饋：播放系統提示音（Windows）、閃爍視窗邊框
+    fn show_feedback(&mut self, ctx: &egui::Context) {
+        let style = self.config.feedback;
+        if !self.engine.take_feedback().is_empty() && style != FeedbackStyle::Off {
+            #[cfg(target_os = "windows")]
+            if style.sound() {
+                play_feedback_sound();
+            }
+            if style.flash() {
+                self.flash_at = Some(Instant::now());
+            }
+        }
 
-        // 複製按鈕
-        ui.horizontal(|ui| {
-            if ui.button("📋 複製輸出到剪貼簿").clicked() {
-                let output_text = self.engine.get_output_text();
-                if let Some(mut clipboard) = arboard::Clipboard::new().ok() {
-                    let _ = clipboard.set_text(&output_text);
-                    self.clipboard_content = output_text;
+        let Some(at) = self.flash_at else {
+            return;
+        };
+        let elapsed = at.elapsed();
+        if elapsed >= FLASH_DURATION {
+            self.flash_at = None;
+            return;
+        }
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("feedback_flash")));
+        let color = ctx.style().visuals.warn_fg_color;
+        painter.rect_stroke(ctx.screen_rect().shrink(2.0), 0.0, egui::Stroke::new(4.0, color));
+        ctx.request_repaint_after(FLASH_DURATION - elapsed);
+    }
+
+    /// 處理系統匣選單命令，並同步選單勾選狀態
+    #[cfg(target_os = "windows")]
+    fn handle_tray_commands(&mut self, ctx: &egui::Context) {
+        let Some(ref tray) = self.tray else {
+            return;
+        };
+        let commands: Vec<TrayCommand> = std::iter::from_fn(|| tray.poll()).collect();
+
+        for command in commands {
+            match command {
+                TrayCommand::ToggleWindow if self.candidate_bar => self.set_candidate_bar(ctx, false),
+                TrayCommand::ToggleWindow => {
+                    self.window_visible = !self.window_visible;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Visible(self.window_visible));
+                    if self.window_visible {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                    }
+                }
+                TrayCommand::ToggleLanguage => self.engine.toggle_english_mode(),
+                TrayCommand::SwitchVariant(variant) => self.switch_variant(variant),
+                TrayCommand::Quit => {
+                    self.quitting = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
             }
+        }
 
-            if !self.clipboard_content.is_empty() {
-                ui.label(format!("已複製 {} 字元", self.clipboard_content.len()));
+        if let Some(ref tray) = self.tray {
+            tray.set_english_mode(self.engine.state().english_mode);
+            tray.set_variant(self.config.array_variant);
+        }
+    }
+
+    /// 切換輸入法變體並重新載入字表
+    /// 載入失敗時保留目前的字表
+    #[cfg(target_os = "windows")]
+    fn switch_variant(&mut self, variant: keymap::ArrayVariant) {
+        if variant == self.config.array_variant {
+            return;
+        }
+        let (phrase_file, char_file) = self.table_options.files(variant, self.use_big_char);
+        let dict = match tables::load_dictionary(&self.table_options, variant, self.use_big_char) {
+            Ok(dict) => dict,
+            Err(e) => {
+                tracing::warn!("無法切換至{}：{}", variant.display_name(), e);
+                return;
             }
+        };
+
+        self.config.array_variant = variant;
+        self.engine = new_engine(dict, self.transforms.engine_options(&self.config), self.annotations.clone(), self.remote.clone());
+        self.dictionary_changed();
+        self.phrase_file_path = phrase_file;
+        self.cin2_file_path = char_file;
+        self.save_config();
+        tracing::info!("已切換至{}", variant.display_name());
+    }
+
+    /// 可切換的字表：標準、大字集、表格目錄中使用者加入的字表與目前指定的字表
+    fn table_choices(&self) -> Vec<TableChoice> {
+        let mut choices = vec![TableChoice::Regular, TableChoice::Big];
+        choices.extend(tables::user_tables(&self.table_options.table_dir()).into_iter().map(TableChoice::User));
+        let current = TableChoice::from_options(&self.table_options, self.use_big_char);
+        if !choices.contains(&current) {
+            choices.push(current);
+        }
+        choices
+    }
+
+    /// 在背景載入字表，完成後才替換（輸出區保留）
+    fn switch_table(&mut self, choice: TableChoice) {
+        if self.table_loading.is_some() {
+            return;
+        }
+        let mut options = self.table_options.clone();
+        let use_big_char = choice.apply(&mut options);
+        let variant = self.config.array_variant;
+        let (phrase_file, char_file) = options.files(variant, use_big_char);
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = tables::load_dictionary(&options, variant, use_big_char);
+            let _ = sender.send(TableLoad {
+                options,
+                use_big_char,
+                phrase_file,
+                char_file,
+                result,
+            });
         });
+        self.table_loading = Some(receiver);
+        self.table_status = Some(format!("正在載入{}…", choice.display_name()));
+    }
 
-        // 檔案資訊
-        ui.separator();
-        ui.label(format!("詞庫：{}", self.phrase_file_path.display()));
-        ui.label(format!("字表：{}", self.cin2_file_path.display()));
+    /// 儲存設定檔；失敗時記錄並在狀態列顯示
+    fn save_config(&mut self) {
+        self.config_save_error = match self.config.save() {
+            Ok(()) => None,
+            Err(e) => {
+                tracing::warn!("無法儲存設定：{}", e);
+                Some(format!("設定儲存失敗：{}", e))
+            }
+        };
+    }
 
-        // 鍵盤輸入處理
-        ui.input(|i| {
-            for event in &i.events {
-                if let egui::Event::Key { key, pressed: true, .. } = event {
-                    self.handle_egui_key(key);
+    /// 找不到表格時的提示列：選擇表格目錄、下載或安裝內建表格後重新載入
+    fn show_missing_tables(&mut self, ctx: &egui::Context) {
+        let Some(error) = &self.missing_tables else {
+            return;
+        };
+        let mut retry_dir = None;
+        let mut retry = false;
+        egui::TopBottomPanel::top("missing_tables").show(ctx, |ui| {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}，目前無法輸入。", error));
+            ui.horizontal(|ui| {
+                if ui.button("選擇表格目錄…").clicked() {
+                    retry_dir = rfd::FileDialog::new().pick_folder();
                 }
-                if let egui::Event::Text(text) = event {
-                    for c in text.chars() {
-                        // 只處理可見字元
-                        if c.is_ascii() && !c.is_ascii_control() {
-                            self.engine.handle_key(c);
+                ui.hyperlink_to("下載表格", tables::TABLE_DOWNLOAD_URL);
+                if tables::has_embedded_tables() && ui.button("使用內建表格").clicked() {
+                    match tables::data_table_dir()
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "無法取得使用者資料目錄"))
+                        .and_then(|dir| tables::install_embedded_tables(&dir).map(|_| dir))
+                    {
+                        Ok(dir) => retry_dir = Some(dir),
+                        Err(e) => self.table_status = Some(format!("無法安裝內建表格：{}", e)),
+                    }
+                }
+                retry |= ui.add_enabled(self.table_loading.is_none(), egui::Button::new("重新載入")).clicked();
+                if let Some(status) = &self.table_status {
+                    ui.label(status);
+                }
+            });
+        });
+        if let Some(dir) = retry_dir {
+            self.config.table_dir = dir.display().to_string();
+            self.table_options.table_dir = self.config.table_dir.clone();
+            self.save_config();
+            retry = true;
+        }
+        if retry {
+            self.switch_table(TableChoice::from_options(&self.table_options, self.use_big_char));
+        }
+    }
+
+    /// 檢查背景載入是否完成，成功時套用並記住選擇
+    fn poll_table_load(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = &self.table_loading else {
+            return;
+        };
+        let load = match receiver.try_recv() {
+            Ok(load) => load,
+            Err(mpsc::TryRecvError::Empty) => {
+                ctx.request_repaint_after(Duration::from_millis(100));
+                return;
+            }
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.table_loading = None;
+                self.table_status = Some("字表載入中斷".to_string());
+                return;
+            }
+        };
+        self.table_loading = None;
+
+        let choice = TableChoice::from_options(&load.options, load.use_big_char);
+        match load.result {
+            Ok(dict) => {
+                self.engine.load_dict(dict);
+                self.dictionary_changed();
+                self.missing_tables = None;
+                self.phrase_file_path = load.phrase_file;
+                self.cin2_file_path = load.char_file;
+                self.config.big_char = load.use_big_char;
+                self.config.char_table = load.options.char_table.clone();
+                self.table_options = load.options;
+                self.use_big_char = load.use_big_char;
+                self.save_config();
+                self.table_status = Some(format!("已切換至{}", choice.display_name()));
+                tracing::info!(table = %choice.display_name(), "已切換字表");
+            }
+            Err(e @ Array30Error::TableNotFound { .. }) => {
+                self.table_status = Some(format!("{}，請先安裝此字表", e));
+            }
+            Err(e) => {
+                self.table_status = Some(format!("無法載入{}：{}", choice.display_name(), e));
+            }
+        }
+    }
+
+    /// 讀取拖放到視窗上的第一個檔案，等待使用者確認
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        if let Some(path) = dropped.into_iter().find_map(|file| file.path) {
+            let table = TableFile::load(&path);
+            self.pending_table = Some((path, table));
+        }
+
+        // 拖曳經過時提示可放開
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_overlay")));
+            let rect = ctx.screen_rect();
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "放開以載入字表（.cin2/.cin）或詞庫（.txt）",
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+
+    /// 拖放表格檔的確認對話框：顯示設定與項目數，選擇取代或合併
+    fn show_table_drop_dialog(&mut self, ctx: &egui::Context) {
+        let Some((path, table)) = &self.pending_table else {
+            return;
+        };
+        // Some(true) 合併、Some(false) 取代、None 取消
+        let mut decision = None;
+        let mut close = false;
+        egui::Window::new("載入表格檔")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("檔案：{}", path.display()));
+                match table {
+                    Ok(table) => {
+                        ui.label(format!("種類：{}", table.kind.display_name()));
+                        if let Some(name) = table.meta("cname").or_else(|| table.meta("ename")) {
+                            ui.label(format!("名稱：{}", name));
+                        }
+                        ui.label(format!("項目數：{}", table.entries));
+                        if !table.metadata.is_empty() {
+                            ui.collapsing("檔案設定", |ui| {
+                                egui::Grid::new("table_metadata").num_columns(2).show(ui, |ui| {
+                                    for (key, value) in &table.metadata {
+                                        ui.label(format!("%{}", key));
+                                        ui.label(value);
+                                        ui.end_row();
+                                    }
+                                });
+                            });
+                        }
+                        ui.separator();
+                        ui.label("只在本次執行有效，不會寫入設定");
+                        ui.horizontal(|ui| {
+                            if ui.button(format!("取代目前的{}", table.kind.display_name())).clicked() {
+                                decision = Some(false);
+                            }
+                            if ui.button("合併到目前的字典").clicked() {
+                                decision = Some(true);
+                            }
+                            if ui.button("取消").clicked() {
+                                close = true;
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("無法讀取：{}", e));
+                        if ui.button("關閉").clicked() {
+                            close = true;
                         }
                     }
                 }
+            });
+
+        if close {
+            self.pending_table = None;
+        } else if let Some(merge) = decision {
+            if let Some((path, Ok(table))) = self.pending_table.take() {
+                self.apply_table_file(path, table, merge);
+            }
+        }
+    }
+
+    /// 將拖放的表格檔載入或合併到使用中的字典（輸出區保留）
+    fn apply_table_file(&mut self, path: PathBuf, table: TableFile, merge: bool) {
+        let (kind, entries) = (table.kind, table.entries);
+        let mut dict = self.engine.dict().clone();
+        table.apply_to(&mut dict, merge);
+        self.user_dict.apply_to(&mut dict);
+        self.engine.load_dict(dict);
+        self.dictionary_changed();
+
+        let verb = if merge { "合併" } else { "載入" };
+        self.table_status = Some(format!("已{}{}：{}（{} 項）", verb, kind.display_name(), path.display(), entries));
+        tracing::info!(path = %path.display(), entries, merge, "已載入拖放的表格檔");
+        if !merge {
+            match kind {
+                TableKind::Chars => self.cin2_file_path = path,
+                TableKind::Phrases => self.phrase_file_path = Some(path),
+            }
+        }
+    }
+
+    /// 字表選單項目（選單與設定面板共用）
+    fn table_selector(&mut self, ui: &mut egui::Ui) {
+        let current = TableChoice::from_options(&self.table_options, self.use_big_char);
+        let mut selected = None;
+        ui.add_enabled_ui(self.table_loading.is_none(), |ui| {
+            for choice in self.table_choices() {
+                if ui.radio(choice == current, choice.display_name()).clicked() && choice != current {
+                    selected = Some(choice);
+                }
+            }
+        });
+        if let Some(choice) = selected {
+            self.switch_table(choice);
+        }
+    }
+
+    /// 切換設定檔組合，重新載入字表、引擎選項、字型與快速鍵
+    /// 載入失敗時保留目前的設定
+    fn switch_profile(&mut self, ctx: &egui::Context, name: &str) {
+        let config = match Config::load_profile(name) {
+            Ok(config) => config,
+            Err(e) => {
+                self.profile_error = Some(e.to_string());
+                return;
+            }
+        };
+        let table_options = TableOptions::from_config(&config);
+        let (phrase_file, char_file) = table_options.files(config.array_variant, config.big_char);
+        let dict = match tables::load_dictionary(&table_options, config.array_variant, config.big_char) {
+            Ok(dict) => dict,
+            Err(e) => {
+                self.profile_error = Some(format!("無法載入字表：{}", e));
+                return;
             }
+        };
+
+        self.remote = remote::from_url(&config.remote_dict_url);
+        self.engine = new_engine(dict, self.transforms.engine_options(&config), self.annotations.clone(), self.remote.clone());
+        self.dictionary_changed();
+        self.phrase_file_path = phrase_file;
+        self.cin2_file_path = char_file;
+        self.table_options = table_options;
+        self.use_big_char = config.big_char;
+        // 捨棄切換前開始載入的字表
+        self.table_loading = None;
+
+        // 同步設定面板中編輯中的欄位
+        self.selected_font_index = self
+            .available_fonts
+            .iter()
+            .position(|f| f.path == config.font_path)
+            .unwrap_or(0);
+        self.temp_font_size = config.font_size;
+        self.temp_candidate_font_size = config.candidate_font_size;
+        self.temp_output_font_size = config.output_font_size;
+        self.temp_ui_scale = config.ui_scale;
+        self.temp_theme = config.theme;
+        self.temp_accent_color = config.accent_color;
+        self.temp_candidate_highlight_color = config.candidate_highlight_color;
+        self.temp_window_width = config.window_width;
+        self.temp_window_height = config.window_height;
+        self.temp_root_table_image = config.root_table_image.clone();
+        self.temp_keymap_profile = config.keymap_profile;
+        self.temp_keymap_file = config.keymap_file.clone();
+        self.temp_annotation_file = config.annotation_file.clone();
+        self.temp_shortcuts = Action::all()
+            .iter()
+            .map(|a| config.shortcuts.chords_text(*a))
+            .collect();
+        self.shortcut_error = None;
+        #[cfg(target_os = "windows")]
+        {
+            self.temp_global_hotkey_window = config.global_hotkey_window.clone();
+            self.temp_global_hotkey_language = config.global_hotkey_language.clone();
+            self.direct_output.set_method(config.direct_output_method);
+        }
+
+        apply_theme(ctx, config.theme, config.accent_color);
+        self.config = config;
+        self.needs_font_reload = true;
+        self.reload_root_table_image();
+        self.reload_annotations();
+        #[cfg(target_os = "windows")]
+        {
+            drop(self.global_hotkeys.take());
+            self.global_hotkeys = Self::register_global_hotkeys(ctx, &self.config);
+        }
+
+        self.profiles = Config::list_profiles();
+        self.profile_error = None;
+        tracing::info!(profile = name, "已切換設定檔組合");
+    }
+
+    /// 匯出設定封包
+    fn export_settings(&mut self) {
+        let path = PathBuf::from(self.temp_bundle_path.trim());
+        let result = Bundle::export(&bundle::default_roots(), Config::config_file_path().as_deref())
+            .and_then(|bundle| bundle.save(&path).map(|()| bundle.files.len()));
+        self.bundle_status = Some(match result {
+            Ok(count) => format!("已匯出 {} 個檔案", count),
+            Err(e) => format!("匯出失敗：{}", e),
         });
+    }
 
-        // 請求自動重繪以處理鍵盤輸入
-        ctx.request_repaint();
+    /// 覆寫資料前先備份；失敗時回傳錯誤訊息，呼叫端不應繼續
+    fn backup_before(&mut self, reason: BackupReason) -> Result<(), String> {
+        let result = backup::create(reason).map_err(|e| format!("備份失敗，未執行操作：{}", e));
+        self.backups = backup::list();
+        self.selected_backup = 0;
+        result.map(|_| ())
     }
 
-    fn show_root_table_panel(&mut self, ctx: &egui::Context) {
-        match self.config.root_table_position {
-            RootTablePosition::Up => {
-                egui::TopBottomPanel::top("root_table_top")
-                    .default_height(400.0)
-                    .show(ctx, |ui| {
-                        self.show_root_table_content(ui, ctx);
-                    });
+    /// 從選取的備份還原，之後重新載入使用者資料與目前的設定檔組合
+    fn restore_backup(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.backups.get(self.selected_backup).map(|backup| backup.path.clone()) else {
+            return;
+        };
+        let result = backup::restore(&path);
+        self.backups = backup::list();
+        self.selected_backup = 0;
+        self.backup_status = Some(match result {
+            Ok(count) => {
+                self.stats = Stats::load_default();
+                self.user_dict = UserDict::load_default();
+                let profile = self.config.profile.clone();
+                self.switch_profile(ctx, &profile);
+                format!("已還原 {} 個檔案", count)
             }
-            RootTablePosition::Left => {
-                egui::SidePanel::left("root_table_left")
-                    .default_width(400.0)
-                    .show(ctx, |ui| {
-                        self.show_root_table_content(ui, ctx);
-                    });
+            Err(e) => format!("還原失敗：{}", e),
+        });
+    }
+
+    /// 匯入設定封包並重新載入目前的設定檔組合
+    fn import_settings(&mut self, ctx: &egui::Context) {
+        if let Err(e) = self.backup_before(BackupReason::ImportSettings) {
+            self.bundle_status = Some(e);
+            return;
+        }
+        let path = PathBuf::from(self.temp_bundle_path.trim());
+        let result = Bundle::load(&path)
+            .and_then(|bundle| bundle.import(&bundle::default_roots(), Config::config_file_path().as_deref()));
+        self.bundle_status = Some(match result {
+            Ok(count) => {
+                let profile = self.config.profile.clone();
+                self.switch_profile(ctx, &profile);
+                format!("已匯入 {} 個檔案", count)
             }
-            RootTablePosition::Right => {
-                egui::SidePanel::right("root_table_right")
-                    .default_width(400.0)
-                    .show(ctx, |ui| {
-                        self.show_root_table_content(ui, ctx);
-                    });
+            Err(e) => format!("匯入失敗：{}", e),
+        });
+    }
+
+    /// 與同步資料夾同步使用者資料，之後重新載入目前的設定檔組合
+    fn sync_user_data(&mut self, ctx: &egui::Context) {
+        self.config.sync_folder = self.temp_sync_folder.trim().to_string();
+        // 先寫入設定與統計，同步的是最新的內容
+        self.save_config();
+        self.save_stats();
+        if let Err(e) = self.backup_before(BackupReason::Sync) {
+            self.sync_status = Some(e);
+            return;
+        }
+        let result = sync::LocalPaths::default_paths()
+            .ok_or_else(|| std::io::Error::other("無法取得使用者資料目錄"))
+            .and_then(|local| sync::sync(Path::new(&self.config.sync_folder), &local));
+        self.sync_status = Some(match result {
+            Ok(outcomes) => {
+                self.stats = Stats::load_default();
+                self.user_dict = UserDict::load_default();
+                let profile = self.config.profile.clone();
+                self.switch_profile(ctx, &profile);
+                outcomes
+                    .iter()
+                    .map(|(item, outcome)| format!("{}{}", item.display_name(), outcome.display_name()))
+                    .collect::<Vec<_>>()
+                    .join("、")
             }
-            RootTablePosition::Down => {
-                // Down case is handled separately in show_main_panel
+            Err(e) => format!("同步失敗：{}", e),
+        });
+    }
+
+    /// 另存輸出區文字（UTF-8）
+    fn save_output_as(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("文字檔", &["txt"])
+            .set_file_name("輸出.txt")
+            .save_file()
+        else {
+            return;
+        };
+        let result = std::fs::write(&path, self.engine.get_output_text()).map(|_| format!("已儲存到 {}", path.display()));
+        self.journal_stale |= result.is_ok();
+        self.finish_output_file(&path, result);
+    }
+
+    /// 以檔案對話框選擇要開啟的輸出檔
+    fn open_output_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().add_filter("文字檔", &["txt"]).pick_file() {
+            self.open_output_file(&path);
+        }
+    }
+
+    /// 讀取 UTF-8 文字檔取代輸出區（不送到外部視窗）
+    fn open_output_file(&mut self, path: &Path) {
+        let result = std::fs::read_to_string(path).map(|text| {
+            self.record_history();
+            self.engine.clear_output();
+            self.engine.set_output(text);
+            self.journal_stale = true;
+            format!("已開啟 {}", path.display())
+        });
+        self.finish_output_file(path, result);
+    }
+
+    /// 啟用保存時讀取輸出歷史
+    fn load_history(config: &Config) -> History {
+        if !config.persist_history {
+            return History::default();
+        }
+        let Some(path) = History::file_path().filter(|p| p.exists()) else {
+            return History::default();
+        };
+        History::load(&path).unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), "無法讀取輸出歷史：{}", e);
+            History::default()
+        })
+    }
+
+    /// 取出引擎的選字記錄計入統計，並定期寫入統計檔
+    fn collect_stats(&mut self) {
+        for candidate in self.engine.take_commit_log() {
+            self.stats.record_commit(&candidate);
+            self.stats_dirty = true;
+        }
+        if self.stats_dirty && self.stats_saved_at.elapsed() >= STATS_SAVE_INTERVAL {
+            self.save_stats();
+        }
+    }
+
+    /// 超過字數上限而移出輸出區的較早內容記錄到輸出歷史（輸出日誌已有這些文字）
+    fn archive_output(&mut self) {
+        let archived = self.engine.take_archived_output();
+        if archived.is_empty() {
+            return;
+        }
+        for text in &archived {
+            self.history.push(text);
+        }
+        self.save_history();
+    }
+
+    /// 將新上屏的文字寫入輸出日誌；輸出區有其他變動時改以目前的輸出重設日誌
+    /// （等待決定是否恢復時保留上次留下的記錄，決定後再重設）
+    fn write_journal(&mut self, committed: &[String]) {
+        let Some(journal) = self.journal.as_mut() else {
+            return;
+        };
+        let result = if self.journal_stale && self.recovered_output.is_none() {
+            self.journal_stale = false;
+            journal.reset(&self.engine.get_output_text())
+        } else {
+            committed.iter().try_for_each(|text| journal.append(text))
+        }
+        .and_then(|()| journal.sync_if_due());
+        if let Err(e) = result {
+            tracing::warn!("無法寫入輸出日誌，停用日誌：{}", e);
+            self.journal = None;
+        }
+    }
+
+    /// 上次異常結束時詢問是否恢復未儲存的輸出
+    fn show_recovery_dialog(&mut self, ctx: &egui::Context) {
+        let Some(text) = &self.recovered_output else {
+            return;
+        };
+        // Some(true) 恢復、Some(false) 捨棄
+        let mut decision = None;
+        egui::Window::new("恢復輸出")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("上次未正常結束，有 {} 字上屏的文字未儲存", text.chars().count()));
+                let preview: String = text.chars().take(100).collect();
+                ui.weak(if preview.len() < text.len() { format!("{}…", preview) } else { preview });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("恢復").clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button("捨棄").clicked() {
+                        decision = Some(false);
+                    }
+                });
+            });
+
+        match decision {
+            Some(true) => {
+                let text = self.recovered_output.take().unwrap_or_default();
+                // 目前分頁已有文字時恢復到新分頁
+                if !self.engine.state().output.is_empty() {
+                    self.output_buffers.add(&mut self.engine);
+                }
+                self.engine.set_output(text);
+                self.buffer_switched();
+                self.journal_stale = true;
             }
+            Some(false) => {
+                self.recovered_output = None;
+                self.journal_stale = true;
+            }
+            None => {}
         }
     }
 
-    fn show_root_table_content(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
-        ui.group(|ui| {
-            ui.label("行列字根表 v2023");
-            let scale = self.config.root_table_scale;
+    fn save_stats(&mut self) {
+        self.stats_dirty = false;
+        self.stats_saved_at = Instant::now();
+        let Some(path) = Stats::file_path() else {
+            return;
+        };
+        if let Err(e) = self.stats.save(&path) {
+            tracing::warn!(path = %path.display(), "無法寫入使用統計：{}", e);
+        }
+    }
+
+    /// 啟用保存時寫入輸出歷史，停用時刪除記錄檔
+    fn save_history(&self) {
+        let Some(path) = History::file_path() else {
+            return;
+        };
+        let result = if self.config.persist_history {
+            self.history.save(&path)
+        } else if path.exists() {
+            std::fs::remove_file(&path)
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            tracing::warn!(path = %path.display(), "無法更新輸出歷史：{}", e);
+        }
+    }
+
+    /// 複製文字到剪貼簿
+    fn copy_to_clipboard(&mut self, text: String) {
+        if let Ok(mut clipboard) = arboard::Clipboard::new() {
+            let _ = clipboard.set_text(&text);
+            if let Some(watch) = self.clipboard_watch.as_mut() {
+                watch.ignore(&text);
+            }
+            self.clipboard_content = text;
+        }
+    }
+
+    /// 輸出分頁列：點選切換，雙擊重新命名，右鍵複製或關閉
+    fn show_buffer_tabs(&mut self, ui: &mut egui::Ui) {
+        let mut action = None;
+        ui.horizontal_wrapped(|ui| {
+            for i in 0..self.output_buffers.len() {
+                if let Some((_, name)) = self.renaming_buffer.as_mut().filter(|(index, _)| *index == i) {
+                    let edit = egui::TextEdit::singleline(name).desired_width(100.0).id(buffer_rename_id(i));
+                    if ui.add(edit).lost_focus() {
+                        action = Some(BufferAction::Rename(i));
+                    }
+                    continue;
+                }
+
+                let active = i == self.output_buffers.active();
+                let response = ui
+                    .selectable_label(active, self.output_buffers.name(i))
+                    .on_hover_text("雙擊重新命名，右鍵複製或關閉");
+                if response.double_clicked() {
+                    self.renaming_buffer = Some((i, self.output_buffers.name(i).to_string()));
+                    ui.memory_mut(|m| m.request_focus(buffer_rename_id(i)));
+                } else if response.clicked() {
+                    action = Some(BufferAction::Switch(i));
+                }
+                response.context_menu(|ui| {
+                    if ui.button("複製到剪貼簿").clicked() {
+                        ui.close_menu();
+                        action = Some(BufferAction::Copy(i));
+                    }
+                    if ui
+                        .add_enabled(self.output_buffers.len() > 1, egui::Button::new("關閉分頁"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        action = Some(BufferAction::Close(i));
+                    }
+                });
+            }
+            if ui.small_button("＋").on_hover_text("新增輸出分頁").clicked() {
+                action = Some(BufferAction::Add);
+            }
+        });
+
+        match action {
+            Some(BufferAction::Switch(i)) => {
+                let switched = self.output_buffers.switch(i, &mut self.engine);
+                if switched {
+                    self.buffer_switched();
+                }
+            }
+            Some(BufferAction::Add) => {
+                self.output_buffers.add(&mut self.engine);
+                self.buffer_switched();
+            }
+            Some(BufferAction::Close(i)) => {
+                // 關閉的內容記入歷史，可再從歷史取回
+                let text = self.output_buffers.text(i, &self.engine).to_string();
+                if self.output_buffers.close(i, &mut self.engine) {
+                    if self.history.push(&text) {
+                        self.save_history();
+                    }
+                    self.renaming_buffer = None;
+                    self.buffer_switched();
+                }
+            }
+            Some(BufferAction::Copy(i)) => {
+                let text = self.output_buffers.text(i, &self.engine).to_string();
+                self.copy_to_clipboard(text);
+            }
+            Some(BufferAction::Rename(i)) => {
+                if let Some((_, name)) = self.renaming_buffer.take() {
+                    self.output_buffers.rename(i, &name);
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// 切換輸出分頁後同步狀態；換入的文字不送到外部視窗
+    fn buffer_switched(&mut self) {
+        self.output_file_status = None;
+    }
+
+    /// 將目前的輸出區記入歷史（清除、複製或取代輸出區時呼叫）
+    fn record_history(&mut self) {
+        if self.history.push(&self.engine.get_output_text()) {
+            self.save_history();
+        }
+    }
+
+    /// 成功時記錄到最近檔案清單
+    fn finish_output_file(&mut self, path: &Path, result: io::Result<String>) {
+        self.output_file_status = Some(match result {
+            Ok(message) => {
+                self.config.add_recent_file(&path.to_string_lossy());
+                self.save_config();
+                message
+            }
+            Err(e) => {
+                tracing::warn!(path = %path.display(), "無法存取輸出檔：{}", e);
+                format!("無法存取 {}：{}", path.display(), e)
+            }
+        });
+    }
+
+    /// 套用介面縮放比例；自動模式下跟隨視窗所在螢幕的 DPI
+    fn apply_ui_scale(&self, ctx: &egui::Context) {
+        let native = ctx.native_pixels_per_point().unwrap_or(1.0);
+        // 螢幕大小以目前的縮放比例換算為點，還原為實體像素
+        let monitor_width = ctx.input(|i| i.viewport().monitor_size).map(|size| size.x * ctx.pixels_per_point());
+        let scale = self.config.effective_ui_scale(native, monitor_width);
+        if (ctx.pixels_per_point() - scale).abs() > 0.001 {
+            ctx.set_pixels_per_point(scale);
+        }
+    }
+
+    /// 套用字型設定到 egui context
+    fn apply_font_settings(&mut self, ctx: &egui::Context) {
+        if self.needs_font_reload {
+            match self.config.load_font_data(self.available_fonts.first()) {
+                Ok(font_data) => {
+                    let mut fonts = egui::FontDefinitions::default();
+
+                    // 加入自定義字型作為主要字型
+                    fonts.font_data.insert(
+                        "custom_font".to_owned(),
+                        egui::FontData::from_owned(font_data),
+                    );
+
+                    // 設定字型家族
+                    fonts
+                        .families
+                        .entry(egui::FontFamily::Proportional)
+                        .or_default()
+                        .insert(0, "custom_font".to_owned());
+
+                    fonts
+                        .families
+                        .entry(egui::FontFamily::Monospace)
+                        .or_default()
+                        .push("custom_font".to_owned());
+
+                    ctx.set_fonts(fonts);
+                }
+                // 沒有可用字型時使用 egui 內建字型
+                Err(_) if self.config.font_path.is_empty() && self.available_fonts.is_empty() => {}
+                Err(e) => tracing::warn!("{}", e),
+            }
+
+            // 設定各區域的字型大小：一般介面、候選字與輸出區分開設定
+            // 深色與淺色主題各有一組樣式，兩者都要設定
+            let text_styles: std::collections::BTreeMap<_, _> = [
+                (egui::TextStyle::Heading, egui::FontId::new(self.config.font_size * 1.5, egui::FontFamily::Proportional)),
+                (egui::TextStyle::Body, egui::FontId::new(self.config.font_size, egui::FontFamily::Proportional)),
+                (egui::TextStyle::Button, egui::FontId::new(self.config.font_size, egui::FontFamily::Proportional)),
+                (egui::TextStyle::Small, egui::FontId::new(self.config.font_size * 0.8, egui::FontFamily::Proportional)),
+                (candidate_style(), egui::FontId::new(self.config.candidate_font_size, egui::FontFamily::Proportional)),
+                (output_style(), egui::FontId::new(self.config.output_font_size, egui::FontFamily::Proportional)),
+            ].into();
+
+            ctx.all_styles_mut(|style| style.text_styles = text_styles.clone());
+            self.needs_font_reload = false;
+        }
+    }
+
+    /// 載入字根表圖片
+    /// 指定路徑無法載入時改用內建的行列字根表 v2023
+    fn load_root_table_image(custom_path: &str) -> Option<egui::ColorImage> {
+        if !custom_path.is_empty() {
+            let image = std::fs::read(custom_path)
+                .ok()
+                .and_then(|data| Self::decode_image(&data));
+            if image.is_some() {
+                return image;
+            }
+            tracing::warn!("無法載入字根表圖片：{}，改用內建圖片", custom_path);
+        }
+
+        let image = Self::decode_image(BUNDLED_ROOT_TABLE);
+        if image.is_none() {
+            tracing::warn!("無法解碼內建字根表圖片");
+        }
+        image
+    }
+
+    /// 將圖片檔內容解碼為 egui 影像
+    fn decode_image(data: &[u8]) -> Option<egui::ColorImage> {
+        let img = image::load_from_memory(data).ok()?;
+        let rgba = img.to_rgba8();
+        let size = [rgba.width() as usize, rgba.height() as usize];
+        let pixels = rgba.into_raw();
+        Some(egui::ColorImage::from_rgba_unmultiplied(size, &pixels))
+    }
+
+    /// 重新載入字根表圖片（路徑變更後）
+    fn reload_root_table_image(&mut self) {
+        self.root_table_image = Self::load_root_table_image(&self.config.root_table_image);
+        self.root_table_texture = None;
+    }
+
+    /// 取得或建立字根表紋理
+    fn get_root_table_texture(&mut self, ctx: &egui::Context) -> Option<&egui::TextureHandle> {
+        if self.root_table_texture.is_none() {
+            if let Some(ref image) = self.root_table_image {
+                let texture = ctx.load_texture(
+                    "root_table",
+                    image.clone(),
+                    egui::TextureOptions::LINEAR,
+                );
+                self.root_table_texture = Some(texture);
+            }
+        }
+        self.root_table_texture.as_ref()
+    }
+}
+
+impl eframe::App for GuiApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // 全域快速鍵、系統匣命令與直接輸出的目標視窗
+        #[cfg(target_os = "windows")]
+        {
+            self.handle_global_hotkeys(ctx);
+            self.handle_tray_commands(ctx);
+            if self.config.direct_output {
+                self.direct_output.track_foreground();
+            }
+        }
+        self.handle_window_state(ctx);
+
+        // 閒置過久的組字依設定清除或上屏
+        let result = self.engine.tick(Instant::now());
+        self.note_key_result(result);
+        // 背景查到的遠端字詞
+        self.engine_changed |= self.engine.poll_remote();
+
+        // 套用字型設定與縮放比例
+        self.apply_font_settings(ctx);
+        self.apply_ui_scale(ctx);
+
+        // 背景載入的字表與拖放的表格檔
+        self.poll_table_load(ctx);
+        self.handle_dropped_files(ctx);
+        self.poll_clipboard_watch(ctx);
+
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("檔案", |ui| {
+                    if ui.button("重新載入詞庫").clicked() {
+                        // TODO: 實作重新載入
+                    }
+                    if ui.button("開啟輸出...").clicked() {
+                        ui.close_menu();
+                        self.open_output_dialog();
+                    }
+                    if ui.button("另存輸出...").clicked() {
+                        ui.close_menu();
+                        self.save_output_as();
+                    }
+                    ui.add_enabled_ui(!self.config.recent_files.is_empty(), |ui| {
+                        ui.menu_button("最近的輸出", |ui| {
+                            let mut selected = None;
+                            for file in &self.config.recent_files {
+                                if ui.button(file).clicked() {
+                                    selected = Some(PathBuf::from(file));
+                                }
+                            }
+                            if let Some(path) = selected {
+                                ui.close_menu();
+                                self.open_output_file(&path);
+                            }
+                        });
+                    });
+                    ui.separator();
+                    if ui.button("清除輸出").clicked() {
+                        self.record_history();
+                        self.engine.clear_output();
+                        self.journal_stale = true;
+                    }
+                    if ui.button("退出").clicked() {
+                        self.quitting = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                });
+
+                ui.menu_button("字表", |ui| {
+                    self.table_selector(ui);
+                    if let Some(ref status) = self.table_status {
+                        ui.separator();
+                        ui.label(status);
+                    }
+                });
+
+                ui.menu_button("檢視", |ui| {
+                    let main_label = if self.current_panel == Panel::Main {
+                        "• 主畫面"
+                    } else {
+                        "主畫面"
+                    };
+                    if ui.button(main_label).clicked() {
+                        self.current_panel = Panel::Main;
+                    }
+
+                    let settings_label = if self.current_panel == Panel::Settings {
+                        "• 設定"
+                    } else {
+                        "設定"
+                    };
+                    if ui.button(settings_label).clicked() {
+                        self.current_panel = Panel::Settings;
+                    }
+
+                    let dictionary_label = if self.current_panel == Panel::Dictionary {
+                        "• 字典瀏覽"
+                    } else {
+                        "字典瀏覽"
+                    };
+                    if ui.button(dictionary_label).clicked() {
+                        self.current_panel = Panel::Dictionary;
+                    }
+
+                    let user_dict_label = if self.current_panel == Panel::UserDict {
+                        "• 自訂字詞"
+                    } else {
+                        "自訂字詞"
+                    };
+                    if ui.button(user_dict_label).clicked() {
+                        self.current_panel = Panel::UserDict;
+                    }
+
+                    let reverse_label = if self.current_panel == Panel::Reverse {
+                        "• 反查標註"
+                    } else {
+                        "反查標註"
+                    };
+                    if ui.button(reverse_label).clicked() {
+                        self.current_panel = Panel::Reverse;
+                    }
+
+                    let stats_label = if self.current_panel == Panel::Stats {
+                        "• 使用統計"
+                    } else {
+                        "使用統計"
+                    };
+                    if ui.button(stats_label).clicked() {
+                        self.current_panel = Panel::Stats;
+                    }
+
+                    let practice_label = if self.current_panel == Panel::Practice {
+                        "• 打字練習"
+                    } else {
+                        "打字練習"
+                    };
+                    if ui.button(practice_label).clicked() {
+                        self.current_panel = Panel::Practice;
+                    }
+                    ui.checkbox(&mut self.show_history, "輸出歷史");
+
+                    ui.separator();
+                    let mut keyboard_changed = ui.checkbox(&mut self.config.show_keyboard, "顯示螢幕鍵盤").changed();
+                    keyboard_changed |= ui
+                        .checkbox(&mut self.config.keyboard_next_key_hint, "標示可接續的鍵")
+                        .changed();
+                    if keyboard_changed {
+                        self.save_config();
+                    }
+
+                    ui.separator();
+                    if ui.button("浮動候選列模式").clicked() {
+                        self.set_candidate_bar(ctx, true);
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("說明", |ui| {
+                    if ui.button("關於").clicked() {
+                        self.show_about = true;
+                    }
+                });
+            });
+        });
+
+        self.show_missing_tables(ctx);
+
+        self.show_status_bar(ctx);
+
+        // 浮動候選列
+        if self.candidate_bar {
+            self.show_candidate_bar(ctx);
+        }
+
+        // 跟隨外部視窗文字游標的候選窗
+        #[cfg(target_os = "windows")]
+        self.show_caret_candidates(ctx);
+
+        // 輸出歷史側欄（需在中央面板之前）
+        if self.show_history {
+            self.show_history_panel(ctx);
+        }
+
+        // 根據當前面板顯示不同內容
+        match self.current_panel {
+            Panel::Main => self.show_main_panel(ctx),
+            Panel::Settings => self.show_settings_panel(ctx),
+            Panel::Dictionary => self.show_dictionary_panel(ctx),
+            Panel::UserDict => self.show_user_dict_panel(ctx),
+            Panel::Reverse => self.show_reverse_panel(ctx),
+            Panel::Stats => self.show_stats_panel(ctx),
+            Panel::Practice => self.show_practice_panel(ctx),
+        }
+
+        // 關於對話框
+        if self.show_about {
+            egui::Window::new("關於行列 30 輸入法")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("行列 30 輸入法");
+                    ui.label("Rust 實作版本");
+                    ui.separator();
+                    ui.label("操作說明：");
+                    ui.label("• 直接輸入英文字母作為行列碼");
+                    ui.label("• 按 ' 進入詞彙輸入模式");
+                    ui.label("• 數字鍵 1-9 選擇候選字");
+                    ui.label("• 空白鍵或 Enter 確認第一候選");
+                    ui.label("• Backspace 刪除");
+                    ui.label("• Esc 清空編輯區");
+                    ui.separator();
+                    if ui.button("關閉").clicked() {
+                        self.show_about = false;
+                    }
+                });
+        }
+
+        self.show_table_drop_dialog(ctx);
+        self.show_recovery_dialog(ctx);
+        self.show_rejection_toast(ctx);
+        self.collect_stats();
+        let committed = self.engine.take_committed();
+        self.write_journal(&committed);
+        self.archive_output();
+        self.show_feedback(ctx);
+
+        // 直接輸出新上屏的文字
+        #[cfg(target_os = "windows")]
+        self.forward_direct_output(&committed);
+
+        self.schedule_repaint(ctx);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.collect_stats();
+        if self.stats_dirty {
+            self.save_stats();
+        }
+        // 正常結束時刪除輸出日誌；尚未決定是否恢復的輸出留到下次啟動
+        let committed = self.engine.take_committed();
+        self.write_journal(&committed);
+        if let Some(journal) = self.journal.take().filter(|_| self.recovered_output.is_none()) {
+            if let Err(e) = journal.close() {
+                tracing::warn!("無法刪除輸出日誌：{}", e);
+            }
+        }
+    }
+}
+
+impl GuiApp {
+    fn show_main_panel(&mut self, ctx: &egui::Context) {
+        // 字根表面板需在中央面板之前加入
+        if self.config.show_root_table {
+            self.show_root_table_panel(ctx);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.show_main_content(ui, ctx);
+        });
+    }
+
+    fn show_main_content(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.heading("行列 30 輸入法");
+        ui.separator();
+
+        // 直接借用引擎的狀態繪製，不複製；點選候選與換頁在繪製完才套用
+        let engine = &self.engine;
+        let state = engine.state();
+        let mut clicked = None;
+        let mut next_page = None;
+
+        // 鍵盤輸入區
+        ui.group(|ui| {
+            ui.label("鍵盤輸入區：");
+            ui.horizontal(|ui| {
+                ui.label(state.recent_keys());
+            });
+        });
+
+        // 編輯區
+        ui.group(|ui| {
+            ui.label("編輯區：");
+            if !state.current_code.is_empty() {
+                ui.horizontal(|ui| {
+                    let code = ui.label(format!("碼：{}", state.current_code));
+                    set_accessible_name(ui, &code, code_accessible_name(&state.current_code), accesskit::Live::Polite);
+                });
+
+                // 候選列表
+                let candidates = engine.current_page_candidates();
+                if !candidates.is_empty() {
+                    ui.separator();
+                    ui.label("候選字/詞：");
+                    ui.horizontal_wrapped(|ui| {
+                        // 反白第一個候選（空白鍵上屏的字）
+                        ui.visuals_mut().selection.bg_fill = candidate_highlight(ui, self.config.candidate_highlight_color);
+                        for (i, cand) in candidates.iter().enumerate() {
+                            // 字與詞同時列出時，各段前加上標記
+                            if let Some(marker) = engine.section_marker(i) {
+                                ui.weak(marker);
+                            }
+                            let label = format!("[{}] {}", i + 1, cand.text);
+                            let text = candidate_text(ui, &label, cand, self.config.show_candidate_codes);
+                            let response = ui
+                                .add(egui::Button::new(text).selected(i == 0))
+                                .on_hover_ui(|ui| candidate_tooltip(ui, cand, engine));
+                            set_accessible_name(ui, &response, candidate_accessible_name(i, cand), accesskit::Live::Off);
+                            if response.clicked() {
+                                clicked = Some(i);
+                            }
+                        }
+                    });
+                    if self.candidate_detail {
+                        for (i, cand) in candidates.iter().enumerate() {
+                            ui.weak(format!("{} {}", i + 1, cand.detail()));
+                        }
+                    }
+                    if let Some(short) = engine.short_code() {
+                        ui.label(format!("空白鍵：{}", short.text));
+                    }
+
+                    // 分頁按鈕
+                    ui.horizontal(|ui| {
+                        if ui.button("◄ 上一頁").clicked() {
+                            next_page = Some(false);
+                        }
+                        if ui.button("下一頁 ►").clicked() {
+                            next_page = Some(true);
+                        }
+                    });
+                } else {
+                    ui.label("（無候選字）");
+                }
+
+                // 延伸碼預覽
+                let preview = engine.preview_candidates();
+                if !preview.is_empty() {
+                    ui.separator();
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(egui::RichText::new("預覽：").weak());
+                        for cand in preview {
+                            ui.label(egui::RichText::new(format!("{}({})", cand.text, cand.code)).weak());
+                        }
+                    });
+                }
+            } else {
+                ui.label("（空）");
+            }
+        });
+
+        if let Some(index) = clicked {
+            self.engine_changed |= self.engine.select_candidate(index);
+        }
+        match next_page {
+            Some(true) => self.engine_changed |= self.engine.next_page(),
+            Some(false) => self.engine_changed |= self.engine.prev_page(),
+            None => {}
+        }
+
+        // 輸出區：可直接點選、修改或刪除已上屏的文字
+        ui.group(|ui| {
+            ui.label("輸出區（可編輯，Esc 回到輸入）：");
+            self.show_buffer_tabs(ui);
+            // 編輯用的緩衝區沿用上一個畫面的記憶體
+            self.output_edit.clone_from(&self.engine.state().output);
+            egui::ScrollArea::vertical()
+                .max_height(100.0)
+                .show(ui, |ui| {
+                    let edit = egui::TextEdit::multiline(&mut self.output_edit)
+                        .font(output_style())
+                        .hint_text("（空）")
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY)
+                        .id(output_edit_id());
+                    if ui.add(edit).changed() {
+                        // 手動編輯的文字不送到外部視窗
+                        self.engine.set_output(self.output_edit.clone());
+                        self.journal_stale = true;
+                    }
+                });
+            if let Some(status) = &self.output_file_status {
+                ui.label(egui::RichText::new(status).weak());
+            }
+        });
+
+        // 提示區
+        ui.group(|ui| {
+            ui.label("提示：");
+            ui.label(self.engine.state().get_hint());
+        });
+
+        // 螢幕鍵盤
+        if self.config.show_keyboard {
+            self.show_keyboard(ui);
+        }
+
+        // 複製按鈕
+        ui.horizontal(|ui| {
+            if ui.button("📋 複製輸出到剪貼簿").clicked() {
+                self.record_history();
+                self.copy_to_clipboard(self.engine.get_output_text());
+            }
+
+            if !self.clipboard_content.is_empty() {
+                ui.label(format!("已複製 {} 字元", self.clipboard_content.len()));
+            }
+        });
+
+        // 檔案資訊
+        ui.separator();
+        match &self.phrase_file_path {
+            Some(path) => ui.label(format!("詞庫：{}", path.display())),
+            None => ui.label("詞庫：（無）"),
+        };
+        ui.label(format!("字表：{}", self.cin2_file_path.display()));
+
+        // 鍵盤輸入處理
+        self.handle_keyboard_input(ctx);
+    }
+
+    /// 解析快速鍵編輯欄位，全部有效時才套用
+    fn apply_shortcut_edits(&mut self) {
+        let mut shortcuts = self.config.shortcuts.clone();
+        for (action, text) in Action::all().iter().zip(&self.temp_shortcuts) {
+            match shortcuts::parse_chords(text) {
+                Some(chords) => shortcuts.set(*action, chords),
+                None => {
+                    self.shortcut_error = Some(format!("無法解析「{}」的快速鍵：{}", action.display_name(), text));
+                    return;
+                }
+            }
+        }
+        self.config.shortcuts = shortcuts;
+        self.shortcut_error = None;
+    }
+
+    /// 記錄按鍵以便在螢幕鍵盤上高亮
+    fn record_key_press(&mut self, c: char) {
+        let c = self.engine.options().keymap.remap(c);
+        if let Some(key) = Array30Key::from_char(c) {
+            self.key_press_times.insert(key, Instant::now());
+            self.stats.record_key(key);
+            self.stats_dirty = true;
+        }
+    }
+
+    /// 螢幕鍵盤：依行列鍵位繪製 30 個鍵，點擊即輸入
+    fn show_keyboard(&mut self, ui: &mut egui::Ui) {
+        let current_code = &self.engine.state().current_code;
+        let next_keys = if self.config.keyboard_next_key_hint && !current_code.is_empty() {
+            self.engine.dict().next_keys(current_code)
+        } else {
+            Default::default()
+        };
+        let now = Instant::now();
+        let mut clicked = None;
+
+        ui.group(|ui| {
+            ui.label("行列鍵盤：");
+            for (row, indent) in [(KeyRow::Top, 0.0), (KeyRow::Home, 16.0), (KeyRow::Bottom, 32.0)] {
+                let mut keys: Vec<_> = Array30Key::all().filter(|k| k.row() == row).collect();
+                keys.sort_by_key(|k| k.column());
+
+                ui.horizontal(|ui| {
+                    ui.add_space(indent);
+                    for key in keys {
+                        let pressed = self
+                            .key_press_times
+                            .get(&key)
+                            .is_some_and(|t| now.duration_since(*t) < KEY_HIGHLIGHT_DURATION);
+                        let fill = if pressed {
+                            ui.visuals().selection.bg_fill
+                        } else if next_keys.contains(&key.code_char()) {
+                            ui.visuals().warn_fg_color.gamma_multiply(0.3)
+                        } else {
+                            ui.visuals().widgets.inactive.weak_bg_fill
+                        };
+
+                        let text = format!("{}\n{}", key.code_char().to_ascii_uppercase(), key.label());
+                        let button = egui::Button::new(text)
+                            .fill(fill)
+                            .min_size(egui::vec2(48.0, 48.0));
+                        if ui.add(button).clicked() {
+                            clicked = Some(key);
+                        }
+                    }
+                });
+            }
+        });
+
+        if let Some(key) = clicked {
+            self.key_press_times.insert(key, now);
+            self.stats.record_key(key);
+            self.stats_dirty = true;
+            let result = self.engine.press_key(key);
+            self.note_key_result(result);
+        }
+    }
+
+    /// 底部狀態列：中英、組字模式、字表、候選頁碼與字典項目數
+    fn show_status_bar(&self, ctx: &egui::Context) {
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                // 中英與組字模式設為即時區域，切換時由螢幕閱讀器朗讀
+                let english = self.engine.is_english_mode();
+                let language = ui.label(if english { "英" } else { "中" });
+                let language_name = if english { "英文模式" } else { "中文模式" };
+                set_accessible_name(ui, &language, language_name.to_string(), accesskit::Live::Polite);
+                ui.separator();
+                let mode = self.engine.compose_mode();
+                let compose = ui.label(mode.display_name());
+                set_accessible_name(ui, &compose, format!("{}輸入", mode.display_name()), accesskit::Live::Polite);
+                ui.separator();
+                let choice = TableChoice::from_options(&self.table_options, self.use_big_char);
+                ui.label(format!("{}（{}）", self.engine.table_name(), choice.display_name()));
+                ui.separator();
+                match self.engine.page_indicator() {
+                    Some((page, count)) => ui.label(format!("第 {}/{} 頁", page, count)),
+                    None => ui.label("第 -/- 頁"),
+                };
+                ui.separator();
+                let (chars, phrases) = self.engine.entry_counts();
+                ui.label(format!("字 {}／詞 {}", chars, phrases));
+                if let Some(error) = &self.config_save_error {
+                    ui.separator();
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", error));
+                }
+            });
+        });
+    }
+
+    fn show_history_panel(&mut self, ctx: &egui::Context) {
+        egui::SidePanel::right("history_panel")
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.heading("輸出歷史");
+                ui.horizontal(|ui| {
+                    if ui
+                        .checkbox(&mut self.config.persist_history, "保存到磁碟")
+                        .on_hover_text("取消勾選會刪除已保存的記錄")
+                        .changed()
+                    {
+                        self.save_config();
+                        self.save_history();
+                    }
+                    if ui.button("全部清除").clicked() {
+                        self.history.clear();
+                        self.save_history();
+                    }
+                });
+                ui.separator();
+
+                if self.history.entries().is_empty() {
+                    ui.label("（尚無記錄）");
+                }
+
+                // 新的記錄在上
+                let mut action = None;
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (i, entry) in self.history.entries().iter().enumerate().rev() {
+                        ui.group(|ui| {
+                            ui.set_width(ui.available_width());
+                            ui.label(egui::RichText::new(history::format_age(entry.timestamp)).weak());
+                            ui.label(&entry.text);
+                            ui.horizontal(|ui| {
+                                if ui.small_button("複製").clicked() {
+                                    action = Some(HistoryAction::Copy(i));
+                                }
+                                if ui.small_button("插入").on_hover_text("附加到輸出區").clicked() {
+                                    action = Some(HistoryAction::Insert(i));
+                                }
+                                if ui.small_button("刪除").clicked() {
+                                    action = Some(HistoryAction::Remove(i));
+                                }
+                            });
+                        });
+                    }
+                });
+
+                match action {
+                    Some(HistoryAction::Copy(i)) => {
+                        let text = self.history.entries()[i].text.clone();
+                        self.copy_to_clipboard(text);
+                    }
+                    Some(HistoryAction::Insert(i)) => {
+                        let text = self.history.entries()[i].text.clone();
+                        self.engine.insert_output(&text);
+                    }
+                    Some(HistoryAction::Remove(i)) => {
+                        self.history.remove(i);
+                        self.save_history();
+                    }
+                    None => {}
+                }
+            });
+    }
+
+    /// 依位置放置字根表面板，大小跟著縮放後的圖片（最多佔視窗的六成）
+    fn show_root_table_panel(&mut self, ctx: &egui::Context) {
+        let image_size = self
+            .get_root_table_texture(ctx)
+            .map_or(egui::vec2(400.0, 200.0), |texture| texture.size_vec2())
+            * self.config.root_table_scale;
+        let screen = ctx.screen_rect().size() * ROOT_TABLE_MAX_FRACTION;
+        // 預留標題列、縮放說明與捲軸的空間
+        let width = (image_size.x + 32.0).min(screen.x);
+        let height = (image_size.y + 80.0).min(screen.y);
+
+        match self.config.root_table_position {
+            RootTablePosition::Up => {
+                egui::TopBottomPanel::top("root_table_top")
+                    .exact_height(height)
+                    .show(ctx, |ui| {
+                        self.show_root_table_content(ui, ctx);
+                    });
+            }
+            RootTablePosition::Down => {
+                egui::TopBottomPanel::bottom("root_table_bottom")
+                    .exact_height(height)
+                    .show(ctx, |ui| {
+                        self.show_root_table_content(ui, ctx);
+                    });
+            }
+            RootTablePosition::Left => {
+                egui::SidePanel::left("root_table_left")
+                    .exact_width(width)
+                    .show(ctx, |ui| {
+                        self.show_root_table_content(ui, ctx);
+                    });
+            }
+            RootTablePosition::Right => {
+                egui::SidePanel::right("root_table_right")
+                    .exact_width(width)
+                    .show(ctx, |ui| {
+                        self.show_root_table_content(ui, ctx);
+                    });
+            }
+        }
+    }
+
+    fn show_root_table_content(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                if self.config.root_table_image.is_empty() {
+                    ui.label("行列字根表 v2023");
+                } else {
+                    ui.label(format!("字根表：{}", self.config.root_table_image));
+                }
+
+                // 即時縮放與位置
+                let old_scale = self.config.root_table_scale;
+                if ui.small_button("－").clicked() {
+                    self.config.root_table_scale -= ROOT_TABLE_SCALE_STEP;
+                }
+                if ui.small_button("＋").clicked() {
+                    self.config.root_table_scale += ROOT_TABLE_SCALE_STEP;
+                }
+                if ui.small_button("100%").clicked() {
+                    self.config.root_table_scale = 1.0;
+                }
+                self.config.root_table_scale = self.config.root_table_scale.clamp(0.1, 2.0);
+                let mut changed = self.config.root_table_scale != old_scale;
+                changed |= root_table_position_selector(ui, "root_table_header_position", &mut self.config.root_table_position);
+                if changed {
+                    self.save_config();
+                }
+            });
+            let scale = self.config.root_table_scale;
+
+            if let Some(texture) = self.get_root_table_texture(ctx) {
+                let original_size = texture.size_vec2();
+                let scaled_size = original_size * scale;
+
+                // 可滾動的圖片區域
+                egui::ScrollArea::both()
+                    .max_width(f32::INFINITY)
+                    .max_height(f32::INFINITY)
+                    .show(ui, |ui| {
+                        ui.image((texture.id(), scaled_size));
+                    });
+
+                ui.label(format!("縮放：{:.0}%", scale * 100.0));
+            } else {
+                ui.label("（無法載入字根表圖片）");
+            }
+        });
+    }
+
+    /// 字典瀏覽：依碼的前綴、單字或詞彙片段搜尋，分頁列出結果
+    /// 按「練習」將該項的碼放入編輯區並回到主畫面
+    fn show_dictionary_panel(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("字典瀏覽");
+            ui.separator();
+
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.label("搜尋方式：");
+                for mode in SearchMode::all() {
+                    changed |= ui
+                        .selectable_value(&mut self.dict_search_mode, mode, mode.display_name())
+                        .changed();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("關鍵字：");
+                let hint = match self.dict_search_mode {
+                    SearchMode::CodePrefix => "例如 ab",
+                    SearchMode::Character => "例如 行列",
+                    SearchMode::Phrase => "例如 輸入",
+                };
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut self.dict_search_query).hint_text(hint))
+                    .changed();
+            });
+            if changed {
+                self.dict_results = None;
+                self.dict_page = 0;
+            }
+
+            let results = self.dict_results.get_or_insert_with(|| {
+                self.engine.dict().search(self.dict_search_mode, &self.dict_search_query)
+            });
+            let pages = results.len().div_ceil(DICT_BROWSER_PAGE_SIZE).max(1);
+            self.dict_page = self.dict_page.min(pages - 1);
+
+            ui.horizontal(|ui| {
+                ui.label(format!("共 {} 筆", results.len()));
+                if ui.add_enabled(self.dict_page > 0, egui::Button::new("上一頁")).clicked() {
+                    self.dict_page -= 1;
+                }
+                ui.label(format!("{}/{}", self.dict_page + 1, pages));
+                if ui.add_enabled(self.dict_page + 1 < pages, egui::Button::new("下一頁")).clicked() {
+                    self.dict_page += 1;
+                }
+            });
+            ui.separator();
+
+            let mut practice = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("dict_browser_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("碼");
+                        ui.strong("字根");
+                        ui.strong("字詞");
+                        ui.strong("種類");
+                        ui.label("");
+                        ui.end_row();
+
+                        let start = self.dict_page * DICT_BROWSER_PAGE_SIZE;
+                        for entry in results.iter().skip(start).take(DICT_BROWSER_PAGE_SIZE) {
+                            ui.monospace(&entry.code);
+                            ui.label(keymap::code_labels(&entry.code));
+                            ui.label(egui::RichText::new(&entry.word).text_style(candidate_style()));
+                            ui.label(match entry.kind {
+                                TableKind::Chars => "字",
+                                TableKind::Phrases => "詞",
+                            });
+                            if ui.small_button("練習").on_hover_text("將碼放入編輯區").clicked() {
+                                practice = Some(entry.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+            if let Some(entry) = practice {
+                self.engine.compose_code(&entry.code, entry.kind == TableKind::Phrases);
+                self.current_panel = Panel::Main;
+            }
+        });
+    }
+
+    /// 讀取讀音註解檔（未設定時為空）
+    fn load_annotations(path: &str) -> io::Result<Annotations> {
+        if path.is_empty() {
+            return Ok(Annotations::default());
+        }
+        Annotations::load(Path::new(path))
+    }
+
+    /// 依設定重新載入讀音註解檔
+    fn reload_annotations(&mut self) {
+        let annotations = match Self::load_annotations(&self.config.annotation_file) {
+            Ok(annotations) => {
+                self.annotation_status = (!annotations.is_empty()).then(|| "已載入讀音註解".to_string());
+                annotations
+            }
+            Err(e) => {
+                self.annotation_status = Some(format!("無法讀取讀音註解檔：{}", e));
+                Annotations::default()
+            }
+        };
+        self.annotations = Arc::new(annotations);
+        self.engine.set_annotations(self.annotations.clone());
+    }
+
+    /// 字典內容改變後，清除字典瀏覽與反查標註的結果
+    fn dictionary_changed(&mut self) {
+        self.dict_results = None;
+        self.reverse_annotations = None;
+    }
+
+    /// 監看剪貼簿：複製了新的中文文字時切到反查標註顯示
+    fn poll_clipboard_watch(&mut self, ctx: &egui::Context) {
+        let Some(watch) = self.clipboard_watch.as_mut() else {
+            return;
+        };
+        if let Some(text) = watch.poll() {
+            self.reverse_text = text;
+            self.reverse_annotations = None;
+            self.reverse_status = Some("已反查剪貼簿的文字".to_string());
+            self.current_panel = Panel::Reverse;
+        }
+        ctx.request_repaint_after(clipwatch::POLL_INTERVAL);
+    }
+
+    /// 以檔案對話框匯出反查標註
+    fn export_annotation(&mut self, text: &str) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("文字檔", &["txt"])
+            .set_file_name("反查標註.txt")
+            .save_file()
+        else {
+            return;
+        };
+        self.reverse_status = Some(match std::fs::write(&path, text) {
+            Ok(()) => format!("已匯出到 {}", path.display()),
+            Err(e) => format!("匯出失敗：{}", e),
+        });
+    }
+
+    /// 開始練習指定的課程
+    fn start_lesson(&mut self, index: usize) {
+        if let Some(lesson) = self.lessons.get(index) {
+            self.practice = Some(Session::new(index, &lesson.items));
+            self.practice_feedback = None;
+        }
+    }
+
+    /// 練習中的按鍵：組碼鍵累積，空白、Enter 或 ' 送出，Backspace 刪除，Esc 清除
+    fn handle_practice_keys(&mut self, ctx: &egui::Context) {
+        let Some(session) = self.practice.as_mut() else {
+            return;
+        };
+        if session.is_finished() {
+            return;
+        }
+        let mut submit = false;
+        for event in ctx.input(|i| i.events.clone()) {
+            match event {
+                egui::Event::Text(text) => {
+                    for c in text.chars() {
+                        let c = self.engine.options().keymap.remap(c);
+                        if c == ' ' || c == '\'' {
+                            submit = true;
+                        } else if self.config.array_variant.is_code_key(c) {
+                            session.push_key(c);
+                        }
+                    }
+                }
+                egui::Event::Key { key, pressed: true, .. } => match key {
+                    egui::Key::Enter => submit = true,
+                    egui::Key::Backspace => session.backspace(),
+                    egui::Key::Escape => session.typed.clear(),
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        if !submit {
+            return;
+        }
+        if let Some(correct) = session.submit(self.engine.dict()) {
+            self.practice_feedback = Some(correct);
+        }
+        if session.is_finished() {
+            self.finish_lesson();
+        }
+    }
+
+    /// 記錄完成的課程並寫入練習進度
+    fn finish_lesson(&mut self) {
+        let Some(session) = &self.practice else {
+            return;
+        };
+        let Some(lesson) = self.lessons.get(session.lesson) else {
+            return;
+        };
+        self.practice_progress.record(&lesson.id, session.result());
+        if let Some(path) = Progress::file_path() {
+            if let Err(e) = self.practice_progress.save(&path) {
+                tracing::warn!(path = %path.display(), "無法寫入練習進度：{}", e);
+            }
+        }
+    }
+
+    /// 打字練習：左側為課程與進度，右側顯示目前的字詞、輸入的碼與成績
+    fn show_practice_panel(&mut self, ctx: &egui::Context) {
+        self.handle_practice_keys(ctx);
+
+        let next_lesson = self.practice_progress.next_lesson(&self.lessons);
+        let mut start = None;
+        let mut finished = false;
+        let mut quit = false;
+        egui::SidePanel::left("practice_lessons")
+            .default_width(240.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("課程");
+                    if ui.small_button("重新載入").clicked() {
+                        self.lessons = practice::load_lessons(&self.table_options.table_dir());
+                        self.practice = None;
+                    }
+                });
+                ui.separator();
+                if self.lessons.is_empty() {
+                    ui.label(format!("（表格目錄的 {}/ 下沒有課程檔）", practice::LESSON_DIR));
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (i, lesson) in self.lessons.iter().enumerate() {
+                        let progress = self.practice_progress.get(&lesson.id);
+                        ui.horizontal(|ui| {
+                            ui.label(if progress.passed { "✔" } else { "　" });
+                            let title = format!("{}（{} 項）", lesson.title, lesson.items.len());
+                            let selected = self.practice.as_ref().is_some_and(|s| s.lesson == i);
+                            if ui.selectable_label(selected, title).clicked() {
+                                start = Some(i);
+                            }
+                            if next_lesson == Some(i) {
+                                ui.label(egui::RichText::new("建議").color(ui.visuals().warn_fg_color));
+                            }
+                        });
+                        if progress.completions > 0 {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "　完成 {} 次・最佳 {:.0}%・{:.1} 字/分",
+                                    progress.completions,
+                                    progress.best_accuracy * 100.0,
+                                    progress.best_chars_per_minute
+                                ))
+                                .weak(),
+                            );
+                        }
+                    }
+                });
+            });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("打字練習");
+            ui.separator();
+
+            let Some(session) = self.practice.as_mut() else {
+                ui.label("從左側選擇課程開始練習；輸入字詞的行列碼後按空白鍵或 Enter 送出。");
+                if let Some(i) = next_lesson {
+                    if ui.button(format!("開始「{}」", self.lessons[i].title)).clicked() {
+                        start = Some(i);
+                    }
+                }
+                return;
+            };
+
+            let (done, total) = session.progress();
+            let result = session.result();
+            ui.horizontal(|ui| {
+                ui.label(format!("進度：{}/{}", done, total));
+                ui.separator();
+                ui.label(format!("正確率：{:.0}%", result.accuracy * 100.0));
+                ui.separator();
+                ui.label(format!("速度：{:.1} 字/分", result.chars_per_minute));
+            });
+            ui.add(egui::ProgressBar::new(done as f32 / total.max(1) as f32));
+            ui.add_space(20.0);
+
+            match session.current() {
+                Some(word) => {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(word).size(self.config.candidate_font_size * 2.0).strong());
+                        ui.add_space(20.0);
+                        let upcoming = session.upcoming(5).join(" ");
+                        ui.label(egui::RichText::new(upcoming).size(self.config.candidate_font_size).weak());
+                    });
+                    ui.add_space(10.0);
+                    let typed = if session.typed.is_empty() { "＿".to_string() } else { session.typed.to_uppercase() };
+                    ui.label(egui::RichText::new(format!("碼：{}", typed)).monospace().size(20.0));
+                    match self.practice_feedback {
+                        Some(true) => {
+                            ui.colored_label(egui::Color32::from_rgb(0x2e, 0x9e, 0x4f), "✔ 正確");
+                        }
+                        Some(false) => {
+                            ui.colored_label(ui.visuals().error_fg_color, "✘ 錯誤");
+                        }
+                        None => {}
+                    }
+                    if let Some(hint) = &session.hint {
+                        let codes: Vec<_> = hint.iter().map(|c| c.to_uppercase()).collect();
+                        ui.label(format!("正確的碼：{}", codes.join("、")));
+                    }
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("略過").clicked() {
+                            session.skip();
+                            finished = session.is_finished();
+                        }
+                        if ui.button("結束練習").clicked() {
+                            quit = true;
+                        }
+                    });
+                    // 持續更新速度
+                    ctx.request_repaint_after(Duration::from_secs(1));
+                }
+                None => {
+                    let lesson = session.lesson;
+                    ui.heading(if result.passed() { "完成！" } else { "完成，再練一次吧" });
+                    ui.label(format!(
+                        "正確率 {:.0}%（及格 {:.0}%）・速度 {:.1} 字/分",
+                        result.accuracy * 100.0,
+                        practice::PASS_ACCURACY * 100.0,
+                        result.chars_per_minute
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("再練一次").clicked() {
+                            start = Some(lesson);
+                        }
+                        if let Some(next) = next_lesson.filter(|&next| next != lesson) {
+                            if ui.button(format!("下一課：{}", self.lessons[next].title)).clicked() {
+                                start = Some(next);
+                            }
+                        }
+                    });
+                }
+            }
+        });
+
+        if finished {
+            self.finish_lesson();
+        }
+        if quit {
+            self.practice = None;
+        }
+        if let Some(i) = start {
+            self.start_lesson(i);
+        }
+    }
+
+    /// 使用統計：行列鍵按鍵熱度圖與每日上屏字數、平均碼長
+    fn show_stats_panel(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("使用統計");
+            ui.separator();
+
+            let today = stats::today();
+            let days = self.stats.recent_days(today, STATS_CHART_DAYS);
+            let total = self.stats.total();
+            let today_stats = days.last().map(|(_, d)| *d).unwrap_or_default();
+            ui.horizontal(|ui| {
+                ui.label(format!("今日上屏：{} 字", today_stats.chars));
+                ui.separator();
+                ui.label(format!("累計上屏：{} 字", total.chars));
+                ui.separator();
+                ui.label(format!("平均碼長：{:.2}", total.average_code_len()));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui
+                        .add_enabled(!self.stats.is_empty(), egui::Button::new("清除統計"))
+                        .clicked()
+                    {
+                        self.save_stats();
+                        match self.backup_before(BackupReason::ClearStats) {
+                            Ok(()) => {
+                                self.stats.clear();
+                                self.save_stats();
+                            }
+                            Err(e) => self.backup_status = Some(e),
+                        }
+                    }
+                });
+            });
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.add_space(10.0);
+                ui.strong("按鍵熱度");
+                let max = self.stats.max_key_count();
+                let cold = ui.visuals().widgets.inactive.weak_bg_fill;
+                let hot = ui.visuals().warn_fg_color;
+                for (row, indent) in [(KeyRow::Top, 0.0), (KeyRow::Home, 16.0), (KeyRow::Bottom, 32.0)] {
+                    let mut keys: Vec<_> = Array30Key::all().filter(|k| k.row() == row).collect();
+                    keys.sort_by_key(|k| k.column());
+                    ui.horizontal(|ui| {
+                        ui.add_space(indent);
+                        for key in keys {
+                            let count = self.stats.key_count(key);
+                            let heat = if max == 0 { 0.0 } else { count as f32 / max as f32 };
+                            let (rect, response) = ui.allocate_exact_size(egui::vec2(48.0, 48.0), egui::Sense::hover());
+                            let painter = ui.painter();
+                            painter.rect_filled(rect, 4.0, cold.lerp_to_gamma(hot, heat));
+                            painter.text(
+                                rect.center(),
+                                egui::Align2::CENTER_CENTER,
+                                format!("{}\n{}", key.code_char().to_ascii_uppercase(), key.label()),
+                                egui::TextStyle::Body.resolve(ui.style()),
+                                ui.visuals().strong_text_color(),
+                            );
+                            response.on_hover_text(format!("{} 次", count));
+                        }
+                    });
+                }
+
+                let labels: Vec<_> = days.iter().map(|(day, _)| stats::day_label(*day)).collect();
+                ui.add_space(10.0);
+                ui.strong(format!("最近 {} 天上屏字數", STATS_CHART_DAYS));
+                let chars: Vec<_> = labels
+                    .iter()
+                    .zip(&days)
+                    .map(|(label, (_, d))| (label.clone(), d.chars as f32))
+                    .collect();
+                bar_chart(ui, &chars, |v| format!("{} 字", v));
+
+                ui.add_space(10.0);
+                ui.strong(format!("最近 {} 天平均碼長", STATS_CHART_DAYS));
+                let code_lens: Vec<_> = labels
+                    .iter()
+                    .zip(&days)
+                    .map(|(label, (_, d))| (label.clone(), d.average_code_len()))
+                    .collect();
+                bar_chart(ui, &code_lens, |v| format!("{:.2} 碼", v));
+            });
+        });
+    }
+
+    /// 反查標註：貼上任意中文，逐字標示行列碼的字根，可複製或匯出標註
+    fn show_reverse_panel(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("反查標註");
+            ui.separator();
+
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                if ui.button("貼上剪貼簿").clicked() {
+                    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+                        Ok(text) => {
+                            self.reverse_text = text;
+                            changed = true;
+                        }
+                        Err(e) => self.reverse_status = Some(format!("無法讀取剪貼簿：{}", e)),
+                    }
+                }
+                if ui.button("清除").clicked() {
+                    self.reverse_text.clear();
+                    changed = true;
+                }
+                let mut watching = self.clipboard_watch.is_some();
+                if ui
+                    .checkbox(&mut watching, "監看剪貼簿")
+                    .on_hover_text("在任何程式複製中文時自動反查")
+                    .changed()
+                {
+                    self.clipboard_watch = watching.then(ClipboardWatch::default);
+                }
+            });
+            changed |= ui
+                .add(
+                    egui::TextEdit::multiline(&mut self.reverse_text)
+                        .hint_text("貼上或輸入要反查的文字")
+                        .desired_rows(4)
+                        .desired_width(f32::INFINITY),
+                )
+                .changed();
+            if changed {
+                self.reverse_annotations = None;
+                self.reverse_status = None;
+            }
+
+            let annotations = self
+                .reverse_annotations
+                .take()
+                .unwrap_or_else(|| self.engine.dict().annotate(&self.reverse_text));
+            let missing = annotations
+                .iter()
+                .filter(|(c, codes)| codes.is_empty() && !c.is_whitespace() && !c.is_ascii())
+                .count();
+
+            ui.horizontal(|ui| {
+                let has_text = !annotations.is_empty();
+                if ui.add_enabled(has_text, egui::Button::new("複製標註")).clicked() {
+                    let text = annotation_text(&annotations);
+                    if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                        let _ = clipboard.set_text(&text);
+                        if let Some(watch) = self.clipboard_watch.as_mut() {
+                            watch.ignore(&text);
+                        }
+                        self.reverse_status = Some(format!("已複製 {} 字元", text.chars().count()));
+                    }
+                }
+                if ui.add_enabled(has_text, egui::Button::new("匯出標註...")).clicked() {
+                    self.export_annotation(&annotation_text(&annotations));
+                }
+                if missing > 0 {
+                    ui.weak(format!("{} 字查無字碼", missing));
+                }
+            });
+            if let Some(ref status) = self.reverse_status {
+                ui.label(status);
+            }
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for (c, codes) in &annotations {
+                        if *c == '\n' {
+                            ui.end_row();
+                            continue;
+                        }
+                        if c.is_whitespace() {
+                            continue;
+                        }
+                        ui.vertical(|ui| {
+                            ui.label(egui::RichText::new(c.to_string()).text_style(candidate_style()));
+                            match codes.first() {
+                                Some(code) => {
+                                    let response = ui.small(keymap::code_labels(code));
+                                    if codes.len() > 1 {
+                                        let all: Vec<String> = codes.iter().map(|code| keymap::code_labels(code)).collect();
+                                        response.on_hover_text(all.join("\n"));
+                                    }
+                                }
+                                None => {
+                                    ui.weak("—");
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+            self.reverse_annotations = Some(annotations);
+        });
+    }
+
+    /// 寫入自訂字詞檔
+    fn save_user_dict(&mut self) {
+        let Some(path) = UserDict::file_path() else {
+            return;
+        };
+        self.user_dict_status = Some(match self.user_dict.save(&path) {
+            Ok(()) => format!("已儲存至 {}", path.display()),
+            Err(e) => format!("儲存失敗：{}", e),
+        });
+    }
+
+    /// 以檔案對話框將自訂字詞匯出為其他行列輸入法的格式
+    fn export_user_dict(&mut self, format: ExportFormat) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.display_name(), &[format.extension()])
+            .set_file_name(format!("自訂字詞.{}", format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        self.user_dict_status = Some(match export::export(format, self.user_dict.entries(), &path) {
+            Ok(()) => format!("已匯出到 {}", path.display()),
+            Err(e) => format!("匯出失敗：{}", e),
+        });
+    }
+
+    /// 以檔案對話框匯入其他輸入法的詞彙，產生詞碼後加入自訂字詞並存檔
+    fn import_user_phrases(&mut self, format: ImportFormat) {
+        let Some(path) = rfd::FileDialog::new().pick_file() else {
+            return;
+        };
+        let content = match std::fs::read(&path).map_err(Array30Error::from).and_then(|bytes| import::decode_text(&bytes)) {
+            Ok(content) => content,
+            Err(e) => {
+                self.user_dict_status = Some(format!("無法讀取 {}：{}", path.display(), e));
+                return;
+            }
+        };
+        if let Err(e) = self.backup_before(BackupReason::ImportPhrases) {
+            self.user_dict_status = Some(e);
+            return;
+        }
+        let options = self.engine.options();
+        let (variant, max_code_len) = (options.variant, options.max_code_len());
+        let report = import::import_phrases(
+            &format.phrases(&content),
+            &mut self.user_dict,
+            self.engine.dict_mut(),
+            variant,
+            max_code_len,
+        );
+        if !report.added.is_empty() {
+            self.dictionary_changed();
+            self.save_user_dict();
+        }
+        self.user_dict_status = Some(format!("已加入 {} 詞，略過 {} 詞", report.added.len(), report.skipped.len()));
+    }
+
+    /// 清空自訂字詞的編輯欄位
+    fn reset_user_dict_form(&mut self) {
+        self.temp_user_code.clear();
+        self.temp_user_text.clear();
+        self.user_dict_editing = None;
+    }
+
+    /// 自訂字詞：新增、修改、刪除使用者的字與詞，變更立即生效並存檔
+    fn show_user_dict_panel(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("自訂字詞");
+            ui.separator();
+            ui.label("單字加入字表，多字加入詞庫（輸入碼後按 ' 選詞）");
+            ui.add_space(10.0);
+
+            egui::Grid::new("user_dict_form").num_columns(3).show(ui, |ui| {
+                ui.label("碼：");
+                ui.text_edit_singleline(&mut self.temp_user_code);
+                ui.weak(keymap::code_labels(self.temp_user_code.trim()));
+                ui.end_row();
+                ui.label("字詞：");
+                ui.text_edit_singleline(&mut self.temp_user_text);
+                // 詞依取碼規則產生詞碼，不必自己算
+                let text = self.temp_user_text.trim();
+                if text.chars().count() > 1 {
+                    match self.engine.dict().encode_phrase(text, self.config.array_variant) {
+                        Some(code) if code != self.temp_user_code.trim() => {
+                            if ui.button(format!("自動取碼：{}", code)).clicked() {
+                                self.temp_user_code = code;
+                            }
+                        }
+                        Some(_) => {}
+                        None => {
+                            ui.weak("有字查無字碼，無法自動取碼");
+                        }
+                    }
+                }
+                ui.end_row();
+            });
+
+            let entry = UserEntry::new(&self.temp_user_code, &self.temp_user_text);
+            let options = self.engine.options();
+            let check = self.user_dict.validate(
+                &entry,
+                self.user_dict_editing,
+                options.variant,
+                options.max_code_len(),
+                self.engine.dict(),
+            );
+
+            ui.horizontal(|ui| {
+                let label = if self.user_dict_editing.is_some() { "儲存修改" } else { "新增" };
+                if ui.add_enabled(check.is_ok(), egui::Button::new(label)).clicked() {
+                    match self.user_dict_editing {
+                        Some(index) => self.user_dict.replace(index, entry, self.engine.dict_mut()),
+                        None => self.user_dict.add(entry, self.engine.dict_mut()),
+                    }
+                    self.reset_user_dict_form();
+                    self.dictionary_changed();
+                    self.save_user_dict();
+                }
+                if self.user_dict_editing.is_some() && ui.button("取消").clicked() {
+                    self.reset_user_dict_form();
+                }
+            });
+            if let Err(message) = check {
+                if !self.temp_user_code.trim().is_empty() || !self.temp_user_text.trim().is_empty() {
+                    ui.colored_label(ui.visuals().error_fg_color, message);
+                }
+            }
+            if let Some(ref status) = self.user_dict_status {
+                ui.label(status);
+            }
+
+            ui.add_enabled_ui(!self.user_dict.entries().is_empty(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("匯出：");
+                    for format in ExportFormat::all() {
+                        if ui.button(format!("{}...", format.display_name())).clicked() {
+                            self.export_user_dict(format);
+                        }
+                    }
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.label("匯入詞彙：");
+                for format in ImportFormat::all() {
+                    if ui
+                        .button(format!("{}...", format.display_name()))
+                        .on_hover_text("依行列取碼規則自動產生詞碼")
+                        .clicked()
+                    {
+                        self.import_user_phrases(format);
+                    }
+                }
+            });
+
+            ui.separator();
+
+            let mut edit = None;
+            let mut remove = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if self.user_dict.entries().is_empty() {
+                    ui.weak("（尚無自訂字詞）");
+                    return;
+                }
+                egui::Grid::new("user_dict_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("碼");
+                        ui.strong("字根");
+                        ui.strong("字詞");
+                        ui.strong("種類");
+                        ui.label("");
+                        ui.end_row();
+
+                        for (i, entry) in self.user_dict.entries().iter().enumerate() {
+                            ui.monospace(&entry.code);
+                            ui.label(keymap::code_labels(&entry.code));
+                            ui.label(egui::RichText::new(&entry.text).text_style(candidate_style()));
+                            ui.label(match entry.kind() {
+                                TableKind::Chars => "字",
+                                TableKind::Phrases => "詞",
+                            });
+                            ui.horizontal(|ui| {
+                                if ui.small_button("編輯").clicked() {
+                                    edit = Some(i);
+                                }
+                                if ui.small_button("刪除").clicked() {
+                                    remove = Some(i);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+            if let Some(index) = edit {
+                let entry = &self.user_dict.entries()[index];
+                self.temp_user_code = entry.code.clone();
+                self.temp_user_text = entry.text.clone();
+                self.user_dict_editing = Some(index);
+            }
+            if let Some(index) = remove {
+                self.user_dict.remove(index, self.engine.dict_mut());
+                self.reset_user_dict_form();
+                self.dictionary_changed();
+                self.save_user_dict();
+            }
+        });
+    }
+
+    fn show_settings_panel(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("設定");
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                // 設定檔組合
+                ui.group(|ui| {
+                    ui.heading("設定檔組合");
+                    ui.separator();
+                    ui.label("每個組合有各自的字表、輸入行為與字型設定");
+
+                    let display = |name: &str| if name.is_empty() { "（預設）".to_string() } else { name.to_string() };
+                    let mut selected = None;
+                    egui::ComboBox::from_id_salt("profile_selector")
+                        .selected_text(display(&self.config.profile))
+                        .width(200.0)
+                        .show_ui(ui, |ui| {
+                            for name in std::iter::once(String::new()).chain(self.profiles.iter().cloned()) {
+                                if ui.selectable_label(name == self.config.profile, display(&name)).clicked() {
+                                    selected = Some(name);
+                                }
+                            }
+                        });
+
+                    ui.add_space(10.0);
+
+                    ui.label("以目前設定建立新組合：");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.temp_profile_name);
+                        if ui.button("建立並切換").clicked() {
+                            let name = self.temp_profile_name.trim().to_string();
+                            if !is_valid_profile_name(&name) {
+                                self.profile_error = Some(format!("無效的組合名稱：{}", name));
+                            } else if self.profiles.contains(&name) {
+                                self.profile_error = Some(format!("組合已存在：{}", name));
+                            } else {
+                                let mut config = self.config.clone();
+                                config.profile = name.clone();
+                                match config.save() {
+                                    Ok(()) => {
+                                        self.temp_profile_name.clear();
+                                        selected = Some(name);
+                                    }
+                                    Err(e) => self.profile_error = Some(format!("儲存失敗：{}", e)),
+                                }
+                            }
+                        }
+                    });
+
+                    if let Some(name) = selected {
+                        if name != self.config.profile {
+                            self.switch_profile(ctx, &name);
+                        }
+                    }
+
+                    if let Some(ref error) = self.profile_error {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+
+                    ui.add_space(10.0);
+
+                    ui.label("設定封包（含所有組合與使用者資料）：");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.temp_bundle_path);
+                        let has_path = !self.temp_bundle_path.trim().is_empty();
+                        if ui.add_enabled(has_path, egui::Button::new("匯出設定")).clicked() {
+                            self.export_settings();
+                        }
+                        if ui.add_enabled(has_path, egui::Button::new("匯入設定")).clicked() {
+                            self.import_settings(ctx);
+                        }
+                    });
+                    if let Some(ref status) = self.bundle_status {
+                        ui.label(status);
+                    }
+
+                    ui.add_space(10.0);
+
+                    ui.label("同步資料夾（自訂字詞、使用統計與設定檔）：");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.temp_sync_folder);
+                        let has_folder = !self.temp_sync_folder.trim().is_empty();
+                        if ui.add_enabled(has_folder, egui::Button::new("立即同步")).clicked() {
+                            self.sync_user_data(ctx);
+                        }
+                    });
+                    if let Some(ref status) = self.sync_status {
+                        ui.label(status);
+                    }
+
+                    ui.add_space(10.0);
+
+                    ui.label("自動備份（恢復預設、匯入與同步前建立）：");
+                    ui.horizontal(|ui| {
+                        let selected = self
+                            .backups
+                            .get(self.selected_backup)
+                            .map_or_else(|| "（尚無備份）".to_string(), Backup::label);
+                        egui::ComboBox::from_id_salt("restore_backup")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for (i, backup) in self.backups.iter().enumerate() {
+                                    ui.selectable_value(&mut self.selected_backup, i, backup.label());
+                                }
+                            });
+                        if ui
+                            .add_enabled(!self.backups.is_empty(), egui::Button::new("還原備份"))
+                            .on_hover_text("還原前會先備份目前的資料")
+                            .clicked()
+                        {
+                            self.restore_backup(ctx);
+                        }
+                    });
+                    if let Some(ref status) = self.backup_status {
+                        ui.label(status);
+                    }
+                });
+
+                ui.add_space(20.0);
+
+                // 字表切換
+                ui.group(|ui| {
+                    ui.heading("字表");
+                    ui.separator();
+                    ui.label("切換後會記住選擇，輸出區的文字會保留");
+                    ui.label(format!("表格目錄 cin2/ 中的其他 .cin2/.cin 檔也會列出（{}）", self.table_options.table_dir().display()));
 
-            if let Some(texture) = self.get_root_table_texture(ctx) {
-                let original_size = texture.size_vec2();
-                let scaled_size = original_size * scale;
+                    self.table_selector(ui);
 
-                // 可滾動的圖片區域
-                egui::ScrollArea::both()
-                    .max_width(f32::INFINITY)
-                    .max_height(f32::INFINITY)
-                    .show(ui, |ui| {
-                        ui.image((texture.id(), scaled_size));
-                    });
+                    let mut symbols = self.table_options.symbol_table;
+                    let label = "合併 emoji 與全形符號補充表（行列 30：W+數字後接英文縮寫，例如 w2c 為「」、w5sm 為 😀）";
+                    if ui.add_enabled(self.table_loading.is_none(), egui::Checkbox::new(&mut symbols, label)).changed() {
+                        self.config.symbol_table = symbols;
+                        self.table_options.symbol_table = symbols;
+                        self.save_config();
+                        self.switch_table(TableChoice::from_options(&self.table_options, self.use_big_char));
+                    }
+                    let mut fallback = self.table_options.big_fallback;
+                    if ui.checkbox(&mut fallback, "標準字表查無此碼時改查大字集（在背景載入，候選標示〔大字集〕）").changed() {
+                        self.config.big_fallback = fallback;
+                        self.table_options.big_fallback = fallback;
+                        self.save_config();
+                        let file = self.table_options.fallback_file(self.config.array_variant, self.use_big_char);
+                        self.engine.dict_mut().set_fallback(file);
+                    }
 
-                ui.label(format!("縮放：{:.0}%", scale * 100.0));
-            } else {
-                ui.label("（無法載入字根表圖片）");
-            }
-        });
-    }
+                    if self.table_loading.is_some() {
+                        ui.spinner();
+                    }
+                    if let Some(ref status) = self.table_status {
+                        ui.label(status);
+                    }
+                    ui.label(format!("字表：{}", self.cin2_file_path.display()));
+                });
 
-    fn show_settings_panel(&mut self, ctx: &egui::Context) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("設定");
-            ui.separator();
+                ui.add_space(20.0);
 
-            egui::ScrollArea::vertical().show(ui, |ui| {
                 // 字型設定
                 ui.group(|ui| {
                     ui.heading("字型設定");
@@ -484,7 +3542,7 @@ impl GuiApp {
                     ui.add_space(10.0);
 
                     // 字型大小滑桿
-                    ui.label("字型大小：");
+                    ui.label("介面字型大小：");
                     ui.horizontal(|ui| {
                         ui.add(egui::Slider::new(&mut self.temp_font_size, 10.0..=72.0)
                             .step_by(1.0)
@@ -492,12 +3550,43 @@ impl GuiApp {
                         ui.label(format!("{:.0} pt", self.temp_font_size));
                     });
 
+                    ui.label("候選字字型大小：");
+                    ui.add(egui::Slider::new(&mut self.temp_candidate_font_size, 10.0..=96.0)
+                        .step_by(1.0)
+                        .suffix(" pt"));
+
+                    ui.label("輸出區字型大小：");
+                    ui.add(egui::Slider::new(&mut self.temp_output_font_size, 10.0..=96.0)
+                        .step_by(1.0)
+                        .suffix(" pt"));
+
+                    // 介面縮放：自動時跟隨螢幕 DPI，手動時以滑桿指定
+                    ui.label("介面縮放：");
+                    ui.horizontal(|ui| {
+                        let mut auto = self.temp_ui_scale == 0.0;
+                        if ui.checkbox(&mut auto, "依螢幕 DPI 自動調整").changed() {
+                            self.temp_ui_scale = if auto {
+                                0.0
+                            } else {
+                                (ctx.pixels_per_point() * 20.0).round() / 20.0
+                            };
+                        }
+                        if !auto {
+                            ui.add(egui::Slider::new(&mut self.temp_ui_scale, MIN_UI_SCALE..=MAX_UI_SCALE)
+                                .step_by(0.05)
+                                .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)));
+                        }
+                    });
+
                     ui.add_space(10.0);
 
                     // 套用按鈕
                     ui.horizontal(|ui| {
                         if ui.button("套用字型設定").clicked() {
                             self.config.font_size = self.temp_font_size;
+                            self.config.candidate_font_size = self.temp_candidate_font_size;
+                            self.config.output_font_size = self.temp_output_font_size;
+                            self.config.ui_scale = self.temp_ui_scale;
                             self.needs_font_reload = true;
 
                             // 儲存設定
@@ -507,14 +3596,29 @@ impl GuiApp {
                         }
 
                         if ui.button("恢復預設").clicked() {
-                            self.config = Config::default();
+                            if let Err(e) = self.backup_before(BackupReason::ResetSettings) {
+                                self.backup_status = Some(e);
+                                return;
+                            }
+                            // 恢復預設時仍寫回目前的設定檔組合
+                            self.config = Config {
+                                profile: self.config.profile.clone(),
+                                ..Config::default()
+                            };
                             self.temp_font_size = self.config.font_size;
+                            self.temp_candidate_font_size = self.config.candidate_font_size;
+                            self.temp_output_font_size = self.config.output_font_size;
+                            self.temp_ui_scale = self.config.ui_scale;
+                            self.temp_theme = self.config.theme;
+                            self.temp_accent_color = self.config.accent_color;
+                            self.temp_candidate_highlight_color = self.config.candidate_highlight_color;
+                            apply_theme(ctx, self.config.theme, self.config.accent_color);
                             self.selected_font_index = self.available_fonts
                                 .iter()
                                 .position(|f| f.path == self.config.font_path)
                                 .unwrap_or(0);
                             self.needs_font_reload = true;
-                            let _ = self.config.save();
+                            self.save_config();
                         }
                     });
 
@@ -526,7 +3630,73 @@ impl GuiApp {
                             .map(|f| &f.name)
                             .unwrap_or(&"未知".to_string())
                     ));
-                    ui.label(format!("目前大小：{:.0} pt", self.config.font_size));
+                    ui.label(format!(
+                        "目前大小：介面 {:.0} pt／候選字 {:.0} pt／輸出區 {:.0} pt",
+                        self.config.font_size, self.config.candidate_font_size, self.config.output_font_size
+                    ));
+                    ui.label(format!(
+                        "目前縮放：{:.0}%{}",
+                        ctx.pixels_per_point() * 100.0,
+                        if self.config.ui_scale == 0.0 { "（自動）" } else { "" }
+                    ));
+                });
+
+                ui.add_space(20.0);
+
+                // 主題設定
+                ui.group(|ui| {
+                    ui.heading("外觀主題");
+                    ui.separator();
+                    ui.label("變更會立即預覽，按「套用主題」後儲存");
+
+                    let mut changed = false;
+                    ui.horizontal(|ui| {
+                        ui.label("主題：");
+                        egui::ComboBox::from_id_salt("theme_mode")
+                            .selected_text(self.temp_theme.display_name())
+                            .show_ui(ui, |ui| {
+                                for mode in ThemeMode::all() {
+                                    changed |= ui.selectable_value(&mut self.temp_theme, mode, mode.display_name()).changed();
+                                }
+                            });
+                    });
+
+                    let accent = ui.visuals().selection.bg_fill;
+                    changed |= color_setting(ui, "自訂強調色", &mut self.temp_accent_color, accent);
+                    changed |= color_setting(ui, "自訂候選反白色", &mut self.temp_candidate_highlight_color, accent);
+
+                    if changed {
+                        apply_theme(ctx, self.temp_theme, self.temp_accent_color);
+                    }
+
+                    // 預覽：以目前編輯中的顏色繪製候選列
+                    ui.add_space(10.0);
+                    ui.label("預覽：");
+                    ui.horizontal(|ui| {
+                        ui.visuals_mut().selection.bg_fill = candidate_highlight(ui, self.temp_candidate_highlight_color);
+                        for (i, text) in ["行", "列", "輸", "入"].iter().enumerate() {
+                            let _ = ui.selectable_label(i == 0, format!("{}{}", i + 1, text));
+                        }
+                    });
+
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("套用主題").clicked() {
+                            self.config.theme = self.temp_theme;
+                            self.config.accent_color = self.temp_accent_color;
+                            self.config.candidate_highlight_color = self.temp_candidate_highlight_color;
+                            if let Err(e) = self.config.save() {
+                                ui.label(format!("儲存失敗：{}", e));
+                            }
+                        }
+
+                        if ui.button("還原").clicked() {
+                            self.temp_theme = self.config.theme;
+                            self.temp_accent_color = self.config.accent_color;
+                            self.temp_candidate_highlight_color = self.config.candidate_highlight_color;
+                            apply_theme(ctx, self.config.theme, self.config.accent_color);
+                        }
+                    });
                 });
 
                 ui.add_space(20.0);
@@ -580,40 +3750,90 @@ impl GuiApp {
                     ui.heading("字根表設定");
                     ui.separator();
 
+                    ui.label("變更會立即套用");
+
+                    // 顯示、縮放與位置直接修改設定
+                    let mut changed = false;
+
                     ui.label("顯示字根表：");
-                    ui.checkbox(&mut self.temp_show_root_table, "啟用字根表顯示");
+                    changed |= ui.checkbox(&mut self.config.show_root_table, "啟用字根表顯示").changed();
 
                     ui.add_space(10.0);
 
                     ui.label("字根表縮放：");
-                    ui.horizontal(|ui| {
-                        ui.add(egui::Slider::new(&mut self.temp_root_table_scale, 0.1..=2.0)
+                    let slider = ui.add(
+                        egui::Slider::new(&mut self.config.root_table_scale, 0.1..=2.0)
                             .step_by(0.1)
-                            .suffix("x"));
-                        ui.label(format!("{:.1}x", self.temp_root_table_scale));
-                    });
+                            .suffix("x"),
+                    );
+                    // 拖曳時即時縮放，放開後才存檔
+                    changed |= (slider.changed() && !slider.dragged()) || slider.drag_stopped();
 
                     ui.add_space(10.0);
 
                     ui.label("字根表位置：");
-                    egui::ComboBox::from_id_salt("root_table_position")
-                        .selected_text(format!("{:?}", self.temp_root_table_position))
+                    changed |= root_table_position_selector(ui, "root_table_position", &mut self.config.root_table_position);
+
+                    ui.add_space(10.0);
+
+                    ui.label("字根表圖片（留空使用內建圖片）：");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.temp_root_table_image);
+                        if ui.button("載入圖片").clicked() {
+                            self.config.root_table_image = self.temp_root_table_image.trim().to_string();
+                            self.reload_root_table_image();
+                            changed = true;
+                        }
+                        if ui.button("使用內建").clicked() {
+                            self.temp_root_table_image.clear();
+                            self.config.root_table_image.clear();
+                            self.reload_root_table_image();
+                            changed = true;
+                        }
+                    });
+
+                    if changed {
+                        if let Err(e) = self.config.save() {
+                            ui.label(format!("儲存失敗：{}", e));
+                        }
+                    }
+
+                    if self.root_table_image.is_none() {
+                        ui.colored_label(ui.visuals().error_fg_color, "無法載入字根表圖片");
+                    }
+                });
+
+                ui.add_space(20.0);
+
+                // 鍵盤配置設定
+                ui.group(|ui| {
+                    ui.heading("鍵盤配置");
+                    ui.separator();
+
+                    ui.label("實體鍵盤配置：");
+                    egui::ComboBox::from_id_salt("keymap_profile")
+                        .selected_text(self.temp_keymap_profile.display_name())
                         .width(200.0)
                         .show_ui(ui, |ui| {
-                            ui.selectable_value(&mut self.temp_root_table_position, RootTablePosition::Up, "上");
-                            ui.selectable_value(&mut self.temp_root_table_position, RootTablePosition::Down, "下");
-                            ui.selectable_value(&mut self.temp_root_table_position, RootTablePosition::Left, "左");
-                            ui.selectable_value(&mut self.temp_root_table_position, RootTablePosition::Right, "右");
+                            for profile in KeymapProfile::all() {
+                                ui.selectable_value(&mut self.temp_keymap_profile, profile, profile.display_name());
+                            }
                         });
 
+                    if self.temp_keymap_profile == KeymapProfile::Custom {
+                        ui.add_space(10.0);
+                        ui.label("自訂對應檔：");
+                        ui.text_edit_singleline(&mut self.temp_keymap_file);
+                    }
+
                     ui.add_space(10.0);
 
                     // 套用按鈕
                     ui.horizontal(|ui| {
-                        if ui.button("套用字根表設定").clicked() {
-                            self.config.show_root_table = self.temp_show_root_table;
-                            self.config.root_table_scale = self.temp_root_table_scale;
-                            self.config.root_table_position = self.temp_root_table_position;
+                        if ui.button("套用鍵盤配置").clicked() {
+                            self.config.keymap_profile = self.temp_keymap_profile;
+                            self.config.keymap_file = self.temp_keymap_file.clone();
+                            self.engine.set_options(self.transforms.engine_options(&self.config));
 
                             // 儲存設定
                             if let Err(e) = self.config.save() {
@@ -624,13 +3844,343 @@ impl GuiApp {
 
                     // 顯示目前設定
                     ui.separator();
-                    ui.label(format!("顯示：{}", if self.config.show_root_table { "是" } else { "否" }));
-                    ui.label(format!("縮放：{:.1}x", self.config.root_table_scale));
-                    ui.label(format!("位置：{:?}", self.config.root_table_position));
+                    ui.label(format!("目前配置：{}", self.config.keymap_profile.display_name()));
+                });
+
+                ui.add_space(20.0);
+
+                // 輸入行為設定
+                ui.group(|ui| {
+                    ui.heading("輸入行為");
+                    ui.separator();
+
+                    ui.label("變更會立即套用");
+
+                    let mut changed = false;
+
+                    ui.label("每頁候選數：");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut self.config.page_size, 1..=MAX_PAGE_SIZE))
+                        .changed();
+
+                    ui.label("碼長上限（0 為預設）：");
+                    changed |= ui
+                        .add(egui::Slider::new(&mut self.config.max_code_len, 0..=8))
+                        .changed();
+
+                    ui.label("輸出區字數上限（0 為不限制，超過時較早的內容移到輸出歷史）：");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.config.max_output_chars).speed(1000))
+                        .changed();
+
+                    ui.add_space(10.0);
+
+                    egui::Grid::new("engine_grid").num_columns(2).show(ui, |ui| {
+                        ui.label("空白鍵：");
+                        egui::ComboBox::from_id_salt("space_behavior")
+                            .selected_text(self.config.space_behavior.display_name())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for behavior in SpaceBehavior::all() {
+                                    changed |= ui
+                                        .selectable_value(&mut self.config.space_behavior, behavior, behavior.display_name())
+                                        .changed();
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("標點符號：");
+                        egui::ComboBox::from_id_salt("punctuation_mode")
+                            .selected_text(self.config.punctuation_mode.display_name())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for mode in PunctuationMode::all() {
+                                    changed |= ui
+                                        .selectable_value(&mut self.config.punctuation_mode, mode, mode.display_name())
+                                        .changed();
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("候選排序：");
+                        egui::ComboBox::from_id_salt("candidate_order")
+                            .selected_text(self.config.candidate_order.display_name())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for order in CandidateOrder::all() {
+                                    changed |= ui
+                                        .selectable_value(&mut self.config.candidate_order, order, order.display_name())
+                                        .changed();
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("同碼字詞：");
+                        egui::ComboBox::from_id_salt("phrase_mix")
+                            .selected_text(self.config.phrase_mix.display_name())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for mix in PhraseMix::all() {
+                                    changed |= ui
+                                        .selectable_value(&mut self.config.phrase_mix, mix, mix.display_name())
+                                        .changed();
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("候選篩選：");
+                        egui::ComboBox::from_id_salt("candidate_filter")
+                            .selected_text(self.config.candidate_filter.display_name())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for filter in CandidateFilter::all() {
+                                    changed |= ui
+                                        .selectable_value(&mut self.config.candidate_filter, filter, filter.display_name())
+                                        .changed();
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("無候選時確認：");
+                        egui::ComboBox::from_id_salt("miss_policy")
+                            .selected_text(self.config.miss_policy.display_name())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for policy in MissPolicy::all() {
+                                    changed |= ui
+                                        .selectable_value(&mut self.config.miss_policy, policy, policy.display_name())
+                                        .changed();
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("提示回饋：");
+                        egui::ComboBox::from_id_salt("feedback")
+                            .selected_text(self.config.feedback.display_name())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for style in FeedbackStyle::all() {
+                                    changed |= ui
+                                        .selectable_value(&mut self.config.feedback, style, style.display_name())
+                                        .changed();
+                                }
+                            })
+                            .response
+                            .on_hover_text("按鍵被拒絕、翻到頁首頁尾或詞彙查無字詞時提醒（聲音只在 Windows 上支援）");
+                        ui.end_row();
+
+                        ui.label("組字閒置逾時：");
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add(egui::DragValue::new(&mut self.config.composition_timeout).range(0..=600).suffix(" 秒"))
+                                .on_hover_text("0 為不逾時")
+                                .changed();
+                            ui.add_enabled_ui(self.config.composition_timeout > 0, |ui| {
+                                egui::ComboBox::from_id_salt("timeout_action")
+                                    .selected_text(self.config.timeout_action.display_name())
+                                    .show_ui(ui, |ui| {
+                                        for action in TimeoutAction::all() {
+                                            changed |= ui
+                                                .selectable_value(&mut self.config.timeout_action, action, action.display_name())
+                                                .changed();
+                                        }
+                                    });
+                            });
+                        });
+                        ui.end_row();
+                    });
+
+                    ui.add_space(10.0);
+
+                    changed |= ui
+                        .checkbox(&mut self.config.auto_commit, "碼打滿且只有一個候選時自動上屏")
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut self.config.prefix_preview, "顯示延伸碼預覽")
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut self.config.official_keys, "官方行列按鍵相容（空白鍵上屏簡碼、數字鍵可接特別碼）")
+                        .on_hover_text("一、二碼時數字鍵選簡碼選單（字表需有 %quick 區塊）")
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut self.config.show_candidate_codes, "候選字下方顯示行列碼（字根標示）")
+                        .changed();
+
+                    ui.add_space(10.0);
+
+                    ui.label("讀音註解檔（候選提示中顯示拼音或注音與釋義，每行為 字<Tab>讀音，或 Unihan 的 kMandarin／kDefinition 資料，留空不載入）：");
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.temp_annotation_file);
+                        if ui.button("載入註解").clicked() {
+                            self.config.annotation_file = self.temp_annotation_file.trim().to_string();
+                            self.reload_annotations();
+                            changed = true;
+                        }
+                    });
+                    if let Some(ref status) = self.annotation_status {
+                        ui.label(status);
+                    }
+
+                    if changed {
+                        self.engine.set_options(self.transforms.engine_options(&self.config));
+                        if let Err(e) = self.config.save() {
+                            ui.label(format!("儲存失敗：{}", e));
+                        }
+                    }
+                });
+
+                ui.add_space(20.0);
+
+                // 快速鍵設定
+                ui.group(|ui| {
+                    ui.heading("快速鍵");
+                    ui.separator();
+                    ui.label("格式如 Ctrl+Z、Shift+PageUp，多個組合鍵以逗號分隔");
+
+                    egui::Grid::new("shortcut_grid").num_columns(2).show(ui, |ui| {
+                        for (action, text) in Action::all().iter().zip(self.temp_shortcuts.iter_mut()) {
+                            ui.label(action.display_name());
+                            ui.text_edit_singleline(text);
+                            ui.end_row();
+                        }
+                    });
+
+                    ui.add_space(10.0);
+
+                    // 套用按鈕
+                    ui.horizontal(|ui| {
+                        if ui.button("套用快速鍵").clicked() {
+                            self.apply_shortcut_edits();
+
+                            // 儲存設定
+                            if self.shortcut_error.is_none() {
+                                if let Err(e) = self.config.save() {
+                                    ui.label(format!("儲存失敗：{}", e));
+                                }
+                            }
+                        }
+
+                        if ui.button("恢復預設快速鍵").clicked() {
+                            self.config.shortcuts = Shortcuts::default();
+                            self.temp_shortcuts = Action::all()
+                                .iter()
+                                .map(|a| self.config.shortcuts.chords_text(*a))
+                                .collect();
+                            self.shortcut_error = None;
+                            self.save_config();
+                        }
+                    });
+
+                    if let Some(ref error) = self.shortcut_error {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+                });
+
+                ui.add_space(20.0);
+
+                // 視窗行為設定
+                ui.group(|ui| {
+                    ui.heading("視窗行為");
+                    ui.separator();
+
+                    let start_changed = ui
+                        .checkbox(&mut self.config.start_minimized, "啟動時隱藏視窗")
+                        .on_hover_text("有系統匣圖示時縮到系統匣，否則最小化；也可用 --minimized 參數")
+                        .changed();
+                    #[cfg(target_os = "windows")]
+                    let tray_changed = ui
+                        .checkbox(&mut self.config.minimize_to_tray, "關閉視窗時縮到系統匣")
+                        .on_hover_text("從系統匣或檔案選單的「退出」結束程式")
+                        .changed();
+                    #[cfg(not(target_os = "windows"))]
+                    let tray_changed = false;
+
+                    if start_changed || tray_changed {
+                        if let Err(e) = self.config.save() {
+                            ui.label(format!("儲存失敗：{}", e));
+                        }
+                    }
                 });
 
                 ui.add_space(20.0);
 
+                // 直接輸出與全域快速鍵設定（僅 Windows）
+                #[cfg(target_os = "windows")]
+                {
+                    // 直接輸出設定
+                    ui.group(|ui| {
+                        ui.heading("直接輸出");
+                        ui.separator();
+                        ui.label("上屏時同時把文字送到最後使用的其他程式視窗");
+
+                        let mut changed = ui.checkbox(&mut self.config.direct_output, "啟用直接輸出").changed();
+                        changed |= ui
+                            .checkbox(&mut self.config.caret_candidates, "候選窗跟隨文字游標")
+                            .on_hover_text("在目標程式的文字游標下方顯示候選字；程式未提供游標位置時不顯示")
+                            .changed();
+
+                        ui.add_space(10.0);
+
+                        ui.label("輸出方式：");
+                        egui::ComboBox::from_id_salt("direct_output_method")
+                            .selected_text(self.config.direct_output_method.display_name())
+                            .width(200.0)
+                            .show_ui(ui, |ui| {
+                                for method in DirectOutputMethod::all() {
+                                    changed |= ui
+                                        .selectable_value(&mut self.config.direct_output_method, method, method.display_name())
+                                        .changed();
+                                }
+                            });
+
+                        if changed {
+                            self.direct_output.set_method(self.config.direct_output_method);
+                            if let Err(e) = self.config.save() {
+                                ui.label(format!("儲存失敗：{}", e));
+                            }
+                        }
+
+                        if self.config.direct_output && !self.direct_output.has_target() {
+                            ui.label("（尚未偵測到目標視窗，請先切換到要輸入的程式）");
+                        }
+                    });
+
+                    ui.add_space(20.0);
+
+                    // 全域快速鍵設定
+                    ui.group(|ui| {
+                        ui.heading("全域快速鍵");
+                        ui.separator();
+                        ui.label("其他程式在前景時也有效，留空停用");
+
+                        egui::Grid::new("global_hotkey_grid").num_columns(2).show(ui, |ui| {
+                            ui.label(HotkeyCommand::ShowWindow.display_name());
+                            ui.text_edit_singleline(&mut self.temp_global_hotkey_window);
+                            ui.end_row();
+                            ui.label(HotkeyCommand::ToggleLanguage.display_name());
+                            ui.text_edit_singleline(&mut self.temp_global_hotkey_language);
+                            ui.end_row();
+                        });
+
+                        ui.add_space(10.0);
+
+                        if ui.button("套用全域快速鍵").clicked() {
+                            self.config.global_hotkey_window = self.temp_global_hotkey_window.trim().to_string();
+                            self.config.global_hotkey_language = self.temp_global_hotkey_language.trim().to_string();
+
+                            // 先釋放舊的註冊再重新註冊
+                            drop(self.global_hotkeys.take());
+                            self.global_hotkeys = Self::register_global_hotkeys(ctx, &self.config);
+
+                            if let Err(e) = self.config.save() {
+                                ui.label(format!("儲存失敗：{}", e));
+                            }
+                        }
+                    });
+
+                    ui.add_space(20.0);
+                }
+
                 // 其他設定
                 ui.group(|ui| {
                     ui.heading("資訊");
@@ -656,28 +4206,61 @@ impl GuiApp {
         });
     }
 
+    /// 查詢按鍵對應的快速鍵動作
+    fn lookup_shortcut(&self, key: egui::Key, modifiers: &egui::Modifiers) -> Option<Action> {
+        let chord = KeyChord {
+            ctrl: modifiers.ctrl,
+            alt: modifiers.alt,
+            shift: modifiers.shift,
+            key: ChordKey::from_name(key.name())?,
+        };
+        self.config.shortcuts.lookup(&chord)
+    }
+
     fn handle_egui_key(&mut self, key: &egui::Key) {
-        match key {
-            egui::Key::Backspace => {
-                self.engine.handle_key('\x08');
-            }
-            egui::Key::Enter => {
-                self.engine.handle_key('\n');
-            }
-            egui::Key::Escape => {
-                self.engine.handle_key('\x1b');
-            }
-            egui::Key::Space => {
-                self.engine.handle_key(' ');
-            }
-            _ => {}
-        }
+        let c = match key {
+            egui::Key::Backspace => '\x08',
+            egui::Key::Enter => '\n',
+            egui::Key::Escape => '\x1b',
+            egui::Key::Space => ' ',
+            _ => return,
+        };
+        let result = self.engine.handle_key(c);
+        self.note_key_result(result);
     }
 }
 
-pub fn run_gui(dict: Dictionary, phrase_file: PathBuf, cin2_file: PathBuf) -> eframe::Result<()> {
-    let config = Config::load();
+/// 命令列指定的啟動選項
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    /// 啟動時隱藏視窗
+    pub start_minimized: bool,
+    /// 啟動時監看剪貼簿並顯示反查標註
+    pub watch_clipboard: bool,
+    /// 找不到表格時的錯誤訊息（以空白字典啟動）
+    pub missing_tables: Option<String>,
+}
+
+/// 播放系統提示音
+#[cfg(target_os = "windows")]
+fn play_feedback_sound() {
+    use windows_sys::Win32::System::Diagnostics::Debug::MessageBeep;
+    use windows_sys::Win32::UI::WindowsAndMessaging::MB_OK;
+    // SAFETY: 只請系統播放提示音，不傳入任何指標
+    unsafe {
+        MessageBeep(MB_OK);
+    }
+}
 
+pub fn run_gui(
+    config: Config,
+    dict: Dictionary,
+    phrase_file: Option<PathBuf>,
+    cin2_file: PathBuf,
+    table_options: TableOptions,
+    use_big_char: bool,
+    launch: LaunchOptions,
+) -> eframe::Result<()> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([config.window_width, config.window_height])
@@ -689,127 +4272,23 @@ pub fn run_gui(dict: Dictionary, phrase_file: PathBuf, cin2_file: PathBuf) -> ef
     eframe::run_native(
         "行列 30 輸入法",
         options,
-        Box::new(|_cc| {
-            Ok(Box::new(GuiApp::new(dict, phrase_file, cin2_file)))
+        Box::new(move |cc| {
+            let mut app = GuiApp::new(
+                &cc.egui_ctx,
+                config,
+                dict,
+                phrase_file,
+                cin2_file,
+                table_options,
+                use_big_char,
+            );
+            app.pending_minimize |= launch.start_minimized;
+            app.missing_tables = launch.missing_tables;
+            if launch.watch_clipboard {
+                app.clipboard_watch = Some(ClipboardWatch::default());
+                app.current_panel = Panel::Reverse;
+            }
+            Ok(Box::new(app))
         }),
     )
 }
-
-/// 終端機模式（跨平台）
-pub fn run_console_mode(dict: Dictionary) -> io::Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    let mut engine = InputEngine::new(dict);
-    let mut should_quit = false;
-
-    while !should_quit {
-        // 繪製介面
-        execute!(stdout, Clear(ClearType::All), crossterm::cursor::MoveTo(0, 0))?;
-
-        let state = engine.state();
-        let candidates = engine.current_page_candidates();
-
-        // 第一行：標題
-        println!("行列 30 輸入法 - 終端機模式");
-        println!();
-
-        // 第二行：鍵盤輸入區
-        println!("鍵盤輸入：{}", state.raw_keys);
-        println!();
-
-        // 第三行：編輯區
-        if !state.current_code.is_empty() {
-            println!("編輯區：碼 = {}", state.current_code);
-            if !candidates.is_empty() {
-                print!("候選：");
-                for (i, cand) in candidates.iter().enumerate() {
-                    print!("[{}]{} ", i + 1, cand.text);
-                }
-                println!();
-            } else {
-                println!("編輯區：無候選字");
-            }
-        } else {
-            println!("編輯區：（空）");
-        }
-        println!();
-
-        // 第四行：輸出區
-        let output = if state.output.is_empty() {
-            "（空）"
-        } else {
-            &state.output
-        };
-        println!("輸出區：{}", output);
-        println!();
-
-        // 第五行：提示區
-        let hint = state.get_hint();
-        println!("提示：{}", hint);
-        println!();
-        println!("按 Ctrl+C 或 Ctrl+Q 離開");
-
-        stdout.flush()?;
-
-        // 讀取按鍵
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let event::Event::Key(key) = event::read()? {
-                should_quit = handle_console_key_event(&mut engine, key);
-            }
-        }
-    }
-
-    // 清理
-    disable_raw_mode()?;
-    execute!(stdout, Clear(ClearType::All))?;
-    println!("行列 30 輸入法 - 再見！");
-
-    Ok(())
-}
-
-fn handle_console_key_event(engine: &mut InputEngine, key: KeyEvent) -> bool {
-    match key.code {
-        // 退出
-        KeyCode::Char('c') | KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            return true;
-        }
-
-        // 退格
-        KeyCode::Backspace => {
-            engine.handle_key('\x08');
-        }
-
-        // Enter
-        KeyCode::Enter => {
-            engine.handle_key('\n');
-        }
-
-        // 空白
-        KeyCode::Char(' ') => {
-            engine.handle_key(' ');
-        }
-
-        // Esc
-        KeyCode::Esc => {
-            engine.handle_key('\x1b');
-        }
-
-        // 一般字元
-        KeyCode::Char(c) => {
-            engine.handle_key(c);
-        }
-
-        // 分頁
-        KeyCode::PageDown | KeyCode::Tab => {
-            engine.next_page();
-        }
-        KeyCode::PageUp => {
-            if key.modifiers.contains(KeyModifiers::SHIFT) {
-                engine.prev_page();
-            }
-        }
-
-        _ => {}
-    }
-    false
-}
@@ -0,0 +1,128 @@
+// Output history
+// 輸出歷史：保留每次清除或複製的輸出區內容，可重新複製或插入
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 保留的歷史筆數上限
+const MAX_ENTRIES: usize = 200;
+/// 歷史記錄檔名（位於使用者資料目錄）
+const HISTORY_FILENAME: &str = "history.json";
+
+/// 一段輸出
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// 記錄時間（Unix 秒數）
+    pub timestamp: u64,
+    pub text: String,
+}
+
+/// 輸出歷史（舊的在前）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+}
+
+/// 目前的 Unix 秒數
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl History {
+    /// 記錄一段輸出；空白或與上一筆相同時略過，回傳是否有新增
+    pub fn push(&mut self, text: &str) -> bool {
+        self.push_at(text, now())
+    }
+
+    fn push_at(&mut self, text: &str, timestamp: u64) -> bool {
+        if text.trim().is_empty() || self.entries.last().is_some_and(|e| e.text == text) {
+            return false;
+        }
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(HistoryEntry {
+            timestamp,
+            text: text.to_string(),
+        });
+        true
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// 歷史記錄檔路徑
+    pub fn file_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rustarray30").join(HISTORY_FILENAME))
+    }
+
+    /// 讀取歷史記錄檔
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// 寫入歷史記錄檔
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(path, content)
+    }
+}
+
+/// 顯示記錄距今多久（如「5 分鐘前」）
+pub fn format_age(timestamp: u64) -> String {
+    let secs = now().saturating_sub(timestamp);
+    match secs {
+        0..=59 => "剛剛".to_string(),
+        60..=3599 => format!("{} 分鐘前", secs / 60),
+        3600..=86399 => format!("{} 小時前", secs / 3600),
+        _ => format!("{} 天前", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_skips_empty_and_repeated() {
+        let mut history = History::default();
+        assert!(history.push_at("台灣", 10));
+        assert!(!history.push_at("台灣", 20));
+        assert!(!history.push_at("  \n", 30));
+        assert!(history.push_at("你好", 40));
+        assert_eq!(history.entries().len(), 2);
+        assert_eq!(history.entries()[1].timestamp, 40);
+
+        for i in 0..MAX_ENTRIES {
+            history.push_at(&i.to_string(), 50);
+        }
+        assert_eq!(history.entries().len(), MAX_ENTRIES);
+        assert_eq!(history.entries()[0].text, "0");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(now()), "剛剛");
+        assert_eq!(format_age(now() - 120), "2 分鐘前");
+        assert_eq!(format_age(now() - 3 * 86400), "3 天前");
+    }
+}
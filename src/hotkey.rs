@@ -0,0 +1,116 @@
+// System-wide hotkeys for the Windows GUI
+// 全域快速鍵（其他程式在前景時也有效）
+
+use eframe::egui;
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use std::sync::mpsc::{self, Receiver};
+
+/// 全域快速鍵命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyCommand {
+    /// 將主視窗帶到前景，已在前景時隱藏
+    ShowWindow,
+    /// 切換中文/英文模式
+    ToggleLanguage,
+}
+
+impl HotkeyCommand {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            HotkeyCommand::ShowWindow => "顯示/隱藏視窗",
+            HotkeyCommand::ToggleLanguage => "切換中／英文",
+        }
+    }
+}
+
+/// 解析全域快速鍵字串（如 "Ctrl+Shift+Space"）
+/// 空字串表示停用，回傳 Ok(None)
+pub fn parse_hotkey(s: &str) -> Result<Option<HotKey>, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    s.parse::<HotKey>().map(Some).map_err(|e| e.to_string())
+}
+
+/// 已註冊的全域快速鍵
+/// 釋放時會取消註冊
+pub struct GlobalHotkeys {
+    manager: GlobalHotKeyManager,
+    bindings: Vec<(HotKey, HotkeyCommand)>,
+    events: Receiver<GlobalHotKeyEvent>,
+}
+
+impl GlobalHotkeys {
+    /// 註冊全域快速鍵
+    /// 個別快速鍵無法解析或已被其他程式佔用時略過並記錄警告
+    pub fn new(ctx: &egui::Context, bindings: &[(&str, HotkeyCommand)]) -> Result<Self, Box<dyn std::error::Error>> {
+        let manager = GlobalHotKeyManager::new()?;
+
+        let mut registered = Vec::new();
+        for (text, command) in bindings {
+            let hotkey = match parse_hotkey(text) {
+                Ok(Some(hotkey)) => hotkey,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!("無法解析全域快速鍵「{}」：{}", text, e);
+                    continue;
+                }
+            };
+            match manager.register(hotkey) {
+                Ok(()) => registered.push((hotkey, *command)),
+                Err(e) => tracing::warn!("無法註冊全域快速鍵「{}」：{}", text, e),
+            }
+        }
+
+        // 轉送事件並喚醒 GUI（視窗隱藏時也要處理）
+        let (sender, events) = mpsc::channel();
+        let ctx = ctx.clone();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event| {
+            let _ = sender.send(event);
+            ctx.request_repaint();
+        }));
+
+        Ok(Self {
+            manager,
+            bindings: registered,
+            events,
+        })
+    }
+
+    /// 取出下一個待處理的命令（只處理按下事件）
+    pub fn poll(&self) -> Option<HotkeyCommand> {
+        while let Ok(event) = self.events.try_recv() {
+            if event.state != HotKeyState::Pressed {
+                continue;
+            }
+            if let Some((_, command)) = self.bindings.iter().find(|(hotkey, _)| hotkey.id() == event.id) {
+                return Some(*command);
+            }
+        }
+        None
+    }
+}
+
+impl Drop for GlobalHotkeys {
+    fn drop(&mut self) {
+        let hotkeys: Vec<HotKey> = self.bindings.iter().map(|(hotkey, _)| *hotkey).collect();
+        let _ = self.manager.unregister_all(&hotkeys);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hotkey() {
+        assert!(parse_hotkey("").unwrap().is_none());
+        assert_eq!(
+            parse_hotkey("Ctrl+Shift+Space").unwrap(),
+            "control+shift+space".parse::<HotKey>().ok()
+        );
+        assert!(parse_hotkey("Ctrl+Bogus").is_err());
+    }
+}
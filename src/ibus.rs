@@ -0,0 +1,388 @@
+// IBus engine front-end for Linux
+// IBus 輸入法引擎前端（以 D-Bus 與 ibus-daemon 溝通）
+
+use crate::dict::Dictionary;
+use crate::input_engine::{EngineOptions, InputEngine, KeyResult};
+use crate::keysym;
+use crate::shared::SharedEngine;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, Value};
+use zbus::{interface, ObjectServer};
+
+/// 向 ibus-daemon 註冊的 D-Bus 名稱（與元件描述檔一致）
+pub const BUS_NAME: &str = "org.freedesktop.IBus.Array30";
+/// 輸入法引擎名稱
+pub const ENGINE_NAME: &str = "array30";
+
+const FACTORY_PATH: &str = "/org/freedesktop/IBus/Factory";
+const ENGINE_PATH_PREFIX: &str = "/org/freedesktop/IBus/Engine/";
+
+/// 預編輯文字底線樣式
+const ATTR_TYPE_UNDERLINE: u32 = 1;
+const ATTR_UNDERLINE_SINGLE: u32 = 1;
+/// 預編輯文字在失去焦點時捨棄
+const PREEDIT_MODE_CLEAR: u32 = 0;
+/// 沒有組字時檢查逾時的間隔
+const TICK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 目前取得焦點的引擎物件路徑（組字逾時時向其送出訊號）
+type FocusedEngine = Arc<Mutex<Option<OwnedObjectPath>>>;
+
+/// 建立 IBusText 序列化值
+fn ibus_text(text: &str, underline: bool) -> Value<'static> {
+    let attrs: Vec<Value<'static>> = if underline && !text.is_empty() {
+        let len = text.chars().count() as u32;
+        vec![Value::from((
+            "IBusAttribute",
+            HashMap::<String, Value<'static>>::new(),
+            ATTR_TYPE_UNDERLINE,
+            ATTR_UNDERLINE_SINGLE,
+            0u32,
+            len,
+        ))]
+    } else {
+        Vec::new()
+    };
+    let attr_list = Value::from(("IBusAttrList", HashMap::<String, Value<'static>>::new(), attrs));
+    Value::from((
+        "IBusText",
+        HashMap::<String, Value<'static>>::new(),
+        text.to_string(),
+        attr_list,
+    ))
+}
+
+/// 建立 IBusLookupTable 序列化值
+fn ibus_lookup_table(candidates: &[String], page_size: u32) -> Value<'static> {
+    let texts: Vec<Value<'static>> = candidates.iter().map(|c| ibus_text(c, false)).collect();
+    let labels: Vec<Value<'static>> = (1..=candidates.len())
+        .map(|i| ibus_text(&format!("{}.", i % 10), false))
+        .collect();
+    Value::from((
+        "IBusLookupTable",
+        HashMap::<String, Value<'static>>::new(),
+        page_size,
+        0u32,
+        false,
+        false,
+        0i32,
+        texts,
+        labels,
+    ))
+}
+
+/// IBus 輸入法引擎物件
+/// 所有輸入情境共用同一個引擎，失去焦點時重設組字狀態
+struct IbusEngine {
+    engine: SharedEngine,
+    path: OwnedObjectPath,
+    focused: FocusedEngine,
+}
+
+impl IbusEngine {
+    /// 送出上屏文字並更新預編輯與候選表
+    async fn refresh(&self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        let (commit, code, candidates) = self.engine.with(|engine| {
+            let candidates: Vec<String> = engine
+                .current_page_candidates()
+                .iter()
+                .map(|c| c.text.clone())
+                .collect();
+            (engine.take_output(), engine.state().current_code.clone(), candidates)
+        });
+        let page_size = candidates.len().max(1) as u32;
+
+        if !commit.is_empty() {
+            Self::commit_text(emitter, ibus_text(&commit, false)).await?;
+        }
+
+        let cursor = code.chars().count() as u32;
+        Self::update_preedit_text(emitter, ibus_text(&code, true), cursor, !code.is_empty(), PREEDIT_MODE_CLEAR).await?;
+        Self::update_lookup_table(emitter, ibus_lookup_table(&candidates, page_size), !candidates.is_empty()).await
+    }
+
+    /// 捨棄組字中的碼
+    async fn clear(&self, emitter: &SignalEmitter<'_>) {
+        self.engine.handle_key('\x1b');
+        let _ = self.refresh(emitter).await;
+    }
+}
+
+#[interface(name = "org.freedesktop.IBus.Engine")]
+impl IbusEngine {
+    async fn process_key_event(
+        &self,
+        keyval: u32,
+        _keycode: u32,
+        state: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> bool {
+        let handled = self.engine.with(|engine| keysym::process_key(engine, keyval, state));
+        if handled {
+            let _ = self.refresh(&emitter).await;
+        }
+        handled
+    }
+
+    async fn candidate_clicked(
+        &self,
+        index: u32,
+        _button: u32,
+        _state: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) {
+        self.engine.with(|engine| engine.select_candidate(index as usize));
+        let _ = self.refresh(&emitter).await;
+    }
+
+    async fn page_up(&self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        self.engine.with(|engine| engine.prev_page());
+        let _ = self.refresh(&emitter).await;
+    }
+
+    async fn page_down(&self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        self.engine.with(|engine| engine.next_page());
+        let _ = self.refresh(&emitter).await;
+    }
+
+    async fn focus_out(&self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        {
+            let mut focused = self.focused.lock().unwrap_or_else(|e| e.into_inner());
+            if focused.as_ref() == Some(&self.path) {
+                *focused = None;
+            }
+        }
+        self.clear(&emitter).await;
+    }
+
+    async fn reset(&self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        self.clear(&emitter).await;
+    }
+
+    async fn disable(&self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) {
+        self.clear(&emitter).await;
+    }
+
+    fn focus_in(&self) {
+        *self.focused.lock().unwrap_or_else(|e| e.into_inner()) = Some(self.path.clone());
+    }
+
+    fn enable(&self) {}
+
+    fn set_capabilities(&self, _caps: u32) {}
+
+    fn set_cursor_location(&self, _x: i32, _y: i32, _w: i32, _h: i32) {}
+
+    fn property_activate(&self, _name: &str, _state: u32) {}
+
+    #[zbus(signal)]
+    async fn commit_text(emitter: &SignalEmitter<'_>, text: Value<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn update_preedit_text(
+        emitter: &SignalEmitter<'_>,
+        text: Value<'_>,
+        cursor_pos: u32,
+        visible: bool,
+        mode: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn update_lookup_table(emitter: &SignalEmitter<'_>, table: Value<'_>, visible: bool) -> zbus::Result<()>;
+}
+
+/// 引擎物件的 Service 介面（ibus-daemon 以此釋放引擎）
+struct IbusService {
+    path: OwnedObjectPath,
+}
+
+#[interface(name = "org.freedesktop.IBus.Service")]
+impl IbusService {
+    async fn destroy(&self, #[zbus(object_server)] server: &ObjectServer) {
+        let _ = server.remove::<IbusEngine, _>(&self.path).await;
+        let _ = server.remove::<IbusService, _>(&self.path).await;
+    }
+}
+
+/// 引擎工廠：ibus-daemon 啟用輸入法時呼叫 CreateEngine
+struct IbusFactory {
+    engine: SharedEngine,
+    focused: FocusedEngine,
+    next_id: AtomicU32,
+}
+
+#[interface(name = "org.freedesktop.IBus.Factory")]
+impl IbusFactory {
+    async fn create_engine(
+        &self,
+        name: &str,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> zbus::fdo::Result<OwnedObjectPath> {
+        if name != ENGINE_NAME {
+            return Err(zbus::fdo::Error::InvalidArgs(format!("未知的引擎：{}", name)));
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let path = ObjectPath::try_from(format!("{}{}", ENGINE_PATH_PREFIX, id))
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        let path = OwnedObjectPath::from(path);
+
+        let engine = IbusEngine {
+            engine: self.engine.clone(),
+            path: path.clone(),
+            focused: self.focused.clone(),
+        };
+        server.at(&path, engine).await?;
+        server.at(&path, IbusService { path: path.clone() }).await?;
+        Ok(path)
+    }
+}
+
+/// 解析 IBus 位址檔（內容為 KEY=VALUE 行）
+fn parse_address_file(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("IBUS_ADDRESS="))
+        .map(|addr| addr.trim().to_string())
+        .filter(|addr| !addr.is_empty())
+}
+
+/// 由 DISPLAY 取得顯示編號（":0.0" → "0"）
+fn display_number(display: &str) -> &str {
+    let display = display.rsplit(':').next().unwrap_or(display);
+    display.split('.').next().unwrap_or(display)
+}
+
+/// 找出 ibus-daemon 的 D-Bus 位址
+/// 依序使用 IBUS_ADDRESS 環境變數與 ~/.config/ibus/bus/ 下的位址檔
+fn ibus_address() -> Option<String> {
+    if let Ok(address) = std::env::var("IBUS_ADDRESS") {
+        if !address.is_empty() {
+            return Some(address);
+        }
+    }
+
+    let machine_id = std::fs::read_to_string("/etc/machine-id")
+        .or_else(|_| std::fs::read_to_string("/var/lib/dbus/machine-id"))
+        .ok()?;
+    let display = match std::env::var("WAYLAND_DISPLAY") {
+        Ok(wayland) if !wayland.is_empty() => wayland,
+        _ => display_number(&std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string())).to_string(),
+    };
+
+    let path: PathBuf = dirs::config_dir()?
+        .join("ibus")
+        .join("bus")
+        .join(format!("{}-unix-{}", machine_id.trim(), display));
+    parse_address_file(&std::fs::read_to_string(path).ok()?)
+}
+
+/// 產生 IBus 元件描述檔
+/// 安裝到 /usr/share/ibus/component/ 後執行 `ibus write-cache` 即可在 IBus 中選用
+pub fn component_xml(exec: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<component>
+  <name>{bus_name}</name>
+  <description>Array30 Input Method</description>
+  <exec>{exec} --ibus</exec>
+  <version>{version}</version>
+  <license>MIT</license>
+  <textdomain>rustarray30</textdomain>
+  <engines>
+    <engine>
+      <name>{engine}</name>
+      <language>zh_TW</language>
+      <license>MIT</license>
+      <longname>行列 30</longname>
+      <description>Array30 Input Method</description>
+      <layout>us</layout>
+      <symbol>行</symbol>
+      <rank>0</rank>
+    </engine>
+  </engines>
+</component>
+"#,
+        bus_name = BUS_NAME,
+        exec = exec,
+        version = env!("CARGO_PKG_VERSION"),
+        engine = ENGINE_NAME,
+    )
+}
+
+/// 以 IBus 引擎模式執行（由 ibus-daemon 啟動）
+pub fn run_ibus(dict: Dictionary, options: EngineOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let address = ibus_address().ok_or("找不到 IBus 位址，請確認 ibus-daemon 正在執行")?;
+
+    let engine = SharedEngine::from_engine(InputEngine::with_options(dict, options));
+    let focused = FocusedEngine::default();
+    let factory = IbusFactory {
+        engine: engine.clone(),
+        focused: focused.clone(),
+        next_id: AtomicU32::new(1),
+    };
+
+    let connection = zbus::blocking::connection::Builder::address(address.as_str())?
+        .serve_at(FACTORY_PATH, factory)?
+        .name(BUS_NAME)?
+        .build()?;
+
+    tracing::info!("IBus 引擎已啟動：{}", BUS_NAME);
+
+    // D-Bus 訊息由 zbus 的背景執行緒處理，這裡只負責組字逾時
+    loop {
+        let wait = engine
+            .lock()
+            .composition_deadline()
+            .map_or(TICK_INTERVAL, |deadline| deadline.saturating_duration_since(Instant::now()));
+        std::thread::sleep(wait.min(TICK_INTERVAL));
+
+        if engine.with(|engine| engine.tick(Instant::now())) == KeyResult::NoChange {
+            continue;
+        }
+        let Some(path) = focused.lock().unwrap_or_else(|e| e.into_inner()).clone() else {
+            continue;
+        };
+        let conn = connection.inner().clone();
+        connection
+            .inner()
+            .executor()
+            .spawn(
+                async move {
+                    if let Ok(iface) = conn.object_server().interface::<_, IbusEngine>(&path).await {
+                        let _ = iface.get().await.refresh(iface.signal_emitter()).await;
+                    }
+                },
+                "composition-timeout",
+            )
+            .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ibus_address_helpers() {
+        assert_eq!(
+            parse_address_file("# comment\nIBUS_ADDRESS=unix:abstract=/tmp/x\nIBUS_DAEMON_PID=1\n"),
+            Some("unix:abstract=/tmp/x".to_string())
+        );
+        assert_eq!(parse_address_file("IBUS_ADDRESS=\n"), None);
+        assert_eq!(display_number(":0.0"), "0");
+        assert_eq!(display_number("localhost:10"), "10");
+    }
+
+    #[test]
+    fn test_component_xml() {
+        let xml = component_xml("/usr/bin/rustarray30");
+        assert!(xml.contains("<exec>/usr/bin/rustarray30 --ibus</exec>"));
+        assert!(xml.contains(BUS_NAME));
+    }
+}
@@ -0,0 +1,166 @@
+// Import phrases from other input methods
+// 匯入其他輸入法的詞彙：微軟注音匯出的 TSV、RIME 使用者詞典與純詞彙清單，依行列取碼規則產生詞碼後加入自訂字詞
+
+use crate::dict::Dictionary;
+use crate::error::{Array30Error, Result};
+use crate::keymap::ArrayVariant;
+use crate::userdict::{UserDict, UserEntry};
+
+/// 匯入格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// 微軟注音匯出的 TSV：詞<Tab>注音…，取第一欄
+    MsBopomofo,
+    /// RIME 使用者詞典：userdb.txt 匯出（碼<Tab>詞<Tab>統計）或 dict.yaml（... 之後為 詞<Tab>碼<Tab>權重）
+    Rime,
+    /// 每行一個詞，詞之後的欄位（例如詞頻）略過
+    WordList,
+}
+
+impl ImportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MsBopomofo => "msbopomofo",
+            Self::Rime => "rime",
+            Self::WordList => "wordlist",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::MsBopomofo => "微軟注音",
+            Self::Rime => "RIME",
+            Self::WordList => "詞彙清單",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "msbopomofo" | "bopomofo" => Some(Self::MsBopomofo),
+            "rime" => Some(Self::Rime),
+            "wordlist" | "words" => Some(Self::WordList),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [Self; 3] {
+        [Self::MsBopomofo, Self::Rime, Self::WordList]
+    }
+
+    /// 從檔案內容取出詞彙（略過空行與 # 註解）
+    pub fn phrases(&self, content: &str) -> Vec<String> {
+        let lines = content
+            .lines()
+            .map(|line| line.trim_start_matches('\u{feff}').trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        let field = |line: &str, index: usize| line.split('\t').nth(index).map(|f| f.trim().to_string());
+        match self {
+            Self::MsBopomofo => lines.filter_map(|line| field(line, 0)).collect(),
+            Self::Rime if content.lines().any(|line| line.trim() == "...") => {
+                // dict.yaml：... 之前為 YAML 標頭
+                lines.skip_while(|line| *line != "...").skip(1).filter_map(|line| field(line, 0)).collect()
+            }
+            Self::Rime => lines.filter_map(|line| field(line, 1)).collect(),
+            Self::WordList => lines
+                .filter_map(|line| line.split_whitespace().next().map(str::to_string))
+                .collect(),
+        }
+    }
+}
+
+/// 讀取匯入檔：UTF-16 LE/BE（有 BOM）或 UTF-8
+pub fn decode_text(bytes: &[u8]) -> Result<String> {
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, u16::from_be_bytes),
+        _ => String::from_utf8(bytes.to_vec()).map_err(|e| Array30Error::Encoding(e.to_string())),
+    }
+}
+
+/// 解碼不含 BOM 的 UTF-16，`from` 決定位元組順序（u16::from_le_bytes 或 u16::from_be_bytes）
+pub fn decode_utf16(bytes: &[u8], from: fn([u8; 2]) -> u16) -> Result<String> {
+    let pairs = bytes.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(Array30Error::Encoding("檔案長度不是 UTF-16 的偶數位元組".to_string()));
+    }
+    let units: Vec<u16> = pairs.map(|pair| from([pair[0], pair[1]])).collect();
+    String::from_utf16(&units).map_err(|e| Array30Error::Encoding(e.to_string()))
+}
+
+/// 匯入結果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    /// 加入的字詞
+    pub added: Vec<UserEntry>,
+    /// 略過的詞與原因
+    pub skipped: Vec<(String, String)>,
+}
+
+/// 產生詞碼並加入自訂字詞（同時加入字典）；查無字碼、已在字典或重複的詞略過
+/// 加入的字詞只更新記憶體，由呼叫端存檔
+pub fn import_phrases(
+    phrases: &[String],
+    user_dict: &mut UserDict,
+    dict: &mut Dictionary,
+    variant: ArrayVariant,
+    max_code_len: usize,
+) -> ImportReport {
+    let mut report = ImportReport::default();
+    for phrase in phrases {
+        if phrase.chars().count() < 2 {
+            report.skipped.push((phrase.clone(), "不到兩字".to_string()));
+            continue;
+        }
+        let Some(code) = dict.encode_phrase(phrase, variant) else {
+            report.skipped.push((phrase.clone(), "有字查無字碼".to_string()));
+            continue;
+        };
+        let entry = UserEntry::new(&code, phrase);
+        match user_dict.validate(&entry, None, variant, max_code_len, dict) {
+            Ok(()) => {
+                user_dict.add(entry.clone(), dict);
+                report.added.push(entry);
+            }
+            Err(reason) => report.skipped.push((phrase.clone(), reason)),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phrases() {
+        let tsv = "\u{feff}台灣\tㄊㄞˊ ㄨㄢ\n# 註解\n\n大家\tㄉㄚˋ ㄐㄧㄚ\n";
+        assert_eq!(ImportFormat::MsBopomofo.phrases(tsv), ["台灣", "大家"]);
+        let userdb = "# Rime user dictionary\n#@/db_name\tluna_pinyin.userdb\ntai wan \t台灣\tc=3 d=1 t=2\n";
+        assert_eq!(ImportFormat::Rime.phrases(userdb), ["台灣"]);
+        let yaml = "---\nname: custom\n...\n台灣\ttai wan\t100\n";
+        assert_eq!(ImportFormat::Rime.phrases(yaml), ["台灣"]);
+        assert_eq!(ImportFormat::WordList.phrases("台灣 120\n大家\n"), ["台灣", "大家"]);
+
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain("台灣".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        assert_eq!(decode_text(&utf16).unwrap(), "台灣");
+        assert_eq!(decode_text("大家".as_bytes()).unwrap(), "大家");
+        assert!(matches!(decode_text(b"\xff"), Err(Array30Error::Encoding(_))));
+    }
+
+    #[test]
+    fn test_import_phrases() {
+        let mut dict = Dictionary::new();
+        for (code, c) in [("x;", "台"), ("cyxg", "灣"), ("z", "大"), ("na.", "家")] {
+            dict.insert_char(code, c);
+        }
+        dict.insert_phrase("zn.", "大家");
+        let mut user_dict = UserDict::default();
+        let phrases = ["台灣", "台灣", "大家", "台", "台北"].map(String::from);
+        let report = import_phrases(&phrases, &mut user_dict, &mut dict, ArrayVariant::Array30, 5);
+
+        assert_eq!(report.added, [UserEntry::new("x;cg", "台灣")]);
+        assert_eq!(dict.lookup_phrases("x;cg"), Some(&["台灣".to_string()][..]));
+        let reasons: Vec<&str> = report.skipped.iter().map(|(_, reason)| reason.as_str()).collect();
+        assert_eq!(reasons, ["已有相同的自訂字詞", "字典中已有此字詞", "不到兩字", "有字查無字碼"]);
+    }
+}
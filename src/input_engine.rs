@@ -1,63 +1,693 @@
 // Input Engine for Array30
 // 行列 30 輸入法引擎
 
-use crate::dict::Dictionary;
-use crate::keymap::Array30Key;
-use crate::state::{Candidate, InputMode, InputState};
+use crate::dict::{Annotations, Dictionary, TableKind, QUICK_PLACEHOLDER};
+use crate::remote::{DictBackend, RemoteLookup, RemoteWords};
+use crate::keymap::{Array30Key, ArrayVariant, Keymap};
+use crate::state::{Candidate, InputMode, InputState, OutputDoc};
+use crate::transform::TransformChain;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 預設每頁候選數（1-9 鍵選字）
+pub const PAGE_SIZE: usize = 9;
+/// 每頁候選數上限（選字鍵 1-9、0）
+pub const MAX_PAGE_SIZE: usize = 10;
+
+/// 無候選時確認鍵的處理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissPolicy {
+    /// 保留組字，不上屏
+    #[default]
+    Keep,
+    /// 將原始按鍵（英文碼）直接上屏
+    CommitRaw,
+}
+
+impl MissPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MissPolicy::Keep => "keep",
+            MissPolicy::CommitRaw => "commit_raw",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            MissPolicy::Keep => "保留組字",
+            MissPolicy::CommitRaw => "輸出英文碼",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "keep" => Some(MissPolicy::Keep),
+            "commit_raw" | "raw" => Some(MissPolicy::CommitRaw),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [MissPolicy; 2] {
+        [MissPolicy::Keep, MissPolicy::CommitRaw]
+    }
+}
+
+/// 空白鍵的行為
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpaceBehavior {
+    /// 上屏目前頁面的第一個候選
+    #[default]
+    CommitFirst,
+    /// 候選超過一頁時翻到下一頁，最後一頁才上屏第一個候選
+    NextPage,
+}
+
+impl SpaceBehavior {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpaceBehavior::CommitFirst => "commit_first",
+            SpaceBehavior::NextPage => "next_page",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SpaceBehavior::CommitFirst => "上屏第一候選",
+            SpaceBehavior::NextPage => "翻頁，最後一頁才上屏",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "commit_first" | "commit" => Some(SpaceBehavior::CommitFirst),
+            "next_page" | "page" => Some(SpaceBehavior::NextPage),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [SpaceBehavior; 2] {
+        [SpaceBehavior::CommitFirst, SpaceBehavior::NextPage]
+    }
+}
+
+/// 組字閒置逾時的處理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutAction {
+    /// 捨棄未完成的碼
+    #[default]
+    Clear,
+    /// 如同按下 Enter：上屏第一候選，沒有候選時依 miss_policy 處理（保留組字時改為捨棄）
+    Commit,
+}
+
+impl TimeoutAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeoutAction::Clear => "clear",
+            TimeoutAction::Commit => "commit",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TimeoutAction::Clear => "清除組字",
+            TimeoutAction::Commit => "上屏第一候選",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "clear" => Some(TimeoutAction::Clear),
+            "commit" | "commit_first" => Some(TimeoutAction::Commit),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [TimeoutAction; 2] {
+        [TimeoutAction::Clear, TimeoutAction::Commit]
+    }
+}
+
+/// 標點符號輸出方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PunctuationMode {
+    /// 半形（原樣輸出）
+    #[default]
+    Half,
+    /// 全形
+    Full,
+}
+
+impl PunctuationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PunctuationMode::Half => "half",
+            PunctuationMode::Full => "full",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PunctuationMode::Half => "半形",
+            PunctuationMode::Full => "全形",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "half" => Some(PunctuationMode::Half),
+            "full" => Some(PunctuationMode::Full),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [PunctuationMode; 2] {
+        [PunctuationMode::Half, PunctuationMode::Full]
+    }
+
+    /// 依模式轉換直接輸出的標點符號
+    pub fn convert(&self, c: char) -> char {
+        match self {
+            // 全形 ASCII 標點位於 U+FF01-U+FF5E，與半形相差 0xFEE0
+            PunctuationMode::Full if c.is_ascii_punctuation() => char::from_u32(c as u32 + 0xFEE0).unwrap_or(c),
+            _ => c,
+        }
+    }
+}
+
+/// 候選排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateOrder {
+    /// 依字表順序
+    #[default]
+    Table,
+    /// 基本區漢字優先，擴充區與相容字排在後面（大字集適用）
+    BasicFirst,
+}
+
+impl CandidateOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandidateOrder::Table => "table",
+            CandidateOrder::BasicFirst => "basic_first",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CandidateOrder::Table => "字表順序",
+            CandidateOrder::BasicFirst => "基本區漢字優先",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "table" => Some(CandidateOrder::Table),
+            "basic_first" => Some(CandidateOrder::BasicFirst),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [CandidateOrder; 2] {
+        [CandidateOrder::Table, CandidateOrder::BasicFirst]
+    }
+}
+
+/// 候選篩選
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateFilter {
+    /// 不篩選
+    #[default]
+    All,
+    /// 只列出字
+    CharsOnly,
+    /// 只列出詞
+    PhrasesOnly,
+    /// 排除罕用字：擴充區與相容區的漢字
+    ExcludeRare,
+    /// 排除擴充 B 區以後的漢字（大字集才有的字）
+    ExcludeExtensions,
+}
+
+impl CandidateFilter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandidateFilter::All => "all",
+            CandidateFilter::CharsOnly => "chars_only",
+            CandidateFilter::PhrasesOnly => "phrases_only",
+            CandidateFilter::ExcludeRare => "exclude_rare",
+            CandidateFilter::ExcludeExtensions => "exclude_extensions",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CandidateFilter::All => "全部",
+            CandidateFilter::CharsOnly => "只列字",
+            CandidateFilter::PhrasesOnly => "只列詞",
+            CandidateFilter::ExcludeRare => "排除罕用字",
+            CandidateFilter::ExcludeExtensions => "排除擴充 B 區以後",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::all().into_iter().find(|f| f.as_str() == s.to_lowercase())
+    }
+
+    pub fn all() -> [CandidateFilter; 5] {
+        [
+            CandidateFilter::All,
+            CandidateFilter::CharsOnly,
+            CandidateFilter::PhrasesOnly,
+            CandidateFilter::ExcludeRare,
+            CandidateFilter::ExcludeExtensions,
+        ]
+    }
+
+    /// 候選是否通過篩選
+    pub fn allows(&self, candidate: &Candidate) -> bool {
+        self.allows_word(&candidate.text, candidate.is_phrase)
+    }
+
+    /// 字詞是否通過篩選（建立候選之前先檢查）
+    pub fn allows_word(&self, text: &str, is_phrase: bool) -> bool {
+        match self {
+            CandidateFilter::All => true,
+            CandidateFilter::CharsOnly => !is_phrase,
+            CandidateFilter::PhrasesOnly => is_phrase,
+            CandidateFilter::ExcludeRare => !text.chars().any(is_extended_cjk),
+            CandidateFilter::ExcludeExtensions => !text.chars().any(|c| c >= '\u{20000}'),
+        }
+    }
+}
+
+/// 一般模式下同一個碼同時有字與詞時的候選列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhraseMix {
+    /// 只列出字，詞彙需先按 ' 再輸入詞碼
+    #[default]
+    Separate,
+    /// 字在前、詞在後
+    CharsFirst,
+    /// 詞在前、字在後
+    PhrasesFirst,
+}
+
+impl PhraseMix {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PhraseMix::Separate => "separate",
+            PhraseMix::CharsFirst => "chars_first",
+            PhraseMix::PhrasesFirst => "phrases_first",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PhraseMix::Separate => "字詞分開（詞彙需按 '）",
+            PhraseMix::CharsFirst => "字在前、詞在後",
+            PhraseMix::PhrasesFirst => "詞在前、字在後",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "separate" => Some(PhraseMix::Separate),
+            "chars_first" => Some(PhraseMix::CharsFirst),
+            "phrases_first" => Some(PhraseMix::PhrasesFirst),
+            _ => None,
+        }
+    }
+
+    pub fn all() -> [PhraseMix; 3] {
+        [PhraseMix::Separate, PhraseMix::CharsFirst, PhraseMix::PhrasesFirst]
+    }
+}
+
+/// 組字模式（狀態列顯示用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComposeMode {
+    /// 一般字詞輸入
+    Normal,
+    /// 詞彙輸入（已按下 '）
+    Phrase,
+    /// 符號輸入（行列 30 的 W+數字符號表）
+    Symbol,
+    /// 數字組碼（數字鍵接在碼後面，不選字）
+    DigitCode,
+}
+
+impl ComposeMode {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ComposeMode::Normal => "一般",
+            ComposeMode::Phrase => "詞彙",
+            ComposeMode::Symbol => "符號",
+            ComposeMode::DigitCode => "數字碼",
+        }
+    }
+}
+
+/// 是否為擴充區或相容區的漢字
+fn is_extended_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{3FFFF}')
+}
+
+/// 引擎行為選項
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineOptions {
+    /// 碼無候選時按 Enter/空白的處理方式
+    pub miss_policy: MissPolicy,
+    /// 組字時顯示延伸碼的預覽候選
+    pub prefix_preview: bool,
+    /// 實體鍵盤配置的重映射表
+    pub keymap: Keymap,
+    /// 輸入法變體（決定組碼鍵、選字鍵與碼長）
+    pub variant: ArrayVariant,
+    /// 每頁候選數（1 至 MAX_PAGE_SIZE）
+    pub page_size: usize,
+    /// 碼長上限（0 為輸入法變體的預設）
+    pub max_code_len: usize,
+    /// 空白鍵的行為
+    pub space_behavior: SpaceBehavior,
+    /// 達到碼長上限且只有一個候選時自動上屏
+    pub auto_commit: bool,
+    /// 標點符號輸出方式
+    pub punctuation: PunctuationMode,
+    /// 候選排序方式
+    pub candidate_order: CandidateOrder,
+    /// 一般模式下字與詞候選的合併方式
+    pub phrase_mix: PhraseMix,
+    /// 候選篩選
+    pub candidate_filter: CandidateFilter,
+    /// 官方行列按鍵相容：一、二碼時數字鍵選簡碼選單、空白鍵上屏簡碼字，
+    /// 組字中的數字鍵可接成字表中的特別碼
+    pub official_keys: bool,
+    /// 上屏文字的轉換串列（選字與直接輸出的標點、數字）
+    pub transforms: TransformChain,
+    /// 輸出區字元數上限（0 為不限制），超過時較早的內容移出，由前端以 take_archived_output 取走
+    pub max_output_chars: usize,
+    /// 組字閒置多久後自動處理未完成的碼（None 為不處理），由前端定期呼叫 tick 檢查
+    pub composition_timeout: Option<Duration>,
+    /// 組字逾時的處理方式
+    pub timeout_action: TimeoutAction,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            miss_policy: MissPolicy::default(),
+            prefix_preview: true,
+            keymap: Keymap::qwerty(),
+            variant: ArrayVariant::Array30,
+            page_size: PAGE_SIZE,
+            max_code_len: 0,
+            space_behavior: SpaceBehavior::default(),
+            auto_commit: false,
+            punctuation: PunctuationMode::default(),
+            candidate_order: CandidateOrder::default(),
+            phrase_mix: PhraseMix::default(),
+            candidate_filter: CandidateFilter::default(),
+            official_keys: false,
+            transforms: TransformChain::default(),
+            max_output_chars: 0,
+            composition_timeout: None,
+            timeout_action: TimeoutAction::default(),
+        }
+    }
+}
+
+impl EngineOptions {
+    /// 實際使用的碼長上限
+    pub fn max_code_len(&self) -> usize {
+        if self.max_code_len == 0 {
+            self.variant.max_code_len()
+        } else {
+            self.max_code_len
+        }
+    }
+}
+
+/// 候選在字典中的位置：查詢時只記位置，顯示目前頁面或上屏時才建立候選
+/// （單鍵碼在大字集中可能有數百個候選）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CandidateRef {
+    /// 字表或詞庫中此碼下的第幾個字詞（lookup_chars、lookup_phrases 的位置）
+    Table(TableKind, usize),
+    /// 遠端字典查到的第幾個字詞
+    Remote(TableKind, usize),
+    /// 大字集後備字表中此碼下的第幾個字
+    Fallback(usize),
+    /// 簡碼選單的第幾個位置
+    Quick(usize),
+}
+
+impl CandidateRef {
+    fn is_phrase(self) -> bool {
+        matches!(self, Self::Table(TableKind::Phrases, _) | Self::Remote(TableKind::Phrases, _))
+    }
+}
 
 /// 輸入法引擎
 pub struct InputEngine {
     /// 字典
     dict: Dictionary,
+    /// 字典的修訂次數：載入或取得可變參考時遞增，供前端判斷快取是否過期
+    dict_revision: u64,
+    /// 讀音與釋義註解，填入候選
+    annotations: Arc<Annotations>,
+    /// 遠端字典（設定 remote_dict_url 時），在背景查詢，查到的字詞接在本機字詞之後
+    remote: Option<RemoteLookup>,
+    /// 遠端字典對目前碼的查詢結果
+    remote_words: RemoteWords,
+    /// 目前碼的遠端查詢尚未回來
+    remote_pending: bool,
     /// 當前狀態
     state: InputState,
-    /// 候選列表
-    candidates: Vec<Candidate>,
+    /// 候選列表（字典中的位置）
+    candidates: Vec<CandidateRef>,
+    /// 目前頁面的候選
+    page: Vec<Candidate>,
+    /// 預覽候選：延伸目前碼可得到的候選（尚未確定）
+    preview: Vec<Candidate>,
+    /// 官方按鍵相容模式下空白鍵上屏的簡碼字（候選列為簡碼選單時）
+    short_code: Option<Candidate>,
+    /// 數字組碼模式：數字鍵接在碼後面（輸入含數字的特別碼），不作選字鍵
+    digit_codes: bool,
     /// 候選頁面索引
     page_index: usize,
     /// 每頁顯示候選數
     page_size: usize,
+    /// 行為選項
+    options: EngineOptions,
+    /// 選字上屏記錄（啟用後由前端定期取出，供使用統計）
+    commit_log: Option<Vec<Candidate>>,
+    /// 提示回饋（啟用後由前端定期取出，以響鈴或閃爍呈現）
+    feedback: Option<Vec<Feedback>>,
 }
 
 impl InputEngine {
     pub fn new(dict: Dictionary) -> Self {
+        Self::with_options(dict, EngineOptions::default())
+    }
+
+    /// 以指定選項建立引擎
+    pub fn with_options(dict: Dictionary, options: EngineOptions) -> Self {
+        let mut state = InputState::new();
+        state.set_output_limit(options.max_output_chars);
         Self {
             dict,
-            state: InputState::new(),
+            dict_revision: 0,
+            annotations: Arc::default(),
+            remote: None,
+            remote_words: RemoteWords::default(),
+            remote_pending: false,
+            state,
             candidates: Vec::new(),
+            page: Vec::new(),
+            preview: Vec::new(),
+            short_code: None,
+            digit_codes: false,
             page_index: 0,
-            page_size: 9, // 1-9 鍵選字
+            page_size: options.page_size.clamp(1, MAX_PAGE_SIZE),
+            options,
+            commit_log: None,
+            feedback: None,
         }
     }
 
-    /// 載入字典
+    /// 取得行為選項
+    pub fn options(&self) -> &EngineOptions {
+        &self.options
+    }
+
+    /// 變更行為選項
+    pub fn set_options(&mut self, options: EngineOptions) {
+        self.page_size = options.page_size.clamp(1, MAX_PAGE_SIZE);
+        self.page_index = 0;
+        self.state.set_output_limit(options.max_output_chars);
+        self.options = options;
+        self.fill_page();
+    }
+
+    /// 變更候選篩選，組字中的候選立即更新
+    pub fn set_candidate_filter(&mut self, filter: CandidateFilter) {
+        self.options.candidate_filter = filter;
+        self.update_candidates();
+    }
+
+    /// 設定讀音與釋義註解，之後的候選會帶有讀音與釋義
+    pub fn set_annotations(&mut self, annotations: Arc<Annotations>) {
+        self.annotations = annotations;
+        self.update_candidates();
+    }
+
+    /// 設定遠端字典，None 為只用本機字典
+    /// 查詢在背景執行緒進行，前端需定期呼叫 poll_remote 合併查到的字詞
+    pub fn set_remote(&mut self, remote: Option<Arc<dyn DictBackend>>) {
+        self.remote = remote.map(RemoteLookup::spawn);
+        self.update_candidates();
+    }
+
+    /// 是否正在等待目前碼的遠端查詢結果（前端可據此安排下次 poll_remote）
+    pub fn remote_pending(&self) -> bool {
+        self.remote_pending
+    }
+
+    /// 合併背景查到的遠端字詞；目前碼的候選有變動時回傳 true
+    /// 遠端字詞接在本機字詞之後，目前頁面不變
+    pub fn poll_remote(&mut self) -> bool {
+        let Some(remote) = self.remote.as_ref() else {
+            return false;
+        };
+        let code = &self.state.current_code;
+        let Some((_, words)) = remote.poll().into_iter().rev().find(|(found, _)| found == code) else {
+            return false;
+        };
+        if !self.remote_pending {
+            return false;
+        }
+        self.remote_pending = false;
+        if words == RemoteWords::default() {
+            return false;
+        }
+        self.remote_words = words;
+        let page_index = self.page_index;
+        self.query_candidates();
+        self.page_index = page_index.min(self.page_count().saturating_sub(1));
+        self.fill_page();
+        true
+    }
+
+    /// 載入字典，組字中的候選改查新字典
     pub fn load_dict(&mut self, dict: Dictionary) {
         self.dict = dict;
+        self.dict_revision += 1;
+        self.update_candidates();
     }
 
     /// 處理按鍵輸入
     /// 回傳是否需要重新整理介面
     pub fn handle_key(&mut self, key: char) -> KeyResult {
+        // 英文模式：可見字元直接輸出
+        if self.state.english_mode && !key.is_control() {
+            self.state.commit_direct(&key.to_string());
+            return KeyResult::Committed;
+        }
+        let key = self.options.keymap.remap(key);
+        let result = self.process_key(key);
+        tracing::trace!(?key, code = %self.state.current_code, ?result, "按鍵");
+        self.note_rejection(result);
+        result
+    }
+
+    /// 組字逾時的時間點（沒有組字或未設定逾時時為 None），前端可據此排定下次檢查
+    pub fn composition_deadline(&self) -> Option<Instant> {
+        let timeout = self.options.composition_timeout?;
+        if self.state.current_code.is_empty() {
+            return None;
+        }
+        Some(self.state.last_key_at()? + timeout)
+    }
+
+    /// 由前端定期呼叫：組字閒置超過 composition_timeout 時依 timeout_action 清除或上屏
+    pub fn tick(&mut self, now: Instant) -> KeyResult {
+        if self.composition_deadline().is_none_or(|deadline| now < deadline) {
+            return KeyResult::NoChange;
+        }
+        tracing::debug!(code = %self.state.current_code, action = self.options.timeout_action.as_str(), "組字逾時");
+        match self.options.timeout_action {
+            TimeoutAction::Clear => self.process_key('\x1b'),
+            TimeoutAction::Commit => match self.process_key('\n') {
+                KeyResult::Rejected(_) => self.process_key('\x1b'),
+                result => result,
+            },
+        }
+    }
+
+    /// 直接按下行列鍵（例如點擊螢幕鍵盤），不經過鍵盤配置重映射
+    pub fn press_key(&mut self, key: Array30Key) -> KeyResult {
+        let result = self.process_key(key.code_char());
+        self.note_rejection(result);
+        result
+    }
+
+    /// 被拒絕的按鍵加入提示回饋
+    fn note_rejection(&mut self, result: KeyResult) {
+        if let KeyResult::Rejected(reason) = result {
+            self.push_feedback(Feedback::Rejected(reason));
+        }
+    }
+
+    fn push_feedback(&mut self, feedback: Feedback) {
+        if let Some(queue) = self.feedback.as_mut() {
+            queue.push(feedback);
+        }
+    }
+
+    /// 開始記錄提示回饋（需定期以 take_feedback 取出）
+    pub fn enable_feedback(&mut self) {
+        self.feedback.get_or_insert_with(Vec::new);
+    }
+
+    /// 取出啟用記錄後產生的提示回饋（依發生順序）
+    pub fn take_feedback(&mut self) -> Vec<Feedback> {
+        self.feedback.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// 處理已轉換為 QWERTY 鍵位的按鍵
+    fn process_key(&mut self, key: char) -> KeyResult {
+        let variant = self.options.variant;
+        let max_code_len = self.options.max_code_len();
+
         match key {
             // 詞彙終結鍵
             '\'' => {
-                if self.state.current_code.len() >= 1 && self.state.current_code.len() <= 4 {
+                if self.state.current_code.len() >= 1 && self.state.current_code.len() <= max_code_len {
                     self.state.set_phrase_mode();
                     self.update_candidates();
                     KeyResult::NeedUpdate
                 } else {
                     // 碼數不正確
-                    KeyResult::NeedUpdate
+                    KeyResult::Rejected(RejectReason::InvalidCode)
                 }
             }
 
             // 退格鍵
             '\x08' | '\x7f' => {
                 // 先清空候選
-                if !self.candidates.is_empty() {
-                    self.candidates.clear();
-                    self.page_index = 0;
-                }
+                self.clear_candidates();
                 if self.state.backspace() {
                     self.update_candidates();
                 }
@@ -67,74 +697,87 @@ impl InputEngine {
             // Esc 清空
             '\x1b' => {
                 self.state.clear_composing();
-                self.candidates.clear();
-                self.page_index = 0;
+                self.clear_candidates();
                 KeyResult::NeedUpdate
             }
 
-            // Enter 或空白確認第一候選
+            // Enter 或空白確認第一候選（空白鍵可設定為先翻頁）
             '\n' | '\r' | ' ' => {
-                if !self.candidates.is_empty() {
+                if key == ' ' && self.options.space_behavior == SpaceBehavior::NextPage && self.turn_page(true) {
+                    KeyResult::NeedUpdate
+                } else if let Some(candidate) = self.short_code.clone() {
+                    self.commit_candidate(candidate);
+                    KeyResult::NeedUpdate
+                } else if !self.candidates.is_empty() {
                     self.select_candidate(0);
                     KeyResult::NeedUpdate
                 } else if !self.state.current_code.is_empty() {
-                    // 沒有候選但有碼，嘗試直接上屏
-                    KeyResult::NeedUpdate
+                    // 沒有候選但有碼，依設定決定是否上屏原始按鍵
+                    match self.options.miss_policy {
+                        MissPolicy::Keep => KeyResult::Rejected(RejectReason::NoCandidates),
+                        MissPolicy::CommitRaw => {
+                            let raw = std::mem::take(&mut self.state.raw_keys);
+                            self.state.clear_composing();
+                            self.clear_candidates();
+                            self.state.commit_direct(&raw);
+                            KeyResult::Committed
+                        }
+                    }
                 } else {
                     KeyResult::NoChange
                 }
             }
 
-            // 數字鍵選字
-            '1'..='9' => {
-                if !self.candidates.is_empty() {
-                    let idx = (key as usize) - ('1' as usize);
-                    if self.select_candidate(idx) {
-                        KeyResult::Committed
-                    } else {
-                        KeyResult::NeedUpdate
-                    }
-                } else {
-                    // 數字鍵可能直接輸出
-                    self.state.commit_direct(&key.to_string());
-                    KeyResult::Committed
+            // 行列鍵輸入（組碼鍵優先於選字鍵，行列 40/10 的數字鍵為組碼鍵）
+            // 數字鍵接成碼的情況見 is_digit_code_key
+            c if variant.is_code_key(c) || self.is_digit_code_key(c) => {
+                // 碼已達最長碼長，不再接受組碼鍵
+                if self.state.current_code.len() >= max_code_len {
+                    return KeyResult::Rejected(RejectReason::TooManyKeys);
                 }
-            }
-            '0' => {
-                if !self.candidates.is_empty() {
-                    self.select_candidate(9);
-                    KeyResult::Committed
-                } else {
-                    self.state.commit_direct(&key.to_string());
-                    KeyResult::Committed
+
+                // 接上這個鍵後不可能有候選時拒絕，組字狀態不變
+                let mut code = self.state.current_code.clone();
+                code.push(c);
+                if !self.accepts_code(&code) {
+                    return KeyResult::Rejected(RejectReason::InvalidCode);
                 }
-            }
 
-            // 行列鍵輸入
-            c if Array30Key::from_char(c).is_some() => {
                 // 如果已有候選列表，先清空
-                if !self.candidates.is_empty() {
-                    self.candidates.clear();
-                    self.page_index = 0;
-                }
+                self.clear_candidates();
 
                 self.state.add_key(c);
+                self.state.current_code = code;
 
-                // 根據模式處理
-                if self.state.mode == InputMode::PhraseInput {
-                    // 詞彙模式：只接受 4 碼
-                    if self.state.current_code.len() < 4 {
-                        self.state.current_code.push(c);
+                self.update_candidates();
+
+                // 碼已打滿且只有一個候選時自動上屏
+                if self.options.auto_commit
+                    && self.state.mode == InputMode::Normal
+                    && self.state.current_code.len() == max_code_len
+                    && self.candidates.len() == 1
+                {
+                    self.select_candidate(0);
+                    return KeyResult::Committed;
+                }
+                KeyResult::NeedUpdate
+            }
+
+            // 選字鍵（行列 30 為數字鍵）
+            c if variant.selection_index(c).is_some() => {
+                if !self.candidates.is_empty() {
+                    let idx = variant.selection_index(c).unwrap_or_default();
+                    if self.select_candidate(idx) {
+                        KeyResult::Committed
+                    } else {
+                        KeyResult::NeedUpdate
                     }
                 } else {
-                    // 一般模式：最多 4 碼
-                    if self.state.current_code.len() < 4 {
-                        self.state.current_code.push(c);
-                    }
+                    // 無候選時直接輸出
+                    let text = self.options.transforms.apply(&c.to_string());
+                    self.state.commit_direct(&text);
+                    KeyResult::Committed
                 }
-
-                self.update_candidates();
-                KeyResult::NeedUpdate
             }
 
             // 其他字元直接輸出
@@ -142,98 +785,466 @@ impl InputEngine {
                 // 先確認當前組字
                 if !self.state.current_code.is_empty() {
                     self.state.clear_composing();
+                    self.clear_candidates();
                 }
-                self.state.commit_direct(&key.to_string());
+                let key = self.options.punctuation.convert(key);
+                let text = self.options.transforms.apply(&key.to_string());
+                self.state.commit_direct(&text);
                 KeyResult::Committed
             }
         }
     }
 
+    /// 數字鍵是否接成碼而不是選字：數字組碼模式下一律接成碼；
+    /// 否則行列 30 的 W+數字開啟符號表，官方按鍵相容模式下可接成字表中的任何特別碼
+    fn is_digit_code_key(&self, c: char) -> bool {
+        if !c.is_ascii_digit() {
+            return false;
+        }
+        if self.digit_codes {
+            return true;
+        }
+        let code = &self.state.current_code;
+        let symbol = self.options.variant == ArrayVariant::Array30 && code == "w";
+        (symbol || self.options.official_keys)
+            && self.state.mode == InputMode::Normal
+            && !code.is_empty()
+            && code.len() < self.options.max_code_len()
+            && self.dict.has_prefix(&format!("{}{}", code, c))
+    }
+
+    /// 以指定的碼取代組字區（字典瀏覽練習用），詞彙碼進入詞彙模式
+    /// 不經過鍵盤配置重映射，也不自動上屏
+    pub fn compose_code(&mut self, code: &str, phrase: bool) {
+        self.state.clear_composing();
+        self.clear_candidates();
+        for c in code.chars().take(self.options.max_code_len()) {
+            self.state.add_key(c);
+            self.state.current_code.push(c);
+        }
+        if phrase && !self.state.current_code.is_empty() {
+            self.state.set_phrase_mode();
+        }
+        self.update_candidates();
+    }
+
+    /// 碼是否可以繼續組字：詞彙模式、無候選時上屏原始按鍵或設定了遠端字典時都接受，
+    /// 一般模式下需本機字典有此碼或以此碼開頭的碼，或大字集後備字表有此碼
+    fn accepts_code(&self, code: &str) -> bool {
+        self.state.mode != InputMode::Normal
+            || self.options.miss_policy == MissPolicy::CommitRaw
+            || self.remote.is_some()
+            || self.dict.has_prefix(code)
+            || self.dict.lookup_fallback_chars(code).is_some_and(|words| !words.is_empty())
+    }
+
+    /// 依序輸入一串按鍵
+    /// 回傳這段按鍵期間上屏的文字（取自上屏記錄，輸出區移出較早內容時也不受影響）
+    pub fn feed_str(&mut self, keys: &str) -> String {
+        let outer = self.state.begin_commit_capture();
+        for key in keys.chars() {
+            self.handle_key(key);
+        }
+        self.state.end_commit_capture(outer).concat()
+    }
+
     /// 更新候選列表
+    /// 重新查詢候選；沿用候選與預覽清單的記憶體，只查詢會顯示的字或詞，
+    /// 候選只記字典中的位置，目前頁面才建立候選
     fn update_candidates(&mut self) {
-        self.candidates.clear();
-        self.page_index = 0;
+        // 遠端字典只取快取中的結果，沒有時在背景查詢，查到後由 poll_remote 合併
+        self.remote_words = RemoteWords::default();
+        self.remote_pending = false;
+        let code = self.state.current_code.as_str();
+        if let Some(remote) = self.remote.as_ref().filter(|_| !code.is_empty()) {
+            match remote.cached(code) {
+                Some(words) => self.remote_words = words,
+                None => {
+                    remote.request(code);
+                    self.remote_pending = true;
+                }
+            }
+        }
+        self.query_candidates();
+    }
 
-        let code = &self.state.current_code;
+    /// 以本機字典與已取得的遠端字詞查詢目前碼的候選
+    fn query_candidates(&mut self) {
+        self.clear_candidates();
 
-        if code.is_empty() {
+        if self.state.current_code.is_empty() {
             return;
         }
+        let mut candidates = std::mem::take(&mut self.candidates);
+        let code = self.state.current_code.as_str();
+
+        // 遠端字典的字詞接在本機字詞之後（已有的不重複），查詢失敗時只用本機字典
+        let push = |list: &mut Vec<CandidateRef>, kind| {
+            let local = self.dict.ordered_positions(kind, code);
+            let remote = self.remote_words.words(kind);
+            let remote = remote.iter().enumerate().filter(|&(i, word)| {
+                !self.dict.contains_word(kind, code, word) && !remote[..i].contains(word)
+            });
+            let refs = local
+                .into_iter()
+                .map(|(i, word)| (CandidateRef::Table(kind, i), word))
+                .chain(remote.map(|(i, word)| (CandidateRef::Remote(kind, i), word.as_str())));
+            self.push_refs(list, refs);
+        };
 
-        // 詞彙模式優先查找詞庫
         if self.state.mode == InputMode::PhraseInput {
-            if let Some(phrases) = self.dict.lookup_phrases(code) {
-                for phrase in phrases {
-                    self.candidates
-                        .push(Candidate::phrase(phrase.clone(), code.clone()));
+            // 詞彙模式優先查找詞庫，沒有詞時退回字庫
+            push(&mut candidates, TableKind::Phrases);
+            if candidates.is_empty() {
+                push(&mut candidates, TableKind::Chars);
+            }
+        } else {
+            // 一般模式：依設定合併同碼的字與詞，各自成段
+            match self.options.phrase_mix {
+                PhraseMix::Separate => push(&mut candidates, TableKind::Chars),
+                PhraseMix::CharsFirst => {
+                    push(&mut candidates, TableKind::Chars);
+                    push(&mut candidates, TableKind::Phrases);
+                }
+                PhraseMix::PhrasesFirst => {
+                    push(&mut candidates, TableKind::Phrases);
+                    push(&mut candidates, TableKind::Chars);
                 }
             }
         }
 
-        // 一般模式查找字庫
-        if self.candidates.is_empty() {
-            if let Some(chars) = self.dict.lookup_chars(code) {
-                for char_str in chars {
-                    self.candidates
-                        .push(Candidate::char(char_str.clone(), code.clone()));
-                }
+        // 字表查無此碼時改查大字集後備字表（背景讀取完成後才有）
+        if candidates.is_empty() {
+            let words = self.dict.lookup_fallback_chars(code).unwrap_or_default();
+            let refs = words.iter().enumerate().map(|(i, word)| (CandidateRef::Fallback(i), word.as_str()));
+            self.push_refs(&mut candidates, refs);
+        }
+
+        // 官方按鍵相容：一、二碼時候選列為簡碼選單（數字鍵依位置選字），空白鍵上屏簡碼字
+        if self.options.official_keys && self.state.mode == InputMode::Normal && code.chars().count() <= 2 {
+            if let Some(quick) = self.dict.lookup_quick(code) {
+                self.short_code = candidates.iter().find(|c| !c.is_phrase()).and_then(|&c| self.materialize(c));
+                candidates.clear();
+                candidates.extend((0..quick.len()).map(CandidateRef::Quick));
             }
         }
+
+        // 延伸碼預覽（詞彙碼長度固定，不需預覽）；只取一頁，不展開全部延伸碼的字
+        if self.options.prefix_preview && self.state.mode == InputMode::Normal {
+            let filter = self.options.candidate_filter;
+            let preview = self
+                .dict
+                .char_prefix_iter(code, self.options.max_code_len())
+                .filter(|(_, text)| filter.allows_word(text, false))
+                .take(self.page_size)
+                .map(|(code, text)| Candidate::char(text.to_string(), code.to_string()));
+            self.preview.extend(preview);
+        }
+        if candidates.is_empty() && self.state.mode == InputMode::PhraseInput {
+            self.push_feedback(Feedback::EmptyLookup);
+        }
+        self.candidates = candidates;
+        self.fill_page();
+    }
+
+    /// 把通過篩選的字詞位置加入候選清單
+    /// 字典已依來源、詞頻與字表順序排好；基本區優先時再以穩定排序把擴充區移到後面
+    fn push_refs<'a>(&self, list: &mut Vec<CandidateRef>, refs: impl Iterator<Item = (CandidateRef, &'a str)>) {
+        let filter = self.options.candidate_filter;
+        let mut refs: Vec<_> = refs.filter(|(cand, text)| filter.allows_word(text, cand.is_phrase())).collect();
+        if self.options.candidate_order == CandidateOrder::BasicFirst {
+            refs.sort_by_key(|(_, text)| text.chars().any(is_extended_cjk));
+        }
+        list.extend(refs.into_iter().map(|(cand, _)| cand));
+    }
+
+    /// 候選的文字（字典在查詢後被修改而找不到時為 None）
+    fn candidate_text(&self, cand: CandidateRef) -> Option<&str> {
+        let code = self.state.current_code.as_str();
+        let (words, index) = match cand {
+            CandidateRef::Table(TableKind::Chars, i) => (self.dict.lookup_chars(code), i),
+            CandidateRef::Table(TableKind::Phrases, i) => (self.dict.lookup_phrases(code), i),
+            CandidateRef::Remote(kind, i) => (Some(self.remote_words.words(kind)), i),
+            CandidateRef::Fallback(i) => (self.dict.lookup_fallback_chars(code), i),
+            CandidateRef::Quick(i) => (self.dict.lookup_quick(code), i),
+        };
+        words?.get(index).map(String::as_str)
+    }
+
+    /// 建立候選，並填入讀音與釋義
+    fn materialize(&self, cand: CandidateRef) -> Option<Candidate> {
+        let text = self.candidate_text(cand)?;
+        let mut candidate = Candidate::new(text.to_string(), self.state.current_code.clone(), cand.is_phrase());
+        candidate.big_set = matches!(cand, CandidateRef::Fallback(_));
+        if !self.annotations.is_empty() {
+            candidate.readings = self.annotations.readings(text).to_vec();
+            candidate.definition = self.annotations.definition(text).map(str::to_string);
+        }
+        Some(candidate)
+    }
+
+    /// 建立目前頁面的候選
+    fn fill_page(&mut self) {
+        let mut page = std::mem::take(&mut self.page);
+        page.clear();
+        let start = (self.page_index * self.page_size).min(self.candidates.len());
+        let end = (start + self.page_size).min(self.candidates.len());
+        page.extend(self.candidates[start..end].iter().filter_map(|&cand| self.materialize(cand)));
+        self.page = page;
+    }
+
+    /// 清空候選與預覽
+    fn clear_candidates(&mut self) {
+        self.candidates.clear();
+        self.page.clear();
+        self.preview.clear();
+        self.short_code = None;
+        self.page_index = 0;
     }
 
     /// 選擇候選字
     /// 回傳是否成功選擇
     pub fn select_candidate(&mut self, index: usize) -> bool {
-        let actual_index = self.page_index * self.page_size + index;
-
-        if actual_index < self.candidates.len() {
-            let candidate = self.candidates[actual_index].clone();
-            self.state.composing = candidate.text;
-            self.state.commit_composing();
-            self.candidates.clear();
-            self.page_index = 0;
-            true
-        } else {
-            false
+        match self.page.get(index) {
+            // 簡碼選單的空位不能選
+            Some(candidate) if candidate.text != QUICK_PLACEHOLDER => {
+                self.commit_candidate(candidate.clone());
+                true
+            }
+            _ => false,
         }
     }
 
+    /// 上屏候選並清空組字
+    fn commit_candidate(&mut self, candidate: Candidate) {
+        tracing::debug!(code = %self.state.current_code, text = %candidate.text, "選字上屏");
+        if let Some(log) = self.commit_log.as_mut() {
+            log.push(candidate.clone());
+        }
+        self.state.composing = self.options.transforms.apply(&candidate.text);
+        self.state.commit_composing();
+        self.clear_candidates();
+    }
+
+    /// 官方按鍵相容模式下空白鍵上屏的簡碼字
+    pub fn short_code(&self) -> Option<&Candidate> {
+        self.short_code.as_ref()
+    }
+
+    /// 開始記錄選字上屏的候選（需定期以 take_commit_log 取出）
+    pub fn enable_commit_log(&mut self) {
+        self.commit_log.get_or_insert_with(Vec::new);
+    }
+
+    /// 取出啟用記錄後選字上屏的候選
+    pub fn take_commit_log(&mut self) -> Vec<Candidate> {
+        self.commit_log.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// 開始記錄上屏的文字（含直接輸出，供輸出日誌）
+    pub fn enable_commit_record(&mut self) {
+        self.state.enable_commit_record();
+    }
+
+    /// 取出啟用記錄後上屏的文字
+    pub fn take_committed(&mut self) -> Vec<String> {
+        self.state.take_committed()
+    }
+
+    /// 取出因超過 max_output_chars 而移出輸出區的較早內容
+    pub fn take_archived_output(&mut self) -> Vec<String> {
+        self.state.take_archived()
+    }
+
+    /// 取得字典的唯讀參考
+    pub fn dict(&self) -> &Dictionary {
+        &self.dict
+    }
+
+    /// 取得字典的可變參考（編輯自訂字詞），變更在下次查詢時生效
+    pub fn dict_mut(&mut self) -> &mut Dictionary {
+        self.dict_revision += 1;
+        &mut self.dict
+    }
+
+    /// 字典的修訂次數
+    pub fn dict_revision(&self) -> u64 {
+        self.dict_revision
+    }
+
     /// 取得當前狀態的唯讀參考
     pub fn state(&self) -> &InputState {
         &self.state
     }
 
-    /// 取得當前候選列表
-    pub fn candidates(&self) -> &[Candidate] {
-        &self.candidates
+    /// 建立全部候選（候選多時較慢，介面只需 current_page_candidates）
+    pub fn candidates(&self) -> Vec<Candidate> {
+        self.candidates.iter().filter_map(|&cand| self.materialize(cand)).collect()
+    }
+
+    /// 候選總數
+    pub fn candidate_count(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// 取得預覽候選
+    /// 為延伸目前碼可得到的候選，依碼長排序，最多一頁
+    pub fn preview_candidates(&self) -> &[Candidate] {
+        &self.preview
     }
 
     /// 取得當前頁面的候選
     pub fn current_page_candidates(&self) -> &[Candidate] {
-        let start = self.page_index * self.page_size;
-        let end = (start + self.page_size).min(self.candidates.len());
-        &self.candidates[start..end]
+        &self.page
     }
 
-    /// 下一頁
-    pub fn next_page(&mut self) -> bool {
-        let total_pages = (self.candidates.len() + self.page_size - 1) / self.page_size;
-        if self.page_index + 1 < total_pages {
-            self.page_index += 1;
-            true
+    /// 當頁第 index 個候選開始新的一段（字或詞）時回傳該段的標記，供介面分段顯示
+    /// 只有同一個碼同時列出字與詞時才分段
+    pub fn section_marker(&self, index: usize) -> Option<&'static str> {
+        let page = self.current_page_candidates();
+        let cand = page.get(index)?;
+        if cand.big_set {
+            return (index == 0 || !page[index - 1].big_set).then_some("〔大字集〕");
+        }
+        let mixed = self.candidates.iter().any(|c| c.is_phrase()) && self.candidates.iter().any(|c| !c.is_phrase());
+        let starts = index == 0 || page[index - 1].is_phrase != cand.is_phrase;
+        match (mixed && starts, cand.is_phrase) {
+            (false, _) => None,
+            (true, true) => Some("〔詞〕"),
+            (true, false) => Some("〔字〕"),
+        }
+    }
+
+    /// 目前頁碼（從 0 開始）
+    pub fn page_index(&self) -> usize {
+        self.page_index
+    }
+
+    /// 候選總頁數
+    pub fn page_count(&self) -> usize {
+        (self.candidates.len() + self.page_size - 1) / self.page_size
+    }
+
+    /// 目前頁碼與總頁數（從 1 開始），沒有候選時為 None
+    pub fn page_indicator(&self) -> Option<(usize, usize)> {
+        (!self.candidates.is_empty()).then(|| (self.page_index + 1, self.page_count()))
+    }
+
+    /// 切換數字組碼模式
+    pub fn toggle_digit_codes(&mut self) {
+        self.digit_codes = !self.digit_codes;
+    }
+
+    /// 是否為數字組碼模式
+    pub fn is_digit_code_mode(&self) -> bool {
+        self.digit_codes
+    }
+
+    /// 目前的組字模式
+    pub fn compose_mode(&self) -> ComposeMode {
+        let mut code = self.state.current_code.chars();
+        if self.state.mode == InputMode::PhraseInput {
+            ComposeMode::Phrase
+        } else if self.digit_codes {
+            ComposeMode::DigitCode
+        } else if self.options.variant == ArrayVariant::Array30
+            && code.next() == Some('w')
+            && code.next().is_some_and(|c| c.is_ascii_digit())
+        {
+            ComposeMode::Symbol
         } else {
-            false
+            ComposeMode::Normal
         }
     }
 
-    /// 上一頁
+    /// 是否為英文模式
+    pub fn is_english_mode(&self) -> bool {
+        self.state.english_mode
+    }
+
+    /// 使用中字表的名稱
+    pub fn table_name(&self) -> &str {
+        self.dict.name()
+    }
+
+    /// 字典的 (單字, 詞彙) 項目數
+    pub fn entry_counts(&self) -> (usize, usize) {
+        self.dict.entry_counts()
+    }
+
+    /// 下一頁；已在最後一頁時產生 PageBoundary 回饋
+    pub fn next_page(&mut self) -> bool {
+        self.turn_page_with_feedback(true)
+    }
+
+    /// 上一頁；已在第一頁時產生 PageBoundary 回饋
     pub fn prev_page(&mut self) -> bool {
-        if self.page_index > 0 {
+        self.turn_page_with_feedback(false)
+    }
+
+    fn turn_page_with_feedback(&mut self, forward: bool) -> bool {
+        let turned = self.turn_page(forward);
+        if !turned && !self.candidates.is_empty() {
+            self.push_feedback(Feedback::PageBoundary);
+        }
+        turned
+    }
+
+    /// 翻頁，回傳是否有換頁
+    fn turn_page(&mut self, forward: bool) -> bool {
+        if forward && self.page_index + 1 < self.page_count() {
+            self.page_index += 1;
+        } else if !forward && self.page_index > 0 {
             self.page_index -= 1;
-            true
         } else {
-            false
+            return false;
         }
+        self.fill_page();
+        true
+    }
+
+    /// 切換中文／英文模式
+    /// 切換時捨棄未完成的組字
+    pub fn toggle_english_mode(&mut self) {
+        self.state.clear_composing();
+        self.clear_candidates();
+        self.state.english_mode = !self.state.english_mode;
+        tracing::debug!(english_mode = self.state.english_mode, "切換中英文模式");
+    }
+
+    /// 復原最後一次上屏
+    pub fn undo(&mut self) -> bool {
+        self.state.undo_commit()
+    }
+
+    /// 取出已上屏的文字並清空輸出區
+    pub fn take_output(&mut self) -> String {
+        self.state.take_output()
+    }
+
+    /// 將文字附加到輸出區（可復原）
+    pub fn insert_output(&mut self, text: &str) {
+        self.state.commit_direct(text);
+    }
+
+    /// 在輸出區第 at 個字元前插入文字（編輯用，不是上屏）
+    pub fn insert_output_at(&mut self, at: usize, text: &str) {
+        self.state.insert_output_at(at, text);
+    }
+
+    /// 刪除輸出區第 at 個字元
+    pub fn delete_output_at(&mut self, at: usize) -> bool {
+        self.state.delete_output_at(at)
+    }
+
+    /// 以手動編輯後的文字取代輸出區
+    pub fn set_output(&mut self, text: String) {
+        self.state.replace_output(text);
+    }
+
+    /// 與另一份輸出文件交換輸出區（切換輸出分頁用）
+    pub fn swap_output(&mut self, doc: &mut OutputDoc) {
+        self.state.swap_output(doc);
     }
 
     /// 清空輸出區
@@ -248,7 +1259,8 @@ impl InputEngine {
 }
 
 /// 按鍵處理結果
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum KeyResult {
     /// 無變化
     NoChange,
@@ -256,23 +1268,62 @@ pub enum KeyResult {
     NeedUpdate,
     /// 已確認輸出（需要更新剪貼簿等）
     Committed,
+    /// 按鍵被拒絕（介面可提示使用者）
+    Rejected(RejectReason),
+}
+
+/// 按鍵被拒絕的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// 碼不存在或碼數不正確
+    InvalidCode,
+    /// 已達最長碼長
+    TooManyKeys,
+    /// 沒有候選可確認
+    NoCandidates,
+}
+
+/// 提示回饋：前端依設定以響鈴、閃爍或聲音提醒使用者
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feedback {
+    /// 按鍵被拒絕
+    Rejected(RejectReason),
+    /// 已在第一頁或最後一頁，無法再翻頁
+    PageBoundary,
+    /// 詞彙模式查不到任何字詞
+    EmptyLookup,
+}
+
+impl RejectReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectReason::InvalidCode => "invalid_code",
+            RejectReason::TooManyKeys => "too_many_keys",
+            RejectReason::NoCandidates => "no_candidates",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            RejectReason::InvalidCode => "查無此碼",
+            RejectReason::TooManyKeys => "已達最長碼長",
+            RejectReason::NoCandidates => "沒有候選可確認",
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keymap::KeymapProfile;
 
     fn create_test_dict() -> Dictionary {
         let mut dict = Dictionary::new();
         // 測試用簡單數據
-        dict.char_table
-            .entry("abc".to_string())
-            .or_insert_with(Vec::new)
-            .push("測".to_string());
-        dict.phrase_table
-            .entry("abcd".to_string())
-            .or_insert_with(Vec::new)
-            .push("測試".to_string());
+        dict.insert_char("abc", "測");
+        dict.insert_phrase("abcd", "測試");
         dict
     }
 
@@ -313,4 +1364,493 @@ mod tests {
         engine.handle_key('\x08');
         assert_eq!(engine.state().current_code, "a");
     }
+
+    #[test]
+    fn test_miss_policy() {
+        // 預設保留組字：查無此碼的鍵被拒絕，不進入組字
+        let mut engine = InputEngine::new(create_test_dict());
+        engine.feed_str("xyz\n");
+        assert_eq!(engine.state().current_code, "");
+        assert!(engine.state().output.is_empty());
+
+        let options = EngineOptions {
+            miss_policy: MissPolicy::CommitRaw,
+            ..Default::default()
+        };
+        let mut engine = InputEngine::with_options(create_test_dict(), options);
+        assert_eq!(engine.handle_key('x'), KeyResult::NeedUpdate);
+        engine.handle_key('y');
+        assert_eq!(engine.handle_key('\n'), KeyResult::Committed);
+        assert_eq!(engine.state().output, "xy");
+        assert!(engine.state().current_code.is_empty());
+
+        // 有候選時仍上屏第一候選
+        assert_eq!(engine.feed_str("abc "), "測");
+    }
+
+    #[test]
+    fn test_rejected_keys() {
+        let mut engine = InputEngine::new(create_test_dict());
+        // 詞彙碼的前綴不算錯碼
+        assert_eq!(engine.feed_str("abcd"), "");
+        assert_eq!(engine.handle_key('e'), KeyResult::Rejected(RejectReason::TooManyKeys));
+        assert_eq!(engine.state().current_code, "abcd");
+        assert_eq!(engine.state().raw_keys, "abcd");
+        assert_eq!(engine.handle_key(' '), KeyResult::Rejected(RejectReason::NoCandidates));
+
+        engine.handle_key('\x1b');
+        assert_eq!(engine.handle_key('\''), KeyResult::Rejected(RejectReason::InvalidCode));
+        engine.feed_str("ab");
+        assert_eq!(engine.handle_key('q'), KeyResult::Rejected(RejectReason::InvalidCode));
+        assert_eq!(engine.state().current_code, "ab");
+        assert_eq!(engine.state().raw_keys, "ab");
+    }
+
+    #[test]
+    fn test_phrase_mix() {
+        let mut dict = create_test_dict();
+        dict.insert_char("abcd", "\u{20000}");
+        dict.insert_char("abcd", "字");
+        let texts = |engine: &InputEngine| engine.candidates().iter().map(|c| c.text.clone()).collect::<Vec<_>>();
+
+        let options = EngineOptions {
+            phrase_mix: PhraseMix::CharsFirst,
+            ..Default::default()
+        };
+        let mut engine = InputEngine::with_options(dict.clone(), options);
+        engine.feed_str("abcd");
+        assert_eq!(texts(&engine), ["\u{20000}", "字", "測試"]);
+        let markers: Vec<_> = (0..3).map(|i| engine.section_marker(i)).collect();
+        assert_eq!(markers, [Some("〔字〕"), None, Some("〔詞〕")]);
+
+        // 基本區優先只在各段內排序
+        let options = EngineOptions {
+            phrase_mix: PhraseMix::PhrasesFirst,
+            candidate_order: CandidateOrder::BasicFirst,
+            ..Default::default()
+        };
+        let mut engine = InputEngine::with_options(dict, options);
+        engine.feed_str("abcd");
+        assert_eq!(texts(&engine), ["測試", "字", "\u{20000}"]);
+        // 詞彙模式只列出詞
+        engine.handle_key('\x1b');
+        engine.feed_str("abcd'");
+        assert_eq!(texts(&engine), ["測試"]);
+    }
+
+    #[test]
+    fn test_commit_log() {
+        let mut engine = InputEngine::new(create_test_dict());
+        engine.feed_str("abc ");
+        assert!(engine.take_commit_log().is_empty());
+
+        engine.enable_commit_log();
+        engine.feed_str("abc 1abcd' ");
+        let log = engine.take_commit_log();
+        let texts: Vec<_> = log.iter().map(|c| (c.text.as_str(), c.code.as_str())).collect();
+        assert_eq!(texts, [("測", "abc"), ("測試", "abcd")]);
+        assert!(engine.take_commit_log().is_empty());
+    }
+
+    #[test]
+    fn test_prefix_preview() {
+        let mut engine = InputEngine::new(create_test_dict());
+        engine.handle_key('a');
+        let preview: Vec<_> = engine.preview_candidates().iter().map(|c| &c.code).collect();
+        assert_eq!(preview, ["abc"]);
+
+        engine.feed_str("bc");
+        assert!(engine.preview_candidates().is_empty());
+        assert_eq!(engine.candidates().len(), 1);
+
+        engine.handle_key('\x1b');
+        engine.handle_key('a');
+        assert!(!engine.preview_candidates().is_empty());
+        engine.handle_key('\x1b');
+        assert!(engine.preview_candidates().is_empty());
+    }
+
+    #[test]
+    fn test_keymap_remap() {
+        let options = EngineOptions {
+            keymap: Keymap::for_profile(KeymapProfile::Dvorak),
+            ..Default::default()
+        };
+        let mut engine = InputEngine::with_options(create_test_dict(), options);
+        // Dvorak 的 a x j 位於 QWERTY 的 a b c 鍵位
+        assert_eq!(engine.feed_str("axj "), "測");
+
+        // 螢幕鍵盤直接指定鍵位，不受重映射影響
+        engine.press_key(Array30Key::A);
+        engine.press_key(Array30Key::B);
+        engine.press_key(Array30Key::C);
+        assert_eq!(engine.state().current_code, "abc");
+    }
+
+    #[test]
+    fn test_array40_variant() {
+        let mut dict = create_test_dict();
+        dict.insert_char("a1", "甲");
+        dict.insert_char("a1", "乙");
+        let options = EngineOptions {
+            variant: ArrayVariant::Array40,
+            ..Default::default()
+        };
+        let mut engine = InputEngine::with_options(dict, options);
+
+        // 數字鍵為組碼鍵，Shift+數字選字
+        engine.feed_str("a1");
+        assert_eq!(engine.state().current_code, "a1");
+        assert_eq!(engine.candidates().len(), 2);
+        assert_eq!(engine.handle_key('@'), KeyResult::Committed);
+        assert_eq!(engine.state().output, "乙");
+    }
+
+    #[test]
+    fn test_english_mode_and_undo() {
+        let mut engine = InputEngine::new(create_test_dict());
+        engine.handle_key('a');
+        engine.toggle_english_mode();
+        assert!(engine.state().current_code.is_empty());
+        assert_eq!(engine.feed_str("abc 1"), "abc 1");
+
+        engine.toggle_english_mode();
+        engine.feed_str("abc ");
+        assert_eq!(engine.state().output, "abc 1測");
+        assert!(engine.undo());
+        assert_eq!(engine.state().output, "abc 1");
+    }
+
+    #[test]
+    fn test_engine_behavior_options() {
+        let mut dict = create_test_dict();
+        dict.insert_char("ab", "\u{3400}");
+        dict.insert_char("ab", "甲");
+        dict.insert_char("ab", "乙");
+        let options = EngineOptions {
+            page_size: 2,
+            max_code_len: 3,
+            space_behavior: SpaceBehavior::NextPage,
+            auto_commit: true,
+            punctuation: PunctuationMode::Full,
+            candidate_order: CandidateOrder::BasicFirst,
+            ..Default::default()
+        };
+        let mut engine = InputEngine::with_options(dict, options);
+
+        // 擴充區字排後，空白先翻頁再上屏
+        engine.feed_str("ab");
+        assert_eq!(engine.candidates()[0].text, "甲");
+        assert_eq!(engine.page_count(), 2);
+        assert_eq!(engine.handle_key(' '), KeyResult::NeedUpdate);
+        assert_eq!(engine.page_index(), 1);
+        assert_eq!(engine.feed_str(" "), "\u{3400}");
+
+        // 碼長上限 3，唯一候選自動上屏
+        assert_eq!(engine.feed_str("abc"), "測");
+        assert_eq!(engine.feed_str("!"), "！");
+    }
+
+    #[test]
+    fn test_official_keys() {
+        let mut dict = create_test_dict();
+        dict.insert_char("a", "一");
+        dict.insert_char("a1b", "特");
+        dict.quick_table.insert("a".to_string(), ["到", QUICK_PLACEHOLDER, "聽"].map(String::from).to_vec());
+        let options = EngineOptions {
+            official_keys: true,
+            ..Default::default()
+        };
+        let mut engine = InputEngine::with_options(dict.clone(), options);
+
+        // 空白鍵上屏簡碼字，數字鍵依位置選簡碼選單
+        assert_eq!(engine.feed_str("a "), "一");
+        assert_eq!(engine.feed_str("a3"), "聽");
+        engine.feed_str("a");
+        assert_eq!(engine.short_code().map(|c| c.text.as_str()), Some("一"));
+        // 簡碼選單的空位不上屏，組字維持不變
+        let texts = |engine: &InputEngine| engine.candidates().iter().map(|c| c.text.clone()).collect::<Vec<_>>();
+        let candidates = texts(&engine);
+        assert_eq!(engine.handle_key('2'), KeyResult::NeedUpdate);
+        assert_eq!(engine.state().output, "一聽");
+        assert_eq!(engine.state().current_code, "a");
+        assert_eq!(texts(&engine), candidates);
+        // 數字鍵接成特別碼
+        engine.handle_key('\x1b');
+        assert_eq!(engine.feed_str("a1b "), "特");
+
+        // 一般模式：數字鍵選字
+        let mut engine = InputEngine::new(dict);
+        assert_eq!(engine.feed_str("a1"), "一");
+    }
+
+    #[test]
+    fn test_digit_codes() {
+        let mut dict = create_test_dict();
+        dict.insert_char("a", "一");
+        dict.insert_char("a1", "壹");
+        let mut engine = InputEngine::new(dict);
+        assert_eq!(engine.feed_str("a1"), "一");
+
+        engine.toggle_digit_codes();
+        assert_eq!(engine.compose_mode(), ComposeMode::DigitCode);
+        assert_eq!(engine.feed_str("a1"), "");
+        assert_eq!(engine.state().current_code, "a1");
+        assert_eq!(engine.feed_str(" "), "壹");
+        assert_eq!(engine.handle_key('2'), KeyResult::Rejected(RejectReason::InvalidCode));
+    }
+
+    #[test]
+    fn test_candidate_filter() {
+        let mut dict = create_test_dict();
+        for c in ["\u{3400}", "\u{20000}", "字"] {
+            dict.insert_char("abcd", c);
+        }
+        let texts = |engine: &InputEngine| engine.candidates().iter().map(|c| c.text.clone()).collect::<Vec<_>>();
+        let options = EngineOptions {
+            phrase_mix: PhraseMix::CharsFirst,
+            ..Default::default()
+        };
+        let mut engine = InputEngine::with_options(dict, options);
+        engine.feed_str("abcd");
+        assert_eq!(texts(&engine).len(), 4);
+
+        let cases = [
+            (CandidateFilter::CharsOnly, vec!["\u{3400}", "\u{20000}", "字"]),
+            (CandidateFilter::PhrasesOnly, vec!["測試"]),
+            (CandidateFilter::ExcludeRare, vec!["字", "測試"]),
+            (CandidateFilter::ExcludeExtensions, vec!["\u{3400}", "字", "測試"]),
+        ];
+        for (filter, expected) in cases {
+            engine.set_candidate_filter(filter);
+            assert_eq!(texts(&engine), expected, "{:?}", filter);
+        }
+        assert_eq!(CandidateFilter::from_str("Exclude_Rare"), Some(CandidateFilter::ExcludeRare));
+    }
+
+    #[test]
+    fn test_output_transforms() {
+        use crate::transform::Transform;
+        let options = EngineOptions {
+            transforms: TransformChain::new(vec![Transform::parse_char_map("測\t测"), Transform::FullWidthDigits]),
+            ..EngineOptions::default()
+        };
+        let mut engine = InputEngine::with_options(create_test_dict(), options);
+        assert_eq!(engine.feed_str("abc 5"), "测５");
+        // 復原仍以轉換後的文字為單位
+        assert!(engine.undo());
+        assert_eq!(engine.state().output, "测");
+    }
+
+    #[test]
+    fn test_candidate_annotations() {
+        let mut engine = InputEngine::new(create_test_dict());
+        engine.feed_str("abc");
+        assert!(engine.candidates()[0].readings.is_empty());
+        let annotations = Annotations::parse("測\tcè ㄘㄜˋ\nU+6E2C\tkDefinition\tmeasure, estimate\n");
+        engine.set_annotations(Arc::new(annotations));
+        let cand = &engine.candidates()[0];
+        assert_eq!(cand.text, "測");
+        assert_eq!(cand.readings, ["cè ㄘㄜˋ"]);
+        assert_eq!(cand.definition.as_deref(), Some("measure, estimate"));
+    }
+
+    #[test]
+    fn test_big_set_fallback() {
+        let path = std::env::temp_dir().join(format!("rustarray30-fallback-{}.cin", std::process::id()));
+        std::fs::write(&path, "%chardef begin\nabc\t測\nzzz\t𠀀\n%chardef end\n").unwrap();
+        let mut dict = create_test_dict();
+        dict.set_fallback(Some(path.clone()));
+        dict.wait_fallback();
+        let mut engine = InputEngine::new(dict);
+
+        // 字表有的碼不查後備字表
+        engine.feed_str("abc");
+        assert!(!engine.candidates()[0].big_set);
+        assert_eq!(engine.section_marker(0), None);
+
+        engine.feed_str("\x1bzz");
+        assert_eq!(engine.state().current_code, "zz");
+        engine.feed_str("z");
+        assert_eq!(engine.candidates()[0].text, "𠀀");
+        assert!(engine.candidates()[0].big_set);
+        assert_eq!(engine.section_marker(0), Some("〔大字集〕"));
+        assert_eq!(engine.feed_str(" "), "𠀀");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_status_accessors() {
+        let mut dict = create_test_dict();
+        dict.insert_char("w1", "，");
+        let mut engine = InputEngine::new(dict);
+        assert_eq!(engine.page_indicator(), None);
+        assert_eq!(engine.compose_mode(), ComposeMode::Normal);
+
+        engine.feed_str("w1");
+        assert_eq!(engine.compose_mode(), ComposeMode::Symbol);
+        assert_eq!(engine.page_indicator(), Some((1, 1)));
+        assert_eq!(engine.feed_str("1"), "，");
+
+        engine.feed_str("\x1babcd'");
+        assert_eq!(engine.compose_mode(), ComposeMode::Phrase);
+        assert!(engine.entry_counts().0 > 0);
+    }
+
+    #[test]
+    fn test_compose_code() {
+        let dict = create_test_dict();
+        let mut engine = InputEngine::new(dict);
+        engine.feed_str("ab");
+
+        engine.compose_code("abcd", true);
+        assert_eq!(engine.compose_mode(), ComposeMode::Phrase);
+        assert_eq!(engine.state().raw_keys, "abcd'");
+        assert_eq!(engine.candidates()[0].text, "測試");
+
+        engine.compose_code("abc", false);
+        assert_eq!(engine.compose_mode(), ComposeMode::Normal);
+        assert_eq!(engine.candidates()[0].text, "測");
+        assert_eq!(engine.state().output, "");
+    }
+
+    #[test]
+    fn test_remote_lookup_in_background() {
+        use crate::remote::CachedBackend;
+        struct Slow;
+        impl DictBackend for Slow {
+            fn lookup(&self, code: &str) -> std::io::Result<RemoteWords> {
+                std::thread::sleep(Duration::from_millis(20));
+                Ok(RemoteWords {
+                    chars: vec![format!("遠{}", code)],
+                    phrases: Vec::new(),
+                })
+            }
+            fn reverse(&self, _: &str) -> std::io::Result<Vec<String>> {
+                Ok(Vec::new())
+            }
+        }
+
+        let mut engine = InputEngine::new(create_test_dict());
+        engine.set_remote(Some(Arc::new(CachedBackend::new(Box::new(Slow), 8))));
+        // 本機候選立即出現，遠端字詞查到後才接在後面
+        engine.feed_str("abc");
+        assert!(engine.remote_pending());
+        let local = engine.candidate_count();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !engine.poll_remote() {
+            assert!(Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(engine.candidate_count(), local + 1);
+        assert_eq!(engine.candidates().last().unwrap().text, "遠abc");
+
+        // 查過的碼直接用快取
+        engine.feed_str("\x1babc");
+        assert!(!engine.remote_pending());
+        assert_eq!(engine.candidate_count(), local + 1);
+    }
+
+    #[test]
+    fn test_remote_lookup_failure() {
+        struct Down;
+        impl DictBackend for Down {
+            fn lookup(&self, _: &str) -> std::io::Result<RemoteWords> {
+                Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+            }
+            fn reverse(&self, _: &str) -> std::io::Result<Vec<String>> {
+                Ok(Vec::new())
+            }
+        }
+
+        let mut engine = InputEngine::new(create_test_dict());
+        engine.set_remote(Some(Arc::new(Down)));
+        engine.feed_str("abc");
+        let local = engine.candidate_count();
+        // 查詢失敗時不再等待，候選維持本機字詞
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while engine.remote_pending() {
+            assert!(!engine.poll_remote());
+            assert!(Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(engine.candidate_count(), local);
+    }
+
+    #[test]
+    fn test_feed_str() {
+        let dict = create_test_dict();
+        let mut engine = InputEngine::new(dict);
+
+        assert_eq!(engine.feed_str("abc "), "測");
+        assert_eq!(engine.feed_str("abcd' "), "測試");
+        assert_eq!(engine.state().output, "測測試");
+    }
+
+    #[test]
+    fn test_feedback() {
+        let dict = create_test_dict();
+        let mut engine = InputEngine::new(dict);
+        engine.feed_str("x");
+        assert!(engine.take_feedback().is_empty());
+
+        engine.enable_feedback();
+        engine.feed_str("\x1babc");
+        assert!(!engine.next_page() && !engine.prev_page());
+        engine.feed_str("\x1bzab'");
+        assert_eq!(
+            engine.take_feedback(),
+            [
+                Feedback::PageBoundary,
+                Feedback::PageBoundary,
+                Feedback::Rejected(RejectReason::InvalidCode),
+                Feedback::EmptyLookup,
+            ]
+        );
+        assert!(engine.take_feedback().is_empty());
+    }
+
+    #[test]
+    fn test_composition_timeout() {
+        let dict = create_test_dict();
+        let mut options = EngineOptions {
+            composition_timeout: Some(Duration::from_secs(10)),
+            ..EngineOptions::default()
+        };
+        let mut engine = InputEngine::with_options(dict, options.clone());
+        assert_eq!(engine.composition_deadline(), None);
+        engine.feed_str("ab");
+        let deadline = engine.composition_deadline().unwrap();
+        assert_eq!(engine.tick(deadline - Duration::from_secs(1)), KeyResult::NoChange);
+        assert_eq!(engine.tick(deadline), KeyResult::NeedUpdate);
+        assert!(engine.state().current_code.is_empty() && engine.state().output.is_empty());
+        assert_eq!(engine.composition_deadline(), None);
+
+        options.timeout_action = TimeoutAction::Commit;
+        engine.set_options(options);
+        engine.feed_str("abc");
+        let deadline = engine.composition_deadline().unwrap();
+        engine.tick(deadline);
+        assert_eq!(engine.state().output, "測");
+    }
+
+    #[test]
+    fn test_lazy_candidate_pages() {
+        let mut dict = Dictionary::new();
+        let chars: Vec<String> = (0..300).map(|i| char::from_u32(0x4e00 + i).unwrap().to_string()).collect();
+        for c in &chars {
+            dict.insert_char("a", c);
+        }
+        let mut engine = InputEngine::new(dict);
+        engine.feed_str("a");
+        assert_eq!(engine.candidate_count(), 300);
+        assert_eq!(engine.current_page_candidates().len(), PAGE_SIZE);
+        assert_eq!(engine.page_indicator(), Some((1, 34)));
+
+        assert!(engine.next_page() && engine.next_page());
+        assert_eq!(engine.current_page_candidates()[0].text, chars[2 * PAGE_SIZE]);
+        assert!(engine.prev_page());
+        assert_eq!(engine.feed_str("2"), chars[PAGE_SIZE + 1]);
+        assert!(engine.current_page_candidates().is_empty());
+    }
 }
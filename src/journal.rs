@@ -0,0 +1,139 @@
+// Crash-safe output journal
+// 輸出日誌：每次上屏的文字立即附加到日誌檔（定期 fsync），程式異常結束後下次啟動可恢復未儲存的輸出
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 日誌檔名（位於使用者資料目錄）
+const JOURNAL_FILENAME: &str = "journal.jsonl";
+/// 寫入後最多隔多久 fsync 一次
+const SYNC_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 輸出日誌：每行為一段上屏文字（JSON 字串）
+pub struct OutputJournal {
+    path: PathBuf,
+    file: File,
+    /// 有尚未 fsync 的寫入
+    dirty: bool,
+    synced_at: Instant,
+}
+
+impl OutputJournal {
+    /// 預設的日誌檔路徑
+    pub fn file_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rustarray30").join(JOURNAL_FILENAME))
+    }
+
+    /// 開啟日誌並讀出上次留下的內容（正常結束時日誌已刪除，有內容表示上次異常結束）
+    /// 留下的記錄保留在日誌中，直到呼叫 reset
+    pub fn open(path: &Path) -> io::Result<(Self, String)> {
+        let content = match std::fs::read(path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        // 不完整的最後一行先斷行，新的記錄不接在它後面
+        if !content.is_empty() && !content.ends_with('\n') {
+            file.write_all(b"\n")?;
+        }
+        let journal = Self {
+            path: path.to_path_buf(),
+            file,
+            dirty: false,
+            synced_at: Instant::now(),
+        };
+        Ok((journal, parse(&content)))
+    }
+
+    /// 附加一段上屏文字
+    pub fn append(&mut self, text: &str) -> io::Result<()> {
+        let line = serde_json::to_string(text).map_err(io::Error::other)?;
+        self.file.write_all(format!("{}\n", line).as_bytes())?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// 下次 fsync 前還需等待的時間（沒有尚未 fsync 的寫入時為 None）
+    pub fn sync_due_in(&self) -> Option<Duration> {
+        self.dirty.then(|| SYNC_INTERVAL.saturating_sub(self.synced_at.elapsed()))
+    }
+
+    /// 距上次 fsync 已超過間隔時寫入磁碟（每個畫面呼叫）
+    pub fn sync_if_due(&mut self) -> io::Result<()> {
+        if self.dirty && self.synced_at.elapsed() >= SYNC_INTERVAL {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_data()?;
+        self.dirty = false;
+        self.synced_at = Instant::now();
+        Ok(())
+    }
+
+    /// 清空日誌（捨棄之前的記錄），之後以 `text` 開頭
+    /// 輸出區被復原、清除、編輯或儲存後以目前的輸出重設，恢復時不會重複或找回已刪除的文字
+    pub fn reset(&mut self, text: &str) -> io::Result<()> {
+        self.file.set_len(0)?;
+        if !text.is_empty() {
+            self.append(text)?;
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// 正常結束：刪除日誌檔
+    pub fn close(self) -> io::Result<()> {
+        drop(self.file);
+        std::fs::remove_file(&self.path)
+    }
+}
+
+/// 串接日誌中的文字；程式在寫入途中結束時，最後不完整的一行略過
+fn parse(content: &str) -> String {
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<String>(line).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_recovery() {
+        let path = std::env::temp_dir().join(format!("rustarray30-journal-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (mut journal, recovered) = OutputJournal::open(&path).unwrap();
+        assert!(recovered.is_empty());
+        assert_eq!(journal.sync_due_in(), None);
+        journal.append("台灣").unwrap();
+        assert!(journal.sync_due_in().is_some_and(|delay| delay <= SYNC_INTERVAL));
+        journal.append("\n\"好\"").unwrap();
+        // 模擬異常結束：未呼叫 close，最後一行只寫了一半
+        drop(journal);
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"\"\xe5").unwrap();
+
+        let (mut journal, recovered) = OutputJournal::open(&path).unwrap();
+        assert_eq!(recovered, "台灣\n\"好\"");
+        journal.append("們").unwrap();
+        drop(journal);
+        let (mut journal, recovered) = OutputJournal::open(&path).unwrap();
+        assert_eq!(recovered, "台灣\n\"好\"們");
+        journal.reset("大家").unwrap();
+        drop(journal);
+        let (journal, recovered) = OutputJournal::open(&path).unwrap();
+        assert_eq!(recovered, "大家");
+        journal.close().unwrap();
+        assert!(!path.exists());
+    }
+}
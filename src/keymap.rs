@@ -1,6 +1,10 @@
 // Key mapping for Array30 Input Method
 // 行列 30 鍵位配置
 
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
 /// Array30 鍵盤配置
 /// 將行列鍵碼對應到實際按鍵
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -37,7 +41,116 @@ pub enum Array30Key {
     Comma,  // 8v
 }
 
+/// 鍵位所在列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KeyRow {
+    /// 上列（^）
+    Top,
+    /// 中列（-）
+    Home,
+    /// 下列（v）
+    Bottom,
+}
+
+impl KeyRow {
+    /// 行列標示符號
+    pub fn symbol(&self) -> char {
+        match self {
+            KeyRow::Top => '^',
+            KeyRow::Home => '-',
+            KeyRow::Bottom => 'v',
+        }
+    }
+}
+
 impl Array30Key {
+    /// 所有鍵（依列舉順序）
+    pub const ALL: [Array30Key; 30] = [
+        Array30Key::A,
+        Array30Key::B,
+        Array30Key::C,
+        Array30Key::D,
+        Array30Key::E,
+        Array30Key::F,
+        Array30Key::G,
+        Array30Key::H,
+        Array30Key::I,
+        Array30Key::J,
+        Array30Key::K,
+        Array30Key::L,
+        Array30Key::M,
+        Array30Key::N,
+        Array30Key::O,
+        Array30Key::P,
+        Array30Key::Q,
+        Array30Key::R,
+        Array30Key::S,
+        Array30Key::T,
+        Array30Key::U,
+        Array30Key::V,
+        Array30Key::W,
+        Array30Key::X,
+        Array30Key::Y,
+        Array30Key::Z,
+        Array30Key::Period,
+        Array30Key::Slash,
+        Array30Key::Semicolon,
+        Array30Key::Comma,
+    ];
+
+    /// 依列舉順序走訪所有鍵
+    pub fn all() -> impl Iterator<Item = Array30Key> {
+        Self::ALL.into_iter()
+    }
+
+    /// 鍵位所在行（1-10，第 10 行標示為 0）
+    pub fn column(&self) -> u8 {
+        match self {
+            Array30Key::A | Array30Key::Q | Array30Key::Z => 1,
+            Array30Key::S | Array30Key::W | Array30Key::X => 2,
+            Array30Key::D | Array30Key::E | Array30Key::C => 3,
+            Array30Key::F | Array30Key::R | Array30Key::V => 4,
+            Array30Key::G | Array30Key::T | Array30Key::B => 5,
+            Array30Key::H | Array30Key::Y | Array30Key::N => 6,
+            Array30Key::J | Array30Key::U | Array30Key::M => 7,
+            Array30Key::K | Array30Key::I | Array30Key::Comma => 8,
+            Array30Key::L | Array30Key::O | Array30Key::Period => 9,
+            Array30Key::Semicolon | Array30Key::P | Array30Key::Slash => 10,
+        }
+    }
+
+    /// 鍵位所在列
+    pub fn row(&self) -> KeyRow {
+        match self {
+            Array30Key::Q
+            | Array30Key::W
+            | Array30Key::E
+            | Array30Key::R
+            | Array30Key::T
+            | Array30Key::Y
+            | Array30Key::U
+            | Array30Key::I
+            | Array30Key::O
+            | Array30Key::P => KeyRow::Top,
+            Array30Key::Z
+            | Array30Key::X
+            | Array30Key::C
+            | Array30Key::V
+            | Array30Key::B
+            | Array30Key::N
+            | Array30Key::M
+            | Array30Key::Comma
+            | Array30Key::Period
+            | Array30Key::Slash => KeyRow::Bottom,
+            _ => KeyRow::Home,
+        }
+    }
+
+    /// 行列標示，例如 A 為 "1-"、B 為 "5v"、P 為 "0^"
+    pub fn label(&self) -> String {
+        format!("{}{}", self.column() % 10, self.row().symbol())
+    }
+
     /// 從字元轉換為 Array30Key
     pub fn from_char(c: char) -> Option<Self> {
         match c {
@@ -113,6 +226,243 @@ impl Array30Key {
     }
 }
 
+/// 將碼轉為以空白分隔的行列標示，例如 "ab" 為 "1- 5v"
+/// 非行列鍵（如詞彙標記 '）原樣保留
+pub fn code_labels(code: &str) -> String {
+    code.chars()
+        .map(|c| match Array30Key::from_char(c) {
+            Some(key) if c != '\'' => key.label(),
+            _ => c.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 行列輸入法變體
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrayVariant {
+    /// 行列 30：30 個字母鍵組碼，數字鍵選字
+    #[default]
+    Array30,
+    /// 行列 40：加上數字列共 40 鍵組碼，Shift+數字選字
+    Array40,
+    /// 行列 10：僅以數字鍵組碼（數字鍵盤），空白鍵選第一候選
+    Array10,
+}
+
+impl ArrayVariant {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArrayVariant::Array30 => "array30",
+            ArrayVariant::Array40 => "array40",
+            ArrayVariant::Array10 => "array10",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ArrayVariant::Array30 => "行列 30",
+            ArrayVariant::Array40 => "行列 40",
+            ArrayVariant::Array10 => "行列 10",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "array30" | "30" => Some(ArrayVariant::Array30),
+            "array40" | "40" => Some(ArrayVariant::Array40),
+            "array10" | "10" => Some(ArrayVariant::Array10),
+            _ => None,
+        }
+    }
+
+    /// 所有變體（用於選單）
+    pub fn all() -> [ArrayVariant; 3] {
+        [ArrayVariant::Array30, ArrayVariant::Array40, ArrayVariant::Array10]
+    }
+
+    /// 是否為組碼鍵
+    pub fn is_code_key(&self, c: char) -> bool {
+        match self {
+            ArrayVariant::Array30 => c != '\'' && Array30Key::from_char(c).is_some(),
+            ArrayVariant::Array40 => {
+                c.is_ascii_digit() || (c != '\'' && Array30Key::from_char(c).is_some())
+            }
+            ArrayVariant::Array10 => c.is_ascii_digit(),
+        }
+    }
+
+    /// 字表碼可用的鍵：組碼鍵加上特別碼中的數字（如 W+數字符號表）
+    pub fn is_table_key(&self, c: char) -> bool {
+        self.is_code_key(c) || c.is_ascii_digit()
+    }
+
+    /// 選字鍵，依序對應當頁第 1、2、3… 個候選
+    pub fn selection_keys(&self) -> &'static str {
+        match self {
+            ArrayVariant::Array30 => "1234567890",
+            ArrayVariant::Array40 => "!@#$%^&*()",
+            ArrayVariant::Array10 => "",
+        }
+    }
+
+    /// 取得選字鍵對應的候選索引
+    pub fn selection_index(&self, c: char) -> Option<usize> {
+        self.selection_keys().chars().position(|k| k == c)
+    }
+
+    /// 最長碼長
+    pub fn max_code_len(&self) -> usize {
+        match self {
+            ArrayVariant::Array30 | ArrayVariant::Array40 => 4,
+            ArrayVariant::Array10 => 5,
+        }
+    }
+}
+
+/// 實體鍵盤配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapProfile {
+    /// 標準 QWERTY（不重映射）
+    #[default]
+    Qwerty,
+    /// Dvorak
+    Dvorak,
+    /// Colemak
+    Colemak,
+    /// 自訂對應檔
+    Custom,
+}
+
+impl KeymapProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeymapProfile::Qwerty => "qwerty",
+            KeymapProfile::Dvorak => "dvorak",
+            KeymapProfile::Colemak => "colemak",
+            KeymapProfile::Custom => "custom",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            KeymapProfile::Qwerty => "QWERTY",
+            KeymapProfile::Dvorak => "Dvorak",
+            KeymapProfile::Colemak => "Colemak",
+            KeymapProfile::Custom => "自訂",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "qwerty" => Some(KeymapProfile::Qwerty),
+            "dvorak" => Some(KeymapProfile::Dvorak),
+            "colemak" => Some(KeymapProfile::Colemak),
+            "custom" => Some(KeymapProfile::Custom),
+            _ => None,
+        }
+    }
+
+    /// 所有配置（用於選單）
+    pub fn all() -> [KeymapProfile; 4] {
+        [
+            KeymapProfile::Qwerty,
+            KeymapProfile::Dvorak,
+            KeymapProfile::Colemak,
+            KeymapProfile::Custom,
+        ]
+    }
+}
+
+/// QWERTY 各鍵位的字元（上、中、下三列，再加上 ' 詞彙鍵）
+const QWERTY_LAYOUT: &str = "qwertyuiopasdfghjkl;zxcvbnm,./'";
+/// Dvorak 在相同鍵位輸出的字元
+const DVORAK_LAYOUT: &str = "',.pyfgcrlaoeuidhtns;qjkxbmwvz-";
+/// Colemak 在相同鍵位輸出的字元
+const COLEMAK_LAYOUT: &str = "qwfpgjluy;arstdhneiozxcvbkm,./'";
+
+/// 按鍵重映射表
+/// 將實體配置輸出的字元轉回 QWERTY 同一鍵位的字元，使行列鍵位不受作業系統鍵盤配置影響
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keymap {
+    map: HashMap<char, char>,
+}
+
+impl Keymap {
+    /// QWERTY：不做任何轉換
+    pub fn qwerty() -> Self {
+        Self::default()
+    }
+
+    /// 取得內建配置的對應表
+    /// `Custom` 需另外以 `load_file` 載入，此處回傳 QWERTY
+    pub fn for_profile(profile: KeymapProfile) -> Self {
+        match profile {
+            KeymapProfile::Qwerty | KeymapProfile::Custom => Self::qwerty(),
+            KeymapProfile::Dvorak => Self::from_layout(DVORAK_LAYOUT),
+            KeymapProfile::Colemak => Self::from_layout(COLEMAK_LAYOUT),
+        }
+    }
+
+    /// 由與 QWERTY_LAYOUT 鍵位對齊的字串建立對應表
+    fn from_layout(layout: &str) -> Self {
+        let map = layout
+            .chars()
+            .zip(QWERTY_LAYOUT.chars())
+            .filter(|(from, to)| from != to)
+            .collect();
+        Self { map }
+    }
+
+    /// 載入自訂對應檔
+    pub fn load_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&content))
+    }
+
+    /// 解析自訂對應
+    /// 格式：每行 "實體字元 行列字元"，以空白分隔，# 開頭為註解
+    /// 例如 "o s" 表示按下輸出 o 的鍵時視為行列的 s 鍵
+    pub fn parse(content: &str) -> Self {
+        let mut map = HashMap::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            if let (Some(from), Some(to)) = (fields.next(), fields.next()) {
+                let mut from_chars = from.chars();
+                let mut to_chars = to.chars();
+                if let (Some(f), None, Some(t), None) = (
+                    from_chars.next(),
+                    from_chars.next(),
+                    to_chars.next(),
+                    to_chars.next(),
+                ) {
+                    map.insert(f, t);
+                }
+            }
+        }
+
+        Self { map }
+    }
+
+    /// 轉換按鍵字元
+    pub fn remap(&self, c: char) -> char {
+        self.map.get(&c).copied().unwrap_or(c)
+    }
+
+    /// 是否為不做轉換的對應表
+    pub fn is_identity(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,4 +475,82 @@ mod tests {
         assert_eq!(Array30Key::from_char('\''), Some(Array30Key::Slash));
         assert_eq!(Array30Key::from_char('1'), None);
     }
+
+    #[test]
+    fn test_key_labels() {
+        assert_eq!(Array30Key::A.label(), "1-");
+        assert_eq!(Array30Key::B.label(), "5v");
+        assert_eq!(Array30Key::P.label(), "0^");
+        assert_eq!(Array30Key::Semicolon.label(), "0-");
+        assert_eq!(Array30Key::Comma.column(), 8);
+        assert_eq!(Array30Key::Comma.row(), KeyRow::Bottom);
+        assert_eq!(code_labels("ab;"), "1- 5v 0-");
+        assert_eq!(code_labels("a'"), "1- '");
+    }
+
+    #[test]
+    fn test_all_keys() {
+        assert_eq!(Array30Key::all().count(), 30);
+        // 每個鍵位（行、列）恰好對應一個鍵
+        let mut positions: Vec<_> = Array30Key::all().map(|k| (k.row(), k.column())).collect();
+        positions.sort();
+        positions.dedup();
+        assert_eq!(positions.len(), 30);
+        // 鍵與字元互相對應
+        for key in Array30Key::all() {
+            assert_eq!(Array30Key::from_char(key.code_char()), Some(key));
+        }
+    }
+
+    #[test]
+    fn test_array_variants() {
+        let array30 = ArrayVariant::Array30;
+        assert!(array30.is_code_key('a'));
+        assert!(array30.is_code_key(';'));
+        assert!(!array30.is_code_key('1'));
+        assert!(!array30.is_code_key('\''));
+        assert!(array30.is_table_key('1'));
+        assert!(!array30.is_table_key('\''));
+        assert_eq!(array30.selection_index('1'), Some(0));
+        assert_eq!(array30.selection_index('0'), Some(9));
+
+        let array40 = ArrayVariant::Array40;
+        assert!(array40.is_code_key('1'));
+        assert!(array40.is_code_key('a'));
+        assert_eq!(array40.selection_index('!'), Some(0));
+        assert_eq!(array40.selection_index('1'), None);
+
+        let array10 = ArrayVariant::Array10;
+        assert!(array10.is_code_key('5'));
+        assert!(!array10.is_code_key('a'));
+        assert_eq!(array10.selection_index('1'), None);
+
+        assert_eq!(ArrayVariant::from_str("40"), Some(ArrayVariant::Array40));
+    }
+
+    #[test]
+    fn test_keymap_profiles() {
+        let qwerty = Keymap::for_profile(KeymapProfile::Qwerty);
+        assert!(qwerty.is_identity());
+        assert_eq!(qwerty.remap('s'), 's');
+
+        // Dvorak 的 o 位於 QWERTY 的 s 鍵位
+        let dvorak = Keymap::for_profile(KeymapProfile::Dvorak);
+        assert_eq!(dvorak.remap('o'), 's');
+        assert_eq!(dvorak.remap('-'), '\'');
+        assert_eq!(dvorak.remap('a'), 'a');
+
+        // Colemak 的 r 位於 QWERTY 的 s 鍵位
+        let colemak = Keymap::for_profile(KeymapProfile::Colemak);
+        assert_eq!(colemak.remap('r'), 's');
+        assert_eq!(colemak.remap('q'), 'q');
+    }
+
+    #[test]
+    fn test_custom_keymap() {
+        let keymap = Keymap::parse("# comment\no s\n\nx y extra\nbad line-too-long\n");
+        assert_eq!(keymap.remap('o'), 's');
+        assert_eq!(keymap.remap('x'), 'y');
+        assert_eq!(keymap.remap('b'), 'b');
+    }
 }
@@ -0,0 +1,85 @@
+// X11 keysym handling shared by the IME front-ends
+// X11 keysym 按鍵處理（IBus、Fcitx5、Wayland、PIME 等前端共用）
+
+use crate::input_engine::InputEngine;
+
+// 修飾鍵遮罩（IBus 與 Fcitx5 相同）
+pub const SHIFT_MASK: u32 = 1 << 0;
+pub const CONTROL_MASK: u32 = 1 << 2;
+pub const MOD1_MASK: u32 = 1 << 3;
+pub const SUPER_MASK: u32 = 1 << 26;
+pub const RELEASE_MASK: u32 = 1 << 30;
+
+// 特殊鍵的 keysym
+pub const KEY_BACKSPACE: u32 = 0xff08;
+pub const KEY_RETURN: u32 = 0xff0d;
+pub const KEY_ESCAPE: u32 = 0xff1b;
+pub const KEY_PAGE_UP: u32 = 0xff55;
+pub const KEY_PAGE_DOWN: u32 = 0xff56;
+pub const KEY_KP_ENTER: u32 = 0xff8d;
+
+/// 處理一個 keysym 按鍵事件
+/// 回傳是否由輸入法處理；未處理的按鍵交還給應用程式
+pub fn process_key(engine: &mut InputEngine, keyval: u32, state: u32) -> bool {
+    if state & (RELEASE_MASK | CONTROL_MASK | MOD1_MASK | SUPER_MASK) != 0 {
+        return false;
+    }
+    if engine.state().english_mode {
+        return false;
+    }
+
+    let composing = !engine.state().current_code.is_empty();
+    let key = match keyval {
+        KEY_BACKSPACE => '\x08',
+        KEY_RETURN | KEY_KP_ENTER => '\n',
+        KEY_ESCAPE => '\x1b',
+        KEY_PAGE_UP => return composing && engine.prev_page(),
+        KEY_PAGE_DOWN => return composing && engine.next_page(),
+        0x20..=0x7e => match char::from_u32(keyval) {
+            Some(c) => c,
+            None => return false,
+        },
+        _ => return false,
+    };
+
+    // 未組字時只攔截字根鍵與詞彙標記，其餘按鍵（含 Shift 組合）直接交還
+    if !composing {
+        let c = engine.options().keymap.remap(key);
+        if state & SHIFT_MASK != 0 || !engine.options().variant.is_code_key(c) {
+            return false;
+        }
+    }
+
+    engine.handle_key(key);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::Dictionary;
+
+    fn create_test_engine() -> InputEngine {
+        let mut dict = Dictionary::new();
+        dict.insert_char("ab", "甲");
+        InputEngine::new(dict)
+    }
+
+    #[test]
+    fn test_process_key() {
+        let mut engine = create_test_engine();
+
+        // 未組字時標點與 Shift 字母交還應用程式
+        assert!(!process_key(&mut engine, '-' as u32, 0));
+        assert!(!process_key(&mut engine, 'A' as u32, SHIFT_MASK));
+        assert!(!process_key(&mut engine, KEY_BACKSPACE, 0));
+        assert!(!process_key(&mut engine, 'a' as u32, CONTROL_MASK));
+
+        assert!(process_key(&mut engine, 'a' as u32, 0));
+        assert!(!process_key(&mut engine, 'b' as u32, RELEASE_MASK));
+        assert!(process_key(&mut engine, 'b' as u32, 0));
+        assert!(process_key(&mut engine, ' ' as u32, 0));
+        assert_eq!(engine.take_output(), "甲");
+        assert!(engine.state().current_code.is_empty());
+    }
+}
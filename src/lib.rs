@@ -1,18 +1,107 @@
 // rustarray30 - Array30 Input Method in Rust
 //行列 30 輸入法實作
 
-pub mod config;
+pub mod api;
+pub mod batch;
+pub mod buffers;
+pub mod compiled;
 pub mod dict;
+pub mod error;
 pub mod input_engine;
 pub mod keymap;
+pub mod keysym;
+pub mod pime;
+pub mod remote;
+pub mod shared;
 pub mod state;
+pub mod tablediff;
+pub mod transform;
+pub mod unicode;
 
-// 平台特定模組
-#[cfg(target_os = "windows")]
-pub mod gui;
+// 設定檔、使用者資料與命令列（feature "cli"）
+#[cfg(feature = "cli")]
+pub mod backup;
+
+#[cfg(feature = "cli")]
+pub mod bundle;
+
+#[cfg(feature = "cli")]
+pub mod commands;
+
+#[cfg(feature = "cli")]
+pub mod config;
+
+#[cfg(feature = "cli")]
+pub mod export;
+
+#[cfg(feature = "cli")]
+pub mod frequency;
+
+#[cfg(feature = "cli")]
+pub mod history;
+
+#[cfg(feature = "cli")]
+pub mod import;
+
+#[cfg(feature = "cli")]
+pub mod journal;
+
+#[cfg(feature = "cli")]
+pub mod logging;
+
+#[cfg(feature = "cli")]
+pub mod practice;
+
+#[cfg(feature = "cli")]
+pub mod shortcuts;
+
+#[cfg(feature = "cli")]
+pub mod stats;
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(feature = "cli")]
+pub mod sync;
+
+#[cfg(feature = "cli")]
+pub mod tables;
+
+#[cfg(feature = "cli")]
+pub mod userdict;
+
+// C 介面（feature "capi"，不需命令列的相依套件）
+#[cfg(feature = "capi")]
+pub mod capi;
+
+// 介面與平台特定模組
+#[cfg(feature = "console")]
 pub mod console;
 
-pub use input_engine::InputEngine;
+#[cfg(feature = "gui")]
+pub mod gui;
+
+#[cfg(feature = "gui")]
+pub mod clipwatch;
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+pub mod direct_output;
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+pub mod hotkey;
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+pub mod tray;
+
+#[cfg(all(feature = "cli", unix))]
+pub mod daemon;
+
+#[cfg(all(feature = "ibus", target_os = "linux"))]
+pub mod ibus;
+
+#[cfg(all(feature = "fcitx5", target_os = "linux"))]
+pub mod fcitx5;
+
+#[cfg(all(feature = "wayland", target_os = "linux"))]
+pub mod wayland;
+
+pub use input_engine::{EngineOptions, InputEngine};
+pub use shared::SharedEngine;
 pub use state::InputState;
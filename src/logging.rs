@@ -0,0 +1,62 @@
+// Diagnostic logging setup
+// 診斷記錄：以 tracing 輸出，層級由 -v/-q 或 RUST_LOG 控制，可寫入記錄檔
+
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+/// 指定記錄檔路徑的環境變數
+pub const LOG_FILE_ENV: &str = "ARRAY30_LOG_FILE";
+
+/// 依 -v 次數與 -q 產生預設的過濾規則
+/// 相依套件只顯示警告，本程式的層級隨 -v 提高
+pub fn default_filter(verbose: u8, quiet: bool) -> String {
+    if quiet {
+        return "error".to_string();
+    }
+    let level = match verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    format!("warn,rustarray30={}", level)
+}
+
+/// 初始化記錄
+/// 有設定 RUST_LOG 時以其為準；指定 `log_file` 時附加寫入該檔，否則寫到標準錯誤
+pub fn init(verbose: u8, quiet: bool, log_file: Option<&Path>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter(verbose, quiet)));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match log_file {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            builder.with_ansi(false).with_writer(Mutex::new(file)).try_init()?;
+        }
+        None => builder.with_writer(std::io::stderr).try_init()?,
+    }
+    Ok(())
+}
+
+/// 僅依環境變數初始化記錄（供 Fcitx5 外掛等沒有命令列的前端使用）
+/// 未設定 ARRAY30_LOG_FILE 時不輸出
+pub fn init_from_env() {
+    if let Some(path) = std::env::var_os(LOG_FILE_ENV).filter(|p| !p.is_empty()) {
+        let _ = init(0, false, Some(Path::new(&path)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_filter() {
+        assert_eq!(default_filter(0, false), "warn,rustarray30=warn");
+        assert_eq!(default_filter(1, false), "warn,rustarray30=info");
+        assert_eq!(default_filter(5, false), "warn,rustarray30=trace");
+        assert_eq!(default_filter(2, true), "error");
+    }
+}
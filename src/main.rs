@@ -3,141 +3,914 @@
 
 #![allow(dead_code)]
 
-use std::env;
-use std::path::PathBuf;
+use backup::BackupReason;
+use batch::UnknownCode;
+use clap::builder::{FalseyValueParser, PossibleValuesParser, TypedValueParser};
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 
+mod backup;
+mod batch;
+mod buffers;
+mod bundle;
+mod commands;
+mod compiled;
 mod config;
 mod dict;
+mod error;
+mod export;
+mod frequency;
+mod history;
+mod import;
 mod input_engine;
+mod journal;
 mod keymap;
+mod keysym;
+mod logging;
+mod pime;
+mod practice;
+mod remote;
+mod shared;
+mod shortcuts;
 mod state;
+mod stats;
+mod sync;
+mod tablediff;
+mod tables;
+mod transform;
+mod unicode;
+mod userdict;
 
 // 平台特定模組
-#[cfg(target_os = "windows")]
+#[cfg(feature = "console")]
+mod console;
+
+#[cfg(feature = "gui")]
 mod gui;
 
-#[cfg(not(target_os = "windows"))]
-mod console;
+#[cfg(feature = "gui")]
+mod clipwatch;
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+mod direct_output;
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+mod hotkey;
+
+#[cfg(all(feature = "gui", target_os = "windows"))]
+mod tray;
 
-use dict::Dictionary;
+#[cfg(unix)]
+mod daemon;
 
-#[cfg(target_os = "windows")]
+#[cfg(all(feature = "ibus", target_os = "linux"))]
+mod ibus;
+
+#[cfg(all(feature = "wayland", target_os = "linux"))]
+mod wayland;
+
+use config::Config;
+use dict::{Dictionary, TableKind, WordSource};
+use error::Array30Error;
+use export::ExportFormat;
+use import::ImportFormat;
+use tables::TableOptions;
+
+#[cfg(feature = "gui")]
 use gui::run_gui;
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(feature = "console")]
 use console::run_console;
 
+const TABLE_HELP: &str = "\
+表格檔案位置（相對於表格目錄 table/）：
+  表格目錄依序搜尋：目前目錄、執行檔目錄、使用者資料目錄、設定目錄下的 table/
+  可用 --table-dir、--char-table、--phrase-table 或設定檔的 table_dir、char_table、phrase_table 指定
+  詞庫：table/array30-phrase-20210725.txt
+  字表：table/cin2/ar30-regular-v2023-1.0-20251012.cin2
+       或 table/cin2/ar30-big-v2023-1.0-20251012.cin2（--big）
+  行列 40：table/cin2/array40.cin（設定 array_variant=array40）
+  行列 10：table/cin2/array10.cin（設定 array_variant=array10）
+  符號補充表：table/symbols.cin（--symbols 或設定 symbol_table=true，W+數字後接英文縮寫，例如 w2c「、w5sm 😀）
+  練習課程：table/lessons/*.txt（GUI 打字練習）
+  詞頻：字表與詞庫的字詞後可再接 Tab 與詞頻；候選依來源（字表、補充表、自訂字詞）、詞頻（高者在前）、表格順序排列
+        train 由自己的文章統計的詞頻（使用者資料目錄的 frequency.txt）優先於表格的詞頻
+  編譯字表：compile-table 產生的 .a30t（已含詞庫），以 --char-table 指定後直接記憶體映射，不必解析文字檔
+
+環境變數（命令列參數優先，其次為環境變數，最後為設定檔）：
+  ARRAY30_CONFIG        設定檔路徑
+  ARRAY30_PROFILE       設定檔組合名稱
+  ARRAY30_TABLE_DIR     表格目錄
+  ARRAY30_CHAR_TABLE    字表檔
+  ARRAY30_PHRASE_TABLE  詞庫檔
+  ARRAY30_BIG=1         使用大字集字表
+  ARRAY30_SYMBOLS=1     合併符號補充表
+  ARRAY30_LOG_FILE      記錄檔
+  RUST_LOG              記錄層級（覆寫 -v/-q，例如 rustarray30=debug）";
+
+/// 行列 30 輸入法 - Rust 實作版本
+#[derive(Parser)]
+#[command(name = "rustarray30", version, after_help = TABLE_HELP)]
+struct Cli {
+    /// 使用大字集字表（預設使用標準版）
+    #[arg(short, long, global = true, env = "ARRAY30_BIG", value_parser = FalseyValueParser::new())]
+    big: bool,
+
+    /// 合併 emoji 與全形符號補充表（table/symbols.cin）
+    #[arg(long, global = true, env = "ARRAY30_SYMBOLS", value_parser = FalseyValueParser::new())]
+    symbols: bool,
+
+    /// 表格目錄（覆寫設定檔的 table_dir）
+    #[arg(long, global = true, env = "ARRAY30_TABLE_DIR", value_name = "DIR")]
+    table_dir: Option<String>,
+
+    /// 字表檔（覆寫設定檔的 char_table）
+    #[arg(long, global = true, env = "ARRAY30_CHAR_TABLE", value_name = "FILE")]
+    char_table: Option<String>,
+
+    /// 詞庫檔（覆寫設定檔的 phrase_table）
+    #[arg(long, global = true, env = "ARRAY30_PHRASE_TABLE", value_name = "FILE")]
+    phrase_table: Option<String>,
+
+    /// 使用指定的設定檔組合（不存在時以預設設定建立）
+    #[arg(short, long, global = true, env = "ARRAY30_PROFILE", value_name = "NAME")]
+    profile: Option<String>,
+
+    /// 顯示更多診斷訊息（可重複：-v 資訊、-vv 除錯、-vvv 追蹤）
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// 只顯示錯誤訊息
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// 輸出 man 手冊頁（roff 格式）後結束
+    #[arg(long)]
+    gen_man: bool,
+
+    /// 將診斷訊息附加寫入檔案（終端機模式或由輸入法框架啟動時使用）
+    #[arg(long, global = true, env = logging::LOG_FILE_ENV, value_name = "FILE")]
+    log_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// 未指定子命令時等同 run
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 執行輸入法（預設）
+    Run(RunArgs),
+    /// 列出碼對應的所有單字與詞彙候選（含序號、來源與頁次）
+    Lookup {
+        /// 行列碼，例如 "ab"
+        code: String,
+    },
+    /// 反查字詞的行列碼
+    Reverse {
+        /// 要反查的文字；逐字反查，整段為詞時一併列出詞碼
+        text: String,
+    },
+    /// 將鍵序轉換為文字（未指定時逐行讀取標準輸入）
+    Convert {
+        /// 鍵序，例如 "ab cd' "
+        keys: Option<String>,
+    },
+    /// 將碼序列檔逐行轉換為文字檔
+    ConvertFile {
+        /// 輸入檔（每行為以分隔符號隔開的碼，結尾加 ' 為詞彙）
+        input: PathBuf,
+        /// 輸出檔
+        output: PathBuf,
+        /// 碼之間的分隔符號（預設為空白）
+        #[arg(long, value_name = "STR")]
+        delimiter: Option<String>,
+        /// 無法轉換的碼：keep 原樣保留、skip 略過、error 停止
+        #[arg(
+            long,
+            value_name = "MODE",
+            default_value = "keep",
+            value_parser = PossibleValuesParser::new(UnknownCode::all().map(|u| u.as_str()))
+                .map(|s| UnknownCode::from_str(&s).unwrap_or_default()),
+        )]
+        on_unknown: UnknownCode,
+    },
+    /// 檢查字表內容
+    Validate,
+    /// 顯示字表統計
+    Stats,
+    /// 列出設定檔組合
+    Profiles,
+    /// 匯出或匯入設定（設定檔、設定檔組合與使用者資料）
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// 將自訂字詞匯出為官方 Windows 行列輸入法或 OpenVanilla 的格式
+    ExportUser {
+        /// 輸出檔
+        file: PathBuf,
+        /// 格式：windows 官方 Windows 行列自建詞彙、openvanilla cin 表格
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            default_value = "windows",
+            value_parser = PossibleValuesParser::new(ExportFormat::all().map(|f| f.as_str()))
+                .map(|s| ExportFormat::from_str(&s).unwrap_or(ExportFormat::WindowsArray)),
+        )]
+        format: ExportFormat,
+    },
+    /// 匯入其他輸入法的詞彙，依行列取碼規則產生詞碼後加入自訂字詞
+    ImportPhrases {
+        /// 匯入檔（UTF-8 或有 BOM 的 UTF-16）
+        file: PathBuf,
+        /// 格式：msbopomofo 微軟注音 TSV、rime 使用者詞典、wordlist 每行一詞
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            default_value = "wordlist",
+            value_parser = PossibleValuesParser::new(ImportFormat::all().map(|f| f.as_str()))
+                .map(|s| ImportFormat::from_str(&s).unwrap_or(ImportFormat::WordList)),
+        )]
+        format: ImportFormat,
+    },
+    /// 將詞庫與字表編譯為可記憶體映射的表格檔（.a30t），之後可用 --char-table 指定，啟動時不必解析文字檔
+    CompileTable {
+        /// 輸出檔
+        output: PathBuf,
+    },
+    /// 統計文章中字與詞的出現次數，寫成詞頻檔，候選依自己的用字排序
+    Train {
+        /// 語料檔（UTF-8 或有 BOM 的 UTF-16）
+        corpus: PathBuf,
+        /// 詞頻檔（預設為使用者資料目錄的 frequency.txt，啟動時自動載入）
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// 比較兩版字表（或詞庫），列出新增、移除與改碼的字詞
+    Diff {
+        /// 舊版表格檔（.cin2/.cin/.txt/.a30t）
+        old: PathBuf,
+        /// 新版表格檔
+        new: PathBuf,
+    },
+    /// 與同步資料夾（Dropbox、OneDrive 等）合併自訂字詞、使用統計與設定檔
+    Sync {
+        /// 同步資料夾（預設為設定檔的 sync_folder）
+        #[arg(long, value_name = "DIR")]
+        folder: Option<PathBuf>,
+    },
+    /// 從自動備份還原設定與使用者資料；未指定備份時列出所有備份
+    Restore {
+        /// 備份編號（見不帶參數時的列表）或備份檔路徑
+        backup: Option<String>,
+    },
+    /// 將表格複製到使用者資料目錄，之後可在任何目錄執行
+    InstallTables {
+        /// 來源表格目錄（預設為目前找到的表格目錄）
+        #[arg(long, value_name = "DIR")]
+        from: Option<PathBuf>,
+    },
+    /// 輸出 shell 自動完成腳本，例如 `rustarray30 completions bash > ~/.local/share/bash-completion/completions/rustarray30`
+    Completions {
+        /// 目標 shell
+        shell: Shell,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// 將設定打包為單一檔案
+    Export {
+        /// 輸出檔（JSON）
+        file: PathBuf,
+    },
+    /// 從封包還原設定（覆寫同名檔案）
+    Import {
+        /// 由 export 產生的封包檔
+        file: PathBuf,
+    },
+}
+
+/// 執行模式與選項
+#[derive(Args, Default)]
+struct RunArgs {
+    #[command(flatten)]
+    mode: RunMode,
+    /// GUI 啟動時隱藏視窗（有系統匣時縮到系統匣，否則最小化）
+    #[arg(long)]
+    minimized: bool,
+    /// GUI 監看剪貼簿，複製中文時在反查標註顯示行列碼
+    #[arg(long)]
+    watch_clipboard: bool,
+    /// 終端機模式下將輸出區持續寫入檔案（接在檔案原有內容之後）
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// 執行模式（擇一）
+#[derive(Args, Default)]
+#[group(multiple = false)]
+struct RunMode {
+    /// 使用終端機模式（Windows 以外的預設）
+    #[arg(short, long)]
+    console: bool,
+    /// 使用 GUI 模式（Windows 的預設，需啟用 gui 功能）
+    #[arg(short, long)]
+    gui: bool,
+    /// 從標準輸入讀取以空白分隔的碼，轉換後寫到標準輸出
+    #[arg(long)]
+    pipe: bool,
+    /// 以 PIME 後端模式執行（由 PIMELauncher 啟動）
+    #[arg(long)]
+    pime: bool,
+    /// 以常駐服務模式執行（Unix socket，JSON 協定）
+    #[cfg(unix)]
+    #[arg(long)]
+    daemon: bool,
+    /// 以 IBus 引擎模式執行（由 ibus-daemon 啟動）
+    #[cfg(all(feature = "ibus", target_os = "linux"))]
+    #[arg(long)]
+    ibus: bool,
+    /// 輸出 IBus 元件描述檔
+    #[cfg(all(feature = "ibus", target_os = "linux"))]
+    #[arg(long)]
+    ibus_component: bool,
+    /// 以 Wayland 輸入法模式執行（input-method-v2）
+    #[cfg(all(feature = "wayland", target_os = "linux"))]
+    #[arg(long)]
+    wayland: bool,
+}
+
+impl RunMode {
+    /// 沒有介面的模式（由其他程式啟動或轉換標準輸入），無法提示安裝表格
+    fn is_headless(&self) -> bool {
+        #[allow(unused_mut)]
+        let mut headless = self.pipe || self.pime;
+        #[cfg(unix)]
+        {
+            headless |= self.daemon;
+        }
+        #[cfg(all(feature = "ibus", target_os = "linux"))]
+        {
+            headless |= self.ibus || self.ibus_component;
+        }
+        #[cfg(all(feature = "wayland", target_os = "linux"))]
+        {
+            headless |= self.wayland;
+        }
+        headless
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
+
+    // 由命令列定義產生的輸出不需要設定與字表
+    if cli.gen_man {
+        clap_mangen::Man::new(Cli::command()).render(&mut io::stdout())?;
+        return Ok(());
+    }
+    if let Some(Command::Completions { shell }) = cli.command {
+        clap_complete::generate(shell, &mut Cli::command(), "rustarray30", &mut io::stdout());
+        return Ok(());
+    }
+
+    logging::init(cli.verbose, cli.quiet, cli.log_file.as_deref()).map_err(|e| format!("無法初始化記錄：{}", e))?;
+    let config = Config::load_profile(cli.profile.as_deref().unwrap_or_default())?;
+    let mut table_options =
+        TableOptions::from_config(&config).with_overrides(cli.table_dir, cli.char_table, cli.phrase_table);
+    table_options.symbol_table |= cli.symbols;
+    let table_args = TableArgs {
+        options: table_options,
+        use_big_char: cli.big || config.big_char,
+    };
+
+    match cli.command {
+        None => run(&config, &table_args, cli.run),
+        Some(Command::Run(args)) => run(&config, &table_args, args),
+        Some(Command::Lookup { code }) => {
+            let (dict, phrase_file, char_file) = load_tables(&config, &table_args, false)?;
+            lookup(&dict, &config, &code, phrase_file.as_deref(), &char_file);
+            Ok(())
+        }
+        Some(Command::Reverse { text }) => {
+            let dict = load_tables(&config, &table_args, false)?.0;
+            reverse(&dict, &config, &text);
+            Ok(())
+        }
+        Some(Command::Convert { keys }) => {
+            let dict = load_tables(&config, &table_args, false)?.0;
+            convert(dict, &config, keys)
+        }
+        Some(Command::ConvertFile {
+            input,
+            output,
+            delimiter,
+            on_unknown,
+        }) => {
+            let dict = load_tables(&config, &table_args, false)?.0;
+            convert_file(dict, &config, &input, &output, delimiter.as_deref(), on_unknown)
+        }
+        Some(Command::Validate) => {
+            let dict = load_tables(&config, &table_args, true)?.0;
+            validate(&dict, &config)
+        }
+        Some(Command::Stats) => {
+            let dict = load_tables(&config, &table_args, true)?.0;
+            stats(&dict);
+            Ok(())
+        }
+        Some(Command::Profiles) => {
+            profiles(&config);
+            Ok(())
+        }
+        Some(Command::Config { action }) => config_bundle(action),
+        Some(Command::ExportUser { file, format }) => export_user(format, &file),
+        Some(Command::ImportPhrases { file, format }) => {
+            let dict = load_tables(&config, &table_args, false)?.0;
+            import_phrases(dict, &config, format, &file)
+        }
+        Some(Command::CompileTable { output }) => compile_table(&config, &table_args, &output),
+        Some(Command::Train { corpus, output }) => {
+            let dict = load_tables(&config, &table_args, false)?.0;
+            train(&dict, &corpus, output)
+        }
+        Some(Command::Diff { old, new }) => diff_tables(&old, &new),
+        Some(Command::Sync { folder }) => sync_user_data(&config, folder),
+        Some(Command::Restore { backup }) => restore_backup(backup),
+        Some(Command::InstallTables { from }) => install_tables(&table_args.options, from),
+        Some(Command::Completions { .. }) => unreachable!("已於載入設定前處理"),
+    }
+}
+
+/// 命令列指定的字表選項
+struct TableArgs {
+    options: TableOptions,
+    use_big_char: bool,
+}
+
+/// 載入設定中輸入法變體的字表
+/// `verbose` 時印出載入的檔案；回傳 (字典, 詞庫檔, 字表檔)
+fn load_tables(
+    config: &Config,
+    table_args: &TableArgs,
+    verbose: bool,
+) -> Result<(Dictionary, Option<PathBuf>, PathBuf), Box<dyn std::error::Error>> {
+    try_load_tables(config, table_args, verbose).map_err(table_error)
+}
+
+/// 找不到表格時的錯誤附上安裝方式
+fn table_error(e: Array30Error) -> Box<dyn std::error::Error> {
+    match e {
+        Array30Error::TableNotFound { .. } => {
+            format!("{}；可用 --table-dir 指定表格目錄，或執行 install-tables 安裝表格", e).into()
+        }
+        e => e.into(),
+    }
+}
 
-    // 解析命令列參數
-    let (use_big_char, mode) = parse_args(&args);
+fn try_load_tables(
+    config: &Config,
+    table_args: &TableArgs,
+    verbose: bool,
+) -> error::Result<(Dictionary, Option<PathBuf>, PathBuf)> {
+    let (phrase_file, char_file) = table_args.options.files(config.array_variant, table_args.use_big_char);
+    let symbol_file = table_args.options.symbol_file(config.array_variant);
+    let fallback_file = table_args.options.fallback_file(config.array_variant, table_args.use_big_char);
 
-    // 取得表格檔案路徑
-    let base_dir = PathBuf::from("table");
-    let phrase_file = base_dir.join("array30-phrase-20210725.txt");
+    if verbose {
+        let (table_dir, source) = table_args.options.locate_table_dir();
+        println!("表格目錄：{}（{}）", table_dir.display(), source.display_name());
+        println!("輸入法：{}", config.array_variant.display_name());
+        if let Some(ref phrase_file) = phrase_file {
+            println!("載入詞庫：{}", phrase_file.display());
+        }
+        println!("載入字表：{}", char_file.display());
+        if let Some(ref symbol_file) = symbol_file {
+            println!("合併符號補充表：{}", symbol_file.display());
+        }
+        if let Some(ref fallback_file) = fallback_file {
+            println!("後備字表（背景載入）：{}", fallback_file.display());
+        }
+    }
+    let dict = tables::load_dictionary(&table_args.options, config.array_variant, table_args.use_big_char)?;
+    Ok((dict, phrase_file, char_file))
+}
 
-    let cin2_dir = base_dir.join("cin2");
-    let char_file = if use_big_char {
-        cin2_dir.join("ar30-big-v2023-1.0-20251012.cin2")
-    } else {
-        cin2_dir.join("ar30-regular-v2023-1.0-20251012.cin2")
+/// 執行輸入法
+fn run(config: &Config, table_args: &TableArgs, args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(all(feature = "ibus", target_os = "linux"))]
+    if args.mode.ibus_component {
+        let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("rustarray30"));
+        print!("{}", ibus::component_xml(&exe.display().to_string()));
+        return Ok(());
+    }
+
+    // 管線模式的標準輸出只能有轉換結果
+    if args.mode.pipe {
+        let dict = load_tables(config, table_args, false)?.0;
+        return pipe(dict, config);
+    }
+
+    // 有介面的模式找不到表格時以空字典啟動，由介面提示安裝表格後重新載入
+    let (dict, phrase_file, char_file, missing_tables) = match try_load_tables(config, table_args, false) {
+        Ok((dict, phrase_file, char_file)) => {
+            let (table_dir, source) = table_args.options.locate_table_dir();
+            tracing::info!(
+                table_dir = %table_dir.display(),
+                source = source.display_name(),
+                variant = config.array_variant.display_name(),
+                "已載入表格"
+            );
+            (dict, phrase_file, char_file, None)
+        }
+        Err(e @ Array30Error::TableNotFound { .. }) if !args.mode.is_headless() => {
+            tracing::warn!("{}，以空字典啟動", e);
+            let (phrase_file, char_file) = table_args.options.files(config.array_variant, table_args.use_big_char);
+            let missing = tables::MissingTables {
+                options: table_args.options.clone(),
+                variant: config.array_variant,
+                use_big_char: table_args.use_big_char,
+                error: e.to_string(),
+            };
+            (Dictionary::new(), phrase_file, char_file, Some(missing))
+        }
+        Err(e) => return Err(table_error(e)),
     };
 
-    // 載入字典
-    println!("載入詞庫：{}", phrase_file.display());
-    println!("載入字表：{}", char_file.display());
+    // 常駐服務與 PIME 後端模式不需要介面
+    if args.mode.pime {
+        return pime::run_pime(dict, config.engine_options());
+    }
 
-    let mut dict = Dictionary::new();
+    #[cfg(unix)]
+    if args.mode.daemon {
+        return daemon::run_daemon(dict, config.engine_options(), daemon::default_socket_path());
+    }
 
-    if let Err(e) = dict.load_phrase_file(&phrase_file) {
-        eprintln!("無法載入詞庫檔：{}", e);
-        eprintln!("請確保檔案存在於：{}", phrase_file.display());
-        return Err(e.into());
+    #[cfg(all(feature = "ibus", target_os = "linux"))]
+    if args.mode.ibus {
+        return ibus::run_ibus(dict, config.engine_options());
     }
 
-    if let Err(e) = dict.load_cin2_file(&char_file) {
-        eprintln!("無法載入字表檔：{}", e);
-        eprintln!("請確保檔案存在於：{}", char_file.display());
-        return Err(e.into());
+    #[cfg(all(feature = "wayland", target_os = "linux"))]
+    if args.mode.wayland {
+        return wayland::run_wayland(dict, config.engine_options());
     }
 
-    let (char_count, phrase_count) = dict.stats();
-    println!("已載入 {} 個字碼、{} 個詞碼", char_count, phrase_count);
-    println!();
+    // 依參數選擇介面：Windows 預設 GUI，其他平台預設終端機
+    #[cfg(feature = "gui")]
+    if args.mode.gui || (cfg!(target_os = "windows") && !args.mode.console) {
+        tracing::info!("以 GUI 模式執行");
+        if args.output.is_some() {
+            tracing::warn!("--output 只適用於終端機模式");
+        }
+        run_gui(
+            config.clone(),
+            dict,
+            phrase_file,
+            char_file,
+            table_args.options.clone(),
+            table_args.use_big_char,
+            gui::LaunchOptions {
+                start_minimized: args.minimized,
+                watch_clipboard: args.watch_clipboard,
+                missing_tables: missing_tables.map(|missing| missing.error),
+            },
+        )?;
+        return Ok(());
+    }
 
-    // 根據平台執行對應介面
-    #[cfg(target_os = "windows")]
+    #[cfg(not(feature = "gui"))]
+    if args.mode.gui {
+        tracing::warn!("未啟用 gui 功能，改用終端機模式");
+    }
+
+    let _ = (phrase_file, char_file);
+    if args.watch_clipboard {
+        tracing::warn!("--watch-clipboard 只適用於 GUI 模式");
+    }
+    #[cfg(feature = "console")]
     {
-        match mode.as_deref() {
-            Some("console") => {
-                println!("以終端機模式執行...");
-                // Windows 也使用 GUI 模組中的 console 功能
-                // 或者可以實作一個跨平台的 console 模式
-                gui::run_console_mode(dict)?;
-            }
-            _ => {
-                println!("以 GUI 模式執行...");
-                run_gui(dict, phrase_file, char_file)?;
+        tracing::info!("以終端機模式執行");
+        run_console(dict, config.clone(), args.output.as_deref(), missing_tables)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "console"))]
+    {
+        let _ = (dict, missing_tables);
+        Err("未啟用 console 功能，請以 --pipe 等模式執行".into())
+    }
+}
+
+/// 列出碼對應的所有單字與詞彙候選
+/// 每個候選顯示序號、所在頁次與選字鍵；詞彙需先按 ' 再選字
+fn lookup(dict: &Dictionary, config: &Config, code: &str, phrase_file: Option<&Path>, char_file: &Path) {
+    let chars = dict.ordered_words(TableKind::Chars, code);
+    let phrases = dict.ordered_words(TableKind::Phrases, code);
+    if chars.is_empty() && phrases.is_empty() {
+        println!("{}：查無字詞", code);
+        return;
+    }
+
+    let keys: Vec<char> = config.array_variant.selection_keys().chars().collect();
+    let page_size = config.page_size;
+    let file_name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let print_candidates = |title: &str, kind: TableKind, source: String, candidates: &[&str]| {
+        println!("{}（{}）：", title, source);
+        for (i, text) in candidates.iter().enumerate() {
+            let page = i / page_size;
+            let key = keys.get(i % page_size).copied().unwrap_or(' ');
+            // 不是來自主字表的字詞標示來源
+            let origin = match dict.word_source(kind, code, text) {
+                WordSource::Table => String::new(),
+                other => format!("  〔{}〕", other.display_name()),
+            };
+            println!("  {:>3}  {}  第 {} 頁 {}{}", i + 1, text, page + 1, key, origin);
+        }
+    };
+
+    println!("碼：{}", code);
+    if !chars.is_empty() {
+        print_candidates("單字", TableKind::Chars, file_name(char_file), &chars);
+    }
+    if !phrases.is_empty() {
+        print_candidates("詞彙", TableKind::Phrases, phrase_file.map(file_name).unwrap_or_default(), &phrases);
+    }
+}
+
+/// 逐字列出行列碼；整段文字為詞時先列出詞碼
+/// 設定遠端字典時，另列出遠端字典的碼
+fn reverse(dict: &Dictionary, config: &Config, text: &str) {
+    for line in commands::reverse_lines(dict, text) {
+        println!("{}", line);
+    }
+    if let Some(remote) = remote::from_url(&config.remote_dict_url) {
+        match remote.reverse(text.trim()) {
+            Ok(codes) if !codes.is_empty() => println!("遠端字典 {}：{}", text.trim(), codes.join(" ")),
+            Ok(_) => println!("遠端字典 {}：查無字碼", text.trim()),
+            Err(e) => println!("無法查詢遠端字典：{}", e),
+        }
+    }
+}
+
+/// 建立批次轉換用的引擎
+/// 批次轉換的結果直接寫出，不需限制輸出區長度；先等待後備字表讀取完成，每一行的結果都相同
+fn batch_engine(dict: Dictionary, config: &Config) -> input_engine::InputEngine {
+    dict.wait_fallback();
+    let options = input_engine::EngineOptions {
+        max_output_chars: 0,
+        ..config.engine_options()
+    };
+    input_engine::InputEngine::with_options(dict, options)
+}
+
+/// 轉換參數或標準輸入中的鍵序
+fn convert(dict: Dictionary, config: &Config, keys: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = batch_engine(dict, config);
+    match keys {
+        Some(keys) => println!("{}", batch::convert_with(&mut engine, &keys)),
+        None => {
+            for line in io::stdin().lock().lines() {
+                println!("{}", batch::convert_with(&mut engine, &line?));
             }
         }
     }
+    Ok(())
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        println!("以終端機模式執行...");
-        run_console(dict)?;
+/// 逐行轉換檔案
+fn convert_file(
+    dict: Dictionary,
+    config: &Config,
+    input: &Path,
+    output: &Path,
+    delimiter: Option<&str>,
+    on_unknown: UnknownCode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(input)?;
+    let mut engine = batch_engine(dict, config);
+
+    let mut converted = String::new();
+    for (i, line) in content.lines().enumerate() {
+        let text = batch::convert_codes(&mut engine, line, delimiter, on_unknown)
+            .map_err(|code| format!("第 {} 行：無法轉換「{}」", i + 1, code))?;
+        converted.push_str(&text);
+        converted.push('\n');
     }
 
+    std::fs::write(output, converted)?;
+    println!("已轉換 {} 行：{}", content.lines().count(), output.display());
     Ok(())
 }
 
-/// 解析命令列參數
-/// 回傳 (是否使用大字集, 模式)
-fn parse_args(args: &[String]) -> (bool, Option<String>) {
-    let mut use_big_char = false;
-    let mut mode = None;
+/// 管線模式：逐行轉換標準輸入
+fn pipe(dict: Dictionary, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = batch_engine(dict, config);
+    let mut out = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        writeln!(out, "{}", batch::convert_tokens(&mut engine, &line?))?;
+    }
+    Ok(())
+}
 
-    for arg in args.iter().skip(1) {
-        match arg.as_str() {
-            "--big" | "-b" => {
-                use_big_char = true;
-            }
-            "--console" | "-c" => {
-                mode = Some("console".to_string());
-            }
-            "--gui" | "-g" => {
-                mode = Some("gui".to_string());
-            }
-            "--help" | "-h" => {
-                print_help();
-                std::process::exit(0);
-            }
-            _ => {
-                eprintln!("未知參數：{}", arg);
-                print_help();
-                std::process::exit(1);
-            }
+/// 檢查字表並列出問題
+fn validate(dict: &Dictionary, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    /// 最多列出的問題數
+    const MAX_SHOWN: usize = 20;
+
+    let variant = config.array_variant;
+    let issues = dict.validate(|c| variant.is_table_key(c));
+    if issues.is_empty() {
+        println!("字表檢查通過");
+        return Ok(());
+    }
+
+    for (code, message) in issues.iter().take(MAX_SHOWN) {
+        println!("{}：{}", code, message);
+    }
+    if issues.len() > MAX_SHOWN {
+        println!("……（另有 {} 個問題）", issues.len() - MAX_SHOWN);
+    }
+    Err(format!("字表有 {} 個問題", issues.len()).into())
+}
+
+/// 顯示字表統計
+fn stats(dict: &Dictionary) {
+    for line in commands::stats_lines(dict) {
+        println!("{}", line);
+    }
+}
+
+/// 編譯詞庫與字表（不含符號補充表與自訂字詞）
+fn compile_table(config: &Config, table_args: &TableArgs, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let (phrase_file, char_file) = table_args.options.files(config.array_variant, table_args.use_big_char);
+    let dict = Dictionary::load_tables(phrase_file.as_deref(), &char_file)?;
+    dict.write_compiled(output)?;
+    let (char_count, phrase_count) = dict.stats();
+    println!(
+        "已將 {} 個單字碼、{} 個詞彙碼編譯至 {}",
+        char_count,
+        phrase_count,
+        output.display()
+    );
+    Ok(())
+}
+
+/// 統計語料詞頻並寫入詞頻檔
+fn train(dict: &Dictionary, corpus: &Path, output: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let text = import::decode_text(&std::fs::read(corpus)?)?;
+    let frequencies = frequency::Frequencies::train(dict, &text);
+    let path = output
+        .or_else(frequency::Frequencies::file_path)
+        .ok_or("無法取得使用者資料目錄")?;
+    frequencies.save(&path)?;
+    println!("已統計 {} 個字詞的詞頻，寫入 {}", frequencies.len(), path.display());
+    Ok(())
+}
+
+/// 比較兩版表格
+fn diff_tables(old: &Path, new: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let load = |path: &Path| tablediff::load_table(path).map_err(|e| format!("無法讀取 {}：{}", path.display(), e));
+    let diff = tablediff::TableDiff::compare(&load(old)?, &load(new)?);
+    for line in diff.lines() {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// 與同步資料夾同步使用者資料
+fn sync_user_data(config: &Config, folder: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let folder = folder
+        .or_else(|| (!config.sync_folder.is_empty()).then(|| PathBuf::from(&config.sync_folder)))
+        .ok_or("未指定同步資料夾，請以 --folder 或設定 sync_folder 指定")?;
+    let local = sync::LocalPaths::default_paths().ok_or("無法取得使用者資料目錄")?;
+    backup::create(BackupReason::Sync)?;
+    for (item, outcome) in sync::sync(&folder, &local)? {
+        println!("{}：{}", item.display_name(), outcome.display_name());
+    }
+    Ok(())
+}
+
+/// 列出自動備份，或從指定的備份還原
+fn restore_backup(backup: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let backups = backup::list();
+    let Some(backup) = backup else {
+        if backups.is_empty() {
+            println!("尚無備份");
+        }
+        for (i, backup) in backups.iter().enumerate() {
+            println!("{:>3}  {}  {}", i + 1, backup.label(), backup.path.display());
+        }
+        return Ok(());
+    };
+    let path = match backup.parse::<usize>() {
+        Ok(number) => backups
+            .get(number.wrapping_sub(1))
+            .map(|backup| backup.path.clone())
+            .ok_or_else(|| format!("沒有第 {} 份備份", number))?,
+        Err(_) => PathBuf::from(backup),
+    };
+    let count = backup::restore(&path)?;
+    println!("已從 {} 還原 {} 個檔案", path.display(), count);
+    Ok(())
+}
+
+/// 將表格複製到使用者資料目錄
+fn install_tables(options: &TableOptions, from: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let dest = tables::data_table_dir().ok_or("無法取得使用者資料目錄")?;
+    let src = match from {
+        Some(dir) => dir,
+        None => match options.locate_table_dir() {
+            (_, tables::TableDirSource::NotFound) => return Err("找不到表格目錄，請以 --from 指定".into()),
+            (dir, _) => dir,
+        },
+    };
+    if dest.exists() && src.canonicalize()? == dest.canonicalize()? {
+        return Err(format!("表格已位於 {}", dest.display()).into());
+    }
+
+    let count = tables::install_tables(&src, &dest)?;
+    println!("已將 {} 個檔案從 {} 複製到 {}", count, src.display(), dest.display());
+    Ok(())
+}
+
+/// 列出設定檔組合，標示目前使用的組合
+fn profiles(config: &Config) {
+    let marker = |active: bool| if active { "*" } else { " " };
+    println!("{} （預設）", marker(config.profile.is_empty()));
+    for name in Config::list_profiles() {
+        println!("{} {}", marker(name == config.profile), name);
+    }
+    if let Some(dir) = Config::profiles_dir() {
+        println!("設定檔組合目錄：{}", dir.display());
+    }
+}
+
+/// 匯出或匯入設定封包
+fn config_bundle(action: ConfigAction) -> Result<(), Box<dyn std::error::Error>> {
+    let roots = bundle::default_roots();
+    let settings = Config::config_file_path();
+    match action {
+        ConfigAction::Export { file } => {
+            let bundle = bundle::Bundle::export(&roots, settings.as_deref())?;
+            bundle.save(&file)?;
+            println!("已匯出 {} 個檔案：{}", bundle.files.len(), file.display());
+        }
+        ConfigAction::Import { file } => {
+            backup::create(BackupReason::ImportSettings)?;
+            let count = bundle::Bundle::load(&file)?.import(&roots, settings.as_deref())?;
+            println!("已匯入 {} 個檔案", count);
         }
     }
+    Ok(())
+}
 
-    (use_big_char, mode)
+/// 匯入其他輸入法的詞彙並寫入自訂字詞檔
+fn import_phrases(
+    mut dict: Dictionary,
+    config: &Config,
+    format: ImportFormat,
+    file: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = import::decode_text(&std::fs::read(file)?)?;
+    let phrases = format.phrases(&content);
+    backup::create(BackupReason::ImportPhrases)?;
+    let mut user_dict = userdict::UserDict::load_default();
+    let report = import::import_phrases(
+        &phrases,
+        &mut user_dict,
+        &mut dict,
+        config.array_variant,
+        config.engine_options().max_code_len(),
+    );
+    for (phrase, reason) in &report.skipped {
+        eprintln!("略過 {}：{}", phrase, reason);
+    }
+    if !report.added.is_empty() {
+        let path = userdict::UserDict::file_path().ok_or("無法取得使用者資料目錄")?;
+        user_dict.save(&path)?;
+    }
+    println!("已加入 {} 詞，略過 {} 詞", report.added.len(), report.skipped.len());
+    Ok(())
 }
 
-fn print_help() {
-    println!("行列 30 輸入法 - Rust 實作版本");
-    println!();
-    println!("使用方法：");
-    println!("  rustarray30 [選項]");
-    println!();
-    println!("選項：");
-    println!("  --big, -b       使用大字集字表（預設使用標準版）");
-    println!("  --console, -c   強制使用終端機模式（僅 Windows）");
-    println!("  --gui, -g       強制使用 GUI 模式（僅 Windows，為預設）");
-    println!("  --help, -h      顯示此說明");
-    println!();
-    println!("表格檔案位置：");
-    println!("  詞庫：table/array30-phrase-20210725.txt");
-    println!("  字表：table/cin2/ar30-regular-v2023-1.0-20251012.cin2");
-    println!("       或 table/cin2/ar30-big-v2023-1.0-20251012.cin2（--big）");
+/// 將自訂字詞匯出為其他行列輸入法的格式
+fn export_user(format: ExportFormat, file: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let user_dict = userdict::UserDict::load_default();
+    export::export(format, user_dict.entries(), file)?;
+    println!(
+        "已匯出 {} 筆自訂字詞（{}）：{}",
+        user_dict.entries().len(),
+        format.display_name(),
+        file.display()
+    );
+    Ok(())
 }
@@ -0,0 +1,212 @@
+// PIME text-service backend
+// PIME 後端：讓 PIME 框架的 TSF 文字服務使用行列 30 引擎
+//
+// PIMELauncher 透過具名管道與 TSF 元件溝通，再以標準輸入/輸出轉給後端：
+//   輸入：<client_id>|<JSON 請求>
+//   輸出：PIME_MSG|<client_id>|<JSON 回應>
+// 其他輸出行（如載入字表的訊息）會被 PIMELauncher 當作記錄
+
+use crate::dict::Dictionary;
+use crate::input_engine::{EngineOptions, InputEngine};
+use crate::keysym;
+use serde_json::{json, Map, Value};
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+/// 回應訊息前綴
+const MSG_PREFIX: &str = "PIME_MSG";
+
+// Windows 虛擬鍵碼
+const VK_BACK: u64 = 0x08;
+const VK_RETURN: u64 = 0x0d;
+const VK_SHIFT: usize = 0x10;
+const VK_CONTROL: usize = 0x11;
+const VK_MENU: usize = 0x12;
+const VK_ESCAPE: u64 = 0x1b;
+const VK_PRIOR: u64 = 0x21;
+const VK_NEXT: u64 = 0x22;
+const VK_LWIN: usize = 0x5b;
+const VK_RWIN: usize = 0x5c;
+
+/// 將 PIME 按鍵事件（keyCode、charCode、keyStates）轉為 keysym 與修飾鍵遮罩
+fn key_event_to_keysym(msg: &Value) -> Option<(u32, u32)> {
+    let key_code = msg["keyCode"].as_u64()?;
+    let char_code = msg["charCode"].as_u64().unwrap_or(0);
+
+    // keyStates 為 256 個虛擬鍵的狀態，最高位元表示按下
+    let pressed = |vk: usize| msg["keyStates"].get(vk).and_then(Value::as_u64).unwrap_or(0) & 0x80 != 0;
+    let mut state = 0;
+    if pressed(VK_SHIFT) {
+        state |= keysym::SHIFT_MASK;
+    }
+    if pressed(VK_CONTROL) {
+        state |= keysym::CONTROL_MASK;
+    }
+    if pressed(VK_MENU) {
+        state |= keysym::MOD1_MASK;
+    }
+    if pressed(VK_LWIN) || pressed(VK_RWIN) {
+        state |= keysym::SUPER_MASK;
+    }
+
+    let sym = match key_code {
+        VK_BACK => keysym::KEY_BACKSPACE,
+        VK_RETURN => keysym::KEY_RETURN,
+        VK_ESCAPE => keysym::KEY_ESCAPE,
+        VK_PRIOR => keysym::KEY_PAGE_UP,
+        VK_NEXT => keysym::KEY_PAGE_DOWN,
+        _ if (0x20..=0x7e).contains(&char_code) => char_code as u32,
+        _ => return None,
+    };
+    Some((sym, state))
+}
+
+/// PIME 後端
+/// 所有用戶端共用一個引擎，切換輸入情境時清除組字
+pub struct PimeBackend {
+    engine: InputEngine,
+}
+
+impl PimeBackend {
+    pub fn new(engine: InputEngine) -> Self {
+        Self { engine }
+    }
+
+    /// 處理一個 PIME 請求並產生回應
+    pub fn handle_message(&mut self, msg: &Value) -> Value {
+        let method = msg["method"].as_str().unwrap_or_default();
+        tracing::trace!(method, "PIME 請求");
+        // 組字逾時在下一則訊息時處理，上屏結果隨回應的 commitString 送出
+        self.engine.tick(Instant::now());
+        let mut reply = Map::new();
+
+        let ret = match method {
+            "init" => {
+                self.reset();
+                reply.insert(
+                    "setSelKeys".to_string(),
+                    json!(self.engine.options().variant.selection_keys()),
+                );
+                true
+            }
+            "onActivate" | "onDeactivate" | "onCompositionTerminated" | "close" => {
+                self.reset();
+                true
+            }
+            // 按鍵在 filterKeyDown 時即交給引擎處理，onKeyDown 只需確認
+            "filterKeyDown" => match key_event_to_keysym(msg) {
+                Some((sym, state)) => keysym::process_key(&mut self.engine, sym, state),
+                None => false,
+            },
+            "onKeyDown" => true,
+            "filterKeyUp" | "onKeyUp" => false,
+            _ => false,
+        };
+
+        reply.insert("success".to_string(), json!(true));
+        reply.insert("seqNum".to_string(), msg["seqNum"].clone());
+        reply.insert("return".to_string(), json!(ret));
+        self.add_state(&mut reply);
+        Value::Object(reply)
+    }
+
+    /// 捨棄組字中的碼與尚未送出的文字
+    fn reset(&mut self) {
+        self.engine.handle_key('\x1b');
+        self.engine.take_output();
+    }
+
+    /// 附上組字區、候選與上屏文字
+    fn add_state(&mut self, reply: &mut Map<String, Value>) {
+        let code = self.engine.state().current_code.clone();
+        let candidates: Vec<String> = self
+            .engine
+            .current_page_candidates()
+            .iter()
+            .map(|cand| cand.text.clone())
+            .collect();
+
+        let commit = self.engine.take_output();
+        if !commit.is_empty() {
+            reply.insert("commitString".to_string(), json!(commit));
+        }
+        reply.insert("compositionCursor".to_string(), json!(code.chars().count()));
+        reply.insert("compositionString".to_string(), json!(code));
+        reply.insert("showCandidates".to_string(), json!(!candidates.is_empty()));
+        reply.insert("candidateCursor".to_string(), json!(0));
+        reply.insert("candidateList".to_string(), json!(candidates));
+    }
+}
+
+/// 解析一行輸入為 (client_id, 請求)
+fn parse_line(line: &str) -> Option<(&str, Value)> {
+    let (client_id, text) = line.split_once('|')?;
+    let msg = serde_json::from_str(text).ok()?;
+    Some((client_id, msg))
+}
+
+/// 以 PIME 後端模式執行（由 PIMELauncher 啟動）
+pub fn run_pime(dict: Dictionary, options: EngineOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut backend = PimeBackend::new(InputEngine::with_options(dict, options));
+    let stdout = io::stdout();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let Some((client_id, msg)) = parse_line(line.trim_end()) else {
+            tracing::warn!("無效的 PIME 訊息：{}", line);
+            continue;
+        };
+        let reply = backend.handle_message(&msg);
+        let mut out = stdout.lock();
+        writeln!(out, "{}|{}|{}", MSG_PREFIX, client_id, reply)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_down(method: &str, key_code: u64, char_code: u64, seq: u64) -> Value {
+        json!({
+            "method": method,
+            "seqNum": seq,
+            "keyCode": key_code,
+            "charCode": char_code,
+            "keyStates": vec![0; 256],
+        })
+    }
+
+    #[test]
+    fn test_key_flow() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("ab", "甲");
+        let mut backend = PimeBackend::new(InputEngine::new(dict));
+
+        let reply = backend.handle_message(&json!({"method": "init", "seqNum": 1}));
+        assert_eq!(reply["setSelKeys"], "1234567890");
+
+        let reply = backend.handle_message(&key_down("filterKeyDown", 0x41, 'a' as u64, 2));
+        assert_eq!(reply["return"], true);
+        assert_eq!(reply["seqNum"], 2);
+        assert_eq!(reply["compositionString"], "a");
+
+        backend.handle_message(&key_down("filterKeyDown", 0x42, 'b' as u64, 3));
+        let reply = backend.handle_message(&key_down("filterKeyDown", 0x20, ' ' as u64, 4));
+        assert_eq!(reply["commitString"], "甲");
+        assert_eq!(reply["compositionString"], "");
+
+        // 未組字時的 Enter 交還應用程式
+        let reply = backend.handle_message(&key_down("filterKeyDown", VK_RETURN, 0x0d, 5));
+        assert_eq!(reply["return"], false);
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let (client, msg) = parse_line(r#"{abc}|{"method":"init","seqNum":1}"#).unwrap();
+        assert_eq!(client, "{abc}");
+        assert_eq!(msg["method"], "init");
+        assert!(parse_line("no separator").is_none());
+    }
+}
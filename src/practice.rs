@@ -0,0 +1,344 @@
+// Typing practice lessons
+// 打字練習：依課程檔逐字練習，比對輸入的碼並記錄各課的正確率與速度
+
+use crate::dict::Dictionary;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 課程目錄（位於表格目錄下）
+pub const LESSON_DIR: &str = "lessons";
+/// 練習進度檔名（位於使用者資料目錄）
+const PROGRESS_FILENAME: &str = "practice.json";
+/// 完成課程所需的正確率
+pub const PASS_ACCURACY: f32 = 0.9;
+
+/// 一個課程
+/// 課程檔格式：`title=課程名稱`，其餘以空白分隔的字詞依序練習，# 開頭為註解
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lesson {
+    /// 課程代號（檔名，不含副檔名）
+    pub id: String,
+    pub title: String,
+    pub items: Vec<String>,
+}
+
+impl Lesson {
+    pub fn parse(id: &str, content: &str) -> Self {
+        let mut title = id.to_string();
+        let mut items = Vec::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("title=") {
+                title = value.trim().to_string();
+            } else {
+                items.extend(line.split_whitespace().map(str::to_string));
+            }
+        }
+        Self {
+            id: id.to_string(),
+            title,
+            items,
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let id = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        Ok(Self::parse(&id, &content))
+    }
+}
+
+/// 讀取表格目錄 lessons/ 下的課程（.txt，依檔名排序），略過沒有字詞的課程
+pub fn load_lessons(table_dir: &Path) -> Vec<Lesson> {
+    let Ok(entries) = std::fs::read_dir(table_dir.join(LESSON_DIR)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| match Lesson::load(path) {
+            Ok(lesson) => Some(lesson),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), "無法讀取練習課程：{}", e);
+                None
+            }
+        })
+        .filter(|lesson| !lesson.items.is_empty())
+        .collect()
+}
+
+/// 一課的練習成績
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LessonResult {
+    /// 正確率（0-1）
+    pub accuracy: f32,
+    /// 每分鐘字數
+    pub chars_per_minute: f32,
+}
+
+impl LessonResult {
+    pub fn passed(&self) -> bool {
+        self.accuracy >= PASS_ACCURACY
+    }
+}
+
+/// 練習進行中的狀態
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// 練習中的課程索引
+    pub lesson: usize,
+    items: Vec<String>,
+    position: usize,
+    /// 目前輸入的碼
+    pub typed: String,
+    /// 目前字詞輸入錯誤後顯示的正確碼
+    pub hint: Option<Vec<String>>,
+    correct: u32,
+    mistakes: u32,
+    chars_done: usize,
+    started: Option<Instant>,
+    elapsed: Duration,
+}
+
+impl Session {
+    pub fn new(lesson: usize, items: &[String]) -> Self {
+        Self {
+            lesson,
+            items: items.to_vec(),
+            position: 0,
+            typed: String::new(),
+            hint: None,
+            correct: 0,
+            mistakes: 0,
+            chars_done: 0,
+            started: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// 目前要輸入的字詞，練習完畢時為 None
+    pub fn current(&self) -> Option<&str> {
+        self.items.get(self.position).map(String::as_str)
+    }
+
+    /// 接下來的字詞（預覽用）
+    pub fn upcoming(&self, count: usize) -> &[String] {
+        let start = (self.position + 1).min(self.items.len());
+        &self.items[start..(start + count).min(self.items.len())]
+    }
+
+    /// 已完成的字詞數與總數
+    pub fn progress(&self) -> (usize, usize) {
+        (self.position, self.items.len())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.items.len()
+    }
+
+    /// 輸入一個組碼鍵；第一次按鍵時開始計時
+    pub fn push_key(&mut self, c: char) {
+        if self.is_finished() {
+            return;
+        }
+        self.started.get_or_insert_with(Instant::now);
+        self.typed.push(c);
+    }
+
+    pub fn backspace(&mut self) {
+        self.typed.pop();
+    }
+
+    /// 送出目前的碼，回傳是否正確（沒有輸入時為 None）
+    /// 正確時前進到下一個字詞；錯誤時記錄並顯示正確碼
+    pub fn submit(&mut self, dict: &Dictionary) -> Option<bool> {
+        let word = self.current()?.to_string();
+        if self.typed.is_empty() {
+            return None;
+        }
+        let codes = dict.reverse_lookup(&word);
+        if codes.contains(&self.typed.as_str()) {
+            self.correct += 1;
+            self.chars_done += word.chars().count();
+            self.position += 1;
+            self.typed.clear();
+            self.hint = None;
+            if self.is_finished() {
+                self.elapsed = self.started.map_or(Duration::ZERO, |t| t.elapsed());
+            }
+            Some(true)
+        } else {
+            self.mistakes += 1;
+            self.typed.clear();
+            self.hint = Some(codes.into_iter().map(str::to_string).collect());
+            Some(false)
+        }
+    }
+
+    /// 略過目前的字詞（計為錯誤）
+    pub fn skip(&mut self) {
+        if !self.is_finished() {
+            self.mistakes += 1;
+            self.position += 1;
+            self.typed.clear();
+            self.hint = None;
+            if self.is_finished() {
+                self.elapsed = self.started.map_or(Duration::ZERO, |t| t.elapsed());
+            }
+        }
+    }
+
+    /// 目前的成績：正確率為答對次數對全部送出（含略過）次數的比例
+    pub fn result(&self) -> LessonResult {
+        let attempts = self.correct + self.mistakes;
+        let accuracy = if attempts == 0 { 1.0 } else { self.correct as f32 / attempts as f32 };
+        let elapsed = if self.is_finished() {
+            self.elapsed
+        } else {
+            self.started.map_or(Duration::ZERO, |t| t.elapsed())
+        };
+        let minutes = elapsed.as_secs_f32() / 60.0;
+        let chars_per_minute = if minutes > 0.0 { self.chars_done as f32 / minutes } else { 0.0 };
+        LessonResult {
+            accuracy,
+            chars_per_minute,
+        }
+    }
+}
+
+/// 一課的累計進度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LessonProgress {
+    /// 完成次數
+    pub completions: u32,
+    /// 最佳正確率
+    pub best_accuracy: f32,
+    /// 最快每分鐘字數
+    pub best_chars_per_minute: f32,
+    /// 是否曾以及格的正確率完成
+    pub passed: bool,
+}
+
+/// 各課的練習進度
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    lessons: BTreeMap<String, LessonProgress>,
+}
+
+impl Progress {
+    pub fn get(&self, lesson_id: &str) -> LessonProgress {
+        self.lessons.get(lesson_id).copied().unwrap_or_default()
+    }
+
+    /// 記錄一次完成的練習
+    pub fn record(&mut self, lesson_id: &str, result: LessonResult) {
+        let progress = self.lessons.entry(lesson_id.to_string()).or_default();
+        progress.completions += 1;
+        progress.best_accuracy = progress.best_accuracy.max(result.accuracy);
+        progress.best_chars_per_minute = progress.best_chars_per_minute.max(result.chars_per_minute);
+        progress.passed |= result.passed();
+    }
+
+    /// 建議的下一課：第一個尚未及格的課程
+    pub fn next_lesson(&self, lessons: &[Lesson]) -> Option<usize> {
+        lessons.iter().position(|lesson| !self.get(&lesson.id).passed)
+    }
+
+    /// 練習進度檔路徑
+    pub fn file_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rustarray30").join(PROGRESS_FILENAME))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// 讀取預設位置的進度檔，不存在或無法讀取時為空
+    pub fn load_default() -> Self {
+        let Some(path) = Self::file_path().filter(|p| p.exists()) else {
+            return Self::default();
+        };
+        Self::load(&path).unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), "無法讀取練習進度：{}", e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_load_lessons() {
+        let lesson = Lesson::parse("01-basic", "# 註解\ntitle= 字根入門 \n一 二\n\n三\n");
+        assert_eq!(lesson.title, "字根入門");
+        assert_eq!(lesson.items, ["一", "二", "三"]);
+        assert_eq!(Lesson::parse("x", "甲").title, "x");
+
+        let dir = std::env::temp_dir().join(format!("rustarray30-lessons-{}", std::process::id()));
+        let lesson_dir = dir.join(LESSON_DIR);
+        std::fs::create_dir_all(&lesson_dir).unwrap();
+        std::fs::write(lesson_dir.join("02-b.txt"), "title=B\n乙").unwrap();
+        std::fs::write(lesson_dir.join("01-a.txt"), "title=A\n甲").unwrap();
+        std::fs::write(lesson_dir.join("03-empty.txt"), "title=空").unwrap();
+        std::fs::write(lesson_dir.join("notes.md"), "甲").unwrap();
+        let lessons = load_lessons(&dir);
+        let ids: Vec<_> = lessons.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(ids, ["01-a", "02-b"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_and_progress() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("a", "一");
+        dict.insert_phrase("x;cg", "台灣");
+        let items = ["一".to_string(), "台灣".to_string()];
+        let mut session = Session::new(0, &items);
+        assert_eq!(session.submit(&dict), None);
+
+        session.push_key('b');
+        assert_eq!(session.submit(&dict), Some(false));
+        assert_eq!(session.hint, Some(vec!["a".to_string()]));
+        session.push_key('a');
+        assert_eq!(session.submit(&dict), Some(true));
+        assert_eq!(session.current(), Some("台灣"));
+        for c in "x;cg".chars() {
+            session.push_key(c);
+        }
+        assert_eq!(session.submit(&dict), Some(true));
+        assert!(session.is_finished());
+
+        let result = session.result();
+        assert!((result.accuracy - 2.0 / 3.0).abs() < 1e-6);
+        assert!(!result.passed());
+
+        let lessons = [Lesson::parse("01", "一"), Lesson::parse("02", "台灣")];
+        let mut progress = Progress::default();
+        progress.record("01", LessonResult { accuracy: 0.5, chars_per_minute: 10.0 });
+        assert_eq!(progress.next_lesson(&lessons), Some(0));
+        progress.record("01", LessonResult { accuracy: 1.0, chars_per_minute: 30.0 });
+        assert_eq!(progress.get("01").completions, 2);
+        assert_eq!(progress.get("01").best_chars_per_minute, 30.0);
+        assert_eq!(progress.next_lesson(&lessons), Some(1));
+    }
+}
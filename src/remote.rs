@@ -0,0 +1,527 @@
+// Remote dictionary over HTTP
+// 遠端字典：向集中管理的字典服務查碼與反查（HTTP + JSON），結果存於本機的 LRU 快取
+//
+// 端點（皆為 GET，參數以百分比編碼）：
+//   /lookup?code=ab   ← {"chars":["叔"],"phrases":[]}
+//   /reverse?text=行  ← {"codes":["a;"]}
+
+use crate::dict::{Dictionary, TableKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 連線與讀取的逾時（逾時太長時遠端字詞會很晚才出現）
+pub const REQUEST_TIMEOUT: Duration = Duration::from_millis(300);
+/// 連線失敗後暫停查詢的時間
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+/// 快取的碼數
+pub const CACHE_CAPACITY: usize = 1024;
+/// 回應內容的大小上限（一個碼的字詞遠小於此）
+const MAX_BODY: usize = 1 << 20;
+
+/// 一個碼在遠端字典中的字與詞
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteWords {
+    #[serde(default)]
+    pub chars: Vec<String>,
+    #[serde(default)]
+    pub phrases: Vec<String>,
+}
+
+impl RemoteWords {
+    pub fn words(&self, kind: TableKind) -> &[String] {
+        match kind {
+            TableKind::Chars => &self.chars,
+            TableKind::Phrases => &self.phrases,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ReverseResponse {
+    codes: Vec<String>,
+}
+
+/// 本機字典以外的字詞來源；查碼的結果排在本機字詞之後
+pub trait DictBackend: Send + Sync {
+    fn lookup(&self, code: &str) -> io::Result<RemoteWords>;
+    fn reverse(&self, text: &str) -> io::Result<Vec<String>>;
+
+    /// 不連線、只查本機快取的結果（沒有快取時為 None）
+    fn cached(&self, _code: &str) -> Option<RemoteWords> {
+        None
+    }
+}
+
+/// HTTP 字典服務（只支援 http://）
+#[derive(Debug)]
+pub struct HttpBackend {
+    /// host:port
+    address: String,
+    host: String,
+    /// 端點前的路徑，結尾沒有 /
+    base_path: String,
+    /// 連線失敗的時間，RETRY_AFTER 內不再連線
+    failed_at: Mutex<Option<Instant>>,
+}
+
+impl HttpBackend {
+    /// 解析網址，例如 http://dict.example.org:8030/array30
+    pub fn new(url: &str) -> Result<Self, String> {
+        let rest = url
+            .trim()
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("只支援 http:// 網址：{}", url))?;
+        let (authority, path) = rest.split_once('/').map_or((rest, ""), |(a, p)| (a, p));
+        if authority.is_empty() {
+            return Err(format!("網址沒有主機名稱：{}", url));
+        }
+        let address = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:80", authority)
+        };
+        Ok(Self {
+            address,
+            host: authority.to_string(),
+            base_path: format!("/{}", path.trim_end_matches('/')).trim_end_matches('/').to_string(),
+            failed_at: Mutex::new(None),
+        })
+    }
+
+    /// 送出查詢；連線期間不持有鎖，其他執行緒的查詢不必等待
+    fn get<T: for<'de> Deserialize<'de>>(&self, endpoint: &str, param: &str, value: &str) -> io::Result<T> {
+        let failed_at = || self.failed_at.lock().unwrap_or_else(|e| e.into_inner());
+        if failed_at().is_some_and(|at| at.elapsed() < RETRY_AFTER) {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "遠端字典暫時無法連線"));
+        }
+        let path = format!("{}/{}?{}={}", self.base_path, endpoint, param, percent_encode(value));
+        match http_get(&self.address, &self.host, &path) {
+            Ok(body) => {
+                *failed_at() = None;
+                serde_json::from_str(&body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Err(e) => {
+                tracing::warn!(address = %self.address, "無法查詢遠端字典，{} 秒後重試：{}", RETRY_AFTER.as_secs(), e);
+                *failed_at() = Some(Instant::now());
+                Err(e)
+            }
+        }
+    }
+}
+
+impl DictBackend for HttpBackend {
+    fn lookup(&self, code: &str) -> io::Result<RemoteWords> {
+        self.get("lookup", "code", code)
+    }
+
+    fn reverse(&self, text: &str) -> io::Result<Vec<String>> {
+        self.get::<ReverseResponse>("reverse", "text", text).map(|r| r.codes)
+    }
+}
+
+/// 最近使用的查碼結果；滿了時丟掉最久沒用的
+#[derive(Debug)]
+struct LruCache {
+    capacity: usize,
+    entries: HashMap<String, (RemoteWords, u64)>,
+    tick: u64,
+}
+
+impl LruCache {
+    fn get(&mut self, code: &str) -> Option<RemoteWords> {
+        self.tick += 1;
+        let (words, used) = self.entries.get_mut(code)?;
+        *used = self.tick;
+        Some(words.clone())
+    }
+
+    fn insert(&mut self, code: &str, words: RemoteWords) {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(code) {
+            let oldest = self.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(code, _)| code.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(code.to_string(), (words, self.tick));
+    }
+}
+
+/// 加上查碼快取的字典來源；查詢失敗不快取
+pub struct CachedBackend {
+    inner: Box<dyn DictBackend>,
+    cache: Mutex<LruCache>,
+}
+
+impl CachedBackend {
+    pub fn new(inner: Box<dyn DictBackend>, capacity: usize) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(LruCache {
+                capacity: capacity.max(1),
+                entries: HashMap::new(),
+                tick: 0,
+            }),
+        }
+    }
+}
+
+impl DictBackend for CachedBackend {
+    fn lookup(&self, code: &str) -> io::Result<RemoteWords> {
+        if let Some(words) = self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(code) {
+            return Ok(words);
+        }
+        let words = self.inner.lookup(code)?;
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).insert(code, words.clone());
+        Ok(words)
+    }
+
+    fn reverse(&self, text: &str) -> io::Result<Vec<String>> {
+        self.inner.reverse(text)
+    }
+
+    fn cached(&self, code: &str) -> Option<RemoteWords> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(code)
+    }
+}
+
+/// 背景查碼：按鍵時只送出要查的碼，由背景執行緒連線（含解析主機名稱），
+/// 前端以 poll 取回結果，網路緩慢或無法連線時不會卡住輸入
+pub struct RemoteLookup {
+    backend: Arc<dyn DictBackend>,
+    requests: Sender<String>,
+    results: Receiver<(String, RemoteWords)>,
+}
+
+impl RemoteLookup {
+    /// 啟動背景查碼執行緒；RemoteLookup 被丟棄後執行緒在目前的查詢結束時停止
+    pub fn spawn(backend: Arc<dyn DictBackend>) -> Self {
+        let (requests, pending) = mpsc::channel::<String>();
+        let (found, results) = mpsc::channel();
+        let worker = backend.clone();
+        std::thread::spawn(move || {
+            while let Ok(mut code) = pending.recv() {
+                // 只查最新的碼，連線期間已經打過去的碼不再查
+                while let Ok(newer) = pending.try_recv() {
+                    code = newer;
+                }
+                // 查詢失敗時回傳空結果，前端才不會一直等待
+                let words = worker.lookup(&code).unwrap_or_default();
+                if found.send((code, words)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            backend,
+            requests,
+            results,
+        }
+    }
+
+    /// 快取中的結果（不連線）
+    pub fn cached(&self, code: &str) -> Option<RemoteWords> {
+        self.backend.cached(code)
+    }
+
+    /// 要求在背景查碼
+    pub fn request(&self, code: &str) {
+        let _ = self.requests.send(code.to_string());
+    }
+
+    /// 取出已查到的結果（碼, 字詞），依查到的順序；查詢失敗的碼字詞為空
+    pub fn poll(&self) -> Vec<(String, RemoteWords)> {
+        self.results.try_iter().collect()
+    }
+}
+
+/// 依設定的網址建立附快取的遠端字典；空字串或網址錯誤時為 None
+pub fn from_url(url: &str) -> Option<Arc<dyn DictBackend>> {
+    if url.trim().is_empty() {
+        return None;
+    }
+    match HttpBackend::new(url) {
+        Ok(backend) => Some(Arc::new(CachedBackend::new(Box::new(backend), CACHE_CAPACITY))),
+        Err(e) => {
+            tracing::warn!("遠端字典設定錯誤：{}", e);
+            None
+        }
+    }
+}
+
+/// 百分比編碼（保留英數與 -_.~）
+pub fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// 百分比解碼（+ 視為空白）；編碼錯誤或不是 UTF-8 時為 None
+pub fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b'+' => bytes.push(b' '),
+            _ => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// 送出 GET 請求，回傳 200 回應的內容（支援 Content-Length 與 chunked）
+fn http_get(address: &str, host: &str, path: &str) -> io::Result<String> {
+    use std::net::ToSocketAddrs;
+    let addr = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "無法解析主機名稱"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, REQUEST_TIMEOUT)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n\r\n", path, host)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status = String::new();
+    reader.read_line(&mut status)?;
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(io::Error::other(format!("伺服器回應：{}", status.trim())));
+    }
+    let mut chunked = false;
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            chunked |= name.eq_ignore_ascii_case("transfer-encoding") && value.trim().eq_ignore_ascii_case("chunked");
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let body = read_body(&mut reader, chunked, length)?;
+    String::from_utf8(body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// 讀取回應內容（chunked 或到連線結束）；超過 MAX_BODY 時為 InvalidData
+fn read_body(reader: &mut impl BufRead, chunked: bool, length: Option<usize>) -> io::Result<Vec<u8>> {
+    let too_large = || io::Error::new(io::ErrorKind::InvalidData, "回應內容過大");
+    if length.is_some_and(|length| length > MAX_BODY) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    if chunked {
+        loop {
+            let mut size = String::new();
+            reader.read_line(&mut size)?;
+            let size = usize::from_str_radix(size.trim().split(';').next().unwrap_or_default(), 16)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if size == 0 {
+                break;
+            }
+            if size > MAX_BODY - body.len() {
+                return Err(too_large());
+            }
+            let start = body.len();
+            body.resize(start + size, 0);
+            reader.read_exact(&mut body[start..])?;
+            reader.read_line(&mut String::new())?;
+        }
+    } else {
+        reader.take(MAX_BODY as u64 + 1).read_to_end(&mut body)?;
+        if body.len() > MAX_BODY {
+            return Err(too_large());
+        }
+    }
+    Ok(body)
+}
+
+/// 以本機字典回應一個請求行（例如 "GET /lookup?code=ab HTTP/1.1"），回傳 (狀態, JSON 內容)
+pub fn handle_request(dict: &Dictionary, request_line: &str) -> (u16, String) {
+    let mut parts = request_line.split_whitespace();
+    let (Some("GET"), Some(target)) = (parts.next(), parts.next()) else {
+        return (405, r#"{"error":"只支援 GET"}"#.to_string());
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .and_then(|(_, value)| percent_decode(value))
+    };
+    let body = match (path.rsplit('/').next(), param("code"), param("text")) {
+        (Some("lookup"), Some(code), _) => {
+            let words = |kind| dict.ordered_words(kind, &code).into_iter().map(String::from).collect();
+            serde_json::to_string(&RemoteWords {
+                chars: words(TableKind::Chars),
+                phrases: words(TableKind::Phrases),
+            })
+        }
+        (Some("reverse"), _, Some(text)) => {
+            let codes = dict.reverse_lookup(&text).into_iter().map(String::from).collect();
+            serde_json::to_string(&ReverseResponse { codes })
+        }
+        _ => return (404, r#"{"error":"端點為 /lookup?code= 或 /reverse?text="}"#.to_string()),
+    };
+    match body {
+        Ok(body) => (200, body),
+        Err(e) => (500, serde_json::json!({ "error": e.to_string() }).to_string()),
+    }
+}
+
+/// 字典服務：每個連線一個執行緒，回應後關閉連線
+#[cfg(feature = "dict-server")]
+pub fn serve(listener: std::net::TcpListener, dict: Arc<Dictionary>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!("無法接受連線：{}", e);
+                continue;
+            }
+        };
+        let dict = dict.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = respond(stream, &dict) {
+                tracing::debug!("連線錯誤：{}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "dict-server")]
+fn respond(stream: TcpStream, dict: &Dictionary) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // 略過其餘標頭
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+    let (status, body) = handle_request(dict, &request_line);
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_and_encoding() {
+        assert_eq!(percent_encode("a;,./"), "a%3B%2C.%2F");
+        assert_eq!(percent_decode(&percent_encode("行列 30")).as_deref(), Some("行列 30"));
+        assert_eq!(percent_decode("%E8"), None);
+
+        let mut dict = Dictionary::new();
+        dict.insert_char("a;", "行");
+        let (status, body) = handle_request(&dict, "GET /array30/lookup?code=a%3B HTTP/1.1");
+        assert_eq!(status, 200);
+        let words: RemoteWords = serde_json::from_str(&body).unwrap();
+        assert_eq!(words.words(TableKind::Chars), ["行"]);
+        let (_, body) = handle_request(&dict, &format!("GET /reverse?text={} HTTP/1.1", percent_encode("行")));
+        assert_eq!(body, r#"{"codes":["a;"]}"#);
+        assert_eq!(handle_request(&dict, "GET /other HTTP/1.1").0, 404);
+
+        let backend = HttpBackend::new("http://dict.example.org/array30/").unwrap();
+        assert_eq!((backend.address.as_str(), backend.base_path.as_str()), ("dict.example.org:80", "/array30"));
+        assert!(HttpBackend::new("https://dict.example.org").is_err());
+    }
+
+    #[test]
+    fn test_cache() {
+        struct Counting(Arc<Mutex<usize>>);
+        impl DictBackend for Counting {
+            fn lookup(&self, code: &str) -> io::Result<RemoteWords> {
+                *self.0.lock().unwrap() += 1;
+                Ok(RemoteWords {
+                    chars: vec![code.to_uppercase()],
+                    phrases: Vec::new(),
+                })
+            }
+            fn reverse(&self, _: &str) -> io::Result<Vec<String>> {
+                Ok(Vec::new())
+            }
+        }
+        let calls = Arc::new(Mutex::new(0));
+        let cached = CachedBackend::new(Box::new(Counting(calls.clone())), 2);
+        for code in ["a", "b", "a", "c", "a", "b"] {
+            assert_eq!(cached.lookup(code).unwrap().chars, [code.to_uppercase()]);
+        }
+        // 加入 c 時丟掉最久沒用的 b，之後再查 b 時丟掉 c
+        assert_eq!(*calls.lock().unwrap(), 4);
+        let mut cache = cached.cache.lock().unwrap();
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_none());
+    }
+
+    #[test]
+    fn test_failures() {
+        struct Down;
+        impl DictBackend for Down {
+            fn lookup(&self, _: &str) -> io::Result<RemoteWords> {
+                Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+            }
+            fn reverse(&self, _: &str) -> io::Result<Vec<String>> {
+                Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+            }
+        }
+        // 查詢失敗也回傳（空的）結果
+        let remote = RemoteLookup::spawn(Arc::new(Down));
+        remote.request("ab");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let results = loop {
+            let results = remote.poll();
+            if !results.is_empty() || Instant::now() > deadline {
+                break results;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(results, [("ab".to_string(), RemoteWords::default())]);
+
+        let body = |bytes: &[u8], chunked| read_body(&mut &bytes[..], chunked, None);
+        assert_eq!(body(b"3\r\nabc\r\n2;x=y\r\nde\r\n0\r\n\r\n", true).unwrap(), b"abcde");
+        assert_eq!(body(b"zz\r\nabc\r\n", true).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(body(b"3\r\nab", true).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        let huge = format!("{:x}\r\n", usize::MAX);
+        assert_eq!(body(huge.as_bytes(), true).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let oversized = vec![b'a'; MAX_BODY + 1];
+        assert_eq!(body(&oversized, false).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let length = read_body(&mut &b""[..], false, Some(MAX_BODY + 1));
+        assert_eq!(length.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,111 @@
+// Thread-safe shared engine handle
+// 跨執行緒共用的輸入法引擎
+
+use crate::dict::Dictionary;
+use crate::input_engine::{InputEngine, KeyResult};
+use crate::state::{Candidate, InputState};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// 引擎狀態快照
+/// 供 UI 執行緒讀取，不持有任何鎖
+#[derive(Debug, Clone)]
+pub struct EngineSnapshot {
+    /// 輸入狀態
+    pub state: InputState,
+    /// 當前頁面的候選
+    pub candidates: Vec<Candidate>,
+}
+
+/// 共用引擎把手
+///
+/// 內部以 `Arc<Mutex<InputEngine>>` 保存引擎，可自由複製並傳遞到其他執行緒
+/// （`Send + Sync`）。事件執行緒透過 `handle_key` 驅動引擎，UI 執行緒以
+/// `snapshot` 取得當下狀態的複本。
+#[derive(Clone)]
+pub struct SharedEngine {
+    inner: Arc<Mutex<InputEngine>>,
+}
+
+impl SharedEngine {
+    pub fn new(dict: Dictionary) -> Self {
+        Self::from_engine(InputEngine::new(dict))
+    }
+
+    /// 包裝既有的引擎
+    pub fn from_engine(engine: InputEngine) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(engine)),
+        }
+    }
+
+    /// 取得引擎鎖
+    /// 若其他執行緒在持鎖時 panic，仍沿用引擎目前的狀態
+    pub fn lock(&self) -> MutexGuard<'_, InputEngine> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 在持鎖狀態下操作引擎
+    pub fn with<R>(&self, f: impl FnOnce(&mut InputEngine) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    /// 處理按鍵輸入
+    pub fn handle_key(&self, key: char) -> KeyResult {
+        self.lock().handle_key(key)
+    }
+
+    /// 取得目前狀態的快照
+    pub fn snapshot(&self) -> EngineSnapshot {
+        let engine = self.lock();
+        EngineSnapshot {
+            state: engine.state().clone(),
+            candidates: engine.current_page_candidates().to_vec(),
+        }
+    }
+}
+
+impl From<InputEngine> for SharedEngine {
+    fn from(engine: InputEngine) -> Self {
+        Self::from_engine(engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn create_test_dict() -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.insert_char("abc", "測");
+        dict
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedEngine>();
+    }
+
+    #[test]
+    fn test_drive_from_other_thread() {
+        let engine = SharedEngine::new(create_test_dict());
+        let worker = engine.clone();
+
+        thread::spawn(move || {
+            for c in "abc".chars() {
+                worker.handle_key(c);
+            }
+        })
+        .join()
+        .unwrap();
+
+        let snapshot = engine.snapshot();
+        assert_eq!(snapshot.state.current_code, "abc");
+        assert_eq!(snapshot.candidates.len(), 1);
+        assert_eq!(snapshot.candidates[0].text, "測");
+
+        engine.handle_key(' ');
+        assert_eq!(engine.snapshot().state.output, "測");
+    }
+}
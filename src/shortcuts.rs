@@ -0,0 +1,454 @@
+// Keyboard shortcuts for Array30
+// 快速鍵設定與分派（終端機與 GUI 共用）
+
+use crate::input_engine::{InputEngine, KeyResult};
+#[cfg(feature = "console")]
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 可綁定快速鍵的動作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// 候選下一頁
+    NextPage,
+    /// 候選上一頁
+    PrevPage,
+    /// 切換中文／英文
+    ToggleLanguage,
+    /// 復原最後一次上屏
+    Undo,
+    /// 清除輸出區
+    Clear,
+    /// 切換浮動候選列（僅 GUI）
+    ToggleCandidateBar,
+    /// 複製輸出區到剪貼簿
+    CopyOutput,
+    /// 切換按鍵提示（終端機的行列標示列、GUI 的螢幕鍵盤）
+    ToggleKeyHints,
+    /// 切換數字組碼（數字鍵接在碼後面輸入特別碼）
+    ToggleDigitCodes,
+    /// 切換候選詳細資訊（僅終端機；GUI 在候選提示中顯示讀音與釋義）
+    ToggleCandidateDetail,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::NextPage => "next_page",
+            Action::PrevPage => "prev_page",
+            Action::ToggleLanguage => "toggle_language",
+            Action::Undo => "undo",
+            Action::Clear => "clear",
+            Action::ToggleCandidateBar => "toggle_candidate_bar",
+            Action::CopyOutput => "copy_output",
+            Action::ToggleKeyHints => "toggle_key_hints",
+            Action::ToggleDigitCodes => "toggle_digit_codes",
+            Action::ToggleCandidateDetail => "toggle_candidate_detail",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Action::NextPage => "下一頁",
+            Action::PrevPage => "上一頁",
+            Action::ToggleLanguage => "切換中／英文",
+            Action::Undo => "復原上屏",
+            Action::Clear => "清除輸出",
+            Action::ToggleCandidateBar => "浮動候選列",
+            Action::CopyOutput => "複製輸出區",
+            Action::ToggleKeyHints => "按鍵提示",
+            Action::ToggleDigitCodes => "數字組碼",
+            Action::ToggleCandidateDetail => "候選詳細資訊",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::all().into_iter().find(|a| a.as_str() == s)
+    }
+
+    /// 所有動作（用於設定畫面）
+    pub fn all() -> [Action; 10] {
+        [
+            Action::NextPage,
+            Action::PrevPage,
+            Action::ToggleLanguage,
+            Action::Undo,
+            Action::Clear,
+            Action::ToggleCandidateBar,
+            Action::CopyOutput,
+            Action::ToggleKeyHints,
+            Action::ToggleDigitCodes,
+            Action::ToggleCandidateDetail,
+        ]
+    }
+}
+
+/// 快速鍵的主鍵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChordKey {
+    /// 字元鍵（一律以小寫表示）
+    Char(char),
+    Space,
+    Tab,
+    Enter,
+    Backspace,
+    Escape,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Up,
+    Down,
+    Left,
+    Right,
+    Insert,
+    Delete,
+    /// 功能鍵 F1-F24
+    F(u8),
+}
+
+impl ChordKey {
+    /// 由按鍵名稱解析，名稱不分大小寫
+    /// 支援 egui 的按鍵名稱（例如 "PageDown"、"Comma"、"A"）
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Some(match c {
+                ' ' => ChordKey::Space,
+                c => ChordKey::Char(c.to_ascii_lowercase()),
+            });
+        }
+
+        let key = match name.to_lowercase().as_str() {
+            "space" => ChordKey::Space,
+            "tab" => ChordKey::Tab,
+            "enter" | "return" => ChordKey::Enter,
+            "backspace" => ChordKey::Backspace,
+            "esc" | "escape" => ChordKey::Escape,
+            "pageup" | "pgup" => ChordKey::PageUp,
+            "pagedown" | "pgdn" => ChordKey::PageDown,
+            "home" => ChordKey::Home,
+            "end" => ChordKey::End,
+            "up" | "arrowup" => ChordKey::Up,
+            "down" | "arrowdown" => ChordKey::Down,
+            "left" | "arrowleft" => ChordKey::Left,
+            "right" | "arrowright" => ChordKey::Right,
+            "insert" => ChordKey::Insert,
+            "delete" | "del" => ChordKey::Delete,
+            "comma" => ChordKey::Char(','),
+            "period" => ChordKey::Char('.'),
+            "semicolon" => ChordKey::Char(';'),
+            "slash" => ChordKey::Char('/'),
+            "quote" => ChordKey::Char('\''),
+            "minus" => ChordKey::Char('-'),
+            "plus" => ChordKey::Char('+'),
+            other => {
+                let n: u8 = other.strip_prefix('f')?.parse().ok()?;
+                if (1..=24).contains(&n) {
+                    ChordKey::F(n)
+                } else {
+                    return None;
+                }
+            }
+        };
+        Some(key)
+    }
+}
+
+impl fmt::Display for ChordKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // 標點以名稱表示，避免與 "+"、"," 分隔符號混淆
+            ChordKey::Char(',') => write!(f, "Comma"),
+            ChordKey::Char('.') => write!(f, "Period"),
+            ChordKey::Char(';') => write!(f, "Semicolon"),
+            ChordKey::Char('/') => write!(f, "Slash"),
+            ChordKey::Char('\'') => write!(f, "Quote"),
+            ChordKey::Char('-') => write!(f, "Minus"),
+            ChordKey::Char('+') => write!(f, "Plus"),
+            ChordKey::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            ChordKey::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// 組合鍵，例如 Ctrl+Z、Shift+PageUp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyChord {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: ChordKey,
+}
+
+impl KeyChord {
+    pub fn new(key: ChordKey) -> Self {
+        Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            key,
+        }
+    }
+
+    /// 解析 "Ctrl+Shift+Z" 格式
+    pub fn parse(s: &str) -> Option<Self> {
+        let parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let (key_name, modifiers) = parts.split_last()?;
+        let mut chord = KeyChord::new(ChordKey::from_name(key_name)?);
+
+        for modifier in modifiers {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                "shift" => chord.shift = true,
+                _ => return None,
+            }
+        }
+        Some(chord)
+    }
+
+    /// 由終端機按鍵事件轉換
+    #[cfg(feature = "console")]
+    pub fn from_key_event(event: &KeyEvent) -> Option<Self> {
+        let mut shift = event.modifiers.contains(KeyModifiers::SHIFT);
+        let key = match event.code {
+            KeyCode::Char(' ') => ChordKey::Space,
+            KeyCode::Char(c) => {
+                // 字元本身已反映 Shift，只有大寫字母視為 Shift 組合
+                shift = c.is_ascii_uppercase();
+                ChordKey::Char(c.to_ascii_lowercase())
+            }
+            KeyCode::Tab => ChordKey::Tab,
+            KeyCode::BackTab => {
+                shift = true;
+                ChordKey::Tab
+            }
+            KeyCode::Enter => ChordKey::Enter,
+            KeyCode::Backspace => ChordKey::Backspace,
+            KeyCode::Esc => ChordKey::Escape,
+            KeyCode::PageUp => ChordKey::PageUp,
+            KeyCode::PageDown => ChordKey::PageDown,
+            KeyCode::Home => ChordKey::Home,
+            KeyCode::End => ChordKey::End,
+            KeyCode::Up => ChordKey::Up,
+            KeyCode::Down => ChordKey::Down,
+            KeyCode::Left => ChordKey::Left,
+            KeyCode::Right => ChordKey::Right,
+            KeyCode::Insert => ChordKey::Insert,
+            KeyCode::Delete => ChordKey::Delete,
+            KeyCode::F(n) => ChordKey::F(n),
+            _ => return None,
+        };
+
+        Some(Self {
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+            alt: event.modifiers.contains(KeyModifiers::ALT),
+            shift,
+            key,
+        })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// 快速鍵對應表
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shortcuts {
+    bindings: Vec<(Action, Vec<KeyChord>)>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        let defaults = [
+            (Action::NextPage, "PageDown, Tab"),
+            (Action::PrevPage, "PageUp, Shift+Tab"),
+            (Action::ToggleLanguage, "Ctrl+Space"),
+            (Action::Undo, "Ctrl+Z"),
+            (Action::Clear, "Ctrl+L"),
+            (Action::ToggleCandidateBar, "Ctrl+B"),
+            (Action::CopyOutput, "Ctrl+Y, F5"),
+            (Action::ToggleKeyHints, "F1"),
+            (Action::ToggleDigitCodes, "Ctrl+D"),
+            (Action::ToggleCandidateDetail, "F2"),
+        ];
+        Self {
+            bindings: defaults
+                .into_iter()
+                .map(|(action, chords)| (action, parse_chords(chords).unwrap_or_default()))
+                .collect(),
+        }
+    }
+}
+
+impl Shortcuts {
+    /// 取得動作綁定的組合鍵
+    pub fn chords(&self, action: Action) -> &[KeyChord] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, chords)| chords.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// 設定動作綁定的組合鍵
+    pub fn set(&mut self, action: Action, chords: Vec<KeyChord>) {
+        match self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, existing)) => *existing = chords,
+            None => self.bindings.push((action, chords)),
+        }
+    }
+
+    /// 查詢組合鍵對應的動作
+    pub fn lookup(&self, chord: &KeyChord) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, chords)| chords.contains(chord))
+            .map(|(action, _)| *action)
+    }
+
+    /// 以逗號分隔的文字表示動作綁定，例如 "PageDown, Tab"
+    pub fn chords_text(&self, action: Action) -> String {
+        self.chords(action)
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// 輸出設定檔的 [shortcuts] 區段
+    pub fn to_ini_section(&self) -> String {
+        let mut section = String::from("[shortcuts]\n# Key chords per action, comma separated (快速鍵，以逗號分隔)\n");
+        for action in Action::all() {
+            section.push_str(&format!("{}={}\n", action.as_str(), self.chords_text(action)));
+        }
+        section
+    }
+}
+
+/// 解析以逗號分隔的組合鍵列表，任一項無法解析時回傳 None
+pub fn parse_chords(s: &str) -> Option<Vec<KeyChord>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(KeyChord::parse)
+        .collect()
+}
+
+/// 執行動作
+pub fn dispatch(engine: &mut InputEngine, action: Action) -> KeyResult {
+    let changed = match action {
+        Action::NextPage => engine.next_page(),
+        Action::PrevPage => engine.prev_page(),
+        Action::ToggleLanguage => {
+            engine.toggle_english_mode();
+            true
+        }
+        Action::ToggleDigitCodes => {
+            engine.toggle_digit_codes();
+            true
+        }
+        Action::Undo => engine.undo(),
+        Action::Clear => {
+            engine.clear_output();
+            true
+        }
+        // 介面動作由前端自行處理
+        Action::ToggleCandidateBar | Action::CopyOutput | Action::ToggleKeyHints | Action::ToggleCandidateDetail => false,
+    };
+
+    if changed {
+        KeyResult::NeedUpdate
+    } else {
+        KeyResult::NoChange
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chord() {
+        let chord = KeyChord::parse("Ctrl+Shift+z").unwrap();
+        assert!(chord.ctrl && chord.shift && !chord.alt);
+        assert_eq!(chord.key, ChordKey::Char('z'));
+        assert_eq!(chord.to_string(), "Ctrl+Shift+Z");
+
+        assert_eq!(KeyChord::parse("pagedown").unwrap().key, ChordKey::PageDown);
+        assert_eq!(KeyChord::parse("F5").unwrap().key, ChordKey::F(5));
+        assert_eq!(KeyChord::parse("Comma").unwrap().key, ChordKey::Char(','));
+        assert!(KeyChord::parse("Hyper+A").is_none());
+        assert!(KeyChord::parse("F99").is_none());
+
+        // 標點以名稱輸出，可再解析回相同組合鍵
+        let comma = KeyChord::parse("Ctrl+Comma").unwrap();
+        assert_eq!(comma.to_string(), "Ctrl+Comma");
+        assert_eq!(parse_chords(&comma.to_string()), Some(vec![comma]));
+    }
+
+    #[test]
+    fn test_default_shortcuts() {
+        let shortcuts = Shortcuts::default();
+        let tab = KeyChord::new(ChordKey::Tab);
+        assert_eq!(shortcuts.lookup(&tab), Some(Action::NextPage));
+        assert_eq!(shortcuts.lookup(&KeyChord::parse("Ctrl+Z").unwrap()), Some(Action::Undo));
+        assert_eq!(shortcuts.lookup(&KeyChord::parse("Z").unwrap()), None);
+        assert_eq!(shortcuts.chords_text(Action::PrevPage), "PageUp, Shift+Tab");
+        assert_eq!(shortcuts.lookup(&KeyChord::parse("F5").unwrap()), Some(Action::CopyOutput));
+        assert_eq!(shortcuts.lookup(&KeyChord::parse("F1").unwrap()), Some(Action::ToggleKeyHints));
+    }
+
+    #[test]
+    #[cfg(feature = "console")]
+    fn test_from_key_event() {
+        let event = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(KeyChord::from_key_event(&event), KeyChord::parse("Ctrl+Z"));
+
+        let event = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from_key_event(&event), KeyChord::parse("Shift+Tab"));
+
+        // 符號字元不因 Shift 修飾而改變
+        let event = KeyEvent::new(KeyCode::Char('!'), KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from_key_event(&event), KeyChord::parse("!"));
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let mut engine = InputEngine::new(crate::dict::Dictionary::new());
+        engine.feed_str("1");
+        assert_eq!(dispatch(&mut engine, Action::Undo), KeyResult::NeedUpdate);
+        assert!(engine.state().output.is_empty());
+        assert_eq!(dispatch(&mut engine, Action::Undo), KeyResult::NoChange);
+
+        dispatch(&mut engine, Action::ToggleLanguage);
+        assert!(engine.state().english_mode);
+        dispatch(&mut engine, Action::ToggleDigitCodes);
+        assert!(engine.is_digit_code_mode());
+    }
+
+    #[test]
+    fn test_rebind() {
+        let mut shortcuts = Shortcuts::default();
+        shortcuts.set(Action::Clear, parse_chords("F12").unwrap());
+        assert_eq!(shortcuts.lookup(&KeyChord::parse("F12").unwrap()), Some(Action::Clear));
+        assert_eq!(shortcuts.lookup(&KeyChord::parse("Ctrl+L").unwrap()), None);
+        assert!(parse_chords("Ctrl+Q, Bogus+X").is_none());
+    }
+}
@@ -1,8 +1,17 @@
 // Input state management for Array30
 // 輸入狀態機
 
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// 可復原的上屏次數上限
+const MAX_UNDO: usize = 100;
+/// 最近按鍵視窗保留的按鍵數
+pub const RECENT_KEYS: usize = 32;
+
 /// 輸入模式
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InputMode {
     /// 一般字詞輸入
     Normal,
@@ -10,8 +19,24 @@ pub enum InputMode {
     PhraseInput,
 }
 
-/// 輸入狀態
-#[derive(Debug, Clone)]
+/// 一份輸出文件：已上屏的文字與可復原的上屏記錄
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputDoc {
+    pub text: String,
+    commit_marks: Vec<usize>,
+}
+
+impl OutputDoc {
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            commit_marks: Vec::new(),
+        }
+    }
+}
+
+/// 輸入狀態（可序列化為 JSON 傳給其他行程；上屏文字記錄不包含在內）
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InputState {
     /// 原始鍵序區：使用者輸入的按鍵序列
     pub raw_keys: String,
@@ -25,6 +50,26 @@ pub struct InputState {
     pub current_code: String,
     /// 是否有詞彙終結符
     pub has_phrase_marker: bool,
+    /// 英文模式：按鍵直接輸出，不組字
+    pub english_mode: bool,
+    /// 每次上屏前輸出區的長度（用於復原）
+    #[serde(default)]
+    commit_marks: Vec<usize>,
+    /// 最近的按鍵（跨越多次組字，最多 RECENT_KEYS 個），供介面顯示
+    #[serde(default)]
+    recent_keys: String,
+    /// 上屏文字記錄（啟用後由前端定期取出，寫入輸出日誌）
+    #[serde(skip)]
+    committed: Option<Vec<String>>,
+    /// 組字中最後一次按鍵的時間（組字逾時用；沒有組字時為 None）
+    #[serde(skip)]
+    last_key_at: Option<Instant>,
+    /// 輸出區字元數上限（0 為不限制），超過時較早的內容移到 archived
+    #[serde(skip)]
+    output_limit: usize,
+    /// 從輸出區移出、尚未被前端取走的較早內容
+    #[serde(skip)]
+    archived: Vec<String>,
 }
 
 impl Default for InputState {
@@ -42,6 +87,13 @@ impl InputState {
             mode: InputMode::Normal,
             current_code: String::new(),
             has_phrase_marker: false,
+            english_mode: false,
+            commit_marks: Vec::new(),
+            recent_keys: String::new(),
+            committed: None,
+            last_key_at: None,
+            output_limit: 0,
+            archived: Vec::new(),
         }
     }
 
@@ -52,17 +104,34 @@ impl InputState {
         self.current_code.clear();
         self.has_phrase_marker = false;
         self.mode = InputMode::Normal;
+        self.last_key_at = None;
     }
 
     /// 清空全部
     pub fn clear_all(&mut self) {
         self.clear_composing();
         self.output.clear();
+        self.commit_marks.clear();
     }
 
     /// 添加按鍵到原始鍵序
     pub fn add_key(&mut self, key: char) {
         self.raw_keys.push(key);
+        self.last_key_at = Some(Instant::now());
+        self.recent_keys.push(key);
+        if let Some((start, _)) = self.recent_keys.char_indices().rev().nth(RECENT_KEYS - 1) {
+            self.recent_keys.drain(..start);
+        }
+    }
+
+    /// 組字中最後一次按鍵（含退格）的時間，沒有組字時為 None
+    pub fn last_key_at(&self) -> Option<Instant> {
+        self.last_key_at
+    }
+
+    /// 最近的按鍵（原始鍵序只含目前組字的按鍵，上屏後清空）
+    pub fn recent_keys(&self) -> &str {
+        &self.recent_keys
     }
 
     /// 設置為詞彙輸入模式
@@ -80,19 +149,147 @@ impl InputState {
     /// 將編輯區內容移到輸出區
     pub fn commit_composing(&mut self) {
         if !self.composing.is_empty() {
-            self.output.push_str(&self.composing);
+            let composing = std::mem::take(&mut self.composing);
+            self.commit_direct(&composing);
             self.clear_composing();
         }
     }
 
     /// 直接添加文字到輸出區
     pub fn commit_direct(&mut self, text: &str) {
+        self.mark_commit();
         self.output.push_str(text);
+        if let Some(committed) = self.committed.as_mut() {
+            committed.push(text.to_string());
+        }
+        self.archive_overflow();
+    }
+
+    /// 設定輸出區字元數上限（0 為不限制）
+    pub fn set_output_limit(&mut self, limit: usize) {
+        self.output_limit = limit;
+        self.archive_overflow();
+    }
+
+    /// 取出從輸出區移出的較早內容（依移出順序）
+    pub fn take_archived(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.archived)
+    }
+
+    /// 輸出區超過上限時，把較早的內容移到 archived，只留上限的四分之三
+    /// （一次移出一段，不必每次上屏都移出幾個字）
+    fn archive_overflow(&mut self) {
+        if self.output_limit == 0 {
+            return;
+        }
+        let count = self.output.chars().count();
+        if count <= self.output_limit {
+            return;
+        }
+        let keep = self.output_limit - self.output_limit / 4;
+        let cut = self.output.char_indices().nth(count - keep).map_or(self.output.len(), |(i, _)| i);
+        self.archived.push(self.output.drain(..cut).collect());
+        self.commit_marks.retain(|&mark| mark >= cut);
+        for mark in &mut self.commit_marks {
+            *mark -= cut;
+        }
+    }
+
+    /// 記錄上屏前的輸出位置
+    fn mark_commit(&mut self) {
+        if self.commit_marks.len() >= MAX_UNDO {
+            self.commit_marks.remove(0);
+        }
+        self.commit_marks.push(self.output.len());
+    }
+
+    /// 開始記錄上屏的文字（需定期以 take_committed 取出）
+    pub fn enable_commit_record(&mut self) {
+        self.committed.get_or_insert_with(Vec::new);
+    }
+
+    /// 取出啟用記錄後上屏的文字
+    pub fn take_committed(&mut self) -> Vec<String> {
+        self.committed.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// 暫時把上屏文字記錄到新的清單，回傳原本的記錄（交給 end_commit_capture 還原）
+    pub fn begin_commit_capture(&mut self) -> Option<Vec<String>> {
+        self.committed.replace(Vec::new())
+    }
+
+    /// 結束暫時記錄並還原原本的記錄，回傳期間上屏的文字
+    /// 原本已啟用記錄時，期間的文字也會接到原本的記錄後面
+    pub fn end_commit_capture(&mut self, outer: Option<Vec<String>>) -> Vec<String> {
+        let captured = std::mem::replace(&mut self.committed, outer).unwrap_or_default();
+        if let Some(committed) = self.committed.as_mut() {
+            committed.extend(captured.iter().cloned());
+        }
+        captured
+    }
+
+    /// 取出輸出區文字並清空（保留編輯中的碼）
+    /// 供把文字交給外部程式的前端使用，取出後無法再復原
+    pub fn take_output(&mut self) -> String {
+        self.commit_marks.clear();
+        std::mem::take(&mut self.output)
+    }
+
+    /// 與另一份輸出文件交換輸出區（含上屏記錄），編輯中的碼不受影響
+    pub fn swap_output(&mut self, doc: &mut OutputDoc) {
+        std::mem::swap(&mut self.output, &mut doc.text);
+        std::mem::swap(&mut self.commit_marks, &mut doc.commit_marks);
+    }
+
+    /// 以使用者手動編輯後的內容取代輸出區
+    /// 只保留位於未變動前段的上屏記錄，其餘無法再復原
+    pub fn replace_output(&mut self, text: String) {
+        let unchanged = self
+            .output
+            .char_indices()
+            .zip(text.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(self.output.len().min(text.len()), |((i, _), _)| i);
+        self.commit_marks.retain(|&mark| mark <= unchanged);
+        self.output = text;
+        self.archive_overflow();
+    }
+
+    /// 在輸出區第 at 個字元前插入文字（超過結尾時接在最後）
+    /// 插入點之後的上屏記錄無法再復原
+    pub fn insert_output_at(&mut self, at: usize, text: &str) {
+        let split = self.output.char_indices().nth(at).map_or(self.output.len(), |(i, _)| i);
+        self.commit_marks.retain(|&mark| mark <= split);
+        self.output.insert_str(split, text);
+        self.archive_overflow();
+    }
+
+    /// 刪除輸出區第 at 個字元，回傳是否有刪除
+    pub fn delete_output_at(&mut self, at: usize) -> bool {
+        let Some((split, _)) = self.output.char_indices().nth(at) else {
+            return false;
+        };
+        self.commit_marks.retain(|&mark| mark <= split);
+        self.output.remove(split);
+        true
+    }
+
+    /// 復原最後一次上屏
+    /// 回傳是否有可復原的內容
+    pub fn undo_commit(&mut self) -> bool {
+        match self.commit_marks.pop() {
+            Some(mark) if mark <= self.output.len() && self.output.is_char_boundary(mark) => {
+                self.output.truncate(mark);
+                true
+            }
+            _ => false,
+        }
     }
 
     /// 退格：刪除最後一個字元
     pub fn backspace(&mut self) -> bool {
         if self.current_code.pop().is_some() {
+            self.last_key_at = Some(Instant::now());
             if let Some(c) = self.raw_keys.pop() {
                 // 如果刪除的是詞彙標記，退出詞彙模式
                 if c == '\'' {
@@ -106,20 +303,19 @@ impl InputState {
     }
 
     /// 取得提示文字
-    pub fn get_hint(&self) -> String {
+    pub fn get_hint(&self) -> &'static str {
+        if self.english_mode {
+            return "英文模式：按鍵直接輸出；切換語言快速鍵可回到中文";
+        }
         match self.mode {
-            InputMode::Normal => {
-                "提示：按 ' 進入詞彙輸入；空白鍵上第一候選；數字鍵選字；Esc 清空".to_string()
-            }
-            InputMode::PhraseInput => {
-                "詞彙模式：輸入四碼後會自動查找詞庫".to_string()
-            }
+            InputMode::Normal => "提示：按 ' 進入詞彙輸入；空白鍵上第一候選；數字鍵選字；Esc 清空",
+            InputMode::PhraseInput => "詞彙模式：輸入四碼後會自動查找詞庫",
         }
     }
 }
 
 /// 候選項
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Candidate {
     /// 顯示文字（漢字或詞彙）
     pub text: String,
@@ -127,6 +323,15 @@ pub struct Candidate {
     pub code: String,
     /// 是否為詞彙
     pub is_phrase: bool,
+    /// 讀音（由註解檔提供）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub readings: Vec<String>,
+    /// 釋義（由 Unihan 註解提供）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+    /// 是否來自大字集後備字表（常用字表查無此碼時）
+    #[serde(default)]
+    pub big_set: bool,
 }
 
 impl Candidate {
@@ -135,6 +340,9 @@ impl Candidate {
             text,
             code,
             is_phrase,
+            readings: Vec::new(),
+            definition: None,
+            big_set: false,
         }
     }
 
@@ -145,11 +353,25 @@ impl Candidate {
     pub fn phrase(text: String, code: String) -> Self {
         Self::new(text, code, true)
     }
+
+    /// 詳細資訊：字、碼位、讀音與釋義，例如「行 U+884C xíng、háng go, walk」
+    pub fn detail(&self) -> String {
+        let codepoints: Vec<String> = self.text.chars().map(crate::unicode::codepoint).collect();
+        let mut line = format!("{} {}", self.text, codepoints.join(" "));
+        if !self.readings.is_empty() {
+            line.push_str(&format!(" {}", self.readings.join("、")));
+        }
+        if let Some(ref definition) = self.definition {
+            line.push_str(&format!(" {}", definition));
+        }
+        line
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input_engine::{KeyResult, RejectReason};
 
     #[test]
     fn test_state_initialization() {
@@ -186,4 +408,99 @@ mod tests {
         assert_eq!(state.output, "台灣");
         assert!(state.composing.is_empty());
     }
+
+    #[test]
+    fn test_undo_commit() {
+        let mut state = InputState::new();
+        state.commit_direct("台");
+        state.composing = "灣".to_string();
+        state.commit_composing();
+        assert_eq!(state.output, "台灣");
+
+        assert!(state.undo_commit());
+        assert_eq!(state.output, "台");
+        assert!(state.undo_commit());
+        assert!(state.output.is_empty());
+        assert!(!state.undo_commit());
+    }
+
+    #[test]
+    fn test_replace_output() {
+        let mut state = InputState::new();
+        state.commit_direct("台");
+        state.commit_direct("灣");
+        state.commit_direct("好");
+        state.replace_output("台灣很好".to_string());
+        assert_eq!(state.output, "台灣很好");
+        assert!(state.undo_commit());
+        assert_eq!(state.output, "台灣");
+        assert!(state.undo_commit());
+        assert_eq!(state.output, "台");
+
+        state.commit_direct("灣");
+        state.insert_output_at(1, "北");
+        assert!(state.delete_output_at(0));
+        assert!(!state.delete_output_at(9));
+        assert_eq!(state.output, "北灣");
+    }
+
+    #[test]
+    fn test_take_output() {
+        let mut state = InputState::new();
+        state.enable_commit_record();
+        state.commit_direct("台");
+        state.current_code = "ab".to_string();
+        assert_eq!(state.take_committed(), ["台"]);
+        assert!(state.take_committed().is_empty());
+        assert_eq!(state.take_output(), "台");
+        assert!(state.output.is_empty());
+        assert_eq!(state.current_code, "ab");
+        assert!(!state.undo_commit());
+    }
+
+    #[test]
+    fn test_output_limit() {
+        let mut state = InputState::new();
+        state.set_output_limit(8);
+        for c in "一二三四五六七八".chars() {
+            state.commit_direct(&c.to_string());
+        }
+        assert!(state.take_archived().is_empty());
+        state.commit_direct("九");
+        // 超過上限時只留上限的四分之三
+        assert_eq!(state.take_archived(), ["一二三"]);
+        assert_eq!(state.output, "四五六七八九");
+        assert!(state.undo_commit());
+        assert_eq!(state.output, "四五六七八");
+
+        for c in "abcdefghijklmnopqrstuvwxyz0123456789".chars() {
+            state.add_key(c);
+        }
+        assert_eq!(state.recent_keys().len(), RECENT_KEYS);
+        assert!(state.recent_keys().starts_with("efg"));
+    }
+
+    #[test]
+    fn test_serde() {
+        let mut state = InputState::new();
+        state.enable_commit_record();
+        state.commit_direct("台");
+        state.mode = InputMode::PhraseInput;
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(json.contains(r#""mode":"phrase_input""#));
+        let mut restored: InputState = serde_json::from_str(&json).unwrap();
+        assert_eq!((restored.output.as_str(), restored.mode), ("台", InputMode::PhraseInput));
+        assert!(restored.undo_commit());
+        assert!(restored.take_committed().is_empty());
+
+        let candidate = Candidate::new("灣".to_string(), "cyxg".to_string(), false);
+        let json = serde_json::to_string(&candidate).unwrap();
+        assert_eq!(json, r#"{"text":"灣","code":"cyxg","is_phrase":false,"big_set":false}"#);
+        let restored: Candidate = serde_json::from_str(r#"{"text":"灣","code":"cyxg","is_phrase":false}"#).unwrap();
+        assert_eq!((restored.text, restored.big_set), ("灣".to_string(), false));
+
+        let result = KeyResult::Rejected(RejectReason::TooManyKeys);
+        assert_eq!(serde_json::to_string(&result).unwrap(), r#"{"rejected":"too_many_keys"}"#);
+        assert_eq!(serde_json::from_str::<KeyResult>(r#""committed""#).unwrap(), KeyResult::Committed);
+    }
 }
@@ -0,0 +1,232 @@
+// Typing statistics
+// 使用統計：各行列鍵的按鍵次數，以及每日上屏字數與平均碼長
+
+use crate::keymap::Array30Key;
+use crate::state::Candidate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 統計檔名（位於使用者資料目錄）
+const STATS_FILENAME: &str = "stats.json";
+/// 每日統計保留的天數
+const MAX_DAYS: u64 = 366;
+
+/// 一天的上屏統計
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayStats {
+    /// 選字上屏的字數
+    pub chars: u64,
+    /// 選字上屏的次數
+    pub commits: u64,
+    /// 上屏候選的碼長總和
+    pub code_keys: u64,
+}
+
+impl DayStats {
+    /// 每次上屏的平均碼長
+    pub fn average_code_len(&self) -> f32 {
+        if self.commits == 0 {
+            0.0
+        } else {
+            self.code_keys as f32 / self.commits as f32
+        }
+    }
+}
+
+/// 使用統計
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    /// 各鍵（以 QWERTY 鍵位字元表示）的按鍵次數
+    keys: BTreeMap<char, u64>,
+    /// 每日統計（自 Unix 紀元起的 UTC 日數）
+    days: BTreeMap<u64, DayStats>,
+}
+
+/// 今天是自 Unix 紀元起的第幾天（UTC）
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86400)
+}
+
+/// 將日數轉為「月/日」標籤
+pub fn day_label(day: u64) -> String {
+    // 由日數推算公曆日期（Howard Hinnant 的 civil_from_days）
+    let z = day as i64 + 719_468;
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    format!("{}/{}", m, d)
+}
+
+impl Stats {
+    /// 記錄一次行列鍵按鍵
+    pub fn record_key(&mut self, key: Array30Key) {
+        *self.keys.entry(key.code_char()).or_default() += 1;
+    }
+
+    /// 記錄選字上屏的候選
+    pub fn record_commit(&mut self, candidate: &Candidate) {
+        self.record_commit_on(today(), candidate);
+    }
+
+    fn record_commit_on(&mut self, day: u64, candidate: &Candidate) {
+        let stats = self.days.entry(day).or_default();
+        stats.chars += candidate.text.chars().count() as u64;
+        stats.commits += 1;
+        stats.code_keys += candidate.code.chars().count() as u64;
+
+        // 只保留最近一年
+        let oldest = day.saturating_sub(MAX_DAYS);
+        self.days.retain(|&d, _| d > oldest);
+    }
+
+    /// 鍵的按鍵次數
+    pub fn key_count(&self, key: Array30Key) -> u64 {
+        self.keys.get(&key.code_char()).copied().unwrap_or(0)
+    }
+
+    /// 最常按的鍵的次數
+    pub fn max_key_count(&self) -> u64 {
+        self.keys.values().copied().max().unwrap_or(0)
+    }
+
+    /// 以 `last` 為最後一天的連續 `count` 天統計（沒有記錄的日子為 0）
+    pub fn recent_days(&self, last: u64, count: u64) -> Vec<(u64, DayStats)> {
+        (last.saturating_sub(count.saturating_sub(1))..=last)
+            .map(|day| (day, self.days.get(&day).copied().unwrap_or_default()))
+            .collect()
+    }
+
+    /// 全部日子的統計合計
+    pub fn total(&self) -> DayStats {
+        self.days.values().fold(DayStats::default(), |sum, day| DayStats {
+            chars: sum.chars + day.chars,
+            commits: sum.commits + day.commits,
+            code_keys: sum.code_keys + day.code_keys,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.days.is_empty()
+    }
+
+    /// 三方合併：`base` 為上次同步的統計，兩邊各自增加的次數相加
+    pub fn merged(&self, other: &Stats, base: &Stats) -> Stats {
+        let merge = |a: u64, b: u64, base: u64| (a + b).saturating_sub(base).max(a).max(b);
+        let mut merged = Stats::default();
+        for &key in self.keys.keys().chain(other.keys.keys()) {
+            let count = |stats: &Stats| stats.keys.get(&key).copied().unwrap_or(0);
+            merged.keys.insert(key, merge(count(self), count(other), count(base)));
+        }
+        for &day in self.days.keys().chain(other.days.keys()) {
+            let stats = |s: &Stats| s.days.get(&day).copied().unwrap_or_default();
+            let (a, b, base) = (stats(self), stats(other), stats(base));
+            merged.days.insert(
+                day,
+                DayStats {
+                    chars: merge(a.chars, b.chars, base.chars),
+                    commits: merge(a.commits, b.commits, base.commits),
+                    code_keys: merge(a.code_keys, b.code_keys, base.code_keys),
+                },
+            );
+        }
+        merged
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.days.clear();
+    }
+
+    /// 統計檔路徑
+    pub fn file_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rustarray30").join(STATS_FILENAME))
+    }
+
+    /// 讀取統計檔
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// 讀取預設位置的統計檔，不存在或無法讀取時為空
+    pub fn load_default() -> Self {
+        let Some(path) = Self::file_path().filter(|p| p.exists()) else {
+            return Self::default();
+        };
+        Self::load(&path).unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), "無法讀取使用統計：{}", e);
+            Self::default()
+        })
+    }
+
+    /// 寫入統計檔
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_summarize() {
+        let mut stats = Stats::default();
+        let key = Array30Key::from_char('a').unwrap();
+        stats.record_key(key);
+        stats.record_key(key);
+        assert_eq!(stats.key_count(key), 2);
+        assert_eq!(stats.max_key_count(), 2);
+
+        stats.record_commit_on(100, &Candidate::char("測".to_string(), "abc".to_string()));
+        stats.record_commit_on(100, &Candidate::phrase("測試".to_string(), "abcd".to_string()));
+        stats.record_commit_on(102, &Candidate::char("甲".to_string(), "a".to_string()));
+
+        let days = stats.recent_days(102, 3);
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0], (100, DayStats { chars: 3, commits: 2, code_keys: 7 }));
+        assert_eq!(days[1].1, DayStats::default());
+        assert_eq!(days[0].1.average_code_len(), 3.5);
+        assert_eq!(stats.total().chars, 4);
+
+        // 超過一年的記錄會被移除
+        stats.record_commit_on(100 + MAX_DAYS, &Candidate::char("乙".to_string(), "b".to_string()));
+        assert_eq!(stats.recent_days(102, 3)[0].1, DayStats::default());
+        assert_eq!(stats.recent_days(102, 3)[2].1.chars, 1);
+
+        // 同步合併：兩邊各自增加的次數相加
+        let base = stats.clone();
+        let mut other = stats.clone();
+        other.record_key(key);
+        stats.record_key(key);
+        stats.record_key(key);
+        assert_eq!(stats.merged(&other, &base).key_count(key), 5);
+    }
+
+    #[test]
+    fn test_save_load_and_day_label() {
+        let path = std::env::temp_dir().join(format!("rustarray30-stats-{}.json", std::process::id()));
+        let mut stats = Stats::default();
+        stats.record_key(Array30Key::from_char(';').unwrap());
+        stats.record_commit_on(20_000, &Candidate::char("測".to_string(), "abc".to_string()));
+        stats.save(&path).unwrap();
+        assert_eq!(Stats::load(&path).unwrap(), stats);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(day_label(0), "1/1");
+        // 2024-02-29
+        assert_eq!(day_label(19_782), "2/29");
+    }
+}
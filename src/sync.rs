@@ -0,0 +1,230 @@
+// User data sync through a shared folder
+// 資料同步：與使用者指定的資料夾（Dropbox、OneDrive 等同步資料夾）合併自訂字詞、使用統計與設定檔
+//
+// 每次同步後把結果存為基準（使用者資料目錄的 sync/），下次同步時與基準比較判斷哪一邊有變動：
+// 只有一邊變動時直接複製；兩邊都變動時，自訂字詞與統計依基準三方合併，設定檔取修改時間較新的一邊。
+
+use crate::config::Config;
+use crate::stats::Stats;
+use crate::userdict::UserDict;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 同步基準所在的子目錄（位於使用者資料目錄）
+const BASE_DIR: &str = "sync";
+
+/// 同步的項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncItem {
+    UserDict,
+    Stats,
+    Settings,
+}
+
+impl SyncItem {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::UserDict => "自訂字詞",
+            Self::Stats => "使用統計",
+            Self::Settings => "設定檔",
+        }
+    }
+
+    pub fn all() -> [Self; 3] {
+        [Self::UserDict, Self::Stats, Self::Settings]
+    }
+
+    /// 在同步資料夾與基準目錄中的檔名
+    fn file_name(&self) -> &'static str {
+        match self {
+            Self::UserDict => "user.txt",
+            Self::Stats => "stats.json",
+            Self::Settings => "settings.ini",
+        }
+    }
+
+    /// 兩邊都有變動時的合併
+    fn merge(&self, local: &File, remote: &File, base: Option<&str>) -> io::Result<String> {
+        match self {
+            Self::UserDict => {
+                let base = UserDict::parse(base.unwrap_or_default());
+                Ok(UserDict::parse(&local.content).merged(&UserDict::parse(&remote.content), &base).to_text())
+            }
+            Self::Stats => {
+                let parse = |content: &str| -> io::Result<Stats> {
+                    serde_json::from_str(content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                };
+                let base = base.map(parse).transpose()?.unwrap_or_default();
+                let merged = parse(&local.content)?.merged(&parse(&remote.content)?, &base);
+                serde_json::to_string_pretty(&merged).map_err(io::Error::other)
+            }
+            Self::Settings => Ok(if remote.modified > local.modified {
+                remote.content.clone()
+            } else {
+                local.content.clone()
+            }),
+        }
+    }
+}
+
+/// 一個項目的同步結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// 兩邊相同
+    Unchanged,
+    /// 本機的變動寫入同步資料夾
+    Pushed,
+    /// 同步資料夾的變動寫入本機
+    Pulled,
+    /// 兩邊都有變動，合併後寫入兩邊
+    Merged,
+}
+
+impl SyncOutcome {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Unchanged => "沒有變動",
+            Self::Pushed => "已上傳",
+            Self::Pulled => "已下載",
+            Self::Merged => "已合併",
+        }
+    }
+}
+
+/// 本機的檔案位置
+#[derive(Debug, Clone)]
+pub struct LocalPaths {
+    pub user_dict: PathBuf,
+    pub stats: PathBuf,
+    pub settings: PathBuf,
+    /// 上次同步結果的目錄
+    pub base_dir: PathBuf,
+}
+
+impl LocalPaths {
+    /// 預設位置：使用者資料目錄與設定目錄
+    pub fn default_paths() -> Option<Self> {
+        let user_dict = UserDict::file_path()?;
+        Some(Self {
+            base_dir: user_dict.parent()?.join(BASE_DIR),
+            user_dict,
+            stats: Stats::file_path()?,
+            settings: Config::config_file_path()?,
+        })
+    }
+
+    fn path(&self, item: SyncItem) -> &Path {
+        match item {
+            SyncItem::UserDict => &self.user_dict,
+            SyncItem::Stats => &self.stats,
+            SyncItem::Settings => &self.settings,
+        }
+    }
+}
+
+/// 檔案內容與修改時間
+struct File {
+    content: String,
+    modified: SystemTime,
+}
+
+fn read(path: &Path) -> io::Result<Option<File>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(File {
+            content,
+            modified: std::fs::metadata(path)?.modified()?,
+        })),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn write(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, content)
+}
+
+/// 與同步資料夾同步全部項目
+pub fn sync(folder: &Path, local: &LocalPaths) -> io::Result<Vec<(SyncItem, SyncOutcome)>> {
+    if !folder.is_dir() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("找不到同步資料夾 {}", folder.display())));
+    }
+    SyncItem::all()
+        .into_iter()
+        .map(|item| {
+            let (remote, base) = (folder.join(item.file_name()), local.base_dir.join(item.file_name()));
+            sync_item(item, &remote, local.path(item), &base).map(|outcome| (item, outcome))
+        })
+        .collect()
+}
+
+fn sync_item(item: SyncItem, remote_path: &Path, local_path: &Path, base_path: &Path) -> io::Result<SyncOutcome> {
+    let base = read(base_path)?.map(|file| file.content);
+    let (outcome, content) = match (read(local_path)?, read(remote_path)?) {
+        (None, None) => return Ok(SyncOutcome::Unchanged),
+        (Some(local), Some(remote)) if local.content == remote.content => (SyncOutcome::Unchanged, local.content),
+        (Some(local), None) => (SyncOutcome::Pushed, local.content),
+        (None, Some(remote)) => (SyncOutcome::Pulled, remote.content),
+        // 只有一邊與上次同步的結果不同
+        (Some(local), Some(remote)) if base.as_deref() == Some(remote.content.as_str()) => {
+            (SyncOutcome::Pushed, local.content)
+        }
+        (Some(local), Some(remote)) if base.as_deref() == Some(local.content.as_str()) => {
+            (SyncOutcome::Pulled, remote.content)
+        }
+        (Some(local), Some(remote)) => (SyncOutcome::Merged, item.merge(&local, &remote, base.as_deref())?),
+    };
+    if matches!(outcome, SyncOutcome::Pushed | SyncOutcome::Merged) {
+        write(remote_path, &content)?;
+    }
+    if matches!(outcome, SyncOutcome::Pulled | SyncOutcome::Merged) {
+        write(local_path, &content)?;
+    }
+    if base.as_deref() != Some(content.as_str()) {
+        write(base_path, &content)?;
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::userdict::UserEntry;
+
+    #[test]
+    fn test_sync() {
+        let dir = std::env::temp_dir().join(format!("rustarray30-sync-{}", std::process::id()));
+        let folder = dir.join("vault");
+        std::fs::create_dir_all(&folder).unwrap();
+        let machine = |name: &str| LocalPaths {
+            user_dict: dir.join(name).join("user.txt"),
+            stats: dir.join(name).join("stats.json"),
+            settings: dir.join(name).join("settings.ini"),
+            base_dir: dir.join(name).join("sync"),
+        };
+        let (a, b) = (machine("a"), machine("b"));
+        let user = |text: &str| UserDict::parse(text).to_text();
+
+        write(&a.user_dict, &user("ab\t甲\ncd\t乙\n")).unwrap();
+        write(&a.settings, "[general]\nfont_size=20\n").unwrap();
+        assert_eq!(sync(&folder, &a).unwrap()[0], (SyncItem::UserDict, SyncOutcome::Pushed));
+        assert_eq!(sync(&folder, &b).unwrap()[2], (SyncItem::Settings, SyncOutcome::Pulled));
+        assert_eq!(sync(&folder, &b).unwrap()[0].1, SyncOutcome::Unchanged);
+
+        // 兩邊都改：A 刪除乙、B 新增丙，合併後兩邊一致
+        write(&a.user_dict, &user("ab\t甲\n")).unwrap();
+        sync(&folder, &a).unwrap();
+        write(&b.user_dict, &user("ab\t甲\ncd\t乙\nef\t丙\n")).unwrap();
+        assert_eq!(sync(&folder, &b).unwrap()[0].1, SyncOutcome::Merged);
+        let merged = UserDict::load(&b.user_dict).unwrap();
+        assert_eq!(merged.entries(), [UserEntry::new("ab", "甲"), UserEntry::new("ef", "丙")]);
+        assert_eq!(sync(&folder, &a).unwrap()[0].1, SyncOutcome::Pulled);
+        assert_eq!(UserDict::load(&a.user_dict).unwrap(), merged);
+
+        assert!(sync(&dir.join("missing"), &a).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+// Differences between two table versions
+// 比較兩版字表：列出新增、移除與改碼的字詞，升級官方字表時可確認變動
+
+use crate::compiled;
+use crate::dict::{DictEntry, Dictionary, TableKind};
+use crate::error::{Array30Error, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// 兩版都有、但碼不同的字詞
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeChange {
+    pub kind: TableKind,
+    pub word: String,
+    pub old_codes: Vec<String>,
+    pub new_codes: Vec<String>,
+}
+
+/// 比較結果；新增與移除依碼排序，改碼依字詞排序
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableDiff {
+    /// 只在新版出現的字詞
+    pub added: Vec<DictEntry>,
+    /// 只在舊版出現的字詞
+    pub removed: Vec<DictEntry>,
+    /// 碼有增減的字詞（不另列於新增與移除）
+    pub changed: Vec<CodeChange>,
+}
+
+impl TableDiff {
+    /// 比較兩個字典的字表與詞庫
+    pub fn compare(old: &Dictionary, new: &Dictionary) -> Self {
+        let mut diff = Self::default();
+        for kind in [TableKind::Chars, TableKind::Phrases] {
+            let codes = |dict: &Dictionary| {
+                let mut codes: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+                for (code, word) in dict.word_pairs(kind) {
+                    codes.entry(word.to_string()).or_default().insert(code.to_string());
+                }
+                codes
+            };
+            let (mut old_codes, new_codes) = (codes(old), codes(new));
+            let entry = |code: &String, word: &String| DictEntry {
+                code: code.clone(),
+                word: word.clone(),
+                kind,
+            };
+            for (word, new) in new_codes {
+                match old_codes.remove(&word) {
+                    None => diff.added.extend(new.iter().map(|code| entry(code, &word))),
+                    Some(old) if old != new => diff.changed.push(CodeChange {
+                        kind,
+                        word,
+                        old_codes: old.into_iter().collect(),
+                        new_codes: new.into_iter().collect(),
+                    }),
+                    Some(_) => {}
+                }
+            }
+            for (word, old) in old_codes {
+                diff.removed.extend(old.iter().map(|code| entry(code, &word)));
+            }
+        }
+        let by_code = |a: &DictEntry, b: &DictEntry| (&a.code, &a.word).cmp(&(&b.code, &b.word));
+        diff.added.sort_by(by_code);
+        diff.removed.sort_by(by_code);
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// 輸出的文字：摘要之後每行一筆，+ 新增、- 移除、~ 改碼
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "新增 {} 筆、移除 {} 筆、改碼 {} 個字詞",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )];
+        lines.extend(self.added.iter().map(|e| format!("+ {}\t{}", e.code, e.word)));
+        lines.extend(self.removed.iter().map(|e| format!("- {}\t{}", e.code, e.word)));
+        lines.extend(
+            self.changed
+                .iter()
+                .map(|c| format!("~ {}\t{} → {}", c.word, c.old_codes.join(","), c.new_codes.join(","))),
+        );
+        lines
+    }
+}
+
+/// 讀取要比較的表格：.cin2/.cin 字表、.txt 詞庫或 .a30t 編譯字表
+pub fn load_table(path: &Path) -> Result<Dictionary> {
+    if compiled::is_compiled(path) {
+        return Dictionary::load_compiled(path);
+    }
+    let mut dict = Dictionary::new();
+    match TableKind::from_path(path) {
+        Some(TableKind::Chars) => dict.load_cin2_file(path)?,
+        Some(TableKind::Phrases) => dict.load_phrase_file(path)?,
+        None => {
+            return Err(Array30Error::UnsupportedTable {
+                path: path.to_path_buf(),
+            })
+        }
+    }
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let dict = |pairs: &[(&str, &str)]| {
+            let mut dict = Dictionary::new();
+            for (code, word) in pairs {
+                dict.insert_char(code, word);
+            }
+            dict
+        };
+        let old = dict(&[("ab", "甲"), ("cd", "乙"), ("ef", "丙"), ("ef", "丁")]);
+        let new = dict(&[("ab", "甲"), ("ce", "乙"), ("cd", "乙"), ("ef", "丁"), ("gh", "戊")]);
+
+        let diff = TableDiff::compare(&old, &new);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!((diff.added[0].code.as_str(), diff.added[0].word.as_str()), ("gh", "戊"));
+        assert_eq!((diff.removed[0].code.as_str(), diff.removed[0].word.as_str()), ("ef", "丙"));
+        assert_eq!(diff.changed[0].old_codes, ["cd"]);
+        assert_eq!(diff.changed[0].new_codes, ["cd", "ce"]);
+        assert_eq!(
+            diff.lines(),
+            ["新增 1 筆、移除 1 筆、改碼 1 個字詞", "+ gh\t戊", "- ef\t丙", "~ 乙\tcd → cd,ce"]
+        );
+        assert!(TableDiff::compare(&new, &new).is_empty());
+        assert!(load_table(Path::new("table.json")).is_err());
+    }
+}
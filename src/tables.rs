@@ -0,0 +1,447 @@
+// Table file locations and loading
+// 字表檔案位置與載入
+
+use crate::compiled;
+use crate::config::Config;
+use crate::dict::{Dictionary, TableFile};
+use crate::error::Result;
+use crate::frequency::Frequencies;
+use crate::keymap::ArrayVariant;
+use crate::userdict::UserDict;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// 預設的表格目錄
+pub const TABLE_DIR: &str = "table";
+/// 表格目錄中的 emoji 與全形符號補充表（碼接在 W+數字之後）
+pub const SYMBOL_TABLE: &str = "symbols.cin";
+/// 表格的下載頁面
+pub const TABLE_DOWNLOAD_URL: &str = "https://github.com/gontera/array30";
+
+/// 內建的表格（embedded-tables 功能）：行列 30 標準字表、詞庫與符號補充表，以表格目錄中的相對路徑為鍵
+#[cfg(feature = "embedded-tables")]
+const EMBEDDED_TABLES: &[(&str, &[u8])] = &[
+    ("array30-phrase-20210725.txt", include_bytes!("../table/array30-phrase-20210725.txt")),
+    ("cin2/ar30-regular-v2023-1.0-20251012.cin2", include_bytes!("../table/cin2/ar30-regular-v2023-1.0-20251012.cin2")),
+    (SYMBOL_TABLE, include_bytes!("../table/symbols.cin")),
+];
+#[cfg(not(feature = "embedded-tables"))]
+const EMBEDDED_TABLES: &[(&str, &[u8])] = &[];
+
+/// 使用者指定的表格位置（命令列參數或設定檔）
+/// 空字串表示使用預設位置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableOptions {
+    /// 表格目錄
+    pub table_dir: String,
+    /// 字表檔（取代輸入法變體的預設字表）
+    pub char_table: String,
+    /// 詞庫檔（取代輸入法變體的預設詞庫）
+    pub phrase_table: String,
+    /// 使用標準字表時以大字集為後備字表
+    pub big_fallback: bool,
+    /// 合併 emoji 與全形符號補充表
+    pub symbol_table: bool,
+}
+
+impl TableOptions {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            table_dir: config.table_dir.clone(),
+            char_table: config.char_table.clone(),
+            phrase_table: config.phrase_table.clone(),
+            big_fallback: config.big_fallback,
+            symbol_table: config.symbol_table,
+        }
+    }
+
+    /// 以命令列參數覆寫設定檔中的值
+    pub fn with_overrides(
+        mut self,
+        table_dir: Option<String>,
+        char_table: Option<String>,
+        phrase_table: Option<String>,
+    ) -> Self {
+        if let Some(dir) = table_dir {
+            self.table_dir = dir;
+        }
+        if let Some(file) = char_table {
+            self.char_table = file;
+        }
+        if let Some(file) = phrase_table {
+            self.phrase_table = file;
+        }
+        self
+    }
+
+    /// 表格目錄：有指定時直接使用，否則依序搜尋預設位置
+    pub fn table_dir(&self) -> PathBuf {
+        self.locate_table_dir().0
+    }
+
+    /// 表格目錄與其來源
+    /// 都找不到時回傳目前目錄下的 table/
+    pub fn locate_table_dir(&self) -> (PathBuf, TableDirSource) {
+        if !self.table_dir.is_empty() {
+            return (PathBuf::from(&self.table_dir), TableDirSource::Configured);
+        }
+        search_dirs()
+            .into_iter()
+            .find(|(_, dir)| dir.is_dir())
+            .map(|(source, dir)| (dir, source))
+            .unwrap_or_else(|| (PathBuf::from(TABLE_DIR), TableDirSource::NotFound))
+    }
+
+    /// 取得 (詞庫檔, 字表檔)，個別指定的檔案優先
+    pub fn files(&self, variant: ArrayVariant, use_big_char: bool) -> (Option<PathBuf>, PathBuf) {
+        let (mut phrase_file, mut char_file) = table_files(&self.table_dir(), variant, use_big_char);
+        if !self.char_table.is_empty() {
+            char_file = PathBuf::from(&self.char_table);
+        }
+        if !self.phrase_table.is_empty() {
+            phrase_file = Some(PathBuf::from(&self.phrase_table));
+        }
+        (phrase_file, char_file)
+    }
+
+    /// 後備字表：使用行列 30 標準字表（未另外指定字表）時為大字集
+    pub fn fallback_file(&self, variant: ArrayVariant, use_big_char: bool) -> Option<PathBuf> {
+        (self.big_fallback && !use_big_char && self.char_table.is_empty() && variant == ArrayVariant::Array30)
+            .then(|| table_files(&self.table_dir(), variant, true).1)
+    }
+
+    /// 要合併的符號補充表；只有行列 30 有 W+數字符號選單
+    pub fn symbol_file(&self, variant: ArrayVariant) -> Option<PathBuf> {
+        (self.symbol_table && variant == ArrayVariant::Array30).then(|| self.table_dir().join(SYMBOL_TABLE))
+    }
+}
+
+/// 可在執行時切換的字表
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableChoice {
+    /// 標準字集
+    Regular,
+    /// 大字集
+    Big,
+    /// 使用者加入的字表檔
+    User(PathBuf),
+}
+
+impl TableChoice {
+    /// 依表格位置判斷目前使用的字表
+    pub fn from_options(options: &TableOptions, use_big_char: bool) -> Self {
+        if !options.char_table.is_empty() {
+            TableChoice::User(PathBuf::from(&options.char_table))
+        } else if use_big_char {
+            TableChoice::Big
+        } else {
+            TableChoice::Regular
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        match self {
+            TableChoice::Regular => "標準字集".to_string(),
+            TableChoice::Big => "大字集".to_string(),
+            TableChoice::User(path) => path
+                .file_name()
+                .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned()),
+        }
+    }
+
+    /// 套用到表格位置，回傳是否使用大字集
+    pub fn apply(&self, options: &mut TableOptions) -> bool {
+        match self {
+            TableChoice::Regular => {
+                options.char_table.clear();
+                false
+            }
+            TableChoice::Big => {
+                options.char_table.clear();
+                true
+            }
+            TableChoice::User(path) => {
+                options.char_table = path.display().to_string();
+                false
+            }
+        }
+    }
+}
+
+/// 表格目錄的來源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableDirSource {
+    /// 命令列參數或設定檔的 table_dir
+    Configured,
+    CurrentDir,
+    ExeDir,
+    DataDir,
+    ConfigDir,
+    /// 所有位置都找不到
+    NotFound,
+}
+
+impl TableDirSource {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TableDirSource::Configured => "指定的目錄",
+            TableDirSource::CurrentDir => "目前目錄",
+            TableDirSource::ExeDir => "執行檔目錄",
+            TableDirSource::DataDir => "使用者資料目錄",
+            TableDirSource::ConfigDir => "設定目錄",
+            TableDirSource::NotFound => "找不到表格目錄",
+        }
+    }
+}
+
+/// 使用者資料目錄下的表格目錄（install-tables 的目的地）
+pub fn data_table_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("rustarray30").join(TABLE_DIR))
+}
+
+/// 未指定表格目錄時的搜尋順序：
+/// 目前目錄、執行檔目錄、使用者資料目錄、設定目錄
+pub fn search_dirs() -> Vec<(TableDirSource, PathBuf)> {
+    let mut dirs = vec![(TableDirSource::CurrentDir, PathBuf::from(TABLE_DIR))];
+    if let Some(exe_dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        dirs.push((TableDirSource::ExeDir, exe_dir.join(TABLE_DIR)));
+    }
+    if let Some(dir) = data_table_dir() {
+        dirs.push((TableDirSource::DataDir, dir));
+    }
+    if let Some(config_dir) = dirs::config_dir() {
+        dirs.push((TableDirSource::ConfigDir, config_dir.join("rustarray30").join(TABLE_DIR)));
+    }
+    dirs
+}
+
+/// 將表格目錄（含子目錄）複製到目的地
+/// 回傳複製的檔案數
+pub fn install_tables(src: &Path, dest: &Path) -> std::io::Result<usize> {
+    std::fs::create_dir_all(dest)?;
+    let mut count = 0;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            count += install_tables(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// 是否內建表格（以 embedded-tables 功能編譯）
+pub fn has_embedded_tables() -> bool {
+    !EMBEDDED_TABLES.is_empty()
+}
+
+/// 將內建的表格寫到表格目錄，回傳寫入的檔案數
+pub fn install_embedded_tables(dest: &Path) -> std::io::Result<usize> {
+    if !has_embedded_tables() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "未內建表格（需以 embedded-tables 功能編譯）",
+        ));
+    }
+    for (name, content) in EMBEDDED_TABLES {
+        let path = dest.join(name);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, content)?;
+    }
+    Ok(EMBEDDED_TABLES.len())
+}
+
+/// 找不到表格時以空字典啟動，記錄重新載入所需的表格位置
+#[derive(Debug, Clone)]
+pub struct MissingTables {
+    pub options: TableOptions,
+    pub variant: ArrayVariant,
+    pub use_big_char: bool,
+    /// 載入失敗的訊息
+    pub error: String,
+}
+
+impl MissingTables {
+    /// 依目前的表格位置重新載入（含符號補充表與後備字表）
+    pub fn reload(&self) -> Result<Dictionary> {
+        load_dictionary(&self.options, self.variant, self.use_big_char)
+    }
+}
+
+/// 表格目錄 cin2/ 下使用者自行加入的字表（.cin2/.cin 與編譯字表 .a30t，不含內建字表）
+pub fn user_tables(base_dir: &Path) -> Vec<PathBuf> {
+    let builtin: Vec<PathBuf> = ArrayVariant::all()
+        .into_iter()
+        .flat_map(|variant| [false, true].map(|big| table_files(base_dir, variant, big).1))
+        .collect();
+    let Ok(entries) = std::fs::read_dir(base_dir.join("cin2")) else {
+        return Vec::new();
+    };
+    let mut tables: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "cin2" || ext == "cin" || ext == compiled::EXTENSION))
+        .filter(|path| !builtin.contains(path))
+        .collect();
+    tables.sort();
+    tables
+}
+
+/// 取得輸入法變體對應的 (詞庫檔, 字表檔)
+/// 只有行列 30 附有詞庫
+pub fn table_files(base_dir: &Path, variant: ArrayVariant, use_big_char: bool) -> (Option<PathBuf>, PathBuf) {
+    let cin2_dir = base_dir.join("cin2");
+    match variant {
+        ArrayVariant::Array30 => {
+            let char_file = if use_big_char {
+                cin2_dir.join("ar30-big-v2023-1.0-20251012.cin2")
+            } else {
+                cin2_dir.join("ar30-regular-v2023-1.0-20251012.cin2")
+            };
+            (Some(base_dir.join("array30-phrase-20210725.txt")), char_file)
+        }
+        ArrayVariant::Array40 => (None, cin2_dir.join("array40.cin")),
+        ArrayVariant::Array10 => (None, cin2_dir.join("array10.cin")),
+    }
+}
+
+/// 依表格位置載入輸入法變體的詞庫、字表與符號補充表，並設定後備字表（在背景讀取）
+/// 補充表讀取失敗只記錄警告
+pub fn load_dictionary(options: &TableOptions, variant: ArrayVariant, use_big_char: bool) -> Result<Dictionary> {
+    let start = Instant::now();
+    let (phrase_file, char_file) = options.files(variant, use_big_char);
+    let mut dict = Dictionary::load_tables(phrase_file.as_deref(), &char_file)?;
+
+    if let Some(symbol_file) = options.symbol_file(variant) {
+        match TableFile::load(&symbol_file) {
+            Ok(table) => table.apply_to(&mut dict, true),
+            Err(e) => tracing::warn!(path = %symbol_file.display(), "無法載入符號補充表：{}", e),
+        }
+    }
+
+    dict.set_fallback(options.fallback_file(variant, use_big_char));
+    finish_loading(dict, &char_file, start)
+}
+
+/// 只載入指定的詞庫與字表（不含補充表與後備字表），並套用自訂字詞與詞頻
+pub fn load_files(phrase_file: Option<&Path>, char_file: &Path) -> Result<Dictionary> {
+    let start = Instant::now();
+    let dict = Dictionary::load_tables(phrase_file, char_file)?;
+    finish_loading(dict, char_file, start)
+}
+
+/// 套用使用者自訂字詞與語料詞頻，記錄載入結果
+fn finish_loading(mut dict: Dictionary, char_file: &Path, start: Instant) -> Result<Dictionary> {
+    UserDict::load_default().apply_to(&mut dict);
+    Frequencies::load_default().apply_to(&mut dict);
+
+    let (char_count, phrase_count) = dict.stats();
+    tracing::info!(
+        path = %char_file.display(),
+        char_count,
+        phrase_count,
+        elapsed_ms = start.elapsed().as_millis() as u64,
+        "已載入字表"
+    );
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_engine::{ComposeMode, InputEngine};
+
+    #[test]
+    fn test_table_files() {
+        let base = Path::new("table");
+        let (phrase, chars) = table_files(base, ArrayVariant::Array30, true);
+        assert!(phrase.is_some());
+        assert!(chars.to_string_lossy().contains("ar30-big"));
+
+        let (phrase, chars) = table_files(base, ArrayVariant::Array40, false);
+        assert!(phrase.is_none());
+        assert_eq!(chars, base.join("cin2").join("array40.cin"));
+    }
+
+    #[test]
+    fn test_install_tables() {
+        let base = std::env::temp_dir().join(format!("rustarray30-install-{}", std::process::id()));
+        let src = base.join("src");
+        std::fs::create_dir_all(src.join("cin2")).unwrap();
+        std::fs::write(src.join("phrase.txt"), "ab\t甲乙").unwrap();
+        std::fs::write(src.join("cin2").join("chars.cin2"), "").unwrap();
+
+        let dest = base.join("dest");
+        assert_eq!(install_tables(&src, &dest).unwrap(), 2);
+        assert!(dest.join("cin2").join("chars.cin2").is_file());
+
+        let options = TableOptions {
+            table_dir: dest.display().to_string(),
+            ..TableOptions::default()
+        };
+        assert_eq!(options.locate_table_dir(), (dest.clone(), TableDirSource::Configured));
+        assert_eq!(user_tables(&dest), vec![dest.join("cin2").join("chars.cin2")]);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_symbol_table() {
+        let options = TableOptions {
+            table_dir: TABLE_DIR.to_string(),
+            symbol_table: true,
+            ..TableOptions::default()
+        };
+        assert_eq!(options.symbol_file(ArrayVariant::Array40), None);
+        let path = options.symbol_file(ArrayVariant::Array30).unwrap();
+
+        // 補充表的碼都接在 W+數字之後，且不超過行列 30 的碼長
+        let mut symbols = Dictionary::new();
+        symbols.load_cin2_file(&path).unwrap();
+        assert!(symbols.iter().all(|entry| {
+            let mut chars = entry.code.chars();
+            chars.next() == Some('w') && chars.next().is_some_and(|c| c.is_ascii_digit()) && entry.code.len() <= 4
+        }));
+
+        let mut dict = Dictionary::new();
+        dict.insert_char("w2", "（");
+        dict.insert_char("w5", "♀");
+        TableFile::load(&path).unwrap().apply_to(&mut dict, true);
+        let mut engine = InputEngine::new(dict);
+        engine.feed_str("w2c");
+        assert_eq!(engine.compose_mode(), ComposeMode::Symbol);
+        let candidates = engine.candidates();
+        let texts: Vec<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["「", "」", "「」"]);
+        assert_eq!(engine.feed_str("\x1bw5sm1"), "😀");
+    }
+
+    #[test]
+    fn test_table_options() {
+        let options = TableOptions {
+            table_dir: "/opt/tables".to_string(),
+            ..TableOptions::default()
+        };
+        let (phrase, chars) = options.files(ArrayVariant::Array30, false);
+        assert_eq!(phrase, Some(PathBuf::from("/opt/tables/array30-phrase-20210725.txt")));
+        assert!(chars.starts_with("/opt/tables/cin2"));
+
+        let mut big = options.clone();
+        assert!(TableChoice::Big.apply(&mut big));
+        assert_eq!(TableChoice::from_options(&big, true), TableChoice::Big);
+
+        let options = options.with_overrides(None, Some("my.cin2".to_string()), Some("my.txt".to_string()));
+        assert_eq!(
+            TableChoice::from_options(&options, true),
+            TableChoice::User(PathBuf::from("my.cin2"))
+        );
+        assert_eq!(options.table_dir, "/opt/tables");
+        assert_eq!(
+            options.files(ArrayVariant::Array40, false),
+            (Some(PathBuf::from("my.txt")), PathBuf::from("my.cin2"))
+        );
+    }
+}
@@ -0,0 +1,146 @@
+// Output transformers applied to committed text
+// 上屏文字轉換：逐字對應（例如繁轉簡）、數字轉全形與自訂正規表示式取代，依序套用
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+/// 一個轉換步驟
+#[derive(Debug, Clone)]
+pub enum Transform {
+    /// 逐字對應，沒有對應的字不變
+    CharMap(HashMap<char, String>),
+    /// 半形數字轉全形
+    FullWidthDigits,
+    /// 正規表示式取代（取代字串可用 $1 等引用群組）
+    Replace { regex: Regex, replacement: String },
+}
+
+impl PartialEq for Transform {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::CharMap(a), Self::CharMap(b)) => a == b,
+            (Self::FullWidthDigits, Self::FullWidthDigits) => true,
+            (
+                Self::Replace { regex: a, replacement: x },
+                Self::Replace { regex: b, replacement: y },
+            ) => a.as_str() == b.as_str() && x == y,
+            _ => false,
+        }
+    }
+}
+
+impl Transform {
+    /// 解析逐字對應檔（OpenCC 格式：每行「原字<Tab>轉換後的字」，多個轉換取第一個，# 開頭為註解）
+    /// 原文超過一個字的行（詞組對應）略過
+    pub fn parse_char_map(content: &str) -> Self {
+        let mut map = HashMap::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(from), Some(to)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let mut chars = from.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                map.insert(c, to.to_string());
+            }
+        }
+        Self::CharMap(map)
+    }
+
+    /// 讀取逐字對應檔
+    pub fn load_char_map(path: &Path) -> io::Result<Self> {
+        std::fs::read_to_string(path).map(|content| Self::parse_char_map(&content))
+    }
+
+    /// 建立正規表示式取代，樣式錯誤時回傳說明
+    pub fn replace(pattern: &str, replacement: &str) -> Result<Self, String> {
+        Regex::new(pattern)
+            .map(|regex| Self::Replace {
+                regex,
+                replacement: replacement.to_string(),
+            })
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            Self::CharMap(map) => text
+                .chars()
+                .fold(String::with_capacity(text.len()), |mut out, c| {
+                    match map.get(&c) {
+                        Some(to) => out.push_str(to),
+                        None => out.push(c),
+                    }
+                    out
+                }),
+            Self::FullWidthDigits => text
+                .chars()
+                .map(|c| match c {
+                    '0'..='9' => char::from_u32(c as u32 - '0' as u32 + 0xFF10).unwrap_or(c),
+                    _ => c,
+                })
+                .collect(),
+            Self::Replace { regex, replacement } => regex.replace_all(text, replacement.as_str()).into_owned(),
+        }
+    }
+}
+
+/// 依序套用的轉換串列
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformChain {
+    transforms: Vec<Transform>,
+}
+
+impl TransformChain {
+    pub fn new(transforms: Vec<Transform>) -> Self {
+        Self { transforms }
+    }
+
+    pub fn push(&mut self, transform: Transform) {
+        self.transforms.push(transform);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// 依序套用全部轉換
+    pub fn apply(&self, text: &str) -> String {
+        self.transforms
+            .iter()
+            .fold(text.to_string(), |text, transform| transform.apply(&text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transforms() {
+        let map = Transform::parse_char_map("# 繁轉簡\n體\t体\n臺\t台 臺\n臺灣\t台湾\n\n");
+        assert_eq!(map.apply("臺灣體育"), "台灣体育");
+        assert_eq!(Transform::FullWidthDigits.apply("第3章"), "第３章");
+        assert!(Transform::replace("(", "").is_err());
+        let replace = Transform::replace(r"(\d+)年", "$1 年").unwrap();
+        assert_eq!(replace.apply("2024年"), "2024 年");
+    }
+
+    #[test]
+    fn test_chain_order() {
+        let mut chain = TransformChain::default();
+        assert!(chain.is_empty());
+        assert_eq!(chain.apply("臺1"), "臺1");
+        chain.push(Transform::parse_char_map("臺\t台"));
+        chain.push(Transform::FullWidthDigits);
+        // 取代在全形轉換之後，只看得到全形數字
+        chain.push(Transform::replace("1", "一").unwrap());
+        chain.push(Transform::replace("１", "壹").unwrap());
+        assert_eq!(chain.apply("臺1"), "台壹");
+    }
+}
@@ -0,0 +1,165 @@
+// System tray icon for the Windows GUI
+// 系統匣圖示
+
+use crate::keymap::ArrayVariant;
+use eframe::egui;
+use std::sync::mpsc::{self, Receiver};
+use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+/// 系統匣圖示大小（像素）
+const ICON_SIZE: u32 = 32;
+
+const ID_TOGGLE_WINDOW: &str = "toggle_window";
+const ID_TOGGLE_LANGUAGE: &str = "toggle_language";
+const ID_QUIT: &str = "quit";
+/// 切換字表選項的 id 前綴，後接 ArrayVariant::as_str()
+const ID_VARIANT_PREFIX: &str = "variant:";
+
+/// 系統匣選單命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayCommand {
+    /// 顯示或隱藏主視窗
+    ToggleWindow,
+    /// 切換中文/英文模式
+    ToggleLanguage,
+    /// 切換字表
+    SwitchVariant(ArrayVariant),
+    /// 結束程式
+    Quit,
+}
+
+impl TrayCommand {
+    fn from_id(id: &str) -> Option<Self> {
+        match id {
+            ID_TOGGLE_WINDOW => Some(TrayCommand::ToggleWindow),
+            ID_TOGGLE_LANGUAGE => Some(TrayCommand::ToggleLanguage),
+            ID_QUIT => Some(TrayCommand::Quit),
+            _ => id
+                .strip_prefix(ID_VARIANT_PREFIX)
+                .and_then(ArrayVariant::from_str)
+                .map(TrayCommand::SwitchVariant),
+        }
+    }
+}
+
+/// 系統匣圖示與其選單
+/// 必須在 GUI 事件迴圈所在的執行緒建立
+pub struct Tray {
+    _icon: TrayIcon,
+    english_item: CheckMenuItem,
+    variant_items: Vec<(ArrayVariant, CheckMenuItem)>,
+    events: Receiver<MenuEvent>,
+}
+
+impl Tray {
+    /// 建立系統匣圖示
+    /// 選單事件發生時會要求 egui 重繪，讓視窗隱藏時也能處理命令
+    pub fn new(ctx: &egui::Context, variant: ArrayVariant, english_mode: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        let toggle_window = MenuItem::with_id(ID_TOGGLE_WINDOW, "顯示/隱藏視窗", true, None);
+        let english_item = CheckMenuItem::with_id(ID_TOGGLE_LANGUAGE, "英文模式", true, english_mode, None);
+
+        let variant_items: Vec<(ArrayVariant, CheckMenuItem)> = ArrayVariant::all()
+            .into_iter()
+            .map(|v| {
+                let id = format!("{}{}", ID_VARIANT_PREFIX, v.as_str());
+                (v, CheckMenuItem::with_id(id, v.display_name(), true, v == variant, None))
+            })
+            .collect();
+        let variant_menu = Submenu::new("切換字表", true);
+        for (_, item) in &variant_items {
+            variant_menu.append(item)?;
+        }
+
+        let quit = MenuItem::with_id(ID_QUIT, "退出", true, None);
+
+        let menu = Menu::with_items(&[
+            &toggle_window,
+            &english_item,
+            &variant_menu,
+            &PredefinedMenuItem::separator(),
+            &quit,
+        ])?;
+
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("行列 30 輸入法")
+            .with_icon(default_icon()?)
+            .build()?;
+
+        // 轉送選單事件並喚醒 GUI
+        let (sender, events) = mpsc::channel();
+        let ctx = ctx.clone();
+        MenuEvent::set_event_handler(Some(move |event| {
+            let _ = sender.send(event);
+            ctx.request_repaint();
+        }));
+
+        Ok(Self {
+            _icon: icon,
+            english_item,
+            variant_items,
+            events,
+        })
+    }
+
+    /// 取出下一個待處理的命令
+    pub fn poll(&self) -> Option<TrayCommand> {
+        while let Ok(event) = self.events.try_recv() {
+            if let Some(command) = TrayCommand::from_id(event.id.as_ref()) {
+                return Some(command);
+            }
+        }
+        None
+    }
+
+    /// 同步英文模式勾選狀態
+    pub fn set_english_mode(&self, english_mode: bool) {
+        if self.english_item.is_checked() != english_mode {
+            self.english_item.set_checked(english_mode);
+        }
+    }
+
+    /// 同步目前字表勾選狀態
+    pub fn set_variant(&self, variant: ArrayVariant) {
+        for (v, item) in &self.variant_items {
+            item.set_checked(*v == variant);
+        }
+    }
+}
+
+/// 產生預設圖示：藍底白框並有三條橫線的方塊
+/// 以程式繪製，避免額外的圖示檔
+fn default_icon() -> Result<Icon, Box<dyn std::error::Error>> {
+    let size = ICON_SIZE as usize;
+    let mut rgba = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let border = x < 2 || y < 2 || x >= size - 2 || y >= size - 2;
+            let stroke = (8..24).contains(&x) && [9, 15, 21].contains(&y);
+            let pixel = if border || stroke {
+                [255, 255, 255, 255]
+            } else {
+                [30, 90, 200, 255]
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+    }
+    Ok(Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_from_id() {
+        assert_eq!(TrayCommand::from_id("quit"), Some(TrayCommand::Quit));
+        assert_eq!(
+            TrayCommand::from_id("variant:array40"),
+            Some(TrayCommand::SwitchVariant(ArrayVariant::Array40))
+        );
+        assert_eq!(TrayCommand::from_id("variant:bogus"), None);
+        assert_eq!(TrayCommand::from_id("unknown"), None);
+    }
+}
@@ -0,0 +1,70 @@
+// Unicode information for candidates
+// 候選字的 Unicode 資訊：碼位與所屬區塊
+
+/// 常見區塊（起點, 終點, 名稱），依碼位排序
+const BLOCKS: &[(u32, u32, &str)] = &[
+    (0x0000, 0x007F, "基本拉丁字母"),
+    (0x0080, 0x00FF, "拉丁字母補充"),
+    (0x2000, 0x206F, "一般標點"),
+    (0x2100, 0x214F, "字母式符號"),
+    (0x2190, 0x21FF, "箭頭"),
+    (0x2200, 0x22FF, "數學運算符號"),
+    (0x2460, 0x24FF, "圍繞字母數字"),
+    (0x2500, 0x257F, "製表符號"),
+    (0x25A0, 0x25FF, "幾何圖形"),
+    (0x2600, 0x26FF, "雜項符號"),
+    (0x2E80, 0x2EFF, "中日韓部首補充"),
+    (0x2F00, 0x2FDF, "康熙部首"),
+    (0x3000, 0x303F, "中日韓符號和標點"),
+    (0x3040, 0x309F, "平假名"),
+    (0x30A0, 0x30FF, "片假名"),
+    (0x3100, 0x312F, "注音符號"),
+    (0x31A0, 0x31BF, "注音符號擴充"),
+    (0x31C0, 0x31EF, "中日韓筆畫"),
+    (0x3200, 0x32FF, "中日韓圍繞字母及月份"),
+    (0x3300, 0x33FF, "中日韓相容字元"),
+    (0x3400, 0x4DBF, "中日韓統一表意文字擴充 A"),
+    (0x4E00, 0x9FFF, "中日韓統一表意文字"),
+    (0xF900, 0xFAFF, "中日韓相容表意文字"),
+    (0xFE30, 0xFE4F, "中日韓相容形式"),
+    (0xFF00, 0xFFEF, "半形及全形字元"),
+    (0x20000, 0x2A6DF, "中日韓統一表意文字擴充 B"),
+    (0x2A700, 0x2B73F, "中日韓統一表意文字擴充 C"),
+    (0x2B740, 0x2B81F, "中日韓統一表意文字擴充 D"),
+    (0x2B820, 0x2CEAF, "中日韓統一表意文字擴充 E"),
+    (0x2CEB0, 0x2EBEF, "中日韓統一表意文字擴充 F"),
+    (0x2EBF0, 0x2EE5F, "中日韓統一表意文字擴充 I"),
+    (0x2F800, 0x2FA1F, "中日韓相容表意文字補充"),
+    (0x30000, 0x3134F, "中日韓統一表意文字擴充 G"),
+    (0x31350, 0x323AF, "中日韓統一表意文字擴充 H"),
+];
+
+/// 碼位，例如 "U+884C"
+pub fn codepoint(c: char) -> String {
+    format!("U+{:04X}", c as u32)
+}
+
+/// 所屬的 Unicode 區塊名稱（不在常見區塊時為「其他」）
+pub fn block_name(c: char) -> &'static str {
+    let cp = c as u32;
+    BLOCKS
+        .iter()
+        .find(|(start, end, _)| (*start..=*end).contains(&cp))
+        .map_or("其他", |(_, _, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codepoint_and_block() {
+        assert_eq!(codepoint('行'), "U+884C");
+        assert_eq!(codepoint('\u{20000}'), "U+20000");
+        assert_eq!(block_name('行'), "中日韓統一表意文字");
+        assert_eq!(block_name('㐀'), "中日韓統一表意文字擴充 A");
+        assert_eq!(block_name('，'), "半形及全形字元");
+        assert_eq!(block_name('ㄅ'), "注音符號");
+        assert_eq!(block_name('\u{E000}'), "其他");
+    }
+}
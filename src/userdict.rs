@@ -0,0 +1,235 @@
+// User dictionary
+// 使用者自訂字詞：單字加入字表、多字加入詞庫，存於使用者資料目錄
+
+use crate::dict::{Dictionary, TableKind};
+use crate::error::Result;
+use crate::keymap::ArrayVariant;
+use std::path::{Path, PathBuf};
+
+/// 自訂字詞檔名（位於使用者資料目錄，格式同詞庫檔：碼<Tab>字詞）
+const USER_DICT_FILENAME: &str = "user.txt";
+
+/// 一筆自訂字詞
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserEntry {
+    pub code: String,
+    pub text: String,
+}
+
+impl UserEntry {
+    /// 整理輸入：碼轉小寫並去除前後空白
+    pub fn new(code: &str, text: &str) -> Self {
+        Self {
+            code: code.trim().to_lowercase(),
+            text: text.trim().to_string(),
+        }
+    }
+
+    /// 單字放入字表，多字放入詞庫
+    pub fn kind(&self) -> TableKind {
+        if self.text.chars().count() == 1 {
+            TableKind::Chars
+        } else {
+            TableKind::Phrases
+        }
+    }
+}
+
+/// 使用者自訂字詞（依加入順序）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserDict {
+    entries: Vec<UserEntry>,
+}
+
+impl UserDict {
+    pub fn entries(&self) -> &[UserEntry] {
+        &self.entries
+    }
+
+    /// 自訂字詞檔路徑
+    pub fn file_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("rustarray30").join(USER_DICT_FILENAME))
+    }
+
+    /// 讀取自訂字詞檔；略過空行與 # 開頭的註解
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// 解析自訂字詞檔的內容
+    pub fn parse(content: &str) -> Self {
+        let entries = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(code, text)| UserEntry::new(code, text))
+            .filter(|entry| !entry.code.is_empty() && !entry.text.is_empty())
+            .collect();
+        Self { entries }
+    }
+
+    /// 讀取預設位置的自訂字詞檔，不存在或無法讀取時為空
+    pub fn load_default() -> Self {
+        let Some(path) = Self::file_path().filter(|p| p.exists()) else {
+            return Self::default();
+        };
+        Self::load(&path).unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), "無法讀取自訂字詞：{}", e);
+            Self::default()
+        })
+    }
+
+    /// 寫入自訂字詞檔
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /// 自訂字詞檔的內容
+    pub fn to_text(&self) -> String {
+        let mut content = String::from("# User dictionary: code<Tab>text (使用者自訂字詞)\n");
+        for entry in &self.entries {
+            content.push_str(&format!("{}\t{}\n", entry.code, entry.text));
+        }
+        content
+    }
+
+    /// 三方合併：`base` 為上次同步的內容；兩邊新增的都保留，任一邊刪除的即刪除
+    /// 順序為本機的字詞在前，另一邊新增的接在後面
+    pub fn merged(&self, other: &UserDict, base: &UserDict) -> UserDict {
+        let keep = |entry: &UserEntry, other: &UserDict| other.entries.contains(entry) || !base.entries.contains(entry);
+        let mut entries: Vec<UserEntry> = self.entries.iter().filter(|e| keep(e, other)).cloned().collect();
+        for entry in &other.entries {
+            if keep(entry, self) && !entries.contains(entry) {
+                entries.push(entry.clone());
+            }
+        }
+        UserDict { entries }
+    }
+
+    /// 將全部自訂字詞加入字典
+    pub fn apply_to(&self, dict: &mut Dictionary) {
+        for entry in &self.entries {
+            dict.add_word(entry.kind(), &entry.code, &entry.text);
+        }
+    }
+
+    /// 檢查字詞是否可加入；`editing` 為修改中的項目（不與自己比較）
+    pub fn validate(
+        &self,
+        entry: &UserEntry,
+        editing: Option<usize>,
+        variant: ArrayVariant,
+        max_code_len: usize,
+        dict: &Dictionary,
+    ) -> std::result::Result<(), String> {
+        if entry.code.is_empty() {
+            return Err("碼不可為空".to_string());
+        }
+        if let Some(c) = entry.code.chars().find(|&c| !variant.is_code_key(c)) {
+            return Err(format!("「{}」不是{}的組碼鍵", c, variant.display_name()));
+        }
+        if entry.code.chars().count() > max_code_len {
+            return Err(format!("碼長不可超過 {} 碼", max_code_len));
+        }
+        if entry.text.is_empty() {
+            return Err("字詞不可為空".to_string());
+        }
+        if entry.text.contains(['\t', '\n', '\r']) {
+            return Err("字詞不可含定位或換行字元".to_string());
+        }
+        let duplicate = self
+            .entries
+            .iter()
+            .enumerate()
+            .any(|(i, e)| Some(i) != editing && e == entry);
+        if duplicate {
+            return Err("已有相同的自訂字詞".to_string());
+        }
+        let unchanged = editing.and_then(|i| self.entries.get(i)) == Some(entry);
+        if !unchanged && dict.contains_word(entry.kind(), &entry.code, &entry.text) {
+            return Err("字典中已有此字詞".to_string());
+        }
+        Ok(())
+    }
+
+    /// 新增字詞並立即加入字典
+    pub fn add(&mut self, entry: UserEntry, dict: &mut Dictionary) {
+        dict.add_word(entry.kind(), &entry.code, &entry.text);
+        self.entries.push(entry);
+    }
+
+    /// 修改字詞並同步更新字典
+    pub fn replace(&mut self, index: usize, entry: UserEntry, dict: &mut Dictionary) {
+        if let Some(old) = self.entries.get_mut(index) {
+            dict.remove_word(old.kind(), &old.code, &old.text);
+            dict.add_word(entry.kind(), &entry.code, &entry.text);
+            *old = entry;
+        }
+    }
+
+    /// 刪除字詞並從字典移除
+    pub fn remove(&mut self, index: usize, dict: &mut Dictionary) {
+        if index < self.entries.len() {
+            let old = self.entries.remove(index);
+            dict.remove_word(old.kind(), &old.code, &old.text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_and_validate() {
+        let mut dict = Dictionary::new();
+        dict.add_word(TableKind::Chars, "ab", "甲");
+        let mut user = UserDict::default();
+        let check = |user: &UserDict, dict: &Dictionary, entry: &UserEntry, editing| {
+            user.validate(entry, editing, ArrayVariant::Array30, 4, dict)
+        };
+
+        assert!(check(&user, &dict, &UserEntry::new("a1", "乙"), None).is_err());
+        assert!(check(&user, &dict, &UserEntry::new("abcde", "乙"), None).is_err());
+        assert!(check(&user, &dict, &UserEntry::new(" AB ", "甲"), None).is_err());
+        assert!(check(&user, &dict, &UserEntry::new("ab", ""), None).is_err());
+
+        let entry = UserEntry::new(" AB ", "乙");
+        assert_eq!(entry.code, "ab");
+        assert!(check(&user, &dict, &entry, None).is_ok());
+        user.add(entry.clone(), &mut dict);
+        assert_eq!(dict.lookup_chars("ab"), Some(&["甲".to_string(), "乙".to_string()][..]));
+        assert!(check(&user, &dict, &entry, None).is_err());
+        assert!(check(&user, &dict, &entry, Some(0)).is_ok());
+
+        user.replace(0, UserEntry::new("cde", "乙丙"), &mut dict);
+        assert_eq!(dict.lookup_chars("ab"), Some(&["甲".to_string()][..]));
+        assert_eq!(dict.lookup_phrases("cde"), Some(&["乙丙".to_string()][..]));
+
+        user.remove(0, &mut dict);
+        assert!(dict.lookup_phrases("cde").is_none());
+        assert!(user.entries().is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("rustarray30-user-{}.txt", std::process::id()));
+        let mut dict = Dictionary::new();
+        let mut user = UserDict::default();
+        user.add(UserEntry::new("ab", "甲"), &mut dict);
+        user.add(UserEntry::new("abcd", "甲乙"), &mut dict);
+        user.save(&path).unwrap();
+
+        let loaded = UserDict::load(&path).unwrap();
+        assert_eq!(loaded, user);
+        let mut fresh = Dictionary::new();
+        loaded.apply_to(&mut fresh);
+        assert_eq!(fresh.entry_counts(), (1, 1));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
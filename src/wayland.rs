@@ -0,0 +1,326 @@
+// Wayland input-method-v2 front-end
+// Wayland 輸入法前端（zwp_input_method_v2，適用 Sway、Hyprland 等 wlroots 合成器）
+
+use crate::dict::Dictionary;
+use crate::input_engine::{EngineOptions, InputEngine, KeyResult};
+use crate::keysym;
+use rustix::event::{PollFd, PollFlags, Timespec};
+use std::collections::HashSet;
+use std::os::fd::AsFd;
+use std::time::Instant;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_keyboard::KeyState;
+use wayland_client::protocol::{wl_registry, wl_seat};
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, QueueHandle, WEnum};
+use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_keyboard_grab_v2::{
+    self, ZwpInputMethodKeyboardGrabV2,
+};
+use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_manager_v2::ZwpInputMethodManagerV2;
+use wayland_protocols_misc::zwp_input_method_v2::client::zwp_input_method_v2::{self, ZwpInputMethodV2};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1;
+
+// xkb 修飾鍵位元（標準 keymap 的順序）
+const XKB_SHIFT: u32 = 1 << 0;
+const XKB_CONTROL: u32 = 1 << 2;
+const XKB_MOD1: u32 = 1 << 3;
+const XKB_MOD4: u32 = 1 << 6;
+
+/// 將 evdev 鍵碼依美式鍵盤位置轉為 keysym
+/// 行列輸入以鍵位為準，因此不依賴合成器提供的 keymap
+fn evdev_to_keysym(key: u32, shift: bool) -> Option<u32> {
+    const ROWS: [(u32, &str, &str); 4] = [
+        (2, "1234567890-=", "!@#$%^&*()_+"),
+        (16, "qwertyuiop[]", "QWERTYUIOP{}"),
+        (30, "asdfghjkl;'`", "ASDFGHJKL:\"~"),
+        (43, "\\zxcvbnm,./", "|ZXCVBNM<>?"),
+    ];
+
+    match key {
+        1 => return Some(keysym::KEY_ESCAPE),
+        14 => return Some(keysym::KEY_BACKSPACE),
+        28 => return Some(keysym::KEY_RETURN),
+        57 => return Some(' ' as u32),
+        96 => return Some(keysym::KEY_KP_ENTER),
+        104 => return Some(keysym::KEY_PAGE_UP),
+        109 => return Some(keysym::KEY_PAGE_DOWN),
+        _ => {}
+    }
+
+    ROWS.iter().find_map(|(first, normal, shifted)| {
+        let index = key.checked_sub(*first)? as usize;
+        let chars = if shift { shifted } else { normal };
+        chars.chars().nth(index).map(|c| c as u32)
+    })
+}
+
+/// 將 xkb 修飾鍵狀態轉為 keysym 遮罩
+fn modifier_state(mods: u32) -> u32 {
+    let mut state = mods & (XKB_SHIFT | XKB_CONTROL | XKB_MOD1);
+    if mods & XKB_MOD4 != 0 {
+        state |= keysym::SUPER_MASK;
+    }
+    state
+}
+
+/// 組成預編輯文字：碼之後接目前頁面的候選（如 "ab 1甲 2乙"）
+/// 回傳 (文字, 游標位元組位置)；沒有組字時為空字串
+fn preedit_text(engine: &InputEngine) -> (String, usize) {
+    let code = &engine.state().current_code;
+    let mut text = code.clone();
+    let keys: Vec<char> = engine.options().variant.selection_keys().chars().collect();
+    for (i, cand) in engine.current_page_candidates().iter().enumerate() {
+        text.push(' ');
+        if let Some(key) = keys.get(i) {
+            text.push(*key);
+        }
+        text.push_str(&cand.text);
+    }
+    (text, code.len())
+}
+
+/// 前端狀態
+struct WaylandIme {
+    engine: InputEngine,
+    input_method: ZwpInputMethodV2,
+    virtual_keyboard: ZwpVirtualKeyboardV1,
+    grab: Option<ZwpInputMethodKeyboardGrabV2>,
+    /// 已收到的 done 事件數（送出 commit 時使用）
+    serial: u32,
+    /// 下一個 done 事件後是否啟用
+    pending_active: bool,
+    active: bool,
+    mods: u32,
+    /// 已由輸入法處理的按鍵，放開時也要攔截
+    consumed: HashSet<u32>,
+    running: bool,
+}
+
+impl WaylandIme {
+    /// 將上屏文字與預編輯文字送給合成器
+    fn flush(&mut self) {
+        let commit = self.engine.take_output();
+        if !commit.is_empty() {
+            self.input_method.commit_string(commit);
+        }
+        let (preedit, cursor) = preedit_text(&self.engine);
+        self.input_method.set_preedit_string(preedit, cursor as i32, cursor as i32);
+        self.input_method.commit(self.serial);
+    }
+
+    fn handle_key(&mut self, time: u32, key: u32, pressed: bool) {
+        if !pressed {
+            if !self.consumed.remove(&key) {
+                self.virtual_keyboard.key(time, key, KeyState::Released.into());
+            }
+            return;
+        }
+
+        let state = modifier_state(self.mods);
+        let handled = evdev_to_keysym(key, state & keysym::SHIFT_MASK != 0)
+            .is_some_and(|sym| keysym::process_key(&mut self.engine, sym, state));
+        if handled {
+            self.consumed.insert(key);
+            self.flush();
+        } else {
+            self.virtual_keyboard.key(time, key, KeyState::Pressed.into());
+        }
+    }
+
+    /// 組字逾時時依設定清除或上屏
+    fn tick(&mut self) {
+        if self.active && self.engine.tick(Instant::now()) != KeyResult::NoChange {
+            self.flush();
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandIme {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpInputMethodV2, ()> for WaylandIme {
+    fn event(
+        state: &mut Self,
+        input_method: &ZwpInputMethodV2,
+        event: zwp_input_method_v2::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_input_method_v2::Event::Activate => state.pending_active = true,
+            zwp_input_method_v2::Event::Deactivate => state.pending_active = false,
+            zwp_input_method_v2::Event::Done => {
+                state.serial += 1;
+                if state.pending_active && !state.active {
+                    state.grab = Some(input_method.grab_keyboard(qh, ()));
+                } else if !state.pending_active && state.active {
+                    if let Some(grab) = state.grab.take() {
+                        grab.release();
+                    }
+                    // 失去焦點時捨棄組字中的碼
+                    state.engine.handle_key('\x1b');
+                    state.engine.take_output();
+                    state.consumed.clear();
+                }
+                if state.active != state.pending_active {
+                    tracing::debug!(active = state.pending_active, "輸入法啟用狀態變更");
+                }
+                state.active = state.pending_active;
+            }
+            zwp_input_method_v2::Event::Unavailable => {
+                tracing::error!("已有其他輸入法在執行");
+                state.running = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpInputMethodKeyboardGrabV2, ()> for WaylandIme {
+    fn event(
+        state: &mut Self,
+        _: &ZwpInputMethodKeyboardGrabV2,
+        event: zwp_input_method_keyboard_grab_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_input_method_keyboard_grab_v2::Event::Keymap {
+                format: WEnum::Value(format),
+                fd,
+                size,
+            } => {
+                // 未處理的按鍵經由虛擬鍵盤轉送，需使用相同的 keymap
+                state.virtual_keyboard.keymap(format.into(), fd.as_fd(), size);
+            }
+            zwp_input_method_keyboard_grab_v2::Event::Key { time, key, state: key_state, .. } => {
+                state.handle_key(time, key, key_state == WEnum::Value(KeyState::Pressed));
+            }
+            zwp_input_method_keyboard_grab_v2::Event::Modifiers {
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+                ..
+            } => {
+                state.mods = mods_depressed | mods_latched;
+                state.virtual_keyboard.modifiers(mods_depressed, mods_latched, mods_locked, group);
+            }
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(WaylandIme: ignore wl_seat::WlSeat);
+delegate_noop!(WaylandIme: ZwpInputMethodManagerV2);
+delegate_noop!(WaylandIme: ZwpVirtualKeyboardManagerV1);
+delegate_noop!(WaylandIme: ZwpVirtualKeyboardV1);
+
+/// 以 Wayland 輸入法模式執行（需合成器支援 input-method-v2 與 virtual-keyboard）
+pub fn run_wayland(dict: Dictionary, options: EngineOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let conn = Connection::connect_to_env().map_err(|e| format!("無法連線到 Wayland 合成器：{}", e))?;
+    let (globals, mut queue) = registry_queue_init::<WaylandIme>(&conn)?;
+    let qh = queue.handle();
+
+    let seat: wl_seat::WlSeat = globals.bind(&qh, 1..=1, ())?;
+    let im_manager: ZwpInputMethodManagerV2 = globals
+        .bind(&qh, 1..=1, ())
+        .map_err(|_| "合成器不支援 zwp_input_method_manager_v2")?;
+    let vk_manager: ZwpVirtualKeyboardManagerV1 = globals
+        .bind(&qh, 1..=1, ())
+        .map_err(|_| "合成器不支援 zwp_virtual_keyboard_manager_v1")?;
+
+    let mut ime = WaylandIme {
+        engine: InputEngine::with_options(dict, options),
+        input_method: im_manager.get_input_method(&seat, &qh, ()),
+        virtual_keyboard: vk_manager.create_virtual_keyboard(&seat, &qh, ()),
+        grab: None,
+        serial: 0,
+        pending_active: false,
+        active: false,
+        mods: 0,
+        consumed: HashSet::new(),
+        running: true,
+    };
+
+    tracing::info!("Wayland 輸入法已啟動");
+
+    while ime.running {
+        let deadline = ime.engine.composition_deadline();
+        dispatch_until(&mut queue, &mut ime, deadline)?;
+        ime.tick();
+    }
+    Ok(())
+}
+
+/// 處理 Wayland 事件，有組字逾時的時間點時最多等到該時間
+fn dispatch_until(
+    queue: &mut EventQueue<WaylandIme>,
+    ime: &mut WaylandIme,
+    deadline: Option<Instant>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(deadline) = deadline else {
+        queue.blocking_dispatch(ime)?;
+        return Ok(());
+    };
+
+    queue.dispatch_pending(ime)?;
+    queue.flush()?;
+    if let Some(guard) = queue.prepare_read() {
+        let timeout = Timespec::try_from(deadline.saturating_duration_since(Instant::now()))?;
+        let ready = {
+            let fd = guard.connection_fd();
+            let mut fds = [PollFd::new(&fd, PollFlags::IN)];
+            match rustix::event::poll(&mut fds, Some(&timeout)) {
+                Ok(n) => n > 0,
+                Err(rustix::io::Errno::INTR) => false,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        if ready {
+            guard.read()?;
+        }
+    }
+    queue.dispatch_pending(ime)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evdev_to_keysym() {
+        assert_eq!(evdev_to_keysym(30, false), Some('a' as u32));
+        assert_eq!(evdev_to_keysym(30, true), Some('A' as u32));
+        assert_eq!(evdev_to_keysym(11, false), Some('0' as u32));
+        assert_eq!(evdev_to_keysym(52, false), Some('.' as u32));
+        assert_eq!(evdev_to_keysym(14, false), Some(keysym::KEY_BACKSPACE));
+        assert_eq!(evdev_to_keysym(29, false), None);
+        assert_eq!(modifier_state(XKB_SHIFT | XKB_MOD4), keysym::SHIFT_MASK | keysym::SUPER_MASK);
+    }
+
+    #[test]
+    fn test_preedit_text() {
+        let mut dict = Dictionary::new();
+        dict.insert_char("ab", "甲");
+        dict.insert_char("ab", "乙");
+        let mut engine = InputEngine::new(dict);
+        assert_eq!(preedit_text(&engine), (String::new(), 0));
+
+        engine.handle_key('a');
+        engine.handle_key('b');
+        assert_eq!(preedit_text(&engine), ("ab 1甲 2乙".to_string(), 2));
+    }
+}